@@ -0,0 +1,57 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Build metadata reported by the health endpoints
+//!
+//! - `CIVICCONNECT_GIT_COMMIT`: `GIT_COMMIT` if set (for builds without
+//!   `.git`, like container images), else `git rev-parse HEAD`, else
+//!   `unknown`
+//! - `CIVICCONNECT_BUILD_EPOCH`: `SOURCE_DATE_EPOCH` if set (reproducible
+//!   builds), else when this script last ran, in Unix seconds
+
+use std::path::Path;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=GIT_COMMIT");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+
+    // Re-run when the checked-out commit changes. Only existing paths are
+    // watched; cargo would otherwise re-run this on every build.
+    if let Some(git_dir) = git(&["rev-parse", "--absolute-git-dir"]) {
+        for watched in ["HEAD", "refs", "packed-refs"] {
+            let path = Path::new(&git_dir).join(watched);
+            if path.exists() {
+                println!("cargo:rerun-if-changed={}", path.display());
+            }
+        }
+    }
+
+    let commit = std::env::var("GIT_COMMIT")
+        .ok()
+        .filter(|commit| !commit.is_empty())
+        .or_else(|| git(&["rev-parse", "HEAD"]))
+        .unwrap_or_else(|| "unknown".into());
+    println!("cargo:rustc-env=CIVICCONNECT_GIT_COMMIT={commit}");
+
+    let epoch = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs())
+        });
+    println!("cargo:rustc-env=CIVICCONNECT_BUILD_EPOCH={epoch}");
+}
+
+/// Trimmed stdout of a successful `git` command
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8(output.stdout).ok()?;
+    Some(stdout.trim().to_string())
+}
@@ -0,0 +1,85 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+--
+-- CivicConnect initial schema
+--
+-- Privacy: no plaintext email, no coordinates. Users are looked up by
+-- email hash; locations are H3 cell IDs at resolution 7.
+
+CREATE TABLE users (
+    id                UUID PRIMARY KEY,
+    email_hash        TEXT NOT NULL UNIQUE,
+    username          TEXT NOT NULL UNIQUE,
+    password_hash     TEXT NOT NULL,
+    current_level     SMALLINT NOT NULL DEFAULT 0 CHECK (current_level BETWEEN 0 AND 5),
+    experience_points INTEGER NOT NULL DEFAULT 0 CHECK (experience_points >= 0),
+    location_hash     TEXT,
+    created_at        TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at        TIMESTAMPTZ NOT NULL DEFAULT now(),
+    last_active       TIMESTAMPTZ NOT NULL DEFAULT now(),
+    is_verified       BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE TABLE events (
+    id            UUID PRIMARY KEY,
+    organizer_id  UUID NOT NULL REFERENCES users (id),
+    title         TEXT NOT NULL,
+    description   TEXT NOT NULL,
+    location_hash TEXT NOT NULL,
+    start_time    TIMESTAMPTZ NOT NULL,
+    end_time      TIMESTAMPTZ NOT NULL,
+    capacity      INTEGER CHECK (capacity > 0),
+    tags          TEXT[] NOT NULL DEFAULT '{}',
+    created_at    TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at    TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CHECK (start_time < end_time)
+);
+
+CREATE INDEX events_location_start_idx ON events (location_hash, start_time);
+
+CREATE TABLE verifications (
+    id                 UUID PRIMARY KEY,
+    event_id           UUID NOT NULL REFERENCES events (id),
+    user_id            UUID NOT NULL REFERENCES users (id),
+    organizer_id       UUID NOT NULL REFERENCES users (id),
+    signature          BYTEA NOT NULL,
+    verified_at        TIMESTAMPTZ NOT NULL DEFAULT now(),
+    experience_awarded INTEGER NOT NULL,
+    location_hash      TEXT NOT NULL,
+    UNIQUE (event_id, user_id)
+);
+
+CREATE INDEX verifications_user_time_idx ON verifications (user_id, verified_at);
+
+CREATE TABLE messages (
+    id                UUID PRIMARY KEY,
+    sender_id         UUID NOT NULL REFERENCES users (id),
+    recipient_id      UUID NOT NULL REFERENCES users (id),
+    encrypted_content BYTEA NOT NULL,
+    sent_at           TIMESTAMPTZ NOT NULL DEFAULT now(),
+    delivered_at      TIMESTAMPTZ,
+    read_at           TIMESTAMPTZ
+);
+
+CREATE INDEX messages_recipient_idx ON messages (recipient_id, sent_at);
+
+CREATE TABLE mentorships (
+    id         UUID PRIMARY KEY,
+    mentor_id  UUID NOT NULL REFERENCES users (id),
+    mentee_id  UUID NOT NULL REFERENCES users (id),
+    status     TEXT NOT NULL,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    ended_at   TIMESTAMPTZ
+);
+
+CREATE TABLE level_progressions (
+    id            UUID PRIMARY KEY,
+    user_id       UUID NOT NULL REFERENCES users (id),
+    from_level    SMALLINT NOT NULL,
+    to_level      SMALLINT NOT NULL,
+    reason        TEXT NOT NULL,
+    progressed_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    metadata      JSONB NOT NULL DEFAULT '{}'
+);
+
+CREATE INDEX level_progressions_user_idx ON level_progressions (user_id, progressed_at);
@@ -0,0 +1,15 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+--
+-- Keyed email blind index
+--
+-- users.email_hash now holds a BLAKE3-keyed blind index. The key version
+-- records which key produced it; 0 is the legacy unkeyed SHA-256, which
+-- is what every existing row holds. Rows are moved to the current key in
+-- the background and on login.
+
+ALTER TABLE users
+    ADD COLUMN email_key_version SMALLINT NOT NULL DEFAULT 0
+        CHECK (email_key_version >= 0);
+
+CREATE INDEX users_email_key_version_idx ON users (email_key_version);
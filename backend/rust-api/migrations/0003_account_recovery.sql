@@ -0,0 +1,20 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+--
+-- Opt-in account recovery
+--
+-- email_encrypted is NULL unless the user opted in. It holds
+-- nonce || XChaCha20-Poly1305 ciphertext under a key kept outside the
+-- database. Reset tokens are stored only as SHA-256 hashes.
+
+ALTER TABLE users ADD COLUMN email_encrypted BYTEA;
+
+CREATE TABLE password_reset_tokens (
+    token_hash TEXT PRIMARY KEY,
+    user_id    UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    used_at    TIMESTAMPTZ
+);
+
+CREATE INDEX password_reset_tokens_user_idx ON password_reset_tokens (user_id);
@@ -0,0 +1,29 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+--
+-- WebAuthn / passkey credentials
+--
+-- passkey holds the serialized credential (public key, counter, flags);
+-- there is no private material on the server. Ceremony state lives in
+-- webauthn_challenges for the few minutes between start and finish.
+
+CREATE TABLE webauthn_credentials (
+    credential_id BYTEA PRIMARY KEY,
+    user_id       UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    name          TEXT NOT NULL,
+    passkey       JSONB NOT NULL,
+    created_at    TIMESTAMPTZ NOT NULL DEFAULT now(),
+    last_used_at  TIMESTAMPTZ
+);
+
+CREATE INDEX webauthn_credentials_user_idx ON webauthn_credentials (user_id);
+
+CREATE TABLE webauthn_challenges (
+    id         UUID PRIMARY KEY,
+    user_id    UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    purpose    TEXT NOT NULL,
+    state      JSONB NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX webauthn_challenges_expires_idx ON webauthn_challenges (expires_at);
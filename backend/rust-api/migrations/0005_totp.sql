@@ -0,0 +1,28 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+--
+-- TOTP second factor and one-time recovery codes
+--
+-- A TOTP secret is pending until the user proves their app works by
+-- entering a code; only confirmed secrets are offered at login.
+-- last_used_step blocks replay of a code within its validity window.
+-- Recovery codes are stored as Argon2id hashes, found by selector.
+
+CREATE TABLE totp_credentials (
+    user_id         UUID PRIMARY KEY REFERENCES users (id) ON DELETE CASCADE,
+    secret          BYTEA NOT NULL,
+    confirmed_at    TIMESTAMPTZ,
+    last_used_step  BIGINT,
+    failed_attempts SMALLINT NOT NULL DEFAULT 0,
+    last_failed_at  TIMESTAMPTZ,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE TABLE mfa_recovery_codes (
+    user_id    UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    selector   TEXT NOT NULL,
+    code_hash  TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    used_at    TIMESTAMPTZ,
+    PRIMARY KEY (user_id, selector)
+);
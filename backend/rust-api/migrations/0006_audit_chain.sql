@@ -0,0 +1,67 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+--
+-- Tamper-evident audit log
+--
+-- Verifications and level progressions form a single hash chain: each
+-- entry gets the next audit_seq and stores the hash of the entry before
+-- it. audit_chain_head is locked while appending, which serializes
+-- writers and records where the chain should end, so truncating the
+-- tail is detectable too. Checkpoints sign the head with a key kept off
+-- the database, so the chain can't be rewritten wholesale either.
+--
+-- Rows from before this migration have no audit_seq and are reported as
+-- unchained by verification.
+
+ALTER TABLE verifications
+    ADD COLUMN audit_seq  BIGINT UNIQUE,
+    ADD COLUMN prev_hash  BYTEA,
+    ADD COLUMN entry_hash BYTEA;
+
+ALTER TABLE level_progressions
+    ADD COLUMN audit_seq  BIGINT UNIQUE,
+    ADD COLUMN prev_hash  BYTEA,
+    ADD COLUMN entry_hash BYTEA;
+
+CREATE TABLE audit_chain_head (
+    singleton  BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (singleton),
+    seq        BIGINT NOT NULL,
+    entry_hash BYTEA NOT NULL
+);
+
+INSERT INTO audit_chain_head (seq, entry_hash) VALUES (0, '\x0000000000000000000000000000000000000000000000000000000000000000');
+
+CREATE TABLE audit_checkpoints (
+    seq        BIGINT PRIMARY KEY,
+    entry_hash BYTEA NOT NULL,
+    signed_at  TIMESTAMPTZ NOT NULL,
+    signature  BYTEA NOT NULL
+);
+
+-- Append-only: entries and checkpoints can be added, never changed.
+-- (A superuser can still disable triggers; the hash chain catches that.)
+CREATE FUNCTION audit_append_only() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'audit log table % is append-only', TG_TABLE_NAME;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER verifications_append_only
+    BEFORE UPDATE OR DELETE ON verifications
+    FOR EACH ROW EXECUTE FUNCTION audit_append_only();
+
+CREATE TRIGGER verifications_no_truncate
+    BEFORE TRUNCATE ON verifications
+    FOR EACH STATEMENT EXECUTE FUNCTION audit_append_only();
+
+CREATE TRIGGER level_progressions_append_only
+    BEFORE UPDATE OR DELETE ON level_progressions
+    FOR EACH ROW EXECUTE FUNCTION audit_append_only();
+
+CREATE TRIGGER level_progressions_no_truncate
+    BEFORE TRUNCATE ON level_progressions
+    FOR EACH STATEMENT EXECUTE FUNCTION audit_append_only();
+
+CREATE TRIGGER audit_checkpoints_append_only
+    BEFORE UPDATE OR DELETE ON audit_checkpoints
+    FOR EACH ROW EXECUTE FUNCTION audit_append_only();
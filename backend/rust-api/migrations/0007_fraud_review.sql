@@ -0,0 +1,20 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+--
+-- Fraud screening of verifications
+--
+-- Each verification records the fraud score it was given and which
+-- rules fired. Verifications scoring at or above the hold threshold are
+-- kept with status 'held_for_review' and award no XP until reviewed.
+-- These columns are part of the audit chain's entry hash.
+
+ALTER TABLE verifications
+    ADD COLUMN status TEXT NOT NULL DEFAULT 'verified'
+        CHECK (status IN ('verified', 'held_for_review')),
+    ADD COLUMN fraud_score SMALLINT NOT NULL DEFAULT 0,
+    ADD COLUMN fraud_findings JSONB NOT NULL DEFAULT '[]';
+
+CREATE INDEX verifications_held_idx ON verifications (verified_at)
+    WHERE status = 'held_for_review';
+
+CREATE INDEX verifications_event_time_idx ON verifications (event_id, verified_at);
@@ -0,0 +1,48 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+--
+-- Moderation
+--
+-- Roles are granted, on top of the level users earn: moderators review
+-- held verifications and hide events, admins also suspend users, reverse
+-- XP and grant roles. There is no API for the first admin; grant it with
+--
+--   UPDATE users SET role = 'admin' WHERE username = '...';
+--
+-- Every moderation action is recorded in admin_actions, which is part of
+-- the audit chain alongside verifications and level progressions.
+
+ALTER TABLE users
+    ADD COLUMN role TEXT NOT NULL DEFAULT 'member'
+        CHECK (role IN ('member', 'moderator', 'admin')),
+    ADD COLUMN suspended_at TIMESTAMPTZ;
+
+ALTER TABLE events
+    ADD COLUMN hidden_at TIMESTAMPTZ;
+
+CREATE TABLE admin_actions (
+    id          UUID PRIMARY KEY,
+    actor_id    UUID NOT NULL REFERENCES users (id),
+    action      TEXT NOT NULL,
+    target_id   UUID NOT NULL,
+    reason      TEXT NOT NULL,
+    acted_at    TIMESTAMPTZ NOT NULL,
+    metadata    JSONB NOT NULL DEFAULT '{}',
+    audit_seq   BIGINT UNIQUE,
+    prev_hash   BYTEA,
+    entry_hash  BYTEA
+);
+
+CREATE INDEX admin_actions_target_idx ON admin_actions (target_id, acted_at);
+
+-- A held verification is reviewed once
+CREATE UNIQUE INDEX admin_actions_one_review_idx ON admin_actions (target_id)
+    WHERE action IN ('release_verification', 'reject_verification');
+
+CREATE TRIGGER admin_actions_append_only
+    BEFORE UPDATE OR DELETE ON admin_actions
+    FOR EACH ROW EXECUTE FUNCTION audit_append_only();
+
+CREATE TRIGGER admin_actions_no_truncate
+    BEFORE TRUNCATE ON admin_actions
+    FOR EACH STATEMENT EXECUTE FUNCTION audit_append_only();
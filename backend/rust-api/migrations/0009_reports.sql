@@ -0,0 +1,38 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+--
+-- Abuse reports
+--
+-- Users report other users, events and messages they received. Messages
+-- are end-to-end encrypted, so a message report carries the plaintext as
+-- evidence, decrypted and submitted by the recipient; the server can't
+-- check it against the ciphertext and moderators should treat it as the
+-- reporter's account of the message.
+--
+-- Reporters are never revealed to the reported party: nothing outside
+-- the moderation API reads this table.
+
+CREATE TABLE reports (
+    id          UUID PRIMARY KEY,
+    reporter_id UUID NOT NULL REFERENCES users (id),
+    target_type TEXT NOT NULL CHECK (target_type IN ('user', 'event', 'message')),
+    target_id   UUID NOT NULL,
+    category    TEXT NOT NULL CHECK (category IN (
+        'harassment', 'threat', 'spam', 'impersonation', 'unsafe_event', 'other'
+    )),
+    description TEXT NOT NULL,
+    evidence    TEXT,
+    status      TEXT NOT NULL DEFAULT 'open'
+        CHECK (status IN ('open', 'triaged', 'actioned', 'dismissed')),
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at  TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CHECK (evidence IS NULL OR target_type = 'message')
+);
+
+-- One unresolved report per reporter and target
+CREATE UNIQUE INDEX reports_unresolved_idx ON reports (reporter_id, target_type, target_id)
+    WHERE status IN ('open', 'triaged');
+
+CREATE INDEX reports_target_idx ON reports (target_type, target_id);
+CREATE INDEX reports_status_idx ON reports (status, created_at);
+CREATE INDEX reports_reporter_time_idx ON reports (reporter_id, created_at);
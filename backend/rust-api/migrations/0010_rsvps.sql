@@ -0,0 +1,17 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+--
+-- Event RSVPs
+--
+-- An RSVP is a user's intent to attend, distinct from a verification
+-- (proof they did). Capacity limits RSVPs; the real-time stream only
+-- delivers updates for events a user has RSVP'd to or organizes.
+
+CREATE TABLE event_rsvps (
+    event_id   UUID NOT NULL REFERENCES events (id),
+    user_id    UUID NOT NULL REFERENCES users (id),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (event_id, user_id)
+);
+
+CREATE INDEX event_rsvps_user_idx ON event_rsvps (user_id);
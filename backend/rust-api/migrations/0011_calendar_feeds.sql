@@ -0,0 +1,20 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+--
+-- iCalendar export
+--
+-- Events only carry a coarse H3 cell, so calendar entries place them at
+-- the cell center unless the organizer opts into publishing venue text.
+--
+-- Each user can have one secret feed URL listing their RSVP'd events.
+-- Calendar apps can't send credentials, so the token in the URL is the
+-- credential: only its SHA-256 is stored, and replacing or deleting the
+-- row revokes it.
+
+ALTER TABLE events ADD COLUMN venue TEXT;
+
+CREATE TABLE calendar_feeds (
+    user_id    UUID PRIMARY KEY REFERENCES users (id) ON DELETE CASCADE,
+    token_hash TEXT NOT NULL UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
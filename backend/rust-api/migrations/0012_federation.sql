@@ -0,0 +1,31 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+--
+-- ActivityPub federation
+--
+-- Organizers are published as ActivityPub actors so groups on Mastodon,
+-- Mobilizon and the like can follow them and receive their events.
+-- Remote actors are identified by their actor URI; inboxes are cached
+-- from the actor document when the relationship is made.
+
+-- Remote actors following one of our organizers
+CREATE TABLE federation_followers (
+    user_id      UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    actor_uri    TEXT NOT NULL,
+    inbox        TEXT NOT NULL,
+    shared_inbox TEXT,
+    -- The Follow activity's ID, matched when it is undone
+    follow_id    TEXT NOT NULL,
+    created_at   TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (user_id, actor_uri)
+);
+
+-- Remote actors one of our organizers follows
+CREATE TABLE federation_following (
+    user_id     UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    actor_uri   TEXT NOT NULL,
+    follow_id   TEXT NOT NULL UNIQUE,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT now(),
+    accepted_at TIMESTAMPTZ,
+    PRIMARY KEY (user_id, actor_uri)
+);
@@ -0,0 +1,54 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+--
+-- Outbound webhooks
+--
+-- Organizers subscribe URLs to changes to their events. Deliveries are
+-- queued in the same transaction as the change, so none are lost or sent
+-- for a change that rolled back, and a background task posts them with
+-- retries. Deliveries that exhaust their retries move to the dead-letter
+-- table, from which the owner can replay them.
+
+CREATE TABLE webhook_subscriptions (
+    id          UUID PRIMARY KEY,
+    owner_id    UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    url         TEXT NOT NULL,
+    -- HMAC key deliveries are signed with; shown to the owner once
+    secret      TEXT NOT NULL,
+    event_types TEXT[] NOT NULL,
+    -- Send user IDs in payloads instead of scrubbing them
+    include_pii BOOLEAN NOT NULL DEFAULT false,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX webhook_subscriptions_owner_idx ON webhook_subscriptions (owner_id);
+
+-- Deliveries waiting to be (re)tried
+CREATE TABLE webhook_deliveries (
+    id              UUID PRIMARY KEY,
+    subscription_id UUID NOT NULL REFERENCES webhook_subscriptions (id) ON DELETE CASCADE,
+    event_type      TEXT NOT NULL,
+    payload         JSONB NOT NULL,
+    attempts        INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    last_error      TEXT,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX webhook_deliveries_due_idx ON webhook_deliveries (next_attempt_at);
+
+-- Deliveries that failed every attempt
+CREATE TABLE webhook_dead_letters (
+    -- The delivery's ID, kept on replay so receivers can deduplicate
+    id              UUID PRIMARY KEY,
+    subscription_id UUID NOT NULL REFERENCES webhook_subscriptions (id) ON DELETE CASCADE,
+    event_type      TEXT NOT NULL,
+    payload         JSONB NOT NULL,
+    attempts        INTEGER NOT NULL,
+    last_error      TEXT,
+    created_at      TIMESTAMPTZ NOT NULL,
+    failed_at       TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX webhook_dead_letters_subscription_idx
+    ON webhook_dead_letters (subscription_id, failed_at);
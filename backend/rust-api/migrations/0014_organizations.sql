@@ -0,0 +1,70 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+--
+-- Organizations (chapters)
+--
+-- Members hold one role per organization: owner, admin or member. What a
+-- member may do combines that role with their platform level (see
+-- `organizations.rs`). Events can belong to an organization, and any event
+-- can have co-organizers besides its organizer.
+--
+-- Invitation links carry a secret token; only its SHA-256 hash is stored.
+
+CREATE TABLE organizations (
+    id          UUID PRIMARY KEY,
+    name        TEXT NOT NULL,
+    slug        TEXT NOT NULL UNIQUE CHECK (slug ~ '^[a-z0-9]+(-[a-z0-9]+)*$'),
+    description TEXT NOT NULL DEFAULT '',
+    created_by  UUID NOT NULL REFERENCES users (id),
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at  TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE TABLE organization_members (
+    organization_id UUID NOT NULL REFERENCES organizations (id) ON DELETE CASCADE,
+    user_id         UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    role            TEXT NOT NULL CHECK (role IN ('owner', 'admin', 'member')),
+    joined_at       TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (organization_id, user_id)
+);
+
+CREATE INDEX organization_members_user_idx ON organization_members (user_id);
+
+CREATE TABLE organization_invitations (
+    id              UUID PRIMARY KEY,
+    organization_id UUID NOT NULL REFERENCES organizations (id) ON DELETE CASCADE,
+    token_hash      TEXT NOT NULL UNIQUE,
+    -- Owners are made by promotion, never by invitation
+    role            TEXT NOT NULL CHECK (role IN ('admin', 'member')),
+    created_by      UUID NOT NULL REFERENCES users (id),
+    max_uses        INTEGER NOT NULL CHECK (max_uses > 0),
+    uses            INTEGER NOT NULL DEFAULT 0,
+    expires_at      TIMESTAMPTZ NOT NULL,
+    revoked_at      TIMESTAMPTZ,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX organization_invitations_organization_idx
+    ON organization_invitations (organization_id);
+
+ALTER TABLE events
+    ADD COLUMN organization_id UUID REFERENCES organizations (id) ON DELETE SET NULL;
+
+CREATE INDEX events_organization_idx ON events (organization_id);
+
+CREATE TABLE event_co_organizers (
+    event_id   UUID NOT NULL REFERENCES events (id) ON DELETE CASCADE,
+    user_id    UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    added_by   UUID NOT NULL REFERENCES users (id),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (event_id, user_id)
+);
+
+CREATE INDEX event_co_organizers_user_idx ON event_co_organizers (user_id);
+
+-- Webhooks can now be subscribed for an organization's events
+ALTER TABLE webhook_subscriptions
+    ADD COLUMN organization_id UUID REFERENCES organizations (id) ON DELETE CASCADE;
+
+CREATE INDEX webhook_subscriptions_organization_idx
+    ON webhook_subscriptions (organization_id);
@@ -0,0 +1,25 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+--
+-- Idempotency keys
+--
+-- Clients retrying a POST or PUT send the same Idempotency-Key header,
+-- and get the first attempt's response instead of a second side effect.
+--
+-- Keys are scoped to the caller's credentials and never stored: rows are
+-- found by a hash of scope and key, and responses (which may carry
+-- session tokens or secrets shown once) are encrypted under a key derived
+-- from them, so the table alone reveals neither keys nor responses.
+
+CREATE TABLE idempotency_keys (
+    key_hash     TEXT PRIMARY KEY,
+    -- Keyed hash of method, path and body, to reject a reused key
+    request_hash TEXT NOT NULL,
+    -- NULL while the first request is still running
+    status       SMALLINT,
+    response     BYTEA,
+    created_at   TIMESTAMPTZ NOT NULL DEFAULT now(),
+    expires_at   TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idempotency_keys_expires_idx ON idempotency_keys (expires_at);
@@ -0,0 +1,12 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+--
+-- Device signing keys
+--
+-- Organizers' devices sign the QR codes they display, so a code can be
+-- shown and scanned with neither device online and checked when the scan
+-- is uploaded. Each user registers at most one ed25519 public key;
+-- replacing it invalidates codes signed by the old one.
+
+ALTER TABLE users ADD COLUMN signing_key BYTEA
+    CHECK (octet_length(signing_key) = 32);
@@ -0,0 +1,44 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+--
+-- Unlinkable attendance credentials
+--
+-- In this optional mode a verified attendee gets a blind signature on a
+-- token only their device knows, instead of a verifications row, and
+-- later redeems it for XP. Nothing here says which user attended which
+-- event once an event's salt is purged.
+
+-- Credentials redeemed, for level progression
+ALTER TABLE users ADD COLUMN credential_attendances INTEGER NOT NULL DEFAULT 0;
+
+-- Per-event salt for the claims below; purged, with its claims, once
+-- scans for the event can no longer arrive
+CREATE TABLE credential_event_salts (
+    event_id      UUID PRIMARY KEY REFERENCES events (id) ON DELETE CASCADE,
+    salt          BYTEA NOT NULL,
+    discard_after TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX credential_event_salts_discard_idx ON credential_event_salts (discard_after);
+
+-- Salted hash of each user issued a credential for the event, so nobody
+-- gets two
+CREATE TABLE credential_claims (
+    event_id UUID NOT NULL REFERENCES credential_event_salts (event_id) ON DELETE CASCADE,
+    tag      BYTEA NOT NULL,
+    PRIMARY KEY (event_id, tag)
+);
+
+-- That a user was issued a credential, not for what; kept a day for the
+-- daily verification limit
+CREATE TABLE credential_issuances (
+    user_id   UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    issued_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX credential_issuances_user_time_idx ON credential_issuances (user_id, issued_at);
+
+-- SHA-256 of each redeemed token, so none is redeemed twice
+CREATE TABLE spent_credentials (
+    token_hash TEXT PRIMARY KEY
+);
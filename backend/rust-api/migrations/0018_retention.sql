@@ -0,0 +1,52 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+--
+-- Data retention
+--
+-- Old verifications are collapsed into per-user and per-event attendance
+-- counters. They are audit chain entries, so each leaves a tombstone in
+-- its place: sequence number, previous hash, content hash and entry hash.
+-- The chain still links end to end, but what the entry said is gone.
+-- A verification can only be deleted once its tombstone exists.
+
+ALTER TABLE users ADD COLUMN archived_attendances INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE events ADD COLUMN archived_attendance INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE audit_tombstones (
+    audit_seq    BIGINT PRIMARY KEY,
+    id           UUID NOT NULL,
+    prev_hash    BYTEA NOT NULL,
+    content_hash BYTEA NOT NULL,
+    entry_hash   BYTEA NOT NULL,
+    pruned_at    TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE TRIGGER audit_tombstones_append_only
+    BEFORE UPDATE OR DELETE ON audit_tombstones
+    FOR EACH ROW EXECUTE FUNCTION audit_append_only();
+
+CREATE TRIGGER audit_tombstones_no_truncate
+    BEFORE TRUNCATE ON audit_tombstones
+    FOR EACH STATEMENT EXECUTE FUNCTION audit_append_only();
+
+CREATE FUNCTION audit_delete_tombstoned() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'DELETE' AND EXISTS (
+        SELECT 1 FROM audit_tombstones t
+        WHERE t.audit_seq = OLD.audit_seq AND t.entry_hash = OLD.entry_hash
+    ) THEN
+        RETURN OLD;
+    END IF;
+    RAISE EXCEPTION 'audit log table % is append-only', TG_TABLE_NAME;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER verifications_append_only ON verifications;
+
+CREATE TRIGGER verifications_append_only
+    BEFORE UPDATE OR DELETE ON verifications
+    FOR EACH ROW EXECUTE FUNCTION audit_delete_tombstoned();
+
+CREATE INDEX verifications_time_idx ON verifications (verified_at);
+CREATE INDEX messages_read_idx ON messages (read_at) WHERE read_at IS NOT NULL;
+CREATE INDEX users_located_activity_idx ON users (last_active) WHERE location_hash IS NOT NULL;
@@ -0,0 +1,37 @@
+-- SPDX-License-Identifier: MPL-2.0
+-- SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+--
+-- Duress mode
+--
+-- A second password that logs in to a sanitized view of the account and
+-- ends every other session. `sessions_valid_after` revokes sessions
+-- issued before it; `duress_at` marks the account as under duress until
+-- the real password is used again. `pending_duress` records which
+-- password began a login still waiting on its second factor, so neither
+-- password changes anything until that factor succeeds.
+
+ALTER TABLE users ADD COLUMN duress_password_hash TEXT;
+ALTER TABLE users ADD COLUMN duress_notify BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE users ADD COLUMN duress_at TIMESTAMPTZ;
+ALTER TABLE users ADD COLUMN sessions_valid_after TIMESTAMPTZ;
+ALTER TABLE users ADD COLUMN pending_duress BOOLEAN;
+
+CREATE TABLE trusted_contacts (
+    user_id    UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    contact_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (user_id, contact_id),
+    CHECK (user_id <> contact_id)
+);
+
+CREATE INDEX trusted_contacts_contact_idx ON trusted_contacts (contact_id);
+
+-- Alerts a contact can see in the app, whether or not they get email
+CREATE TABLE duress_alerts (
+    id         UUID PRIMARY KEY,
+    contact_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    user_id    UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    raised_at  TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX duress_alerts_contact_idx ON duress_alerts (contact_id, raised_at DESC);
@@ -0,0 +1,824 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Moderation endpoints
+//!
+//! Moderators work the queues of verifications held by fraud screening
+//! and of abuse reports, and hide or restore events. Admins can also suspend users, reverse XP and
+//! grant roles.
+//!
+//! Every action takes a reason and is appended to the audit chain in the
+//! same transaction as the change it makes, so there is no moderation
+//! without a record of who did it and why.
+
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+use validator::Validate;
+
+use super::extract::Staff;
+use crate::db::{
+    self,
+    audit::{NewAdminAction, NewLevelProgression},
+    models::{AdminAction, Report, ReportStatus, Role, Verification},
+    reports::QueuedReport,
+};
+use crate::error::{ApiError, Result};
+use crate::federation;
+use crate::leveling;
+use crate::realtime::EventUpdate;
+use crate::state::AppState;
+use crate::verification::{self, XP_EVENT_ATTENDANCE};
+use crate::webhooks::{self, WebhookEvent};
+
+/// Most held verifications returned at once
+const MAX_QUEUE_PAGE: i64 = 100;
+
+/// Why a moderator acted
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct ReasonRequest {
+    #[validate(length(min = 1, max = 500))]
+    pub reason: String,
+}
+
+/// Outcome of reviewing a held verification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewDecision {
+    /// Attendance was genuine; award the XP
+    Release,
+    /// Attendance was not genuine; no XP
+    Reject,
+}
+
+/// Review of a held verification
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct ReviewRequest {
+    pub decision: ReviewDecision,
+    #[validate(length(min = 1, max = 500))]
+    pub reason: String,
+}
+
+/// XP to take back from a user
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct ReverseXpRequest {
+    #[validate(range(min = 1))]
+    pub amount: i32,
+    #[validate(length(min = 1, max = 500))]
+    pub reason: String,
+    /// Verification the XP was earned by, if reversing one
+    pub verification_id: Option<Uuid>,
+}
+
+/// New moderation role for a user
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct SetRoleRequest {
+    pub role: Role,
+    #[validate(length(min = 1, max = 500))]
+    pub reason: String,
+}
+
+/// New triage state for a report
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct TriageRequest {
+    pub status: ReportStatus,
+    #[validate(length(min = 1, max = 500))]
+    pub reason: String,
+}
+
+/// Paging for the review queue
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct QueueQuery {
+    #[serde(default = "default_queue_limit")]
+    pub limit: i64,
+}
+
+/// Filter and paging for the report queue
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ReportQueueQuery {
+    #[serde(default = "default_report_status")]
+    pub status: ReportStatus,
+    #[serde(default = "default_queue_limit")]
+    pub limit: i64,
+}
+
+const fn default_report_status() -> ReportStatus {
+    ReportStatus::Open
+}
+
+const fn default_queue_limit() -> i64 {
+    50
+}
+
+/// A verification awaiting review
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HeldVerification {
+    pub id: Uuid,
+    pub event_id: Uuid,
+    pub user_id: Uuid,
+    pub organizer_id: Uuid,
+    pub verified_at: DateTime<Utc>,
+    pub fraud_score: i16,
+    pub fraud_findings: serde_json::Value,
+}
+
+impl From<Verification> for HeldVerification {
+    fn from(v: Verification) -> Self {
+        Self {
+            id: v.id,
+            event_id: v.event_id,
+            user_id: v.user_id,
+            organizer_id: v.organizer_id,
+            verified_at: v.verified_at,
+            fraud_score: v.fraud_score,
+            fraud_findings: v.fraud_findings,
+        }
+    }
+}
+
+/// A report, as moderators see it
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReportView {
+    #[serde(flatten)]
+    pub report: Report,
+    pub reports_on_target: i64,
+}
+
+impl From<QueuedReport> for ReportView {
+    fn from(q: QueuedReport) -> Self {
+        Self {
+            report: q.report,
+            reports_on_target: q.reports_on_target,
+        }
+    }
+}
+
+/// Record of a moderation action
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ActionResponse {
+    pub action_id: Uuid,
+    pub action: String,
+    pub target_id: Uuid,
+    pub acted_at: DateTime<Utc>,
+}
+
+impl From<AdminAction> for ActionResponse {
+    fn from(a: AdminAction) -> Self {
+        Self {
+            action_id: a.id,
+            action: a.action,
+            target_id: a.target_id,
+            acted_at: a.acted_at,
+        }
+    }
+}
+
+/// Verifications held by fraud screening, oldest first
+/// GET /api/v1/admin/verifications/held
+///
+/// # Errors
+///
+/// Returns [`ApiError::Forbidden`] unless the caller is staff, or an error if
+/// the query fails.
+#[utoipa::path(
+    get,
+    path = "/admin/verifications/held",
+    tag = "admin",
+    params(
+        QueueQuery,
+    ),
+    responses(
+        (status = 200, description = "Held verifications, oldest first", body = Vec<HeldVerification>),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 403, description = "Not allowed", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn held_verifications(
+    State(state): State<AppState>,
+    _staff: Staff,
+    Query(query): Query<QueueQuery>,
+) -> Result<Json<Vec<HeldVerification>>> {
+    let held =
+        db::verifications::awaiting_review(&state.db, query.limit.clamp(1, MAX_QUEUE_PAGE)).await?;
+
+    Ok(Json(held.into_iter().map(HeldVerification::from).collect()))
+}
+
+/// Release or reject a held verification
+/// POST /api/v1/admin/verifications/:id/review
+///
+/// Releasing awards the attendance XP that was withheld.
+///
+/// # Errors
+///
+/// Returns [`ApiError::VerificationNotFound`], [`ApiError::InvalidInput`] if
+/// the verification isn't held, is the caller's own or was already reviewed,
+/// [`ApiError::Forbidden`] unless the caller is staff, or an error if a query
+/// fails.
+#[utoipa::path(
+    post,
+    path = "/admin/verifications/{id}/review",
+    tag = "admin",
+    request_body = ReviewRequest,
+    params(
+        ("id" = Uuid, Path, description = "Verification ID"),
+    ),
+    responses(
+        (status = 200, description = "Recorded action", body = ActionResponse),
+        (status = 400, description = "Invalid input", body = crate::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 403, description = "Not allowed", body = crate::error::ErrorResponse),
+        (status = 404, description = "No such held verification", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn review_verification(
+    State(state): State<AppState>,
+    staff: Staff,
+    Path(id): Path<Uuid>,
+    Json(req): Json<ReviewRequest>,
+) -> Result<Json<ActionResponse>> {
+    req.validate()
+        .map_err(|e| ApiError::InvalidInput(e.to_string()))?;
+
+    let verification = db::verifications::find_by_id(&state.db, id)
+        .await?
+        .ok_or(ApiError::VerificationNotFound)?;
+    if verification.status != Verification::HELD_FOR_REVIEW {
+        return Err(ApiError::InvalidInput(
+            "Verification is not held for review".into(),
+        ));
+    }
+    if verification.user_id == staff.id {
+        return Err(ApiError::InvalidInput(
+            "Staff cannot review their own verifications".into(),
+        ));
+    }
+
+    let (action, xp) = match req.decision {
+        ReviewDecision::Release => (AdminAction::RELEASE_VERIFICATION, XP_EVENT_ATTENDANCE),
+        ReviewDecision::Reject => (AdminAction::REJECT_VERIFICATION, 0),
+    };
+
+    let mut tx = state.db.begin().await?;
+    let recorded = db::audit::append_admin_action(
+        &mut tx,
+        NewAdminAction {
+            actor_id: staff.id,
+            action,
+            target_id: verification.id,
+            reason: &req.reason,
+            metadata: json!({ "user_id": verification.user_id, "xp_awarded": xp }),
+        },
+    )
+    .await
+    .map_err(|e| match e {
+        ApiError::Database(ref err) if db::is_unique_violation(err) => {
+            ApiError::InvalidInput("Verification has already been reviewed".into())
+        }
+        other => other,
+    })?;
+
+    if xp > 0 {
+        db::users::add_experience(&mut tx, verification.user_id, xp).await?;
+        webhooks::queue(
+            &mut tx,
+            &WebhookEvent::VerificationAccepted {
+                event_id: verification.event_id,
+                verification_id: verification.id,
+                user_id: verification.user_id,
+            },
+        )
+        .await?;
+    }
+    tx.commit().await?;
+
+    tracing::info!(
+        actor_id = %staff.id,
+        verification_id = %verification.id,
+        action,
+        "Held verification reviewed"
+    );
+    match req.decision {
+        ReviewDecision::Release => state.metrics.verification("released", "review"),
+        ReviewDecision::Reject => state.metrics.verification("rejected", "review"),
+    }
+    state.metrics.xp_awarded(xp);
+    if xp > 0 {
+        verification::publish_attendance(&state, verification.event_id).await;
+    }
+
+    Ok(Json(recorded.into()))
+}
+
+/// Hide an event from listings
+/// POST /api/v1/admin/events/:id/hide
+///
+/// # Errors
+///
+/// Returns [`ApiError::EventNotFound`], [`ApiError::Forbidden`] unless the
+/// caller is staff, or an error if a query fails.
+#[utoipa::path(
+    post,
+    path = "/admin/events/{id}/hide",
+    tag = "admin",
+    request_body = ReasonRequest,
+    params(
+        ("id" = Uuid, Path, description = "Event ID"),
+    ),
+    responses(
+        (status = 200, description = "Recorded action", body = ActionResponse),
+        (status = 400, description = "Invalid input", body = crate::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 403, description = "Not allowed", body = crate::error::ErrorResponse),
+        (status = 404, description = "No such event", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn hide_event(
+    State(state): State<AppState>,
+    staff: Staff,
+    Path(id): Path<Uuid>,
+    Json(req): Json<ReasonRequest>,
+) -> Result<Json<ActionResponse>> {
+    set_event_hidden(&state, staff, id, &req, true).await
+}
+
+/// Make a hidden event visible again
+/// POST /api/v1/admin/events/:id/restore
+///
+/// # Errors
+///
+/// Returns [`ApiError::EventNotFound`], [`ApiError::Forbidden`] unless the
+/// caller is staff, or an error if a query fails.
+#[utoipa::path(
+    post,
+    path = "/admin/events/{id}/restore",
+    tag = "admin",
+    request_body = ReasonRequest,
+    params(
+        ("id" = Uuid, Path, description = "Event ID"),
+    ),
+    responses(
+        (status = 200, description = "Recorded action", body = ActionResponse),
+        (status = 400, description = "Invalid input", body = crate::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 403, description = "Not allowed", body = crate::error::ErrorResponse),
+        (status = 404, description = "No such event", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn restore_event(
+    State(state): State<AppState>,
+    staff: Staff,
+    Path(id): Path<Uuid>,
+    Json(req): Json<ReasonRequest>,
+) -> Result<Json<ActionResponse>> {
+    set_event_hidden(&state, staff, id, &req, false).await
+}
+
+async fn set_event_hidden(
+    state: &AppState,
+    staff: Staff,
+    id: Uuid,
+    req: &ReasonRequest,
+    hidden: bool,
+) -> Result<Json<ActionResponse>> {
+    req.validate()
+        .map_err(|e| ApiError::InvalidInput(e.to_string()))?;
+
+    let mut tx = state.db.begin().await?;
+    let event = db::events::set_hidden(&mut tx, id, hidden)
+        .await?
+        .ok_or(ApiError::EventNotFound)?;
+
+    let action = if hidden {
+        AdminAction::HIDE_EVENT
+    } else {
+        AdminAction::RESTORE_EVENT
+    };
+    let recorded = db::audit::append_admin_action(
+        &mut tx,
+        NewAdminAction {
+            actor_id: staff.id,
+            action,
+            target_id: id,
+            reason: &req.reason,
+            metadata: json!({}),
+        },
+    )
+    .await?;
+    if hidden {
+        webhooks::queue(&mut tx, &WebhookEvent::EventHidden { event_id: id }).await?;
+    }
+    tx.commit().await?;
+
+    tracing::info!(actor_id = %staff.id, event_id = %id, action, "Event moderated");
+    if hidden {
+        state
+            .realtime
+            .publish(EventUpdate::Cancelled { event_id: id })
+            .await;
+        federation::retract_event(state, event.organizer_id, id);
+    } else {
+        federation::publish_event(state, id).await?;
+    }
+
+    Ok(Json(recorded.into()))
+}
+
+/// Suspend a user, ending their sessions
+/// POST /api/v1/admin/users/:id/suspend
+///
+/// # Errors
+///
+/// Returns [`ApiError::UserNotFound`], [`ApiError::InvalidInput`] for the
+/// caller's own account, [`ApiError::Forbidden`] unless the caller is an admin,
+/// or an error if a query fails.
+#[utoipa::path(
+    post,
+    path = "/admin/users/{id}/suspend",
+    tag = "admin",
+    request_body = ReasonRequest,
+    params(
+        ("id" = Uuid, Path, description = "User ID"),
+    ),
+    responses(
+        (status = 200, description = "Recorded action", body = ActionResponse),
+        (status = 400, description = "Invalid input", body = crate::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 403, description = "Not allowed", body = crate::error::ErrorResponse),
+        (status = 404, description = "No such user", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn suspend_user(
+    State(state): State<AppState>,
+    staff: Staff,
+    Path(id): Path<Uuid>,
+    Json(req): Json<ReasonRequest>,
+) -> Result<Json<ActionResponse>> {
+    set_user_suspended(&state, staff, id, &req, true).await
+}
+
+/// Lift a user's suspension
+/// POST /api/v1/admin/users/:id/unsuspend
+///
+/// # Errors
+///
+/// Returns [`ApiError::UserNotFound`], [`ApiError::InvalidInput`] for the
+/// caller's own account, [`ApiError::Forbidden`] unless the caller is an admin,
+/// or an error if a query fails.
+#[utoipa::path(
+    post,
+    path = "/admin/users/{id}/unsuspend",
+    tag = "admin",
+    request_body = ReasonRequest,
+    params(
+        ("id" = Uuid, Path, description = "User ID"),
+    ),
+    responses(
+        (status = 200, description = "Recorded action", body = ActionResponse),
+        (status = 400, description = "Invalid input", body = crate::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 403, description = "Not allowed", body = crate::error::ErrorResponse),
+        (status = 404, description = "No such user", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn unsuspend_user(
+    State(state): State<AppState>,
+    staff: Staff,
+    Path(id): Path<Uuid>,
+    Json(req): Json<ReasonRequest>,
+) -> Result<Json<ActionResponse>> {
+    set_user_suspended(&state, staff, id, &req, false).await
+}
+
+async fn set_user_suspended(
+    state: &AppState,
+    staff: Staff,
+    id: Uuid,
+    req: &ReasonRequest,
+    suspended: bool,
+) -> Result<Json<ActionResponse>> {
+    staff.require(Role::Admin)?;
+    req.validate()
+        .map_err(|e| ApiError::InvalidInput(e.to_string()))?;
+    if id == staff.id {
+        return Err(ApiError::InvalidInput(
+            "Admins cannot suspend themselves".into(),
+        ));
+    }
+
+    let mut tx = state.db.begin().await?;
+    if !db::users::set_suspended(&mut tx, id, suspended).await? {
+        return Err(ApiError::UserNotFound);
+    }
+
+    let action = if suspended {
+        AdminAction::SUSPEND_USER
+    } else {
+        AdminAction::UNSUSPEND_USER
+    };
+    let recorded = db::audit::append_admin_action(
+        &mut tx,
+        NewAdminAction {
+            actor_id: staff.id,
+            action,
+            target_id: id,
+            reason: &req.reason,
+            metadata: json!({}),
+        },
+    )
+    .await?;
+    tx.commit().await?;
+
+    tracing::info!(actor_id = %staff.id, user_id = %id, action, "User moderated");
+
+    Ok(Json(recorded.into()))
+}
+
+/// Take XP back from a user
+/// POST /api/v1/admin/users/:id/xp-reversals
+///
+/// XP never goes below zero. If what remains no longer supports the
+/// user's level, they are demoted; either way the change is recorded as
+/// a level progression alongside the admin action.
+///
+/// # Errors
+///
+/// Returns [`ApiError::UserNotFound`], [`ApiError::InvalidInput`],
+/// [`ApiError::Forbidden`] unless the caller is an admin, or an error if a
+/// query fails.
+#[utoipa::path(
+    post,
+    path = "/admin/users/{id}/xp-reversals",
+    tag = "admin",
+    request_body = ReverseXpRequest,
+    params(
+        ("id" = Uuid, Path, description = "User ID"),
+    ),
+    responses(
+        (status = 200, description = "Recorded action", body = ActionResponse),
+        (status = 400, description = "Invalid input", body = crate::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 403, description = "Not allowed", body = crate::error::ErrorResponse),
+        (status = 404, description = "No such user", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn reverse_experience(
+    State(state): State<AppState>,
+    staff: Staff,
+    Path(id): Path<Uuid>,
+    Json(req): Json<ReverseXpRequest>,
+) -> Result<Json<ActionResponse>> {
+    staff.require(Role::Admin)?;
+    req.validate()
+        .map_err(|e| ApiError::InvalidInput(e.to_string()))?;
+
+    let mut tx = state.db.begin().await?;
+    let user = db::users::find_for_update(&mut tx, id)
+        .await?
+        .ok_or(ApiError::UserNotFound)?;
+
+    let xp_after = user.experience_points.saturating_sub(req.amount).max(0);
+    let level_after = user.current_level.min(leveling::max_level_for(xp_after));
+
+    let progression = db::audit::append_level_progression(
+        &mut tx,
+        NewLevelProgression {
+            user_id: id,
+            from_level: user.current_level,
+            to_level: level_after,
+            reason: "xp_reversal",
+            metadata: json!({
+                "xp_before": user.experience_points,
+                "xp_after": xp_after,
+                "verification_id": req.verification_id,
+            }),
+        },
+    )
+    .await?;
+    db::users::set_progress(&mut tx, id, level_after, xp_after).await?;
+
+    let recorded = db::audit::append_admin_action(
+        &mut tx,
+        NewAdminAction {
+            actor_id: staff.id,
+            action: AdminAction::REVERSE_XP,
+            target_id: id,
+            reason: &req.reason,
+            metadata: json!({
+                "amount": user.experience_points - xp_after,
+                "level_progression_id": progression.id,
+                "verification_id": req.verification_id,
+            }),
+        },
+    )
+    .await?;
+    tx.commit().await?;
+
+    tracing::info!(
+        actor_id = %staff.id,
+        user_id = %id,
+        from_level = user.current_level,
+        to_level = level_after,
+        "XP reversed"
+    );
+
+    Ok(Json(recorded.into()))
+}
+
+/// Grant or revoke a moderation role
+/// PUT /api/v1/admin/users/:id/role
+///
+/// Moderators must have reached organizer level.
+///
+/// # Errors
+///
+/// Returns [`ApiError::UserNotFound`], [`ApiError::InvalidInput`] for the
+/// caller's own role or a moderator below organizer level,
+/// [`ApiError::Forbidden`] unless the caller is an admin, or an error if a
+/// query fails.
+#[utoipa::path(
+    put,
+    path = "/admin/users/{id}/role",
+    tag = "admin",
+    request_body = SetRoleRequest,
+    params(
+        ("id" = Uuid, Path, description = "User ID"),
+    ),
+    responses(
+        (status = 200, description = "Recorded action", body = ActionResponse),
+        (status = 400, description = "Invalid input", body = crate::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 403, description = "Not allowed", body = crate::error::ErrorResponse),
+        (status = 404, description = "No such user", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn set_role(
+    State(state): State<AppState>,
+    staff: Staff,
+    Path(id): Path<Uuid>,
+    Json(req): Json<SetRoleRequest>,
+) -> Result<Json<ActionResponse>> {
+    staff.require(Role::Admin)?;
+    req.validate()
+        .map_err(|e| ApiError::InvalidInput(e.to_string()))?;
+    if id == staff.id {
+        return Err(ApiError::InvalidInput(
+            "Admins cannot change their own role".into(),
+        ));
+    }
+
+    let mut tx = state.db.begin().await?;
+    let user = db::users::find_for_update(&mut tx, id)
+        .await?
+        .ok_or(ApiError::UserNotFound)?;
+
+    if req.role == Role::Moderator && user.current_level < leveling::ORGANIZER_LEVEL {
+        return Err(ApiError::InvalidInput(format!(
+            "Moderators must be at least level {}",
+            leveling::ORGANIZER_LEVEL
+        )));
+    }
+
+    db::users::set_role(&mut tx, id, req.role).await?;
+    let recorded = db::audit::append_admin_action(
+        &mut tx,
+        NewAdminAction {
+            actor_id: staff.id,
+            action: AdminAction::SET_ROLE,
+            target_id: id,
+            reason: &req.reason,
+            metadata: json!({ "from": user.role, "to": req.role }),
+        },
+    )
+    .await?;
+    tx.commit().await?;
+
+    tracing::info!(actor_id = %staff.id, user_id = %id, role = ?req.role, "Role changed");
+
+    Ok(Json(recorded.into()))
+}
+
+/// Abuse reports in a given state, most-reported targets first
+/// GET /api/v1/admin/reports
+///
+/// # Errors
+///
+/// Returns [`ApiError::Forbidden`] unless the caller is staff, or an error if
+/// the query fails.
+#[utoipa::path(
+    get,
+    path = "/admin/reports",
+    tag = "admin",
+    params(
+        ReportQueueQuery,
+    ),
+    responses(
+        (status = 200, description = "Reports, oldest first", body = Vec<ReportView>),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 403, description = "Not allowed", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn report_queue(
+    State(state): State<AppState>,
+    _staff: Staff,
+    Query(query): Query<ReportQueueQuery>,
+) -> Result<Json<Vec<ReportView>>> {
+    let reports = db::reports::queue(
+        &state.db,
+        query.status,
+        query.limit.clamp(1, MAX_QUEUE_PAGE),
+    )
+    .await?;
+
+    Ok(Json(reports.into_iter().map(ReportView::from).collect()))
+}
+
+/// Move a report through triage
+/// POST /api/v1/admin/reports/:id/status
+///
+/// Reports go open -> triaged -> actioned or dismissed, and may skip
+/// triage. Acting on the target (hiding an event, suspending a user) is a
+/// separate action.
+///
+/// # Errors
+///
+/// Returns [`ApiError::ReportNotFound`], [`ApiError::InvalidInput`] for a move
+/// triage doesn't allow or a concurrent update, [`ApiError::Forbidden`] unless
+/// the caller is staff, or an error if a query fails.
+#[utoipa::path(
+    post,
+    path = "/admin/reports/{id}/status",
+    tag = "admin",
+    request_body = TriageRequest,
+    params(
+        ("id" = Uuid, Path, description = "Report ID"),
+    ),
+    responses(
+        (status = 200, description = "Recorded action", body = ActionResponse),
+        (status = 400, description = "Invalid input", body = crate::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 403, description = "Not allowed", body = crate::error::ErrorResponse),
+        (status = 404, description = "No such report", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn triage_report(
+    State(state): State<AppState>,
+    staff: Staff,
+    Path(id): Path<Uuid>,
+    Json(req): Json<TriageRequest>,
+) -> Result<Json<ActionResponse>> {
+    req.validate()
+        .map_err(|e| ApiError::InvalidInput(e.to_string()))?;
+
+    let report = db::reports::find_by_id(&state.db, id)
+        .await?
+        .ok_or(ApiError::ReportNotFound)?;
+    if !report.status.can_become(req.status) {
+        return Err(ApiError::InvalidInput(format!(
+            "A {:?} report cannot become {:?}",
+            report.status, req.status
+        )));
+    }
+
+    let mut tx = state.db.begin().await?;
+    if !db::reports::set_status(&mut tx, id, report.status, req.status).await? {
+        return Err(ApiError::InvalidInput(
+            "Report was updated by someone else; reload and try again".into(),
+        ));
+    }
+
+    let recorded = db::audit::append_admin_action(
+        &mut tx,
+        NewAdminAction {
+            actor_id: staff.id,
+            action: AdminAction::TRIAGE_REPORT,
+            target_id: id,
+            reason: &req.reason,
+            metadata: json!({ "from": report.status, "to": req.status }),
+        },
+    )
+    .await?;
+    tx.commit().await?;
+
+    tracing::info!(actor_id = %staff.id, report_id = %id, status = ?req.status, "Report triaged");
+
+    Ok(Json(recorded.into()))
+}
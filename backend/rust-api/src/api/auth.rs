@@ -4,78 +4,343 @@
 //! Authentication endpoints
 //!
 //! Security considerations:
-//! - Passwords hashed with Argon2
+//! - Passwords hashed with Argon2id (CPR-001), upgraded on login
+//! - Email kept only as a keyed blind index, plus an encrypted copy if
+//!   the user opts in to account recovery
 //! - JWT tokens with 24-hour expiry
-//! - Rate limiting on login attempts
+//! - Passkey or TOTP second factor; passkeys mandatory from a configured level
+//! - Optional duress password that logs in to a sanitized view
 //! - No PII in logs
 
-use axum::Json;
+use axum::{extract::State, Json};
+use chrono::Duration;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
 use validator::Validate;
 
+use crate::crypto::{
+    jwt::{self, AuthMethod, TokenScope},
+    password::PasswordCheck,
+};
+use crate::db::{self, models::User};
+use crate::duress::{self, LoginPassword};
 use crate::error::{ApiError, Result};
+use crate::state::AppState;
+use crate::telemetry::redact::Redacted;
 
 /// Registration request
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct RegisterRequest {
     #[validate(email)]
-    pub email: String,
+    #[schema(value_type = String)]
+    pub email: Redacted<String>,
     #[validate(length(min = 3, max = 64))]
     pub username: String,
     #[validate(length(min = 12))]
-    pub password: String,
+    #[schema(value_type = String)]
+    pub password: Redacted<String>,
+    /// Store the email (encrypted) so a forgotten password can be reset
+    #[serde(default)]
+    pub enable_recovery: bool,
 }
 
 /// Login request
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct LoginRequest {
     #[validate(email)]
-    pub email: String,
-    pub password: String,
+    #[schema(value_type = String)]
+    pub email: Redacted<String>,
+    #[schema(value_type = String)]
+    pub password: Redacted<String>,
 }
 
+/// Lifetime of the token between the password and second-factor steps
+const MFA_TOKEN_TTL_MINUTES: i64 = 5;
+
+/// Lifetime of the token that only allows second-factor enrollment
+const ENROLLMENT_TOKEN_TTL_MINUTES: i64 = 15;
+
 /// Authentication response with JWT token
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct AuthResponse {
-    pub token: String,
+    #[schema(value_type = String)]
+    pub token: Redacted<String>,
     pub user_id: String,
     pub username: String,
     pub level: u8,
 }
 
+impl AuthResponse {
+    /// Issue a session token for a user
+    pub(crate) fn for_user(state: &AppState, user: User, amr: Vec<AuthMethod>) -> Result<Self> {
+        if user.suspended_at.is_some() {
+            return Err(ApiError::AccountSuspended);
+        }
+
+        let token = jwt::issue_token(
+            user.id,
+            TokenScope::Session,
+            amr,
+            state.config.jwt_secret.as_bytes(),
+            Duration::hours(state.config.token_ttl_hours),
+        )?;
+
+        Ok(Self {
+            token: token.into(),
+            user_id: user.id.to_string(),
+            username: user.username,
+            level: u8::try_from(user.current_level).unwrap_or_default(),
+        })
+    }
+}
+
+/// Second factors offered after the password step
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MfaMethod {
+    Webauthn,
+    Totp,
+}
+
+/// Login outcome
+///
+/// A password alone yields a session only for users without a second
+/// factor, and below the level at which one is required.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum LoginResponse {
+    /// Logged in
+    Authenticated(AuthResponse),
+    /// Password accepted; complete a second factor with `mfa_token`
+    MfaRequired {
+        mfa_required: bool,
+        #[schema(value_type = String)]
+        mfa_token: Redacted<String>,
+        methods: Vec<MfaMethod>,
+    },
+    /// Password accepted, but policy requires enrolling a second factor
+    /// first; `enrollment_token` is accepted only by enrollment endpoints
+    EnrollmentRequired {
+        enrollment_required: bool,
+        #[schema(value_type = String)]
+        enrollment_token: Redacted<String>,
+        methods: Vec<MfaMethod>,
+    },
+}
+
 /// Register a new user
 /// POST /api/v1/auth/register
-pub async fn register(Json(req): Json<RegisterRequest>) -> Result<Json<AuthResponse>> {
+///
+/// # Errors
+///
+/// Returns [`ApiError::InvalidInput`] for invalid input or when account
+/// recovery is not enabled, [`ApiError::AlreadyRegistered`] if the email or
+/// username is taken, or an error if hashing or a query fails.
+#[utoipa::path(
+    post,
+    path = "/auth/register",
+    tag = "auth",
+    request_body = RegisterRequest,
+    responses(
+        (status = 200, description = "Registered and logged in", body = AuthResponse),
+        (status = 400, description = "Invalid input", body = crate::error::ErrorResponse),
+        (status = 409, description = "Email or username already registered", body = crate::error::ErrorResponse),
+    ),
+)]
+pub async fn register(
+    State(state): State<AppState>,
+    Json(req): Json<RegisterRequest>,
+) -> Result<Json<AuthResponse>> {
     // Validate input
     req.validate()
         .map_err(|e| ApiError::InvalidInput(e.to_string()))?;
 
-    // TODO: Check if email already exists
-    // TODO: Hash password with Argon2
-    // TODO: Create user in database
-    // TODO: Generate JWT token
-
-    // Placeholder response
-    Ok(Json(AuthResponse {
-        token: "placeholder_token".to_string(),
-        user_id: "placeholder_id".to_string(),
-        username: req.username,
-        level: 0,
-    }))
+    let id = Uuid::new_v4();
+    let email_candidates = state.email_index.candidates(req.email.expose());
+    let email_encrypted = if req.enable_recovery {
+        let vault = state.email_vault.as_ref().ok_or_else(|| {
+            ApiError::InvalidInput("Account recovery is not enabled on this server".into())
+        })?;
+        Some(vault.encrypt(id, req.email.expose())?)
+    } else {
+        None
+    };
+    let password_hash = state.passwords.hash(req.password.into_inner()).await?;
+
+    // The email is checked under every key version, and uniqueness of the
+    // current one is enforced by the database, so neither a key rotation
+    // nor concurrent registrations can give one email two accounts
+    let user = db::users::insert(
+        &state.db,
+        db::users::NewUser {
+            id,
+            email_candidates: &email_candidates,
+            email_encrypted: email_encrypted.as_deref(),
+            username: &req.username,
+            password_hash: &password_hash,
+        },
+    )
+    .await
+    .map_err(|e| match e {
+        ApiError::Database(ref err) if db::is_unique_violation(err) => ApiError::AlreadyRegistered,
+        other => other,
+    })?
+    .ok_or(ApiError::AlreadyRegistered)?;
+
+    tracing::info!(user_id = %user.id, "User registered");
+
+    Ok(Json(AuthResponse::for_user(
+        &state,
+        user,
+        vec![AuthMethod::Password],
+    )?))
 }
 
 /// Login existing user
 /// POST /api/v1/auth/login
-pub async fn login(Json(req): Json<LoginRequest>) -> Result<Json<AuthResponse>> {
+///
+/// If the stored hash predates the current Argon2id policy, it is
+/// replaced in the background with a fresh hash of the supplied password.
+///
+/// Users with a second factor get a short-lived MFA token instead of a
+/// session, to exchange at the endpoint for one of the listed methods.
+///
+/// The duress password is accepted in place of the password, with the
+/// same response and timing (see [`crate::duress`]).
+///
+/// # Errors
+///
+/// Returns [`ApiError::InvalidCredentials`] for an unknown email or wrong
+/// password, [`ApiError::AccountSuspended`] for a suspended account,
+/// [`ApiError::InvalidInput`] for invalid input, or an error if hashing,
+/// signing or a query fails.
+#[utoipa::path(
+    post,
+    path = "/auth/login",
+    tag = "auth",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "A session, or the second factor still needed", body = LoginResponse),
+        (status = 400, description = "Invalid input", body = crate::error::ErrorResponse),
+        (status = 401, description = "Invalid credentials", body = crate::error::ErrorResponse),
+        (status = 429, description = "Too many attempts", body = crate::error::ErrorResponse),
+    ),
+)]
+pub async fn login(
+    State(state): State<AppState>,
+    Json(req): Json<LoginRequest>,
+) -> Result<Json<LoginResponse>> {
     // Validate input
     req.validate()
         .map_err(|e| ApiError::InvalidInput(e.to_string()))?;
 
-    // TODO: Look up user by email hash
-    // TODO: Verify password with Argon2
-    // TODO: Generate JWT token
-    // TODO: Update last_active timestamp
+    let Some(user) =
+        db::users::find_by_email(&state.db, &state.email_index, req.email.expose()).await?
+    else {
+        // Same work as a real check, so timing doesn't reveal membership
+        duress::check_unknown(&state.passwords, req.password.expose()).await?;
+        return Err(ApiError::InvalidCredentials);
+    };
+
+    let password = duress::check(&state.passwords, &user, req.password.expose()).await?;
+    if password == LoginPassword::Mismatch {
+        return Err(ApiError::InvalidCredentials);
+    }
+
+    // Before any second factor, so suspended users get no tokens at all
+    if user.suspended_at.is_some() {
+        return Err(ApiError::AccountSuspended);
+    }
+
+    // Off the login path, so the extra hash doesn't set the real password
+    // apart by timing
+    if password == LoginPassword::Real(PasswordCheck::ValidNeedsRehash) {
+        tokio::spawn(upgrade_hash(
+            state.clone(),
+            user.id,
+            req.password.into_inner(),
+        ));
+    }
+
+    let secret = state.config.jwt_secret.as_bytes();
+    let passkey_required = requires_passkey(&state, &user);
+
+    let mut methods = Vec::new();
+    if db::webauthn::has_passkey(&state.db, user.id).await? {
+        methods.push(MfaMethod::Webauthn);
+    }
+    // TOTP alone doesn't satisfy the passkey requirement
+    if !passkey_required && db::totp::is_enabled(&state.db, user.id).await? {
+        methods.push(MfaMethod::Totp);
+    }
+
+    if passkey_required && !methods.contains(&MfaMethod::Webauthn) {
+        // No second factor yet, so the password alone decides; a duress
+        // token can't enroll one
+        duress::record_login(&state, &user, password).await?;
+        let enrollment_token = jwt::issue_token(
+            user.id,
+            TokenScope::Enrollment,
+            vec![AuthMethod::Password],
+            secret,
+            Duration::minutes(ENROLLMENT_TOKEN_TTL_MINUTES),
+        )?;
+
+        return Ok(Json(LoginResponse::EnrollmentRequired {
+            enrollment_required: true,
+            enrollment_token: enrollment_token.into(),
+            methods: vec![MfaMethod::Webauthn],
+        }));
+    }
+
+    if !methods.is_empty() {
+        // Duress mode waits for the second factor
+        duress::begin_login(&state, &user, password).await?;
+        let mfa_token = jwt::issue_token(
+            user.id,
+            TokenScope::MfaPending,
+            vec![AuthMethod::Password],
+            secret,
+            Duration::minutes(MFA_TOKEN_TTL_MINUTES),
+        )?;
+
+        return Ok(Json(LoginResponse::MfaRequired {
+            mfa_required: true,
+            mfa_token: mfa_token.into(),
+            methods,
+        }));
+    }
+
+    duress::record_login(&state, &user, password).await?;
+    db::users::touch_last_active(&state.db, user.id).await?;
+
+    Ok(Json(LoginResponse::Authenticated(AuthResponse::for_user(
+        &state,
+        user,
+        vec![AuthMethod::Password],
+    )?)))
+}
+
+/// Whether policy requires this user to log in with a passkey, so no
+/// other second factor will do
+pub(crate) fn requires_passkey(state: &AppState, user: &User) -> bool {
+    state
+        .config
+        .webauthn_required_level
+        .is_some_and(|level| user.current_level >= level)
+}
 
-    // Placeholder - would return InvalidCredentials for bad login
-    Err(ApiError::InvalidCredentials)
+/// Rehash a password under the current policy
+async fn upgrade_hash(state: AppState, user_id: Uuid, password: String) {
+    let upgraded = match state.passwords.hash(password).await {
+        Ok(hash) => db::users::update_password_hash(&state.db, user_id, &hash).await,
+        Err(e) => Err(e),
+    };
+    match upgraded {
+        Ok(()) => tracing::info!(user_id = %user_id, "Password hash upgraded to current policy"),
+        Err(e) => {
+            tracing::error!(error = ?e, user_id = %user_id, "Failed to upgrade password hash");
+        }
+    }
 }
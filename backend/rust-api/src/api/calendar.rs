@@ -0,0 +1,132 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Personal calendar feeds
+//!
+//! A feed is an iCalendar URL listing the events a user RSVP'd to, for
+//! subscribing from a calendar app. Those apps can't log in, so the URL
+//! carries a secret token; the token is shown once, and can be rotated
+//! (revoking the old URL) or revoked outright.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Response,
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use super::events::{calendar_response, EventDetails};
+use super::extract::AuthUser;
+use crate::calendar;
+use crate::crypto;
+use crate::db;
+use crate::error::{ApiError, Result};
+use crate::state::AppState;
+
+/// How long past events stay in a feed
+const FEED_HISTORY_DAYS: i64 = 30;
+
+/// A newly issued feed URL
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CalendarFeedResponse {
+    /// Secret subscription URL; anyone with it can read the feed
+    pub url: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Issue a feed URL, revoking any previous one
+/// PUT /api/v1/users/me/calendar-feed
+///
+/// # Errors
+///
+/// Returns [`ApiError::Forbidden`] under duress, or an error if the query
+/// fails.
+#[utoipa::path(
+    put,
+    path = "/users/me/calendar-feed",
+    tag = "calendar",
+    responses(
+        (status = 200, description = "New feed URL, shown once", body = CalendarFeedResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn issue_feed(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<CalendarFeedResponse>> {
+    // The feed would list the caller's events
+    if auth.sanitized {
+        return Err(ApiError::Forbidden);
+    }
+
+    let token = crypto::generate_nonce();
+    let created_at =
+        db::calendar_feeds::replace(&state.db, auth.id, &crypto::hash_token(&token)).await?;
+
+    Ok(Json(CalendarFeedResponse {
+        url: format!("{}/api/v1/calendar/{token}.ics", state.config.public_url),
+        created_at,
+    }))
+}
+
+/// Revoke the feed URL
+/// DELETE /api/v1/users/me/calendar-feed
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+#[utoipa::path(
+    delete,
+    path = "/users/me/calendar-feed",
+    tag = "calendar",
+    responses(
+        (status = 204, description = "Feed revoked"),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn revoke_feed(State(state): State<AppState>, auth: AuthUser) -> Result<StatusCode> {
+    db::calendar_feeds::revoke(&state.db, auth.id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Read a feed
+/// GET /api/v1/calendar/:token.ics
+///
+/// # Errors
+///
+/// Returns [`ApiError::InvalidToken`] for an unknown or revoked token, or an
+/// error if a query fails.
+#[utoipa::path(
+    get,
+    path = "/calendar/{token}",
+    tag = "calendar",
+    params(
+        ("token" = String, Path, description = "Feed token, with a `.ics` suffix"),
+    ),
+    responses(
+        (status = 200, description = "iCalendar feed", body = String, content_type = "text/calendar"),
+        (status = 404, description = "No such feed", body = crate::error::ErrorResponse),
+    ),
+)]
+pub async fn feed(State(state): State<AppState>, Path(token): Path<String>) -> Result<Response> {
+    let token = token.strip_suffix(".ics").unwrap_or(&token);
+    let user_id = db::calendar_feeds::owner(&state.db, &crypto::hash_token(token))
+        .await?
+        .ok_or(ApiError::InvalidToken)?;
+
+    let since = Utc::now() - Duration::days(FEED_HISTORY_DAYS);
+    let events: Vec<EventDetails> = db::events::rsvp_listings(&state.db, user_id, since)
+        .await?
+        .into_iter()
+        .map(Into::into)
+        .collect();
+
+    let body = calendar::render("CivicConnect", &events, &state.config.public_url);
+    Ok(calendar_response(body))
+}
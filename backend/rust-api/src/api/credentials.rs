@@ -0,0 +1,231 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Attendance credential endpoints
+//!
+//! Where enabled, attendees can have a scan blind-signed instead of
+//! recorded, and redeem the credential later for XP without the server
+//! learning which event it came from. See [`crate::credentials`].
+
+use axum::{extract::State, Json};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::api::extract::AuthUser;
+use crate::api::verify::VerifyRequest;
+use crate::credentials::{self, Issuer};
+use crate::error::{ApiError, Result};
+use crate::state::AppState;
+use crate::telemetry::redact::Redacted;
+use crate::verification::TimeLimits;
+
+/// Most credentials redeemed in one request
+const MAX_REDEEM: usize = 50;
+
+/// The key credentials are signed with
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CredentialKey {
+    /// Hex SHA-256 of the modulus; changes when the key does
+    pub key_id: String,
+    /// RSA public key, as SPKI PEM
+    pub public_key_pem: String,
+}
+
+/// A scan to exchange for a credential
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct IssueCredentialRequest {
+    #[serde(flatten)]
+    pub scan: VerifyRequest,
+    /// When the device scanned the code, if it was queued offline
+    #[serde(default)]
+    pub scanned_at: Option<DateTime<Utc>>,
+    /// Blinded token, hex encoded in the modulus length
+    #[schema(value_type = String)]
+    pub blinded_token: Redacted<String>,
+}
+
+/// Signature over the blinded token
+#[derive(Debug, Serialize, ToSchema)]
+pub struct IssuedCredential {
+    /// Key that signed it
+    pub key_id: String,
+    /// Hex encoded; unblind it to get the credential's signature
+    pub blind_signature: String,
+}
+
+/// An unblinded credential
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct Credential {
+    /// The 32-byte token, hex encoded
+    #[schema(value_type = String)]
+    pub token: Redacted<String>,
+    /// Unblinded signature, hex encoded
+    #[schema(value_type = String)]
+    pub signature: Redacted<String>,
+}
+
+/// Credentials to redeem
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RedeemRequest {
+    pub credentials: Vec<Credential>,
+}
+
+/// Outcome of redeeming credentials
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RedeemResponse {
+    pub redeemed: u32,
+    /// Validly signed, but redeemed before
+    pub already_spent: u32,
+    /// Malformed or not signed by the current key
+    pub invalid: u32,
+    pub xp_awarded: u32,
+    pub new_total_xp: u32,
+    /// Attendances redeemed over the account's lifetime
+    pub attendances: u32,
+}
+
+/// Signing key for attendance credentials
+/// GET /api/v1/credentials/key
+///
+/// # Errors
+///
+/// Returns [`ApiError::InvalidInput`] if attendance credentials are not
+/// enabled.
+#[utoipa::path(
+    get,
+    path = "/credentials/key",
+    tag = "verification",
+    responses(
+        (status = 200, description = "Public key", body = CredentialKey),
+        (status = 400, description = "Credentials not enabled", body = crate::error::ErrorResponse),
+    ),
+)]
+pub async fn key(State(state): State<AppState>) -> Result<Json<CredentialKey>> {
+    let issuer = issuer(&state)?;
+
+    Ok(Json(CredentialKey {
+        key_id: issuer.key_id().to_string(),
+        public_key_pem: issuer.public_key_pem().to_string(),
+    }))
+}
+
+/// Exchange a scan for a blind-signed attendance credential
+/// POST /api/v1/credentials/attendance
+///
+/// The scan is checked as at `/verify/scan`, or as a queued scan if
+/// `scanned_at` is given, but no verification is recorded and no XP is
+/// awarded until the credential is redeemed.
+///
+/// # Errors
+///
+/// Returns [`ApiError::InvalidInput`] if attendance credentials are not enabled
+/// or the blinded token is malformed, the first scan check that failed, or an
+/// error if a query fails.
+#[utoipa::path(
+    post,
+    path = "/credentials/attendance",
+    tag = "verification",
+    request_body = IssueCredentialRequest,
+    responses(
+        (status = 200, description = "Blind signature", body = IssuedCredential),
+        (status = 400, description = "Invalid input or credentials not enabled", body = crate::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 404, description = "No such event", body = crate::error::ErrorResponse),
+        (status = 409, description = "Already verified", body = crate::error::ErrorResponse),
+        (status = 429, description = "Too many attempts", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn issue(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(req): Json<IssueCredentialRequest>,
+) -> Result<Json<IssuedCredential>> {
+    let issuer = issuer(&state)?;
+    let blinded = hex::decode(req.blinded_token.expose())
+        .map_err(|_| ApiError::InvalidInput("Blinded token must be hex".into()))?;
+
+    let rotation = state.config.realtime.qr_rotation_secs;
+    let (scanned_at, limits) = match req.scanned_at {
+        Some(at) => (at, TimeLimits::queued(&state.config.scan, rotation)),
+        None => (Utc::now(), TimeLimits::live(&state.config.scan, rotation)),
+    };
+    let blind_signature = credentials::issue(
+        &state,
+        issuer,
+        auth.id,
+        &req.scan.scan(scanned_at),
+        &limits,
+        &blinded,
+    )
+    .await?;
+
+    Ok(Json(IssuedCredential {
+        key_id: issuer.key_id().to_string(),
+        blind_signature: hex::encode(blind_signature),
+    }))
+}
+
+/// Redeem attendance credentials for XP
+/// POST /api/v1/credentials/redeem
+///
+/// Credentials already redeemed or not validly signed are counted, not
+/// rejected, so a retry after a lost response is harmless.
+///
+/// # Errors
+///
+/// Returns [`ApiError::InvalidInput`] if attendance credentials are not enabled
+/// or there are too many credentials, or an error if a query fails.
+#[utoipa::path(
+    post,
+    path = "/credentials/redeem",
+    tag = "verification",
+    request_body = RedeemRequest,
+    responses(
+        (status = 200, description = "Redemption counts", body = RedeemResponse),
+        (status = 400, description = "Too many credentials or credentials not enabled", body = crate::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn redeem(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(req): Json<RedeemRequest>,
+) -> Result<Json<RedeemResponse>> {
+    let issuer = issuer(&state)?;
+    if req.credentials.len() > MAX_REDEEM {
+        return Err(ApiError::InvalidInput(format!(
+            "At most {MAX_REDEEM} credentials per request"
+        )));
+    }
+
+    // Undecodable ones fail verification, and are counted as invalid
+    let pairs: Vec<(Vec<u8>, Vec<u8>)> = req
+        .credentials
+        .iter()
+        .map(|credential| {
+            (
+                hex::decode(credential.token.expose()).unwrap_or_default(),
+                hex::decode(credential.signature.expose()).unwrap_or_default(),
+            )
+        })
+        .collect();
+    let redemption = credentials::redeem(&state, issuer, auth.id, &pairs).await?;
+
+    Ok(Json(RedeemResponse {
+        redeemed: redemption.redeemed,
+        already_spent: redemption.already_spent,
+        invalid: redemption.invalid,
+        xp_awarded: u32::try_from(redemption.xp_awarded).unwrap_or(0),
+        new_total_xp: u32::try_from(redemption.total_xp).unwrap_or(0),
+        attendances: u32::try_from(redemption.attendances).unwrap_or(0),
+    }))
+}
+
+fn issuer(state: &AppState) -> Result<&Issuer> {
+    state.credentials.as_deref().ok_or_else(|| {
+        ApiError::InvalidInput("Attendance credentials are not enabled on this server".into())
+    })
+}
@@ -0,0 +1,253 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Duress mode endpoints
+//!
+//! Settings for the duress password and the trusted contacts it alerts,
+//! and the alerts a contact has received. See [`crate::duress`].
+//!
+//! A sanitized session sees no contacts or alerts, and its changes are
+//! accepted but not saved, so whoever holds it can't tell they are in one.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+use super::extract::AuthUser;
+use crate::db::{
+    self,
+    duress::{DuressAlert, TrustedContact},
+};
+use crate::error::{ApiError, Result};
+use crate::state::AppState;
+use crate::telemetry::redact::Redacted;
+
+/// Duress password settings
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct DuressPasswordRequest {
+    /// Must differ from the account password
+    #[validate(length(min = 12))]
+    #[schema(value_type = String)]
+    pub password: Redacted<String>,
+    /// Alert trusted contacts when the duress password is used
+    #[serde(default)]
+    pub notify_trusted_contacts: bool,
+}
+
+/// Set the duress password
+/// PUT /api/v1/users/me/duress-password
+///
+/// # Errors
+///
+/// Returns [`ApiError::InvalidInput`] if the password is too short or is the
+/// account password, or an error if hashing or a query fails.
+#[utoipa::path(
+    put,
+    path = "/users/me/duress-password",
+    tag = "users",
+    request_body = DuressPasswordRequest,
+    responses(
+        (status = 204, description = "Duress password set"),
+        (status = 400, description = "Invalid input, or the same as the password", body = crate::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn set_duress_password(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(req): Json<DuressPasswordRequest>,
+) -> Result<StatusCode> {
+    req.validate()
+        .map_err(|e| ApiError::InvalidInput(e.to_string()))?;
+
+    let user = db::users::find_by_id(&state.db, auth.id)
+        .await?
+        .ok_or(ApiError::UserNotFound)?;
+
+    // Otherwise every login would be under duress, or none would
+    let same = state
+        .passwords
+        .verify(req.password.expose().clone(), user.password_hash)
+        .await?;
+    if same.is_valid() {
+        return Err(ApiError::InvalidInput(
+            "The duress password must differ from your password".into(),
+        ));
+    }
+    let password_hash = state.passwords.hash(req.password.into_inner()).await?;
+
+    if !auth.sanitized {
+        db::duress::set_password(
+            &state.db,
+            auth.id,
+            Some(&password_hash),
+            req.notify_trusted_contacts,
+        )
+        .await?;
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Remove the duress password
+/// DELETE /api/v1/users/me/duress-password
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+#[utoipa::path(
+    delete,
+    path = "/users/me/duress-password",
+    tag = "users",
+    responses(
+        (status = 204, description = "Duress password removed"),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn remove_duress_password(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<StatusCode> {
+    if !auth.sanitized {
+        db::duress::set_password(&state.db, auth.id, None, false).await?;
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// The caller's trusted contacts
+/// GET /api/v1/users/me/trusted-contacts
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+#[utoipa::path(
+    get,
+    path = "/users/me/trusted-contacts",
+    tag = "users",
+    responses(
+        (status = 200, description = "Trusted contacts, by username", body = Vec<TrustedContact>),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn list_trusted_contacts(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<Vec<TrustedContact>>> {
+    if auth.sanitized {
+        return Ok(Json(Vec::new()));
+    }
+
+    Ok(Json(db::duress::contacts(&state.db, auth.id).await?))
+}
+
+/// Add a trusted contact
+/// PUT /api/v1/users/me/trusted-contacts/:user_id
+///
+/// # Errors
+///
+/// Returns [`ApiError::InvalidInput`] for the caller,
+/// [`ApiError::UserNotFound`] if there is no such active user, or an error if
+/// the query fails.
+#[utoipa::path(
+    put,
+    path = "/users/me/trusted-contacts/{user_id}",
+    tag = "users",
+    params(
+        ("user_id" = Uuid, Path, description = "Contact's user ID"),
+    ),
+    responses(
+        (status = 204, description = "Contact added"),
+        (status = 400, description = "The caller", body = crate::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 404, description = "No such user", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn add_trusted_contact(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(user_id): Path<Uuid>,
+) -> Result<StatusCode> {
+    if user_id == auth.id {
+        return Err(ApiError::InvalidInput(
+            "You cannot be your own trusted contact".into(),
+        ));
+    }
+    if auth.sanitized {
+        return Ok(StatusCode::NO_CONTENT);
+    }
+
+    if !db::duress::add_contact(&state.db, auth.id, user_id).await? {
+        return Err(ApiError::UserNotFound);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Remove a trusted contact
+/// DELETE /api/v1/users/me/trusted-contacts/:user_id
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+#[utoipa::path(
+    delete,
+    path = "/users/me/trusted-contacts/{user_id}",
+    tag = "users",
+    params(
+        ("user_id" = Uuid, Path, description = "Contact's user ID"),
+    ),
+    responses(
+        (status = 204, description = "Contact removed"),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn remove_trusted_contact(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(user_id): Path<Uuid>,
+) -> Result<StatusCode> {
+    if !auth.sanitized {
+        db::duress::remove_contact(&state.db, auth.id, user_id).await?;
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Duress alerts from users who list the caller as a trusted contact
+/// GET /api/v1/users/me/duress-alerts
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+#[utoipa::path(
+    get,
+    path = "/users/me/duress-alerts",
+    tag = "users",
+    responses(
+        (status = 200, description = "The latest alerts, newest first", body = Vec<DuressAlert>),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn duress_alerts(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<Vec<DuressAlert>>> {
+    if auth.sanitized {
+        return Ok(Json(Vec::new()));
+    }
+
+    Ok(Json(db::duress::alerts_for(&state.db, auth.id).await?))
+}
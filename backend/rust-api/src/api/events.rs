@@ -5,17 +5,46 @@
 //!
 //! Location privacy: Events stored with H3 cell, not exact coordinates
 //! Discovery uses proximity queries on cell neighborhoods
+//!
+//! Any event can be downloaded as iCalendar by adding `.ics` to its URL;
+//! see [`crate::calendar`] for what the export reveals.
+//!
+//! RSVPs are capacity-limited, and each change is published to the
+//! event's followers as a real-time capacity update.
+//!
+//! Organizers can hold events under an organization's name and bring in
+//! co-organizers. A chapter's admins manage its events alongside their
+//! organizers.
 
-use axum::{extract::Path, Json};
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use uuid::Uuid;
 use validator::Validate;
 
+use super::extract::AuthUser;
+use super::organizations::standing;
+use crate::calendar;
+use crate::db::{
+    self,
+    events::{CoOrganizer, EventListing},
+    models::Event,
+};
 use crate::error::{ApiError, Result};
+use crate::leveling::ORGANIZER_LEVEL;
+use crate::organizations::Capability;
+use crate::realtime::EventUpdate;
+use crate::state::AppState;
+use crate::webhooks::{self, WebhookEvent};
 
 /// Event listing response
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct EventSummary {
     pub id: Uuid,
     pub title: String,
@@ -29,7 +58,7 @@ pub struct EventSummary {
 }
 
 /// Full event details
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct EventDetails {
     pub id: Uuid,
     pub title: String,
@@ -37,17 +66,46 @@ pub struct EventDetails {
     pub organizer_id: Uuid,
     pub organizer_username: String,
     pub organizer_level: u8,
+    /// Organization holding the event, if any
+    pub organization_id: Option<Uuid>,
     pub start_time: DateTime<Utc>,
     pub end_time: DateTime<Utc>,
     pub location_cell: String,
+    /// Venue text, only if the organizer chose to publish it
+    pub venue: Option<String>,
     pub attendee_count: u32,
     pub capacity: Option<u32>,
     pub tags: Vec<String>,
     pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<EventListing> for EventDetails {
+    fn from(listing: EventListing) -> Self {
+        let event = listing.event;
+        Self {
+            id: event.id,
+            title: event.title,
+            description: event.description,
+            organizer_id: event.organizer_id,
+            organizer_username: listing.organizer_username,
+            organizer_level: u8::try_from(listing.organizer_level).unwrap_or(0),
+            organization_id: event.organization_id,
+            start_time: event.start_time,
+            end_time: event.end_time,
+            location_cell: event.location_hash,
+            venue: event.venue,
+            attendee_count: u32::try_from(listing.rsvps).unwrap_or(u32::MAX),
+            capacity: event.capacity.and_then(|c| u32::try_from(c).ok()),
+            tags: event.tags,
+            created_at: event.created_at,
+            updated_at: event.updated_at,
+        }
+    }
 }
 
 /// Create event request
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct CreateEventRequest {
     #[validate(length(min = 3, max = 200))]
     pub title: String,
@@ -57,41 +115,494 @@ pub struct CreateEventRequest {
     pub end_time: DateTime<Utc>,
     #[validate(length(equal = 15))]
     pub location_cell: String, // H3 cell ID
+    /// Venue text to publish; without it, only the cell is shown
+    #[validate(length(min = 1, max = 200))]
+    pub venue: Option<String>,
     pub capacity: Option<u32>,
     pub tags: Vec<String>,
 }
 
+/// Move an event into or out of an organization
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SetOrganizationRequest {
+    /// `null` to hold the event in the organizer's own name
+    pub organization_id: Option<Uuid>,
+}
+
+/// Someone helping run an event
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CoOrganizerResponse {
+    pub user_id: Uuid,
+    pub username: String,
+    pub added_at: DateTime<Utc>,
+}
+
+impl From<CoOrganizer> for CoOrganizerResponse {
+    fn from(co_organizer: CoOrganizer) -> Self {
+        Self {
+            user_id: co_organizer.user_id,
+            username: co_organizer.username,
+            added_at: co_organizer.created_at,
+        }
+    }
+}
+
+/// RSVP state after a change
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RsvpResponse {
+    pub event_id: Uuid,
+    pub attending: bool,
+    pub rsvps: i64,
+    pub capacity: Option<i32>,
+}
+
 /// List events (paginated)
 /// GET /api/v1/events
+///
+/// # Errors
+///
+/// Never fails while listing is unimplemented.
+#[utoipa::path(
+    get,
+    path = "/events",
+    tag = "events",
+    responses(
+        (status = 200, description = "Upcoming events", body = Vec<EventSummary>),
+    ),
+)]
 pub async fn list_events() -> Result<Json<Vec<EventSummary>>> {
     // TODO: Parse query parameters (page, limit, filters)
-    // TODO: Query database
+    // TODO: Query database, excluding hidden events
     // TODO: Return paginated results
 
     Ok(Json(vec![]))
 }
 
-/// Get event by ID
+/// Get event by ID, as JSON or iCalendar
 /// GET /api/v1/events/:id
-pub async fn get_event(Path(id): Path<Uuid>) -> Result<Json<EventDetails>> {
-    // TODO: Query database
-    // TODO: Check if event exists
+/// GET /api/v1/events/:id.ics
+///
+/// # Errors
+///
+/// Returns [`ApiError::EventNotFound`] for an unknown or hidden event, or an
+/// error if the query fails.
+#[utoipa::path(
+    get,
+    path = "/events/{id}",
+    tag = "events",
+    params(
+        ("id" = String, Path, description = "Event ID, optionally with a `.ics` suffix"),
+    ),
+    responses(
+        (status = 200, description = "The event, as iCalendar with a `.ics` suffix", body = EventDetails),
+        (status = 404, description = "No such event", body = crate::error::ErrorResponse),
+    ),
+)]
+pub async fn get_event(State(state): State<AppState>, Path(id): Path<String>) -> Result<Response> {
+    let (id, ics) = id
+        .strip_suffix(".ics")
+        .map_or((id.as_str(), false), |id| (id, true));
+    let id: Uuid = id.parse().map_err(|_| ApiError::EventNotFound)?;
+
+    let event: EventDetails = db::events::find_listing(&state.db, id)
+        .await?
+        .ok_or(ApiError::EventNotFound)?
+        .into();
+
+    if !ics {
+        return Ok(Json(event).into_response());
+    }
+    let name = event.title.clone();
+    let body = calendar::render(&name, &[event], &state.config.public_url);
+    Ok(calendar_response(body))
+}
 
-    let _ = id;
-    Err(ApiError::EventNotFound)
+/// An iCalendar response body
+pub(crate) fn calendar_response(body: String) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, "text/calendar; charset=utf-8"),
+            (header::CACHE_CONTROL, "private, max-age=300"),
+        ],
+        body,
+    )
+        .into_response()
 }
 
 /// Create new event
 /// POST /api/v1/events
+///
+/// Not implemented yet: always fails, so no new event is documented.
+///
+/// # Errors
+///
+/// Returns [`ApiError::InvalidInput`] for invalid input, and
+/// [`ApiError::Forbidden`] until creation is implemented.
+#[utoipa::path(
+    post,
+    path = "/events",
+    tag = "events",
+    request_body = CreateEventRequest,
+    responses(
+        (status = 400, description = "Invalid input", body = crate::error::ErrorResponse),
+        (status = 403, description = "Always for valid input, until implemented", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
 pub async fn create_event(Json(req): Json<CreateEventRequest>) -> Result<Json<EventDetails>> {
     // Validate input
     req.validate()
         .map_err(|e| ApiError::InvalidInput(e.to_string()))?;
 
     // TODO: Check user is authenticated and level >= 2
+    // TODO: Accept an `organization_id`, requiring `Capability::CreateEvents`
+    //       there, as `set_organization` does
     // TODO: Validate times (start < end, not in past)
     // TODO: Create event in database
     // TODO: Return created event
+    // TODO: Publish `EventUpdate::Cancelled` / `Capacity` once events can be
+    //       cancelled or edited
+    // TODO: Federate with `federation::publish_event` once created
+    // TODO: Queue `WebhookEvent::EventCreated` in the creating transaction
 
     Err(ApiError::Forbidden)
 }
+
+/// RSVP to an event
+/// PUT /api/v1/events/:id/rsvp
+///
+/// Idempotent. Fails with 409 if the event is at capacity.
+///
+/// # Errors
+///
+/// Returns [`ApiError::EventNotFound`] for an unknown or hidden event,
+/// [`ApiError::EventFull`] at capacity, or an error if a query fails.
+#[utoipa::path(
+    put,
+    path = "/events/{id}/rsvp",
+    tag = "events",
+    params(
+        ("id" = Uuid, Path, description = "Event ID"),
+    ),
+    responses(
+        (status = 200, description = "RSVP state", body = RsvpResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 404, description = "No such event", body = crate::error::ErrorResponse),
+        (status = 409, description = "The event is full", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn rsvp(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<RsvpResponse>> {
+    change_rsvp(&state, auth.id, id, true).await
+}
+
+/// Withdraw an RSVP
+/// DELETE /api/v1/events/:id/rsvp
+///
+/// # Errors
+///
+/// Returns [`ApiError::EventNotFound`] for an unknown or hidden event, or an
+/// error if a query fails.
+#[utoipa::path(
+    delete,
+    path = "/events/{id}/rsvp",
+    tag = "events",
+    params(
+        ("id" = Uuid, Path, description = "Event ID"),
+    ),
+    responses(
+        (status = 200, description = "RSVP state", body = RsvpResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 404, description = "No such event", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn cancel_rsvp(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<RsvpResponse>> {
+    change_rsvp(&state, auth.id, id, false).await
+}
+
+async fn change_rsvp(
+    state: &AppState,
+    user_id: Uuid,
+    event_id: Uuid,
+    attending: bool,
+) -> Result<Json<RsvpResponse>> {
+    // The event row lock serializes RSVPs so capacity can't be overrun
+    let mut tx = state.db.begin().await?;
+    let event = db::events::find_visible_for_update(&mut tx, event_id)
+        .await?
+        .ok_or(ApiError::EventNotFound)?;
+
+    let changed = if attending {
+        let rsvps = db::events::rsvp_count(&mut tx, event_id).await?;
+        let full = event.capacity.is_some_and(|cap| rsvps >= i64::from(cap));
+        let added = db::events::add_rsvp(&mut tx, event_id, user_id).await?;
+        if added && full {
+            return Err(ApiError::EventFull);
+        }
+        added
+    } else {
+        db::events::remove_rsvp(&mut tx, event_id, user_id).await?
+    };
+    let rsvps = db::events::rsvp_count(&mut tx, event_id).await?;
+    if changed {
+        webhooks::queue(
+            &mut tx,
+            &WebhookEvent::RsvpChanged {
+                event_id,
+                user_id,
+                attending,
+                rsvps,
+                capacity: event.capacity,
+            },
+        )
+        .await?;
+    }
+    tx.commit().await?;
+
+    if changed {
+        state
+            .realtime
+            .publish(EventUpdate::Capacity {
+                event_id,
+                capacity: event.capacity,
+                rsvps,
+            })
+            .await;
+    }
+
+    Ok(Json(RsvpResponse {
+        event_id,
+        attending,
+        rsvps,
+        capacity: event.capacity,
+    }))
+}
+
+/// Hold an event under an organization's name, or take it back
+/// PUT /api/v1/events/:id/organization
+///
+/// Only the organizer may, and only into organizations where they may
+/// create events.
+///
+/// # Errors
+///
+/// Returns [`ApiError::EventNotFound`] for an unknown or hidden event,
+/// [`ApiError::Forbidden`] unless the caller is its organizer and may create
+/// events in the organization, or an error if a query fails.
+#[utoipa::path(
+    put,
+    path = "/events/{id}/organization",
+    tag = "events",
+    request_body = SetOrganizationRequest,
+    params(
+        ("id" = Uuid, Path, description = "Event ID"),
+    ),
+    responses(
+        (status = 204, description = "Event moved"),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 403, description = "Not allowed", body = crate::error::ErrorResponse),
+        (status = 404, description = "No such event or organization", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn set_organization(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(req): Json<SetOrganizationRequest>,
+) -> Result<StatusCode> {
+    if let Some(organization_id) = req.organization_id {
+        if !standing(&state, organization_id, auth.id)
+            .await?
+            .can(Capability::CreateEvents)
+        {
+            return Err(ApiError::Forbidden);
+        }
+    }
+
+    let mut tx = state.db.begin().await?;
+    let event = db::events::find_visible_for_update(&mut tx, id)
+        .await?
+        .ok_or(ApiError::EventNotFound)?;
+    if event.organizer_id != auth.id {
+        return Err(ApiError::Forbidden);
+    }
+    db::events::set_organization(&mut tx, id, req.organization_id).await?;
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// An event's co-organizers
+/// GET /api/v1/events/:id/co-organizers
+///
+/// # Errors
+///
+/// Returns [`ApiError::EventNotFound`] for an unknown or hidden event, or an
+/// error if a query fails.
+#[utoipa::path(
+    get,
+    path = "/events/{id}/co-organizers",
+    tag = "events",
+    params(
+        ("id" = Uuid, Path, description = "Event ID"),
+    ),
+    responses(
+        (status = 200, description = "Co-organizers, in the order added", body = Vec<CoOrganizerResponse>),
+        (status = 404, description = "No such event", body = crate::error::ErrorResponse),
+    ),
+)]
+pub async fn list_co_organizers(
+    State(state): State<AppState>,
+    auth: Option<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<CoOrganizerResponse>>> {
+    if db::events::find_listing(&state.db, id).await?.is_none() {
+        return Err(ApiError::EventNotFound);
+    }
+    // Under duress the caller's network stays hidden
+    if auth.is_some_and(|auth| auth.sanitized) {
+        return Ok(Json(Vec::new()));
+    }
+    let co_organizers = db::events::co_organizers(&state.db, id).await?;
+
+    Ok(Json(co_organizers.into_iter().map(Into::into).collect()))
+}
+
+/// Add a co-organizer
+/// PUT /api/v1/events/:id/co-organizers/:user_id
+///
+/// The organizer, or an admin of the event's organization, may add
+/// members of that organization; for an event held in the organizer's
+/// own name, co-organizers must be organizers themselves.
+///
+/// # Errors
+///
+/// Returns [`ApiError::EventNotFound`] for an unknown or hidden event,
+/// [`ApiError::Forbidden`] unless the caller manages it,
+/// [`ApiError::InvalidInput`] for the organizer or an ineligible user,
+/// [`ApiError::UserNotFound`] for an unknown user, or an error if a query
+/// fails.
+#[utoipa::path(
+    put,
+    path = "/events/{id}/co-organizers/{user_id}",
+    tag = "events",
+    params(
+        ("id" = Uuid, Path, description = "Event ID"),
+        ("user_id" = Uuid, Path, description = "User ID"),
+    ),
+    responses(
+        (status = 204, description = "Co-organizer added"),
+        (status = 400, description = "Invalid input", body = crate::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 403, description = "Not allowed", body = crate::error::ErrorResponse),
+        (status = 404, description = "No such event or user", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn add_co_organizer(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((id, user_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode> {
+    let mut tx = state.db.begin().await?;
+    let event = db::events::find_visible_for_update(&mut tx, id)
+        .await?
+        .ok_or(ApiError::EventNotFound)?;
+    if !manages(&state, &event, auth.id).await? {
+        return Err(ApiError::Forbidden);
+    }
+    if user_id == event.organizer_id {
+        return Err(ApiError::InvalidInput(
+            "The organizer can't also be a co-organizer".into(),
+        ));
+    }
+
+    let eligible = match event.organization_id {
+        Some(organization_id) => standing(&state, organization_id, user_id)
+            .await?
+            .role
+            .is_some(),
+        None => {
+            db::users::find_by_id(&state.db, user_id)
+                .await?
+                .ok_or(ApiError::UserNotFound)?
+                .current_level
+                >= ORGANIZER_LEVEL
+        }
+    };
+    if !eligible {
+        return Err(ApiError::InvalidInput(
+            "Co-organizers must be organization members, or organizers".into(),
+        ));
+    }
+    db::events::add_co_organizer(&mut tx, id, user_id, auth.id).await?;
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Remove a co-organizer, or step down as one
+/// DELETE /api/v1/events/:id/co-organizers/:user_id
+///
+/// # Errors
+///
+/// Returns [`ApiError::EventNotFound`] for an unknown or hidden event,
+/// [`ApiError::Forbidden`] unless the caller manages it or is stepping down, or
+/// an error if a query fails.
+#[utoipa::path(
+    delete,
+    path = "/events/{id}/co-organizers/{user_id}",
+    tag = "events",
+    params(
+        ("id" = Uuid, Path, description = "Event ID"),
+        ("user_id" = Uuid, Path, description = "User ID"),
+    ),
+    responses(
+        (status = 204, description = "Co-organizer removed"),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 403, description = "Not allowed", body = crate::error::ErrorResponse),
+        (status = 404, description = "No such event", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn remove_co_organizer(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((id, user_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode> {
+    let mut tx = state.db.begin().await?;
+    let event = db::events::find_visible_for_update(&mut tx, id)
+        .await?
+        .ok_or(ApiError::EventNotFound)?;
+    if user_id != auth.id && !manages(&state, &event, auth.id).await? {
+        return Err(ApiError::Forbidden);
+    }
+    db::events::remove_co_organizer(&mut tx, id, user_id).await?;
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Whether a user may manage an event: its organizer, or an admin of the
+/// organization holding it
+async fn manages(state: &AppState, event: &Event, user_id: Uuid) -> Result<bool> {
+    if event.organizer_id == user_id {
+        return Ok(true);
+    }
+    match event.organization_id {
+        Some(organization_id) => Ok(standing(state, organization_id, user_id)
+            .await?
+            .can(Capability::ManageEvents)),
+        None => Ok(false),
+    }
+}
@@ -0,0 +1,165 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Request extractors
+
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{header, request::Parts},
+};
+use uuid::Uuid;
+
+use crate::crypto::jwt::{self, AuthMethod, Claims, TokenScope};
+use crate::db::{self, models::Role, users::SessionState};
+use crate::error::ApiError;
+use crate::state::AppState;
+
+/// Authenticated caller, from a `Bearer` session token
+///
+/// The account is checked on every request, so suspending a user or
+/// revoking their sessions takes effect immediately.
+#[derive(Debug, Clone)]
+pub struct AuthUser {
+    pub id: Uuid,
+    /// How the session was authenticated
+    pub amr: Vec<AuthMethod>,
+    /// The account is in duress mode: show it without its network (see
+    /// [`crate::duress`])
+    pub sanitized: bool,
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for AuthUser {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let token = bearer_token(parts)?;
+        let claims = jwt::decode_token(
+            token,
+            state.config.jwt_secret.as_bytes(),
+            TokenScope::Session,
+        )?;
+
+        let session = session_state(state, &claims).await?;
+
+        Ok(Self {
+            id: claims.sub,
+            amr: claims.amr,
+            sanitized: session.under_duress,
+        })
+    }
+}
+
+/// Caller allowed to enroll a second factor: either a full session, or a
+/// password-only login that policy requires to enroll before continuing
+///
+/// Checked against the account like [`AuthUser`], so suspended users and
+/// revoked tokens can't enroll, and nobody can while under duress.
+#[derive(Debug, Clone, Copy)]
+pub struct EnrollingUser {
+    pub id: Uuid,
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for EnrollingUser {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let token = bearer_token(parts)?;
+        let secret = state.config.jwt_secret.as_bytes();
+
+        let claims = jwt::decode_token(token, secret, TokenScope::Session)
+            .or_else(|_| jwt::decode_token(token, secret, TokenScope::Enrollment))?;
+        // A passkey enrolled under duress would log in passwordless to an
+        // unsanitized session
+        if session_state(state, &claims).await?.under_duress {
+            return Err(ApiError::Forbidden);
+        }
+
+        Ok(Self { id: claims.sub })
+    }
+}
+
+/// Caller with a moderation role
+///
+/// The role is read from the database, not the token, so granting or
+/// revoking it takes effect on the next request.
+#[derive(Debug, Clone, Copy)]
+pub struct Staff {
+    pub id: Uuid,
+    pub role: Role,
+}
+
+impl Staff {
+    /// Require at least `role`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ApiError::Forbidden`] if the caller's role is lower.
+    pub fn require(&self, role: Role) -> Result<(), ApiError> {
+        if self.role >= role {
+            Ok(())
+        } else {
+            Err(ApiError::Forbidden)
+        }
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for Staff {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let caller = AuthUser::from_request_parts(parts, state).await?;
+        let user = db::users::find_by_id(&state.db, caller.id)
+            .await?
+            .ok_or(ApiError::Unauthorized)?;
+
+        if user.role < Role::Moderator {
+            return Err(ApiError::Forbidden);
+        }
+
+        Ok(Self {
+            id: user.id,
+            role: user.role,
+        })
+    }
+}
+
+/// The account state behind a token, checked on every request
+///
+/// Rejects tokens of suspended users, and tokens issued before the user's
+/// sessions were last revoked.
+async fn session_state(state: &AppState, claims: &Claims) -> Result<SessionState, ApiError> {
+    let session = db::users::session_state(&state.db, claims.sub)
+        .await?
+        .ok_or(ApiError::AccountSuspended)?;
+    if session
+        .sessions_valid_after
+        .is_some_and(|after| claims.iat < after.timestamp())
+    {
+        return Err(ApiError::Unauthorized);
+    }
+
+    Ok(session)
+}
+
+/// The token from an `Authorization: Bearer` header
+fn bearer_token(parts: &Parts) -> Result<&str, ApiError> {
+    parts
+        .headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or(ApiError::Unauthorized)
+}
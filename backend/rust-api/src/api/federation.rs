@@ -0,0 +1,283 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! ActivityPub and WebFinger endpoints
+//!
+//! Served from the site root, since remote servers dereference these
+//! URIs directly, and only routed when federation is configured. See
+//! [`crate::federation`] for what is published.
+
+use std::sync::Arc;
+
+use axum::{
+    body::Bytes,
+    extract::{OriginalUri, Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use uuid::Uuid;
+use validator::Validate;
+
+use super::extract::AuthUser;
+use crate::db::{self, models::User};
+use crate::error::{ApiError, Result};
+use crate::federation::{
+    inbox::{self, Delivery},
+    Federation, ACTIVITY_JSON,
+};
+use crate::state::AppState;
+
+/// Events listed in an outbox
+const OUTBOX_LIMIT: i64 = 20;
+
+/// WebFinger lookup
+#[derive(Debug, Deserialize)]
+pub struct WebFingerQuery {
+    /// `acct:username@host`
+    pub resource: String,
+}
+
+/// Follow a remote actor
+#[derive(Debug, Deserialize, Validate)]
+pub struct FollowRequest {
+    /// Actor URI, e.g. `https://mobilizon.example/@group`
+    #[validate(length(min = 1, max = 2000))]
+    pub actor: String,
+}
+
+/// A Follow that was sent
+#[derive(Debug, Serialize)]
+pub struct FollowResponse {
+    pub follow_id: String,
+    pub actor: String,
+}
+
+/// Resolve `acct:username@host` to an actor
+/// GET /.well-known/webfinger
+///
+/// # Errors
+///
+/// Returns [`ApiError::Forbidden`] if federation is off,
+/// [`ApiError::InvalidInput`] for anything but an `acct:` resource,
+/// [`ApiError::UserNotFound`] for an unknown organizer or another host, or an
+/// error if the query fails.
+pub async fn webfinger(
+    State(state): State<AppState>,
+    Query(query): Query<WebFingerQuery>,
+) -> Result<Response> {
+    let federation = enabled(&state)?;
+
+    let account = query
+        .resource
+        .strip_prefix("acct:")
+        .ok_or_else(|| ApiError::InvalidInput("Only acct: resources are supported".into()))?;
+    let (username, host) = account.rsplit_once('@').ok_or(ApiError::UserNotFound)?;
+    if Some(host) != federation.host().as_deref() {
+        return Err(ApiError::UserNotFound);
+    }
+    let user = db::federation::find_actor_by_username(&state.db, username)
+        .await?
+        .ok_or(ApiError::UserNotFound)?;
+
+    let actor = federation.actor_uri(user.id);
+    let body = json!({
+        "subject": format!("acct:{}@{host}", user.username),
+        "aliases": [actor],
+        "links": [{ "rel": "self", "type": ACTIVITY_JSON, "href": actor }],
+    });
+    Ok((
+        [(header::CONTENT_TYPE, "application/jrd+json")],
+        body.to_string(),
+    )
+        .into_response())
+}
+
+/// An organizer's actor document
+/// GET /ap/actors/:id
+///
+/// # Errors
+///
+/// Returns [`ApiError::Forbidden`] if federation is off,
+/// [`ApiError::UserNotFound`] unless `id` is an organizer, or an error if the
+/// query fails.
+pub async fn actor(State(state): State<AppState>, Path(id): Path<Uuid>) -> Result<Response> {
+    let federation = enabled(&state)?;
+    let user = find_actor(&state, id).await?;
+
+    Ok(activity_json(&federation.actor(&user)))
+}
+
+/// An organizer's most recent events, as `Create` activities
+/// GET /ap/actors/:id/outbox
+///
+/// # Errors
+///
+/// Returns [`ApiError::Forbidden`] if federation is off,
+/// [`ApiError::UserNotFound`] unless `id` is an organizer, or an error if a
+/// query fails.
+pub async fn outbox(State(state): State<AppState>, Path(id): Path<Uuid>) -> Result<Response> {
+    let federation = enabled(&state)?;
+    let user = find_actor(&state, id).await?;
+
+    let items: Vec<Value> = db::events::organized_listings(&state.db, user.id, OUTBOX_LIMIT)
+        .await?
+        .iter()
+        .map(|listing| federation.create(listing))
+        .collect();
+
+    Ok(activity_json(&json!({
+        "@context": "https://www.w3.org/ns/activitystreams",
+        "id": format!("{}/outbox", federation.actor_uri(user.id)),
+        "type": "OrderedCollection",
+        "totalItems": items.len(),
+        "orderedItems": items,
+    })))
+}
+
+/// How many remote actors follow an organizer (not who)
+/// GET /ap/actors/:id/followers
+///
+/// # Errors
+///
+/// Returns [`ApiError::Forbidden`] if federation is off,
+/// [`ApiError::UserNotFound`] unless `id` is an organizer, or an error if a
+/// query fails.
+pub async fn followers(State(state): State<AppState>, Path(id): Path<Uuid>) -> Result<Response> {
+    let federation = enabled(&state)?;
+    let user = find_actor(&state, id).await?;
+    let count = db::federation::follower_count(&state.db, user.id).await?;
+
+    Ok(activity_json(&json!({
+        "@context": "https://www.w3.org/ns/activitystreams",
+        "id": format!("{}/followers", federation.actor_uri(user.id)),
+        "type": "OrderedCollection",
+        "totalItems": count,
+    })))
+}
+
+/// A visible event as an ActivityStreams object
+/// GET /ap/events/:id
+///
+/// # Errors
+///
+/// Returns [`ApiError::Forbidden`] if federation is off,
+/// [`ApiError::EventNotFound`] unless the event is visible and run by an
+/// organizer, or an error if a query fails.
+pub async fn event(State(state): State<AppState>, Path(id): Path<Uuid>) -> Result<Response> {
+    let federation = enabled(&state)?;
+    let listing = db::events::find_listing(&state.db, id)
+        .await?
+        .ok_or(ApiError::EventNotFound)?;
+    if db::federation::find_actor(&state.db, listing.event.organizer_id)
+        .await?
+        .is_none()
+    {
+        return Err(ApiError::EventNotFound);
+    }
+
+    Ok(activity_json(&federation.event(&listing)))
+}
+
+/// Receive an activity
+/// POST /ap/actors/:id/inbox
+///
+/// # Errors
+///
+/// Returns [`ApiError::Forbidden`] if federation is off,
+/// [`ApiError::UserNotFound`] unless `id` is an organizer, or whatever
+/// [`inbox::receive`] rejects the activity with.
+pub async fn inbox(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<StatusCode> {
+    let federation = enabled(&state)?;
+    let owner = find_actor(&state, id).await?;
+
+    let path = uri
+        .path_and_query()
+        .map_or_else(|| uri.path(), |p| p.as_str());
+    inbox::receive(
+        &state,
+        federation,
+        &owner,
+        Delivery {
+            path,
+            headers: &headers,
+            body: &body,
+        },
+    )
+    .await?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// Follow a remote actor as the calling organizer
+/// POST /api/v1/federation/following
+///
+/// The follow is pending until the remote server sends an `Accept`.
+///
+/// # Errors
+///
+/// Returns [`ApiError::InvalidInput`] for invalid input,
+/// [`ApiError::Forbidden`] if federation is off or the caller isn't an
+/// organizer, or an error if the remote actor can't be fetched or delivered to,
+/// or a query fails.
+pub async fn follow(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(req): Json<FollowRequest>,
+) -> Result<(StatusCode, Json<FollowResponse>)> {
+    req.validate()
+        .map_err(|e| ApiError::InvalidInput(e.to_string()))?;
+    let federation = enabled(&state)?;
+
+    // Only organizers are actors
+    let user = db::federation::find_actor(&state.db, auth.id)
+        .await?
+        .ok_or(ApiError::Forbidden)?;
+    let remote = federation.fetch_actor(&req.actor).await?;
+
+    let follow_id = federation.activity_id(user.id);
+    let activity = json!({
+        "@context": "https://www.w3.org/ns/activitystreams",
+        "id": follow_id,
+        "type": "Follow",
+        "actor": federation.actor_uri(user.id),
+        "object": remote.id,
+    });
+    db::federation::add_following(&state.db, user.id, &remote.id, &follow_id).await?;
+    federation
+        .deliver(user.id, &remote.inbox, &activity)
+        .await?;
+
+    tracing::info!(user_id = %user.id, actor = %remote.id, "Federated follow sent");
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(FollowResponse {
+            follow_id,
+            actor: remote.id,
+        }),
+    ))
+}
+
+/// Federation, if configured
+fn enabled(state: &AppState) -> Result<&Arc<Federation>> {
+    state.federation.as_ref().ok_or(ApiError::Forbidden)
+}
+
+async fn find_actor(state: &AppState, id: Uuid) -> Result<User> {
+    db::federation::find_actor(&state.db, id)
+        .await?
+        .ok_or(ApiError::UserNotFound)
+}
+
+fn activity_json(body: &Value) -> Response {
+    ([(header::CONTENT_TYPE, ACTIVITY_JSON)], body.to_string()).into_response()
+}
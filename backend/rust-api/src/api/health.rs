@@ -1,17 +1,164 @@
 // SPDX-License-Identifier: MPL-2.0
 // SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
 
-//! Health check endpoint
+//! Health check endpoints
+//!
+//! - `/health/live`: the process is up and serving; never touches
+//!   dependencies, so a slow database doesn't get the pod restarted
+//! - `/health/ready`: Postgres, Redis (if configured) and the schema are
+//!   usable, and the server isn't draining for shutdown
+//!
+//! Dependency errors are logged rather than returned, since the probes
+//! are public.
 
-use axum::Json;
+use std::future::Future;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+use axum::{extract::State, http::StatusCode, Json};
+use chrono::{DateTime, SecondsFormat};
 use serde::Serialize;
 
+use crate::db;
+use crate::state::AppState;
+
+/// How long each dependency check may take before it counts as down
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
 #[derive(Serialize)]
 pub struct HealthResponse {
     status: &'static str,
     version: &'static str,
 }
 
+/// What this binary was built from
+#[derive(Debug, Serialize)]
+pub struct BuildInfo {
+    pub version: &'static str,
+    pub commit: &'static str,
+    /// RFC 3339 build time
+    pub built_at: String,
+}
+
+impl BuildInfo {
+    /// Metadata recorded by `build.rs`
+    #[must_use]
+    pub fn current() -> Self {
+        let built_at = env!("CIVICCONNECT_BUILD_EPOCH")
+            .parse()
+            .ok()
+            .and_then(|epoch| DateTime::from_timestamp(epoch, 0))
+            .map_or_else(String::new, |at| {
+                at.to_rfc3339_opts(SecondsFormat::Secs, true)
+            });
+
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            commit: env!("CIVICCONNECT_GIT_COMMIT"),
+            built_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct LivenessResponse {
+    status: &'static str,
+    #[serde(flatten)]
+    build: BuildInfo,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReadinessResponse {
+    /// `ready`, `not_ready` or `draining`
+    pub status: &'static str,
+    #[serde(flatten)]
+    pub build: BuildInfo,
+    /// Absent while draining, when dependencies aren't checked
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checks: Option<Checks>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Checks {
+    pub database: Check,
+    pub redis: Check,
+    pub migrations: Check,
+}
+
+impl Checks {
+    /// Whether every configured dependency is usable
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        [&self.database, &self.redis, &self.migrations]
+            .iter()
+            .all(|check| check.status != CheckStatus::Down)
+    }
+}
+
+/// One dependency's state, and how long checking it took
+#[derive(Debug, Serialize)]
+pub struct Check {
+    pub status: CheckStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    /// Why the check failed, in terms safe to show publicly
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Up,
+    Down,
+    /// Not configured, so not required
+    Disabled,
+}
+
+impl Check {
+    const fn disabled() -> Self {
+        Self {
+            status: CheckStatus::Disabled,
+            latency_ms: None,
+            reason: None,
+        }
+    }
+
+    /// Time `check`, which is down if it takes longer than `limit`.
+    /// It resolves to a reason if the dependency is reachable but unusable.
+    async fn run<E: std::fmt::Display>(
+        name: &str,
+        limit: Duration,
+        check: impl Future<Output = Result<Option<String>, E>>,
+    ) -> Self {
+        let started = Instant::now();
+        let outcome = tokio::time::timeout(limit, check).await;
+        let latency_ms = Some(u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX));
+
+        let (status, reason) = match outcome {
+            Ok(Ok(None)) => (CheckStatus::Up, None),
+            Ok(Ok(Some(reason))) => (CheckStatus::Down, Some(reason)),
+            Ok(Err(e)) => {
+                tracing::warn!(check = name, error = %e, "Readiness check failed");
+                (CheckStatus::Down, Some("unavailable".to_string()))
+            }
+            Err(_) => {
+                tracing::warn!(check = name, "Readiness check timed out");
+                (
+                    CheckStatus::Down,
+                    Some(format!("timed out after {} ms", limit.as_millis())),
+                )
+            }
+        };
+
+        Self {
+            status,
+            latency_ms,
+            reason,
+        }
+    }
+}
+
 /// Health check endpoint
 /// GET /health
 pub async fn health_check() -> Json<HealthResponse> {
@@ -20,3 +167,116 @@ pub async fn health_check() -> Json<HealthResponse> {
         version: env!("CARGO_PKG_VERSION"),
     })
 }
+
+/// Liveness probe
+/// GET /health/live
+pub async fn live() -> Json<LivenessResponse> {
+    Json(LivenessResponse {
+        status: "alive",
+        build: BuildInfo::current(),
+    })
+}
+
+/// Readiness probe; 503 if a dependency is down or the server is draining
+/// GET /health/ready
+pub async fn ready(State(state): State<AppState>) -> (StatusCode, Json<ReadinessResponse>) {
+    let build = BuildInfo::current();
+    if state.draining.load(Ordering::Relaxed) {
+        let response = ReadinessResponse {
+            status: "draining",
+            build,
+            checks: None,
+        };
+        return (StatusCode::SERVICE_UNAVAILABLE, Json(response));
+    }
+
+    let (database, redis, migrations) = tokio::join!(
+        Check::run("database", CHECK_TIMEOUT, async {
+            sqlx::query("SELECT 1")
+                .execute(&state.db)
+                .await
+                .map(|_| None)
+        }),
+        async {
+            if state.config.redis_url.is_none() {
+                return Check::disabled();
+            }
+            Check::run("redis", CHECK_TIMEOUT, async {
+                state.realtime.ping().await.unwrap_or(Ok(())).map(|()| None)
+            })
+            .await
+        },
+        Check::run("migrations", CHECK_TIMEOUT, async {
+            db::pending_migrations(&state.db)
+                .await
+                .map(|pending| (pending > 0).then(|| format!("{pending} pending")))
+        }),
+    );
+
+    let checks = Checks {
+        database,
+        redis,
+        migrations,
+    };
+    let (code, status) = if checks.passed() {
+        (StatusCode::OK, "ready")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "not_ready")
+    };
+
+    let response = ReadinessResponse {
+        status,
+        build,
+        checks: Some(checks),
+    };
+    (code, Json(response))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn check(status: CheckStatus) -> Check {
+        Check {
+            status,
+            latency_ms: None,
+            reason: None,
+        }
+    }
+
+    #[test]
+    fn disabled_dependencies_dont_fail_readiness() {
+        let checks = Checks {
+            database: check(CheckStatus::Up),
+            redis: check(CheckStatus::Disabled),
+            migrations: check(CheckStatus::Up),
+        };
+        assert!(checks.passed());
+
+        let checks = Checks {
+            migrations: check(CheckStatus::Down),
+            ..checks
+        };
+        assert!(!checks.passed());
+    }
+
+    #[tokio::test]
+    async fn slow_checks_time_out() {
+        let check = Check::run("slow", Duration::from_millis(10), async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok::<_, std::convert::Infallible>(None)
+        })
+        .await;
+
+        assert_eq!(check.status, CheckStatus::Down);
+        assert!(check.reason.unwrap().starts_with("timed out"));
+    }
+
+    #[test]
+    fn build_info_has_a_commit_and_time() {
+        let build = BuildInfo::current();
+        assert!(!build.commit.is_empty());
+        assert!(DateTime::parse_from_rfc3339(&build.built_at).is_ok());
+    }
+}
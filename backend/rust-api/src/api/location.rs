@@ -14,13 +14,15 @@
 
 use axum::{extract::Query, Json};
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
 use crate::error::Result;
 
 use super::events::EventSummary;
 
 /// Nearby events query parameters
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct NearbyQuery {
     /// H3 cell ID at resolution 7 (~5km hexagon)
     pub cell: String,
@@ -32,12 +34,12 @@ pub struct NearbyQuery {
     pub upcoming_only: bool,
 }
 
-fn default_true() -> bool {
+const fn default_true() -> bool {
     true
 }
 
 /// Response with nearby events and cell info
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct NearbyResponse {
     pub events: Vec<EventSummary>,
     pub cells_searched: Vec<String>,
@@ -49,6 +51,21 @@ pub struct NearbyResponse {
 ///
 /// Note: Client computes H3 cell from GPS locally.
 /// Server never receives exact coordinates.
+///
+/// # Errors
+///
+/// Never fails; an invalid cell finds no events.
+#[utoipa::path(
+    get,
+    path = "/location/nearby",
+    tag = "events",
+    params(
+        NearbyQuery,
+    ),
+    responses(
+        (status = 200, description = "Events in and around the cell", body = NearbyResponse),
+    ),
+)]
 pub async fn nearby_events(Query(query): Query<NearbyQuery>) -> Result<Json<NearbyResponse>> {
     // Validate cell ID format (15 hex characters for resolution 7)
     if query.cell.len() != 15 || !query.cell.chars().all(|c| c.is_ascii_hexdigit()) {
@@ -60,14 +77,14 @@ pub async fn nearby_events(Query(query): Query<NearbyQuery>) -> Result<Json<Near
     }
 
     // Limit rings to prevent large queries
-    let rings = query.rings.min(2);
+    let _rings = query.rings.min(2);
 
     // TODO: Use h3o to compute neighboring cells
-    // TODO: Query database for events in those cells
+    // TODO: Query database for visible events in those cells
     // TODO: Filter by upcoming if requested
     // TODO: Sort by start time
 
-    let cells_searched = vec![query.cell.clone()];
+    let cells_searched = vec![query.cell];
     // Would add neighbor cells here based on rings
 
     Ok(Json(NearbyResponse {
@@ -0,0 +1,25 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Prometheus scrape endpoint
+//!
+//! Metric values are aggregate counts with no user data, but they do
+//! describe traffic; keep `/metrics` off the public ingress.
+
+use axum::{
+    extract::State,
+    http::header,
+    response::{IntoResponse, Response},
+};
+
+use crate::state::AppState;
+
+/// Metrics in the Prometheus text format
+/// GET /metrics
+pub async fn metrics(State(state): State<AppState>) -> Response {
+    (
+        [(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)],
+        state.metrics.render(&state.db),
+    )
+        .into_response()
+}
@@ -3,9 +3,24 @@
 
 //! API endpoint handlers
 
+pub mod admin;
 pub mod auth;
+pub mod calendar;
+pub mod credentials;
+pub mod duress;
 pub mod events;
+pub mod extract;
+pub mod federation;
 pub mod health;
 pub mod location;
+pub mod metrics;
+pub mod openapi;
+pub mod organizations;
+pub mod recovery;
+pub mod reports;
+pub mod stream;
+pub mod totp;
 pub mod users;
 pub mod verify;
+pub mod webauthn;
+pub mod webhooks;
@@ -0,0 +1,183 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Machine-readable description of API v1
+//!
+//! Generated from the handlers' `#[utoipa::path]` attributes and the
+//! request and response types, so clients can generate their types
+//! instead of hand-writing them. Paths are relative to `/api/v1`.
+//!
+//! Every route in `routes::api_v1_routes` must be listed in [`ApiDoc`];
+//! `tests/openapi.rs` fails when the two drift apart.
+
+use axum::Json;
+use utoipa::openapi::path::{ParameterBuilder, ParameterIn};
+use utoipa::openapi::security::{Http, HttpAuthScheme, SecurityScheme};
+use utoipa::openapi::{ObjectBuilder, OpenApi as OpenApiDocument, Required, Type};
+use utoipa::{Modify, OpenApi};
+
+use super::{
+    admin, auth, calendar, credentials, duress, events, location, organizations, recovery, reports,
+    stream, totp, users, verify, webauthn, webhooks,
+};
+
+/// The API v1 document
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "CivicConnect API"),
+    servers((url = "/api/v1")),
+    paths(
+        openapi_json,
+        auth::register,
+        auth::login,
+        webauthn::register_start,
+        webauthn::register_finish,
+        webauthn::login_start,
+        webauthn::login_finish,
+        totp::enroll,
+        totp::confirm,
+        totp::verify,
+        totp::regenerate_recovery_codes,
+        totp::disable,
+        totp::redeem_recovery_code,
+        recovery::request_reset,
+        recovery::confirm_reset,
+        users::get_current_user,
+        recovery::enable_recovery,
+        recovery::disable_recovery,
+        calendar::issue_feed,
+        calendar::revoke_feed,
+        verify::set_signing_key,
+        verify::remove_signing_key,
+        duress::set_duress_password,
+        duress::remove_duress_password,
+        duress::list_trusted_contacts,
+        duress::add_trusted_contact,
+        duress::remove_trusted_contact,
+        duress::duress_alerts,
+        users::get_user,
+        events::list_events,
+        events::create_event,
+        stream::event_updates,
+        events::get_event,
+        events::rsvp,
+        events::cancel_rsvp,
+        calendar::feed,
+        organizations::my_organizations,
+        events::set_organization,
+        events::list_co_organizers,
+        events::add_co_organizer,
+        events::remove_co_organizer,
+        organizations::create_organization,
+        organizations::get_organization,
+        organizations::list_members,
+        organizations::set_member_role,
+        organizations::remove_member,
+        organizations::create_invitation,
+        organizations::revoke_invitation,
+        organizations::analytics,
+        organizations::accept_invitation,
+        verify::verify_attendance,
+        verify::verify_batch,
+        verify::upload_checkins,
+        credentials::key,
+        credentials::issue,
+        credentials::redeem,
+        reports::create_report,
+        location::nearby_events,
+        admin::held_verifications,
+        admin::review_verification,
+        admin::hide_event,
+        admin::restore_event,
+        admin::suspend_user,
+        admin::unsuspend_user,
+        admin::reverse_experience,
+        admin::set_role,
+        admin::report_queue,
+        admin::triage_report,
+        webhooks::list_webhooks,
+        webhooks::create_webhook,
+        webhooks::delete_webhook,
+        webhooks::dead_letters,
+        webhooks::replay_dead_letters,
+        webhooks::replay_dead_letter,
+    ),
+    modifiers(&BearerAuth, &IdempotencyKey),
+    tags(
+        (name = "auth", description = "Registration, login and second factors"),
+        (name = "users", description = "Profiles and account settings"),
+        (name = "calendar", description = "iCalendar feeds"),
+        (name = "events", description = "Events, RSVPs and real-time updates"),
+        (name = "organizations", description = "Chapters, members and invitations"),
+        (name = "verification", description = "QR attendance verification"),
+        (name = "reports", description = "Abuse reports"),
+        (name = "admin", description = "Moderation"),
+        (name = "webhooks", description = "Outbound webhooks"),
+        (name = "meta", description = "This document"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Session tokens, sent as `Authorization: Bearer <token>`
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut OpenApiDocument) {
+        if let Some(components) = openapi.components.as_mut() {
+            components.add_security_scheme(
+                "bearer",
+                SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)),
+            );
+        }
+    }
+}
+
+/// The optional `Idempotency-Key` header on every POST and PUT (see
+/// [`crate::idempotency`])
+struct IdempotencyKey;
+
+impl Modify for IdempotencyKey {
+    fn modify(&self, openapi: &mut OpenApiDocument) {
+        let header = ParameterBuilder::new()
+            .name("Idempotency-Key")
+            .parameter_in(ParameterIn::Header)
+            .required(Required::False)
+            .description(Some(
+                "Random value repeated on retries; a retry gets the first response back",
+            ))
+            .schema(Some(
+                ObjectBuilder::new()
+                    .schema_type(Type::String)
+                    .min_length(Some(1))
+                    .max_length(Some(255)),
+            ))
+            .build();
+
+        let operations = openapi
+            .paths
+            .paths
+            .values_mut()
+            .flat_map(|item| [item.post.as_mut(), item.put.as_mut()])
+            .flatten();
+        for operation in operations {
+            operation
+                .parameters
+                .get_or_insert_with(Vec::new)
+                .push(header.clone());
+        }
+    }
+}
+
+/// This document
+/// GET /api/v1/openapi.json
+#[utoipa::path(
+    get,
+    path = "/openapi.json",
+    tag = "meta",
+    responses(
+        (status = 200, description = "OpenAPI 3.1 document", body = Object),
+    ),
+)]
+pub async fn openapi_json() -> Json<OpenApiDocument> {
+    Json(ApiDoc::openapi())
+}
@@ -0,0 +1,826 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Organization (chapter) endpoints
+//!
+//! Any organizer can found a chapter and becomes its owner. Others join
+//! through invitation links, which carry a secret token shown once; owners
+//! and admins then manage roles. See [`crate::organizations`] for how
+//! roles and platform levels combine into permissions.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+use validator::Validate;
+
+use super::extract::AuthUser;
+use crate::crypto;
+use crate::db::{
+    self,
+    models::{Organization, OrganizationRole},
+    organizations::{Invitation, MemberListing, Membership, NewInvitation, NewOrganization},
+};
+use crate::error::{ApiError, Result};
+use crate::organizations::{self, Capability};
+use crate::state::AppState;
+
+/// Create an organization
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CreateOrganizationRequest {
+    #[validate(length(min = 3, max = 100))]
+    pub name: String,
+    /// Lowercase letters and digits in dash-separated words
+    pub slug: String,
+    #[serde(default)]
+    #[validate(length(max = 2000))]
+    pub description: String,
+}
+
+/// Change a member's role
+#[derive(Debug, Deserialize, ToSchema)]
+#[schema(as = MemberRoleRequest)]
+pub struct SetRoleRequest {
+    pub role: OrganizationRole,
+}
+
+/// Issue an invitation link
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CreateInvitationRequest {
+    /// `member` or `admin`; owners are made by promotion
+    #[serde(default = "default_invitation_role")]
+    pub role: OrganizationRole,
+    #[serde(default = "default_expires_in_hours")]
+    #[validate(range(min = 1, max = 720))]
+    pub expires_in_hours: i64,
+    #[serde(default = "default_max_uses")]
+    #[validate(range(min = 1, max = 1000))]
+    pub max_uses: i32,
+}
+
+const fn default_invitation_role() -> OrganizationRole {
+    OrganizationRole::Member
+}
+
+const fn default_expires_in_hours() -> i64 {
+    24 * 7
+}
+
+const fn default_max_uses() -> i32 {
+    1
+}
+
+/// Window for chapter analytics
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AnalyticsQuery {
+    #[serde(default = "default_since_days")]
+    pub since_days: i64,
+}
+
+const fn default_since_days() -> i64 {
+    30
+}
+
+/// An organization
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OrganizationResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub slug: String,
+    pub description: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<Organization> for OrganizationResponse {
+    fn from(organization: Organization) -> Self {
+        Self {
+            id: organization.id,
+            name: organization.name,
+            slug: organization.slug,
+            description: organization.description,
+            created_at: organization.created_at,
+        }
+    }
+}
+
+/// An organization's public page
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OrganizationDetails {
+    #[serde(flatten)]
+    pub organization: OrganizationResponse,
+    pub member_count: i64,
+}
+
+/// An organization the caller belongs to, with their role
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MembershipResponse {
+    #[serde(flatten)]
+    pub organization: OrganizationResponse,
+    pub role: OrganizationRole,
+}
+
+impl From<Membership> for MembershipResponse {
+    fn from(membership: Membership) -> Self {
+        Self {
+            organization: membership.organization.into(),
+            role: membership.role,
+        }
+    }
+}
+
+/// A member
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MemberResponse {
+    pub user_id: Uuid,
+    pub username: String,
+    pub level: u8,
+    pub role: OrganizationRole,
+    pub joined_at: DateTime<Utc>,
+}
+
+impl From<MemberListing> for MemberResponse {
+    fn from(member: MemberListing) -> Self {
+        Self {
+            user_id: member.user_id,
+            username: member.username,
+            level: u8::try_from(member.current_level).unwrap_or(0),
+            role: member.role,
+            joined_at: member.joined_at,
+        }
+    }
+}
+
+/// An invitation link
+#[derive(Debug, Serialize, ToSchema)]
+pub struct InvitationResponse {
+    pub id: Uuid,
+    pub role: OrganizationRole,
+    pub max_uses: i32,
+    pub uses: i32,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<Invitation> for InvitationResponse {
+    fn from(invitation: Invitation) -> Self {
+        Self {
+            id: invitation.id,
+            role: invitation.role,
+            max_uses: invitation.max_uses,
+            uses: invitation.uses,
+            expires_at: invitation.expires_at,
+            created_at: invitation.created_at,
+        }
+    }
+}
+
+/// A new invitation, with its secret URL
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreatedInvitationResponse {
+    #[serde(flatten)]
+    pub invitation: InvitationResponse,
+    /// Shown only once; anyone with it can join until it runs out
+    pub url: String,
+}
+
+/// Chapter-wide counts
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AnalyticsResponse {
+    pub since: DateTime<Utc>,
+    pub members: i64,
+    pub admins: i64,
+    pub owners: i64,
+    /// Members who joined since `since`
+    pub members_joined: i64,
+    /// Visible chapter events starting since `since`
+    pub events: i64,
+    pub upcoming_events: i64,
+    /// RSVPs to those events
+    pub rsvps: i64,
+    /// Verified attendance at those events
+    pub verified_attendance: i64,
+}
+
+/// A user's role and level in an organization
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Standing {
+    pub role: Option<OrganizationRole>,
+    pub level: i16,
+}
+
+impl Standing {
+    /// Whether they're a member with `capability`
+    pub(crate) fn can(self, capability: Capability) -> bool {
+        self.role
+            .is_some_and(|role| organizations::can(role, self.level, capability))
+    }
+}
+
+/// A user's standing in an organization, which must exist
+pub(crate) async fn standing(
+    state: &AppState,
+    organization_id: Uuid,
+    user_id: Uuid,
+) -> Result<Standing> {
+    if db::organizations::find(&state.db, organization_id)
+        .await?
+        .is_none()
+    {
+        return Err(ApiError::OrganizationNotFound);
+    }
+    let role =
+        db::organizations::role_of(&mut *state.db.acquire().await?, organization_id, user_id)
+            .await?;
+    let level = db::users::find_by_id(&state.db, user_id)
+        .await?
+        .ok_or(ApiError::UserNotFound)?
+        .current_level;
+
+    Ok(Standing { role, level })
+}
+
+/// The caller's standing in an organization, which must exist
+///
+/// Under duress the caller belongs to no organization, so they get what a
+/// non-member would.
+async fn caller_standing(
+    state: &AppState,
+    organization_id: Uuid,
+    auth: &AuthUser,
+) -> Result<Standing> {
+    let standing = standing(state, organization_id, auth.id).await?;
+    Ok(if auth.sanitized {
+        Standing {
+            role: None,
+            ..standing
+        }
+    } else {
+        standing
+    })
+}
+
+/// Found an organization, as its owner
+/// POST /api/v1/organizations
+///
+/// # Errors
+///
+/// Returns [`ApiError::InvalidInput`] for invalid input or slug,
+/// [`ApiError::Forbidden`] unless the caller is an organizer,
+/// [`ApiError::SlugTaken`] if the slug is in use, or an error if a query fails.
+#[utoipa::path(
+    post,
+    path = "/organizations",
+    tag = "organizations",
+    request_body = CreateOrganizationRequest,
+    responses(
+        (status = 201, description = "The new organization", body = OrganizationDetails),
+        (status = 400, description = "Invalid input", body = crate::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 403, description = "Not allowed", body = crate::error::ErrorResponse),
+        (status = 409, description = "Slug already taken", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn create_organization(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(req): Json<CreateOrganizationRequest>,
+) -> Result<(StatusCode, Json<OrganizationDetails>)> {
+    req.validate()
+        .map_err(|e| ApiError::InvalidInput(e.to_string()))?;
+    if !organizations::is_valid_slug(&req.slug) {
+        return Err(ApiError::InvalidInput(
+            "Slug must be 3-50 lowercase letters and digits in dash-separated words".into(),
+        ));
+    }
+
+    let user = db::users::find_by_id(&state.db, auth.id)
+        .await?
+        .ok_or(ApiError::UserNotFound)?;
+    if !organizations::can_hold(OrganizationRole::Owner, user.current_level) {
+        return Err(ApiError::Forbidden);
+    }
+
+    let mut tx = state.db.begin().await?;
+    let organization = db::organizations::create(
+        &mut tx,
+        NewOrganization {
+            name: &req.name,
+            slug: &req.slug,
+            description: &req.description,
+            created_by: user.id,
+        },
+    )
+    .await
+    .map_err(|e| match e {
+        ApiError::Database(ref err) if db::is_unique_violation(err) => ApiError::SlugTaken,
+        other => other,
+    })?;
+    tx.commit().await?;
+
+    tracing::info!(user_id = %user.id, organization_id = %organization.id, "Organization created");
+
+    Ok((
+        StatusCode::CREATED,
+        Json(OrganizationDetails {
+            organization: organization.into(),
+            member_count: 1,
+        }),
+    ))
+}
+
+/// An organization's public page
+/// GET /api/v1/organizations/:id
+///
+/// # Errors
+///
+/// Returns [`ApiError::OrganizationNotFound`] for an unknown organization, or
+/// an error if a query fails.
+#[utoipa::path(
+    get,
+    path = "/organizations/{id}",
+    tag = "organizations",
+    params(
+        ("id" = Uuid, Path, description = "Organization ID"),
+    ),
+    responses(
+        (status = 200, description = "The organization", body = OrganizationDetails),
+        (status = 404, description = "No such organization", body = crate::error::ErrorResponse),
+    ),
+)]
+pub async fn get_organization(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<OrganizationDetails>> {
+    let organization = db::organizations::find(&state.db, id)
+        .await?
+        .ok_or(ApiError::OrganizationNotFound)?;
+    let member_count = db::organizations::member_count(&state.db, id).await?;
+
+    Ok(Json(OrganizationDetails {
+        organization: organization.into(),
+        member_count,
+    }))
+}
+
+/// The organization's members, visible to members
+/// GET /api/v1/organizations/:id/members
+///
+/// # Errors
+///
+/// Returns [`ApiError::OrganizationNotFound`] for an unknown organization,
+/// [`ApiError::Forbidden`] unless the caller is a member, or an error if a
+/// query fails.
+#[utoipa::path(
+    get,
+    path = "/organizations/{id}/members",
+    tag = "organizations",
+    params(
+        ("id" = Uuid, Path, description = "Organization ID"),
+    ),
+    responses(
+        (status = 200, description = "Members, by role then join date", body = Vec<MemberResponse>),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 403, description = "Not allowed", body = crate::error::ErrorResponse),
+        (status = 404, description = "No such organization", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn list_members(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<MemberResponse>>> {
+    if caller_standing(&state, id, &auth).await?.role.is_none() {
+        return Err(ApiError::Forbidden);
+    }
+    let members = db::organizations::members(&state.db, id).await?;
+
+    Ok(Json(members.into_iter().map(Into::into).collect()))
+}
+
+/// Change a member's role
+/// PUT `/api/v1/organizations/:id/members/:user_id`
+///
+/// Owners may change any role; admins only manage ordinary members. Only
+/// organizers may be admins or owners, and the last owner can't step down.
+///
+/// # Errors
+///
+/// Returns [`ApiError::UserNotFound`] for an unknown user,
+/// [`ApiError::OrganizationNotFound`] for an unknown organization,
+/// [`ApiError::Forbidden`] unless the caller may make the change,
+/// [`ApiError::InvalidInput`] for a non-member, a role the user can't hold, or
+/// demoting the last owner, or an error if a query fails.
+#[utoipa::path(
+    put,
+    path = "/organizations/{id}/members/{user_id}",
+    tag = "organizations",
+    request_body = SetRoleRequest,
+    params(
+        ("id" = Uuid, Path, description = "Organization ID"),
+        ("user_id" = Uuid, Path, description = "User ID"),
+    ),
+    responses(
+        (status = 204, description = "Role changed"),
+        (status = 400, description = "Invalid input", body = crate::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 403, description = "Not allowed", body = crate::error::ErrorResponse),
+        (status = 404, description = "No such organization or user", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn set_member_role(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((id, user_id)): Path<(Uuid, Uuid)>,
+    Json(req): Json<SetRoleRequest>,
+) -> Result<StatusCode> {
+    let target = db::users::find_by_id(&state.db, user_id)
+        .await?
+        .ok_or(ApiError::UserNotFound)?;
+
+    // The lock serializes membership changes, so two owners can't demote
+    // each other at once
+    let mut tx = state.db.begin().await?;
+    if !db::organizations::lock(&mut tx, id).await? {
+        return Err(ApiError::OrganizationNotFound);
+    }
+    let actor = db::organizations::role_of(&mut tx, id, auth.id)
+        .await?
+        .filter(|_| !auth.sanitized)
+        .ok_or(ApiError::Forbidden)?;
+    let Some(from) = db::organizations::role_of(&mut tx, id, user_id).await? else {
+        return Err(ApiError::InvalidInput(
+            "Members join through invitation links".into(),
+        ));
+    };
+    if !organizations::can_change_role(actor, Some(from), Some(req.role)) {
+        return Err(ApiError::Forbidden);
+    }
+    if !organizations::can_hold(req.role, target.current_level) {
+        return Err(ApiError::InvalidInput(
+            "Only organizers can be admins or owners".into(),
+        ));
+    }
+    if from == OrganizationRole::Owner
+        && req.role != OrganizationRole::Owner
+        && db::organizations::owner_count(&mut tx, id).await? == 1
+    {
+        return Err(ApiError::InvalidInput(
+            "An organization must keep an owner".into(),
+        ));
+    }
+    db::organizations::set_role(&mut tx, id, user_id, req.role).await?;
+    tx.commit().await?;
+
+    tracing::info!(
+        organization_id = %id,
+        actor_id = %auth.id,
+        user_id = %user_id,
+        role = ?req.role,
+        "Organization role changed"
+    );
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Remove a member, or leave
+/// DELETE `/api/v1/organizations/:id/members/:user_id`
+///
+/// # Errors
+///
+/// Returns [`ApiError::OrganizationNotFound`] for an unknown organization,
+/// [`ApiError::UserNotFound`] for a non-member, [`ApiError::Forbidden`] unless
+/// the caller is leaving or may remove them, [`ApiError::InvalidInput`] for the
+/// last owner, or an error if a query fails.
+#[utoipa::path(
+    delete,
+    path = "/organizations/{id}/members/{user_id}",
+    tag = "organizations",
+    params(
+        ("id" = Uuid, Path, description = "Organization ID"),
+        ("user_id" = Uuid, Path, description = "User ID"),
+    ),
+    responses(
+        (status = 204, description = "Member removed"),
+        (status = 400, description = "Invalid input", body = crate::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 403, description = "Not allowed", body = crate::error::ErrorResponse),
+        (status = 404, description = "No such organization or member", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn remove_member(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((id, user_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode> {
+    let mut tx = state.db.begin().await?;
+    if !db::organizations::lock(&mut tx, id).await? {
+        return Err(ApiError::OrganizationNotFound);
+    }
+    let actor = db::organizations::role_of(&mut tx, id, auth.id)
+        .await?
+        .filter(|_| !auth.sanitized)
+        .ok_or(ApiError::Forbidden)?;
+    let from = db::organizations::role_of(&mut tx, id, user_id)
+        .await?
+        .ok_or(ApiError::UserNotFound)?;
+    if user_id != auth.id && !organizations::can_change_role(actor, Some(from), None) {
+        return Err(ApiError::Forbidden);
+    }
+    if from == OrganizationRole::Owner && db::organizations::owner_count(&mut tx, id).await? == 1 {
+        return Err(ApiError::InvalidInput(
+            "An organization must keep an owner".into(),
+        ));
+    }
+    db::organizations::remove_member(&mut tx, id, user_id).await?;
+    tx.commit().await?;
+
+    tracing::info!(organization_id = %id, actor_id = %auth.id, user_id = %user_id, "Member removed");
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Issue an invitation link
+/// POST /api/v1/organizations/:id/invitations
+///
+/// # Errors
+///
+/// Returns [`ApiError::InvalidInput`] for invalid input or an owner invitation,
+/// [`ApiError::OrganizationNotFound`] for an unknown organization,
+/// [`ApiError::Forbidden`] unless the caller may invite to the role, or an
+/// error if a query fails.
+#[utoipa::path(
+    post,
+    path = "/organizations/{id}/invitations",
+    tag = "organizations",
+    request_body = CreateInvitationRequest,
+    params(
+        ("id" = Uuid, Path, description = "Organization ID"),
+    ),
+    responses(
+        (status = 201, description = "The invitation, with its URL shown once", body = CreatedInvitationResponse),
+        (status = 400, description = "Invalid input", body = crate::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 403, description = "Not allowed", body = crate::error::ErrorResponse),
+        (status = 404, description = "No such organization", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn create_invitation(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(req): Json<CreateInvitationRequest>,
+) -> Result<(StatusCode, Json<CreatedInvitationResponse>)> {
+    req.validate()
+        .map_err(|e| ApiError::InvalidInput(e.to_string()))?;
+    if req.role == OrganizationRole::Owner {
+        return Err(ApiError::InvalidInput(
+            "Owners are made by promotion, not invitation".into(),
+        ));
+    }
+
+    let standing = caller_standing(&state, id, &auth).await?;
+    let allowed = standing.can(Capability::Invite)
+        && standing
+            .role
+            .is_some_and(|actor| organizations::can_change_role(actor, None, Some(req.role)));
+    if !allowed {
+        return Err(ApiError::Forbidden);
+    }
+
+    let token = crypto::generate_nonce();
+    let invitation = db::organizations::create_invitation(
+        &state.db,
+        NewInvitation {
+            organization_id: id,
+            token_hash: &crypto::hash_token(&token),
+            role: req.role,
+            created_by: auth.id,
+            max_uses: req.max_uses,
+            expires_at: Utc::now() + Duration::hours(req.expires_in_hours),
+        },
+    )
+    .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedInvitationResponse {
+            invitation: invitation.into(),
+            url: format!(
+                "{}/api/v1/invitations/{token}/accept",
+                state.config.public_url
+            ),
+        }),
+    ))
+}
+
+/// Revoke an invitation link
+/// DELETE `/api/v1/organizations/:id/invitations/:invitation_id`
+///
+/// # Errors
+///
+/// Returns [`ApiError::OrganizationNotFound`] for an unknown organization,
+/// [`ApiError::Forbidden`] unless the caller may invite,
+/// [`ApiError::InvitationNotFound`] for an unknown invitation, or an error if a
+/// query fails.
+#[utoipa::path(
+    delete,
+    path = "/organizations/{id}/invitations/{invitation_id}",
+    tag = "organizations",
+    params(
+        ("id" = Uuid, Path, description = "Organization ID"),
+        ("invitation_id" = Uuid, Path, description = "Invitation ID"),
+    ),
+    responses(
+        (status = 204, description = "Invitation revoked"),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 403, description = "Not allowed", body = crate::error::ErrorResponse),
+        (status = 404, description = "No such organization or invitation", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn revoke_invitation(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((id, invitation_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode> {
+    if !caller_standing(&state, id, &auth)
+        .await?
+        .can(Capability::Invite)
+    {
+        return Err(ApiError::Forbidden);
+    }
+    if !db::organizations::revoke_invitation(&state.db, id, invitation_id).await? {
+        return Err(ApiError::InvitationNotFound);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Join an organization through an invitation link
+/// POST /api/v1/invitations/:token/accept
+///
+/// Existing members keep their role if it's higher than the invitation's.
+///
+/// # Errors
+///
+/// Returns [`ApiError::InvalidToken`] for an unknown, expired or used-up link,
+/// [`ApiError::Forbidden`] if the caller can't hold the role, or an error if a
+/// query fails.
+#[utoipa::path(
+    post,
+    path = "/invitations/{token}/accept",
+    tag = "organizations",
+    params(
+        ("token" = String, Path, description = "Invitation token"),
+    ),
+    responses(
+        (status = 200, description = "The caller's membership", body = MembershipResponse),
+        (status = 400, description = "Invalid, expired or used-up invitation", body = crate::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 403, description = "Not allowed", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn accept_invitation(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(token): Path<String>,
+) -> Result<Json<MembershipResponse>> {
+    let user = db::users::find_by_id(&state.db, auth.id)
+        .await?
+        .ok_or(ApiError::UserNotFound)?;
+
+    let mut tx = state.db.begin().await?;
+    let invitation = db::organizations::redeem_invitation(&mut tx, &crypto::hash_token(&token))
+        .await?
+        .ok_or(ApiError::InvalidToken)?;
+    let id = invitation.organization_id;
+    if !db::organizations::lock(&mut tx, id).await? {
+        return Err(ApiError::OrganizationNotFound);
+    }
+
+    let current = db::organizations::role_of(&mut tx, id, user.id).await?;
+    let role = match current {
+        Some(current) if current >= invitation.role => current,
+        _ => {
+            if !organizations::can_hold(invitation.role, user.current_level) {
+                return Err(ApiError::Forbidden);
+            }
+            db::organizations::set_role(&mut tx, id, user.id, invitation.role).await?;
+            invitation.role
+        }
+    };
+    tx.commit().await?;
+
+    let organization = db::organizations::find(&state.db, id)
+        .await?
+        .ok_or(ApiError::OrganizationNotFound)?;
+
+    tracing::info!(organization_id = %id, user_id = %user.id, invitation_id = %invitation.id, "Invitation accepted");
+
+    Ok(Json(MembershipResponse {
+        organization: organization.into(),
+        role,
+    }))
+}
+
+/// Chapter-wide counts, over the last `since_days` days (at most a year)
+/// GET /api/v1/organizations/:id/analytics
+///
+/// # Errors
+///
+/// Returns [`ApiError::InvalidInput`] unless `since_days` is 1 to 365,
+/// [`ApiError::OrganizationNotFound`] for an unknown organization,
+/// [`ApiError::Forbidden`] unless the caller may view analytics, or an error if
+/// a query fails.
+#[utoipa::path(
+    get,
+    path = "/organizations/{id}/analytics",
+    tag = "organizations",
+    params(
+        ("id" = Uuid, Path, description = "Organization ID"),
+        AnalyticsQuery,
+    ),
+    responses(
+        (status = 200, description = "Chapter-wide counts", body = AnalyticsResponse),
+        (status = 400, description = "Invalid input", body = crate::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 403, description = "Not allowed", body = crate::error::ErrorResponse),
+        (status = 404, description = "No such organization", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn analytics(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<Uuid>,
+    Query(query): Query<AnalyticsQuery>,
+) -> Result<Json<AnalyticsResponse>> {
+    if !(1..=365).contains(&query.since_days) {
+        return Err(ApiError::InvalidInput(
+            "since_days must be between 1 and 365".into(),
+        ));
+    }
+    if !caller_standing(&state, id, &auth)
+        .await?
+        .can(Capability::ViewAnalytics)
+    {
+        return Err(ApiError::Forbidden);
+    }
+
+    let since = Utc::now() - Duration::days(query.since_days);
+    let counts = db::organizations::stats(&state.db, id, since).await?;
+
+    Ok(Json(AnalyticsResponse {
+        since,
+        members: counts.members,
+        admins: counts.admins,
+        owners: counts.owners,
+        members_joined: counts.members_joined,
+        events: counts.events,
+        upcoming_events: counts.upcoming_events,
+        rsvps: counts.rsvps,
+        verified_attendance: counts.verified_attendance,
+    }))
+}
+
+/// The organizations the caller belongs to
+/// GET /api/v1/users/me/organizations
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+#[utoipa::path(
+    get,
+    path = "/users/me/organizations",
+    tag = "organizations",
+    responses(
+        (status = 200, description = "The caller's memberships, by name", body = Vec<MembershipResponse>),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn my_organizations(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<Vec<MembershipResponse>>> {
+    // Under duress the caller's network stays hidden
+    if auth.sanitized {
+        return Ok(Json(Vec::new()));
+    }
+
+    let memberships = db::organizations::memberships(&state.db, auth.id).await?;
+
+    Ok(Json(memberships.into_iter().map(Into::into).collect()))
+}
@@ -0,0 +1,253 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Account recovery endpoints
+//!
+//! Recovery is opt-in: only users who chose to store an encrypted copy of
+//! their email can receive a reset link. Reset tokens are single-use,
+//! expire after 30 minutes, and are stored only as hashes.
+//!
+//! Privacy: the request endpoint answers the same way, in the same time,
+//! whether or not the account exists or has recovery enabled.
+
+use axum::{extract::State, http::StatusCode, Json};
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use super::extract::AuthUser;
+use crate::crypto;
+use crate::db;
+use crate::error::{ApiError, Result};
+use crate::mail::Mail;
+use crate::state::AppState;
+use crate::telemetry::redact::Redacted;
+
+/// Reset token lifetime
+const RESET_TOKEN_TTL_MINUTES: i64 = 30;
+
+/// Password reset request
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct ResetRequest {
+    #[validate(email)]
+    #[schema(value_type = String)]
+    pub email: Redacted<String>,
+}
+
+/// Password reset confirmation
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct ResetConfirm {
+    #[schema(value_type = String)]
+    pub token: Redacted<String>,
+    #[validate(length(min = 12))]
+    #[schema(value_type = String)]
+    pub new_password: Redacted<String>,
+}
+
+/// Recovery email opt-in
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct RecoveryEmailRequest {
+    #[validate(email)]
+    #[schema(value_type = String)]
+    pub email: Redacted<String>,
+}
+
+/// Request a password reset link
+/// POST /api/v1/auth/password-reset/request
+///
+/// # Errors
+///
+/// Returns [`ApiError::InvalidInput`] for invalid input. Delivery failures are
+/// logged, not returned.
+#[utoipa::path(
+    post,
+    path = "/auth/password-reset/request",
+    tag = "auth",
+    request_body = ResetRequest,
+    responses(
+        (status = 202, description = "A link is sent if the email has recovery enabled"),
+        (status = 400, description = "Invalid input", body = crate::error::ErrorResponse),
+    ),
+)]
+pub async fn request_reset(
+    State(state): State<AppState>,
+    Json(req): Json<ResetRequest>,
+) -> Result<StatusCode> {
+    req.validate()
+        .map_err(|e| ApiError::InvalidInput(e.to_string()))?;
+
+    // Lookup and delivery happen off the request path, so neither the
+    // status nor the latency reveals whether the account exists
+    tokio::spawn(async move {
+        if let Err(e) = send_reset_link(&state, req.email.expose()).await {
+            tracing::error!(error = ?e, "Password reset delivery failed");
+        }
+    });
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// Set a new password using a reset token
+/// POST /api/v1/auth/password-reset/confirm
+///
+/// Every existing session is revoked, so whoever prompted the reset by
+/// taking over the account is signed out.
+///
+/// # Errors
+///
+/// Returns [`ApiError::InvalidInput`] for invalid input,
+/// [`ApiError::InvalidToken`] for an unknown, used or expired token, or an
+/// error if hashing or a query fails.
+#[utoipa::path(
+    post,
+    path = "/auth/password-reset/confirm",
+    tag = "auth",
+    request_body = ResetConfirm,
+    responses(
+        (status = 204, description = "Password changed"),
+        (status = 400, description = "Invalid input", body = crate::error::ErrorResponse),
+    ),
+)]
+pub async fn confirm_reset(
+    State(state): State<AppState>,
+    Json(req): Json<ResetConfirm>,
+) -> Result<StatusCode> {
+    req.validate()
+        .map_err(|e| ApiError::InvalidInput(e.to_string()))?;
+
+    let token_hash = crypto::hash_token(req.token.expose());
+
+    // Cheap check first so junk tokens don't cost an Argon2id hash
+    if !db::password_resets::is_valid(&state.db, &token_hash).await? {
+        return Err(ApiError::InvalidToken);
+    }
+
+    let password_hash = state.passwords.hash(req.new_password.into_inner()).await?;
+    let user_id = db::password_resets::consume(&state.db, &token_hash, &password_hash)
+        .await?
+        .ok_or(ApiError::InvalidToken)?;
+
+    tracing::info!(user_id = %user_id, "Password reset completed");
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Opt in to account recovery by storing the account email encrypted
+/// PUT /api/v1/users/me/recovery-email
+///
+/// # Errors
+///
+/// Returns [`ApiError::InvalidInput`] for invalid input, an email other than
+/// the account's, or when account recovery is not enabled, or an error if
+/// encryption or a query fails.
+#[utoipa::path(
+    put,
+    path = "/users/me/recovery-email",
+    tag = "users",
+    request_body = RecoveryEmailRequest,
+    responses(
+        (status = 204, description = "Recovery enabled"),
+        (status = 400, description = "Invalid input", body = crate::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn enable_recovery(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(req): Json<RecoveryEmailRequest>,
+) -> Result<StatusCode> {
+    req.validate()
+        .map_err(|e| ApiError::InvalidInput(e.to_string()))?;
+
+    let vault = state.email_vault.as_ref().ok_or_else(|| {
+        ApiError::InvalidInput("Account recovery is not enabled on this server".into())
+    })?;
+
+    let user = db::users::find_by_id(&state.db, auth.id)
+        .await?
+        .ok_or(ApiError::UserNotFound)?;
+
+    // Only the account's own email may be stored; anything else would let
+    // a stolen session redirect resets to an attacker's inbox
+    let matches = state
+        .email_index
+        .candidates(req.email.expose())
+        .into_iter()
+        .any(|(version, hash)| version == user.email_key_version && hash == user.email_hash);
+    if !matches {
+        return Err(ApiError::InvalidInput(
+            "Email does not match this account".into(),
+        ));
+    }
+
+    let encrypted = vault.encrypt(user.id, req.email.expose())?;
+    db::users::set_recovery_email(&state.db, user.id, Some(&encrypted)).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Opt out of account recovery, deleting the stored email
+/// DELETE /api/v1/users/me/recovery-email
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+#[utoipa::path(
+    delete,
+    path = "/users/me/recovery-email",
+    tag = "users",
+    responses(
+        (status = 204, description = "Recovery disabled"),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn disable_recovery(State(state): State<AppState>, auth: AuthUser) -> Result<StatusCode> {
+    // Accepted but not saved under duress, so the real account keeps its
+    // way back in
+    if !auth.sanitized {
+        db::users::set_recovery_email(&state.db, auth.id, None).await?;
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Issue a reset token and mail it, if the account has recovery enabled
+async fn send_reset_link(state: &AppState, email: &str) -> Result<()> {
+    let Some(vault) = &state.email_vault else {
+        return Ok(());
+    };
+    let Some(user) = db::users::find_by_email(&state.db, &state.email_index, email).await? else {
+        return Ok(());
+    };
+    let Some(encrypted) = &user.email_encrypted else {
+        return Ok(());
+    };
+
+    let to = vault.decrypt(user.id, encrypted)?;
+    let token = crypto::generate_nonce();
+    db::password_resets::insert(
+        &state.db,
+        user.id,
+        &crypto::hash_token(&token),
+        Utc::now() + Duration::minutes(RESET_TOKEN_TTL_MINUTES),
+    )
+    .await?;
+
+    state
+        .mailer
+        .send(Mail {
+            to,
+            subject: "Reset your CivicConnect password".into(),
+            body: format!(
+                "Someone asked to reset the password for your CivicConnect account.\n\n\
+                 To choose a new password, open:\n{}/reset-password#token={token}\n\n\
+                 The link expires in {RESET_TOKEN_TTL_MINUTES} minutes and works once.\n\
+                 If this wasn't you, ignore this message.",
+                state.config.public_url
+            ),
+        })
+        .await
+}
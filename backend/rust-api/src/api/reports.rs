@@ -0,0 +1,154 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Abuse reporting
+//!
+//! Any user can report a user, an event, or a message they received.
+//! Reports go to the moderation queue (see [`super::admin`]); the reported
+//! party is never told who reported them.
+//!
+//! Report-bombing is limited two ways: each reporter has one unresolved
+//! report per target (repeats return the existing report), and a daily
+//! cap on reports filed.
+
+use axum::{extract::State, http::StatusCode, Json};
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+use super::extract::AuthUser;
+use crate::db::{
+    self,
+    models::{ReportCategory, ReportStatus, ReportTarget},
+    reports::NewReport,
+};
+use crate::error::{ApiError, Result};
+use crate::state::AppState;
+
+/// Reports one user may file per day
+const MAX_REPORTS_PER_DAY: i64 = 10;
+
+/// A new report
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CreateReportRequest {
+    pub target_type: ReportTarget,
+    pub target_id: Uuid,
+    pub category: ReportCategory,
+    #[validate(length(min = 1, max = 2000))]
+    pub description: String,
+    /// Decrypted content of the reported message (message reports only)
+    #[validate(length(min = 1, max = 10000))]
+    pub evidence: Option<String>,
+}
+
+/// Acknowledgement to the reporter
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReportReceipt {
+    pub report_id: Uuid,
+    pub status: ReportStatus,
+    /// An unresolved report of the same target already existed
+    pub duplicate: bool,
+}
+
+/// File an abuse report
+/// POST /api/v1/reports
+///
+/// Returns 201 for a new report, or 200 with the existing report if the
+/// caller already has an unresolved report on the same target.
+///
+/// # Errors
+///
+/// Returns [`ApiError::InvalidInput`] for invalid input, a report on the
+/// caller, or a target they can't report, [`ApiError::RateLimited`] past the
+/// daily cap, or an error if a query fails.
+#[utoipa::path(
+    post,
+    path = "/reports",
+    tag = "reports",
+    request_body = CreateReportRequest,
+    responses(
+        (status = 201, description = "Report filed", body = ReportReceipt),
+        (status = 200, description = "The caller's existing unresolved report", body = ReportReceipt),
+        (status = 400, description = "Invalid input", body = crate::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 429, description = "Too many attempts", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn create_report(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(req): Json<CreateReportRequest>,
+) -> Result<(StatusCode, Json<ReportReceipt>)> {
+    req.validate()
+        .map_err(|e| ApiError::InvalidInput(e.to_string()))?;
+
+    if req.evidence.is_some() != (req.target_type == ReportTarget::Message) {
+        return Err(ApiError::InvalidInput(
+            "Evidence is required for message reports, and only for them".into(),
+        ));
+    }
+    if req.target_type == ReportTarget::User && req.target_id == auth.id {
+        return Err(ApiError::InvalidInput("You cannot report yourself".into()));
+    }
+    // A sanitized session has no messages to report
+    let hidden = auth.sanitized && req.target_type == ReportTarget::Message;
+    if hidden
+        || !db::reports::target_reportable(&state.db, auth.id, req.target_type, req.target_id)
+            .await?
+    {
+        return Err(ApiError::InvalidInput(
+            "Nothing to report with that ID".into(),
+        ));
+    }
+
+    // Lock the reporter so concurrent reports can't slip past the cap
+    let mut tx = state.db.begin().await?;
+    db::users::find_for_update(&mut tx, auth.id).await?;
+
+    if let Some(existing) =
+        db::reports::find_unresolved(&mut tx, auth.id, req.target_type, req.target_id).await?
+    {
+        return Ok((
+            StatusCode::OK,
+            Json(ReportReceipt {
+                report_id: existing.id,
+                status: existing.status,
+                duplicate: true,
+            }),
+        ));
+    }
+
+    let filed = db::reports::count_since(&mut tx, auth.id, Utc::now() - Duration::days(1)).await?;
+    if filed >= MAX_REPORTS_PER_DAY {
+        return Err(ApiError::RateLimited);
+    }
+
+    let report = db::reports::insert(
+        &mut tx,
+        NewReport {
+            reporter_id: auth.id,
+            target_type: req.target_type,
+            target_id: req.target_id,
+            category: req.category,
+            description: &req.description,
+            evidence: req.evidence.as_deref(),
+        },
+    )
+    .await?;
+    tx.commit().await?;
+
+    // Reporter ID stays out of the logs
+    tracing::info!(report_id = %report.id, category = ?report.category, "Report filed");
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ReportReceipt {
+            report_id: report.id,
+            status: report.status,
+            duplicate: false,
+        }),
+    ))
+}
@@ -0,0 +1,87 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Real-time event update stream
+//!
+//! A server-sent event stream of [`EventUpdate`]s for the events the
+//! caller follows: those they RSVP'd to or organize, as of connecting.
+//! Clients reconnect after a new RSVP to pick up that event.
+//!
+//! If a client falls too far behind, some updates are dropped and a
+//! `resync` event tells it to refetch the events it is showing.
+
+use std::collections::HashSet;
+use std::convert::Infallible;
+
+use axum::{
+    extract::State,
+    response::sse::{Event, KeepAlive, Sse},
+};
+use futures_util::{stream, Stream};
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use uuid::Uuid;
+
+use super::extract::AuthUser;
+use crate::db;
+use crate::error::Result;
+use crate::realtime::EventUpdate;
+use crate::state::AppState;
+
+/// Subscribe to updates for followed events
+/// GET /api/v1/events/stream
+///
+/// # Errors
+///
+/// Returns an error if the followed events can't be loaded.
+#[utoipa::path(
+    get,
+    path = "/events/stream",
+    tag = "events",
+    responses(
+        (status = 200, description = "Server-sent updates to followed events", body = String, content_type = "text/event-stream"),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn event_updates(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Sse<impl Stream<Item = std::result::Result<Event, Infallible>>>> {
+    // Subscribe first so nothing published while loading is missed
+    let updates = state.realtime.subscribe();
+    // Under duress the caller follows nothing
+    let followed: HashSet<Uuid> = if auth.sanitized {
+        HashSet::new()
+    } else {
+        db::events::followed_by(&state.db, auth.id)
+            .await?
+            .into_iter()
+            .collect()
+    };
+
+    Ok(Sse::new(followed_updates(updates, followed)).keep_alive(KeepAlive::default()))
+}
+
+/// SSE events for the updates in `followed`, until the hub closes
+fn followed_updates(
+    updates: Receiver<EventUpdate>,
+    followed: HashSet<Uuid>,
+) -> impl Stream<Item = std::result::Result<Event, Infallible>> {
+    stream::unfold((updates, followed), |(mut updates, followed)| async move {
+        loop {
+            let event = match updates.recv().await {
+                Ok(update) if followed.contains(&update.event_id()) => Event::default()
+                    .event(update.kind())
+                    .json_data(&update)
+                    .unwrap_or_else(|_| Event::default().event("resync").data("{}")),
+                Ok(_) => continue,
+                Err(RecvError::Lagged(missed)) => {
+                    tracing::debug!(missed, "Event stream subscriber lagged");
+                    Event::default().event("resync").data("{}")
+                }
+                Err(RecvError::Closed) => return None,
+            };
+            return Some((Ok(event), (updates, followed)));
+        }
+    })
+}
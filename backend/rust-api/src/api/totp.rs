@@ -0,0 +1,406 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! TOTP second factor and recovery code endpoints
+//!
+//! Enrollment is two requests: `enroll` returns a new secret as an
+//! `otpauth://` URI for the user's authenticator app (shown as a QR code
+//! client-side), and `confirm` activates it once the app produces a valid
+//! code. Confirming issues a batch of one-time recovery codes, which are
+//! shown once and stored only as Argon2id hashes.
+//!
+//! At login, a password yields an MFA token (see [`super::auth::login`]),
+//! which is exchanged here for a session with a TOTP or recovery code.
+//! Repeated wrong codes lock TOTP for a while; recovery codes still work.
+
+use axum::{extract::State, http::StatusCode, Json};
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::auth::{requires_passkey, AuthResponse};
+use super::extract::AuthUser;
+use crate::crypto::{
+    jwt::{self, AuthMethod, TokenScope},
+    password::PasswordCheck,
+    recovery_code::{self, RecoveryCode},
+    totp,
+};
+use crate::db::{self, models::User};
+use crate::duress;
+use crate::error::{ApiError, Result};
+use crate::state::AppState;
+use crate::telemetry::redact::Redacted;
+
+/// Issuer label shown in authenticator apps
+const ISSUER: &str = "CivicConnect";
+
+/// Wrong codes allowed before TOTP is locked
+const MAX_FAILED_ATTEMPTS: i16 = 5;
+
+/// How long TOTP stays locked after too many wrong codes
+const LOCKOUT_MINUTES: i64 = 15;
+
+/// New TOTP secret for the user's authenticator app
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EnrollResponse {
+    /// Base32 secret, for manual entry
+    pub secret: String,
+    /// `otpauth://` URI, to render as a QR code
+    pub provisioning_uri: String,
+}
+
+/// A code from the authenticator app
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CodeRequest {
+    #[schema(value_type = String)]
+    pub code: Redacted<String>,
+}
+
+/// Recovery codes, shown once
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RecoveryCodesResponse {
+    pub recovery_codes: Vec<String>,
+}
+
+/// Second step of a login
+#[derive(Debug, Deserialize, ToSchema)]
+#[schema(as = TotpVerifyRequest)]
+pub struct VerifyRequest {
+    #[schema(value_type = String)]
+    pub mfa_token: Redacted<String>,
+    #[schema(value_type = String)]
+    pub code: Redacted<String>,
+}
+
+/// Start TOTP enrollment
+/// POST /api/v1/auth/totp/enroll
+///
+/// Replaces any pending secret; fails if TOTP is already active.
+///
+/// # Errors
+///
+/// Returns [`ApiError::Forbidden`] under duress, [`ApiError::InvalidInput`]
+/// if TOTP is already enabled, or an error if a query fails.
+#[utoipa::path(
+    post,
+    path = "/auth/totp/enroll",
+    tag = "auth",
+    responses(
+        (status = 200, description = "New secret to confirm", body = EnrollResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 403, description = "Not allowed under duress", body = crate::error::ErrorResponse),
+        (status = 409, description = "TOTP is already active", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn enroll(State(state): State<AppState>, auth: AuthUser) -> Result<Json<EnrollResponse>> {
+    forbid_under_duress(&auth)?;
+    let user = db::users::find_by_id(&state.db, auth.id)
+        .await?
+        .ok_or(ApiError::UserNotFound)?;
+
+    let secret = totp::generate_secret();
+    if !db::totp::upsert_pending(&state.db, user.id, &secret).await? {
+        return Err(ApiError::InvalidInput("TOTP is already enabled".into()));
+    }
+
+    Ok(Json(EnrollResponse {
+        secret: totp::encode_secret(&secret),
+        provisioning_uri: totp::provisioning_uri(&secret, ISSUER, &user.username),
+    }))
+}
+
+/// Activate TOTP with a first code, and issue recovery codes
+/// POST /api/v1/auth/totp/confirm
+///
+/// # Errors
+///
+/// Returns [`ApiError::Forbidden`] under duress, [`ApiError::InvalidInput`]
+/// with no enrollment in progress, [`ApiError::InvalidCredentials`] for a
+/// wrong code, [`ApiError::RateLimited`] while locked out, or an error if
+/// hashing or a query fails.
+#[utoipa::path(
+    post,
+    path = "/auth/totp/confirm",
+    tag = "auth",
+    request_body = CodeRequest,
+    responses(
+        (status = 200, description = "TOTP active; recovery codes shown once", body = RecoveryCodesResponse),
+        (status = 400, description = "Invalid input", body = crate::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 403, description = "Not allowed under duress", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn confirm(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(req): Json<CodeRequest>,
+) -> Result<Json<RecoveryCodesResponse>> {
+    forbid_under_duress(&auth)?;
+    let credential = db::totp::find(&state.db, auth.id)
+        .await?
+        .filter(|credential| credential.confirmed_at.is_none())
+        .ok_or_else(|| ApiError::InvalidInput("No TOTP enrollment in progress".into()))?;
+
+    // Stored as the secret is confirmed, so TOTP is never active without
+    // the codes shown here
+    let (recovery_codes, stored) = hash_recovery_codes(&state).await?;
+    check_code(&state, &credential, req.code.expose(), Some(&stored)).await?;
+    tracing::info!(user_id = %auth.id, "TOTP enabled");
+
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
+
+/// Turn TOTP off and discard recovery codes
+/// DELETE /api/v1/auth/totp
+///
+/// Requires a current code, so a stolen session alone can't remove it.
+///
+/// # Errors
+///
+/// Returns [`ApiError::Forbidden`] under duress, [`ApiError::InvalidInput`]
+/// if TOTP is not enabled, [`ApiError::InvalidCredentials`] for a wrong code,
+/// [`ApiError::RateLimited`] while locked out, or an error if a query fails.
+#[utoipa::path(
+    delete,
+    path = "/auth/totp",
+    tag = "auth",
+    request_body = CodeRequest,
+    responses(
+        (status = 204, description = "TOTP turned off"),
+        (status = 400, description = "Invalid input", body = crate::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 403, description = "Not allowed under duress", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn disable(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(req): Json<CodeRequest>,
+) -> Result<StatusCode> {
+    forbid_under_duress(&auth)?;
+    let credential = db::totp::find(&state.db, auth.id)
+        .await?
+        .filter(|credential| credential.confirmed_at.is_some())
+        .ok_or_else(|| ApiError::InvalidInput("TOTP is not enabled".into()))?;
+
+    check_code(&state, &credential, req.code.expose(), None).await?;
+    db::totp::delete(&state.db, auth.id).await?;
+    tracing::info!(user_id = %auth.id, "TOTP disabled");
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Replace recovery codes with a new batch
+/// POST /api/v1/auth/totp/recovery-codes
+///
+/// # Errors
+///
+/// Returns [`ApiError::Forbidden`] under duress, [`ApiError::InvalidInput`]
+/// if TOTP is not enabled, or an error if hashing or a query fails.
+#[utoipa::path(
+    post,
+    path = "/auth/totp/recovery-codes",
+    tag = "auth",
+    responses(
+        (status = 200, description = "New recovery codes, shown once", body = RecoveryCodesResponse),
+        (status = 400, description = "Invalid input", body = crate::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 403, description = "Not allowed under duress", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn regenerate_recovery_codes(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<RecoveryCodesResponse>> {
+    forbid_under_duress(&auth)?;
+    if !db::totp::is_enabled(&state.db, auth.id).await? {
+        return Err(ApiError::InvalidInput("TOTP is not enabled".into()));
+    }
+
+    let (recovery_codes, stored) = hash_recovery_codes(&state).await?;
+    db::totp::replace_recovery_codes(&state.db, auth.id, &stored).await?;
+
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
+
+/// Finish a login with a TOTP code
+/// POST /api/v1/auth/totp/verify
+///
+/// # Errors
+///
+/// Returns [`ApiError::Unauthorized`] for a bad `mfa_token`,
+/// [`ApiError::Forbidden`] if the user must use a passkey,
+/// [`ApiError::InvalidCredentials`] without TOTP or for a wrong code,
+/// [`ApiError::RateLimited`] while locked out, or an error if signing or a
+/// query fails.
+#[utoipa::path(
+    post,
+    path = "/auth/totp/verify",
+    tag = "auth",
+    request_body = VerifyRequest,
+    responses(
+        (status = 200, description = "Logged in", body = AuthResponse),
+        (status = 401, description = "Wrong code or expired MFA token", body = crate::error::ErrorResponse),
+        (status = 403, description = "A passkey is required", body = crate::error::ErrorResponse),
+        (status = 429, description = "Too many attempts", body = crate::error::ErrorResponse),
+    ),
+)]
+pub async fn verify(
+    State(state): State<AppState>,
+    Json(req): Json<VerifyRequest>,
+) -> Result<Json<AuthResponse>> {
+    let user = mfa_pending_user(&state, req.mfa_token.expose()).await?;
+
+    let credential = db::totp::find(&state.db, user.id)
+        .await?
+        .filter(|credential| credential.confirmed_at.is_some())
+        .ok_or(ApiError::InvalidCredentials)?;
+
+    check_code(&state, &credential, req.code.expose(), None).await?;
+    duress::complete_login(&state, &user).await?;
+    db::users::touch_last_active(&state.db, user.id).await?;
+
+    Ok(Json(AuthResponse::for_user(
+        &state,
+        user,
+        vec![AuthMethod::Password, AuthMethod::OneTimePassword],
+    )?))
+}
+
+/// Finish a login with a recovery code, when the second factor is lost
+/// POST /api/v1/auth/mfa/recovery
+///
+/// # Errors
+///
+/// Returns [`ApiError::Unauthorized`] for a bad `mfa_token`,
+/// [`ApiError::Forbidden`] if the user must use a passkey,
+/// [`ApiError::InvalidCredentials`] for an unknown or used code, or an error if
+/// hashing, signing or a query fails.
+#[utoipa::path(
+    post,
+    path = "/auth/mfa/recovery",
+    tag = "auth",
+    request_body = VerifyRequest,
+    responses(
+        (status = 200, description = "Logged in", body = AuthResponse),
+        (status = 401, description = "Wrong code or expired MFA token", body = crate::error::ErrorResponse),
+        (status = 403, description = "A passkey is required", body = crate::error::ErrorResponse),
+    ),
+)]
+pub async fn redeem_recovery_code(
+    State(state): State<AppState>,
+    Json(req): Json<VerifyRequest>,
+) -> Result<Json<AuthResponse>> {
+    let user = mfa_pending_user(&state, req.mfa_token.expose()).await?;
+    let code = RecoveryCode::parse(req.code.expose()).ok_or(ApiError::InvalidCredentials)?;
+
+    let Some(hash) = db::totp::find_recovery_code(&state.db, user.id, &code.selector).await? else {
+        // Same work as a real check, so timing doesn't reveal valid selectors
+        state.recovery_codes.verify_dummy(code.secret).await?;
+        return Err(ApiError::InvalidCredentials);
+    };
+
+    if state.recovery_codes.verify(code.secret, hash).await? == PasswordCheck::Mismatch
+        || !db::totp::consume_recovery_code(&state.db, user.id, &code.selector).await?
+    {
+        return Err(ApiError::InvalidCredentials);
+    }
+
+    let remaining = db::totp::remaining_recovery_codes(&state.db, user.id).await?;
+    tracing::info!(user_id = %user.id, remaining, "Recovery code used");
+    duress::complete_login(&state, &user).await?;
+    db::users::touch_last_active(&state.db, user.id).await?;
+
+    Ok(Json(AuthResponse::for_user(
+        &state,
+        user,
+        vec![AuthMethod::Password, AuthMethod::OneTimePassword],
+    )?))
+}
+
+/// Refuse TOTP changes under duress: a factor enrolled there would outlive
+/// the sanitized session, and removing one would weaken the real account
+const fn forbid_under_duress(auth: &AuthUser) -> Result<()> {
+    if auth.sanitized {
+        Err(ApiError::Forbidden)
+    } else {
+        Ok(())
+    }
+}
+
+/// The user an MFA token was issued to, if TOTP or a recovery code may
+/// finish their login
+async fn mfa_pending_user(state: &AppState, token: &str) -> Result<User> {
+    let claims = jwt::decode_token(
+        token,
+        state.config.jwt_secret.as_bytes(),
+        TokenScope::MfaPending,
+    )?;
+
+    let user = db::users::find_by_id(&state.db, claims.sub)
+        .await?
+        .ok_or(ApiError::InvalidCredentials)?;
+
+    // Only a passkey satisfies the passkey requirement
+    if requires_passkey(state, &user) {
+        return Err(ApiError::Forbidden);
+    }
+
+    Ok(user)
+}
+
+/// Accept a code once, enforcing the lockout, and store any new recovery
+/// codes with it
+async fn check_code(
+    state: &AppState,
+    credential: &db::totp::TotpCredential,
+    code: &str,
+    recovery_codes: Option<&[(String, String)]>,
+) -> Result<()> {
+    let locked = credential.failed_attempts >= MAX_FAILED_ATTEMPTS
+        && credential
+            .last_failed_at
+            .is_some_and(|at| at + Duration::minutes(LOCKOUT_MINUTES) > Utc::now());
+    if locked {
+        return Err(ApiError::RateLimited);
+    }
+
+    let step = totp::verify(
+        &credential.secret,
+        code,
+        Utc::now().timestamp(),
+        credential.last_used_step,
+    );
+
+    match step {
+        Some(step)
+            if db::totp::record_success(&state.db, credential.user_id, step, recovery_codes)
+                .await? =>
+        {
+            Ok(())
+        }
+        _ => {
+            db::totp::record_failure(&state.db, credential.user_id).await?;
+            Err(ApiError::InvalidCredentials)
+        }
+    }
+}
+
+/// Generate a fresh batch of recovery codes; returns them for display and
+/// as `(selector, code_hash)` pairs to store
+async fn hash_recovery_codes(state: &AppState) -> Result<(Vec<String>, Vec<(String, String)>)> {
+    let codes = recovery_code::generate_batch();
+
+    let mut stored = Vec::with_capacity(codes.len());
+    for code in &codes {
+        let hash = state.recovery_codes.hash(code.secret.clone()).await?;
+        stored.push((code.selector.clone(), hash));
+    }
+
+    Ok((codes.iter().map(RecoveryCode::display).collect(), stored))
+}
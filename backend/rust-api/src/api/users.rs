@@ -7,12 +7,13 @@
 
 use axum::{extract::Path, Json};
 use serde::Serialize;
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 use crate::error::{ApiError, Result};
 
 /// Public user profile (minimal PII)
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct UserProfile {
     pub id: Uuid,
     pub username: String,
@@ -25,6 +26,21 @@ pub struct UserProfile {
 
 /// Get current authenticated user
 /// GET /api/v1/users/me
+///
+/// Not implemented yet: always fails, so no profile is documented.
+///
+/// # Errors
+///
+/// Returns [`ApiError::Unauthorized`] until the lookup is implemented.
+#[utoipa::path(
+    get,
+    path = "/users/me",
+    tag = "users",
+    responses(
+        (status = 401, description = "Always, until implemented", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
 pub async fn get_current_user() -> Result<Json<UserProfile>> {
     // TODO: Extract user from JWT token
     // TODO: Look up user in database
@@ -34,6 +50,23 @@ pub async fn get_current_user() -> Result<Json<UserProfile>> {
 
 /// Get user by ID (public profile only)
 /// GET /api/v1/users/:id
+///
+/// Not implemented yet: always fails, so no profile is documented.
+///
+/// # Errors
+///
+/// Returns [`ApiError::UserNotFound`] until the lookup is implemented.
+#[utoipa::path(
+    get,
+    path = "/users/{id}",
+    tag = "users",
+    params(
+        ("id" = Uuid, Path, description = "User ID"),
+    ),
+    responses(
+        (status = 404, description = "Always, until implemented", body = crate::error::ErrorResponse),
+    ),
+)]
 pub async fn get_user(Path(id): Path<Uuid>) -> Result<Json<UserProfile>> {
     // TODO: Look up user in database
     // TODO: Return only public profile fields
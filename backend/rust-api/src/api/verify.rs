@@ -4,47 +4,45 @@
 //! Verification endpoints
 //!
 //! Implements QR code-based event verification with ed25519 signatures.
+//! Organizers' devices sign codes with the key they register here, so
+//! codes can be shown and scanned offline. Where attendees have no data,
+//! organizers collect attendees' signed check-in tokens instead and upload
+//! them together; see [`crate::verification`].
 //! Anti-gaming measures:
 //! - Rate limiting: Max 3 verifications per day
 //! - Temporal validation: Within event time window
 //! - Spatial validation: Within coarse geofence
 
-use axum::Json;
+use axum::{extract::State, http::StatusCode, Json};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-use crate::error::{ApiError, Result};
-
-/// QR code generation request (organizer)
-#[derive(Debug, Deserialize)]
-pub struct GenerateQrRequest {
-    pub event_id: Uuid,
-}
-
-/// QR code payload (to be encoded in QR)
-#[derive(Debug, Serialize)]
-pub struct QrPayload {
-    pub event_id: Uuid,
-    pub organizer_id: Uuid,
-    pub timestamp: DateTime<Utc>,
-    pub nonce: String,
-    pub signature: String, // ed25519 signature, hex encoded
-}
+use crate::api::extract::AuthUser;
+use crate::db;
+use crate::error::{ApiError, ErrorResponse, Result};
+use crate::state::AppState;
+use crate::telemetry::redact::Redacted;
+use crate::verification::{self, CheckinToken, Manifest, Recorded, Scan, TimeLimits};
 
 /// Verification request (attendee)
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct VerifyRequest {
     pub event_id: Uuid,
     pub organizer_id: Uuid,
     pub timestamp: DateTime<Utc>,
-    pub nonce: String,
-    pub signature: String,
-    pub location_cell: String, // User's current H3 cell
+    #[schema(value_type = String)]
+    pub nonce: Redacted<String>,
+    #[schema(value_type = String)]
+    pub signature: Redacted<String>,
+    /// User's current H3 cell
+    #[schema(value_type = String)]
+    pub location_cell: Redacted<String>,
 }
 
 /// Verification response
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct VerifyResponse {
     pub success: bool,
     pub xp_awarded: u32,
@@ -53,30 +51,356 @@ pub struct VerifyResponse {
     pub level_up: bool,
 }
 
-/// Generate QR code for event verification
-/// POST /api/v1/verify/qr
-pub async fn generate_qr(Json(req): Json<GenerateQrRequest>) -> Result<Json<QrPayload>> {
-    // TODO: Check user is authenticated
-    // TODO: Check user is organizer of this event
-    // TODO: Generate random nonce
-    // TODO: Sign payload with organizer's ed25519 private key
+/// Verify attendance by scanning QR code
+/// POST /api/v1/verify/scan
+///
+/// # Errors
+///
+/// Returns the first check the scan failed (see [`verification::verify_scan`]),
+/// or an error if a query fails.
+#[utoipa::path(
+    post,
+    path = "/verify/scan",
+    tag = "verification",
+    request_body = VerifyRequest,
+    responses(
+        (status = 200, description = "Attendance verified", body = VerifyResponse),
+        (status = 400, description = "Invalid input", body = crate::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 404, description = "No such event", body = crate::error::ErrorResponse),
+        (status = 409, description = "Already verified", body = crate::error::ErrorResponse),
+        (status = 429, description = "Too many attempts", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn verify_attendance(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(req): Json<VerifyRequest>,
+) -> Result<Json<VerifyResponse>> {
+    let now = Utc::now();
+    let limits = TimeLimits::live(&state.config.scan, state.config.realtime.qr_rotation_secs);
+    let recorded = verification::verify_scan(&state, auth.id, &req.scan(now), now, &limits).await?;
 
-    let _ = req;
-    Err(ApiError::Forbidden)
+    Ok(Json(verify_response(&state, &recorded).await?))
 }
 
-/// Verify attendance by scanning QR code
-/// POST /api/v1/verify/scan
-pub async fn verify_attendance(Json(req): Json<VerifyRequest>) -> Result<Json<VerifyResponse>> {
-    // TODO: Check user is authenticated
-    // TODO: Check rate limiting (max 3 per day)
-    // TODO: Check not already verified for this event
-    // TODO: Verify ed25519 signature
-    // TODO: Check timestamp is within event window
-    // TODO: Check location is within geofence
-    // TODO: Award XP
-    // TODO: Record in audit log
-
-    let _ = req;
-    Err(ApiError::Unauthorized)
+/// A scan made offline, queued for upload
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct QueuedScan {
+    #[serde(flatten)]
+    pub scan: VerifyRequest,
+    /// When the device scanned the code, by its own clock
+    pub scanned_at: DateTime<Utc>,
+}
+
+/// Scans queued while offline, in the order they were made
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct BatchVerifyRequest {
+    pub scans: Vec<QueuedScan>,
+}
+
+/// What happened to one queued scan
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanStatus {
+    /// Recorded and XP awarded
+    Verified,
+    /// Recorded, but held for review with no XP yet
+    Held,
+    /// Not recorded; see `error`
+    Rejected,
+}
+
+/// Result for one queued scan or check-in token
+#[derive(Serialize, ToSchema)]
+pub struct ScanResult {
+    /// Position of the scan or token in the request
+    pub index: usize,
+    pub status: ScanStatus,
+    /// Present unless rejected
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verification: Option<VerifyResponse>,
+    /// Why the scan was rejected
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorResponse>,
+}
+
+/// Results for a batch, one per scan or token in request order
+#[derive(Serialize, ToSchema)]
+pub struct BatchVerifyResponse {
+    pub results: Vec<ScanResult>,
+}
+
+/// Upload scans queued while offline
+/// POST /api/v1/verify/scan/batch
+///
+/// Each scan is checked against its signed QR timestamp, not when it
+/// arrives, so it is accepted for a grace period after the code was shown.
+/// Rejected scans don't fail the batch; they get a result with the error
+/// the single-scan endpoint would have returned. A retried batch gets
+/// `ALREADY_VERIFIED` for scans recorded the first time.
+///
+/// # Errors
+///
+/// Returns [`ApiError::InvalidInput`] for too many scans, or an error if a
+/// query fails. Scans that fail their checks get a rejected result instead.
+#[utoipa::path(
+    post,
+    path = "/verify/scan/batch",
+    tag = "verification",
+    request_body = BatchVerifyRequest,
+    responses(
+        (status = 200, description = "Per-scan results", body = BatchVerifyResponse),
+        (status = 400, description = "Too many scans", body = crate::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn verify_batch(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(req): Json<BatchVerifyRequest>,
+) -> Result<Json<BatchVerifyResponse>> {
+    let max = state.config.scan.max_batch;
+    if req.scans.len() > max {
+        return Err(ApiError::InvalidInput(format!(
+            "At most {max} scans per batch"
+        )));
+    }
+
+    let received_at = Utc::now();
+    let limits = TimeLimits::queued(&state.config.scan, state.config.realtime.qr_rotation_secs);
+    let mut results = Vec::with_capacity(req.scans.len());
+    // One at a time, so each scan counts towards the next one's rate limit
+    for (index, queued) in req.scans.iter().enumerate() {
+        let scan = queued.scan.scan(queued.scanned_at);
+        let result = verification::verify_scan(&state, auth.id, &scan, received_at, &limits).await;
+        results.push(scan_result(&state, index, result).await?);
+    }
+
+    Ok(Json(BatchVerifyResponse { results }))
+}
+
+/// An attendee's check-in token, as the organizer's device collected it
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CollectedToken {
+    pub user_id: Uuid,
+    /// When the attendee's device signed the token
+    pub issued_at: DateTime<Utc>,
+    #[schema(value_type = String)]
+    pub nonce: Redacted<String>,
+    /// Attendee's ed25519 signature, hex encoded
+    #[schema(value_type = String)]
+    pub signature: Redacted<String>,
+    /// When the organizer's device collected it, by its own clock
+    pub collected_at: DateTime<Utc>,
+}
+
+/// Check-in tokens collected offline, signed by the organizer's device
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CheckinManifest {
+    pub event_id: Uuid,
+    /// H3 cell the organizer's device was in
+    #[schema(value_type = String)]
+    pub location_cell: Redacted<String>,
+    pub tokens: Vec<CollectedToken>,
+    /// Organizer's ed25519 signature over the manifest, hex encoded
+    #[schema(value_type = String)]
+    pub signature: Redacted<String>,
+}
+
+/// Upload attendees' check-in tokens collected offline (reverse flow)
+/// POST /api/v1/verify/checkins
+///
+/// The manifest must be signed by the caller's registered key, and the
+/// caller must run the event. Each token is then checked against its
+/// attendee's registered key and timed like a queued scan; results come
+/// back per token, as for `/verify/scan/batch`.
+///
+/// # Errors
+///
+/// Returns [`ApiError::InvalidInput`] for too many tokens,
+/// [`ApiError::InvalidSignature`] if the manifest isn't signed by someone
+/// running the event, [`ApiError::EventNotFound`], or an error if a query
+/// fails.
+#[utoipa::path(
+    post,
+    path = "/verify/checkins",
+    tag = "verification",
+    request_body = CheckinManifest,
+    responses(
+        (status = 200, description = "Per-token results", body = BatchVerifyResponse),
+        (status = 400, description = "Invalid manifest or signature", body = crate::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 404, description = "No such event", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn upload_checkins(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(req): Json<CheckinManifest>,
+) -> Result<Json<BatchVerifyResponse>> {
+    let max = state.config.scan.max_manifest;
+    if req.tokens.len() > max {
+        return Err(ApiError::InvalidInput(format!(
+            "At most {max} tokens per manifest"
+        )));
+    }
+
+    let manifest = Manifest {
+        event_id: req.event_id,
+        organizer_id: auth.id,
+        location_cell: req.location_cell.expose(),
+        tokens: req
+            .tokens
+            .iter()
+            .map(|token| CheckinToken {
+                user_id: token.user_id,
+                issued_at: token.issued_at,
+                nonce: token.nonce.expose(),
+                signature: token.signature.expose(),
+                collected_at: token.collected_at,
+            })
+            .collect(),
+        signature: req.signature.expose(),
+    };
+    let limits = TimeLimits::queued(&state.config.scan, state.config.realtime.qr_rotation_secs);
+    let checkins = verification::verify_checkins(&state, &manifest, Utc::now(), &limits).await?;
+
+    let mut results = Vec::with_capacity(checkins.len());
+    for (index, result) in checkins.into_iter().enumerate() {
+        results.push(scan_result(&state, index, result).await?);
+    }
+
+    Ok(Json(BatchVerifyResponse { results }))
+}
+
+/// Report one scan or check-in, failing on errors a retry might fix
+async fn scan_result(
+    state: &AppState,
+    index: usize,
+    result: Result<Recorded>,
+) -> Result<ScanResult> {
+    Ok(match result {
+        Ok(recorded) => ScanResult {
+            index,
+            status: if recorded.is_held() {
+                ScanStatus::Held
+            } else {
+                ScanStatus::Verified
+            },
+            verification: Some(verify_response(state, &recorded).await?),
+            error: None,
+        },
+        Err(e @ (ApiError::Database(_) | ApiError::Internal(_))) => return Err(e),
+        Err(e) => ScanResult {
+            index,
+            status: ScanStatus::Rejected,
+            verification: None,
+            error: Some(ErrorResponse::from(&e)),
+        },
+    })
+}
+
+impl VerifyRequest {
+    /// The scan this request describes, made at `scanned_at`
+    #[must_use]
+    pub fn scan(&self, scanned_at: DateTime<Utc>) -> Scan<'_> {
+        Scan {
+            event_id: self.event_id,
+            organizer_id: self.organizer_id,
+            qr_timestamp: self.timestamp,
+            nonce: self.nonce.expose(),
+            signature: self.signature.expose(),
+            location_cell: self.location_cell.expose(),
+            scanned_at,
+        }
+    }
+}
+
+/// Describe a recorded verification to the attendee
+async fn verify_response(state: &AppState, recorded: &Recorded) -> Result<VerifyResponse> {
+    let user = db::users::find_by_id(&state.db, recorded.verification.user_id)
+        .await?
+        .ok_or(ApiError::UserNotFound)?;
+
+    Ok(VerifyResponse {
+        success: true,
+        xp_awarded: u32::try_from(recorded.verification.experience_awarded).unwrap_or(0),
+        new_total_xp: u32::try_from(user.experience_points).unwrap_or(0),
+        level: u8::try_from(user.current_level).unwrap_or(0),
+        // XP alone never promotes (see `crate::leveling`)
+        level_up: false,
+    })
+}
+
+/// Device signing key registration
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SigningKeyRequest {
+    /// ed25519 public key, hex encoded
+    pub public_key: String,
+}
+
+/// Register the key this user's device signs QR codes with
+/// PUT /api/v1/users/me/signing-key
+///
+/// Replaces any earlier key, so codes signed by the old one stop scanning.
+///
+/// # Errors
+///
+/// Returns [`ApiError::InvalidInput`] if the key isn't an ed25519 public key,
+/// or an error if the query fails.
+#[utoipa::path(
+    put,
+    path = "/users/me/signing-key",
+    tag = "users",
+    request_body = SigningKeyRequest,
+    responses(
+        (status = 204, description = "Key registered"),
+        (status = 400, description = "Invalid key", body = crate::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn set_signing_key(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(req): Json<SigningKeyRequest>,
+) -> Result<StatusCode> {
+    let key = hex::decode(&req.public_key)
+        .ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .filter(|bytes| ed25519_dalek::VerifyingKey::from_bytes(bytes).is_ok())
+        .ok_or_else(|| ApiError::InvalidInput("Not an ed25519 public key".into()))?;
+
+    db::users::set_signing_key(&state.db, auth.id, Some(&key)).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Remove this user's device signing key
+/// DELETE /api/v1/users/me/signing-key
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+#[utoipa::path(
+    delete,
+    path = "/users/me/signing-key",
+    tag = "users",
+    responses(
+        (status = 204, description = "Key removed"),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn remove_signing_key(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<StatusCode> {
+    db::users::set_signing_key(&state.db, auth.id, None).await?;
+
+    Ok(StatusCode::NO_CONTENT)
 }
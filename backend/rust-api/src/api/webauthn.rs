@@ -0,0 +1,366 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Passkey endpoints (Web Authentication)
+//!
+//! Each ceremony is two requests: `start` returns options for
+//! `navigator.credentials.create()`/`get()` plus a challenge ID, and
+//! `finish` submits the authenticator's response with that ID. Ceremony
+//! state is kept server-side for five minutes and can be finished once.
+//!
+//! Passkeys can be used two ways:
+//! - As a second factor, starting from the `mfa_token` a password login
+//!   returns for users with a passkey
+//! - Passwordless, starting from a username
+//!
+//! Users above `webauthn_required_level` must enroll a passkey before a
+//! password login yields a session.
+
+use axum::{extract::State, http::StatusCode, Json};
+use chrono::{Duration, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+use webauthn_rs::prelude::{
+    CreationChallengeResponse, PasskeyAuthentication, PasskeyRegistration, PublicKeyCredential,
+    RegisterPublicKeyCredential, RequestChallengeResponse,
+};
+
+use super::auth::AuthResponse;
+use super::extract::EnrollingUser;
+use crate::crypto::jwt::{self, AuthMethod, TokenScope};
+use crate::db;
+use crate::duress;
+use crate::error::{ApiError, Result};
+use crate::state::AppState;
+use crate::telemetry::redact::Redacted;
+
+/// How long a started ceremony may take to finish
+const CHALLENGE_TTL_MINUTES: i64 = 5;
+
+/// Challenge purposes, as stored with the ceremony state
+const PURPOSE_REGISTER: &str = "register";
+const PURPOSE_SECOND_FACTOR: &str = "second_factor";
+const PURPOSE_PASSWORDLESS: &str = "passwordless";
+
+/// Passkey registration request
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct RegisterStartRequest {
+    /// Label shown in the user's credential list
+    #[validate(length(min = 1, max = 64))]
+    pub name: String,
+}
+
+/// Options for `navigator.credentials.create()`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RegisterStartResponse {
+    pub challenge_id: Uuid,
+    #[schema(value_type = Object)]
+    pub options: CreationChallengeResponse,
+}
+
+/// Authenticator response to a registration challenge
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RegisterFinishRequest {
+    pub challenge_id: Uuid,
+    #[schema(value_type = Object)]
+    pub credential: RegisterPublicKeyCredential,
+}
+
+/// Passkey login request: either `mfa_token` (second factor) or
+/// `username` (passwordless)
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct LoginStartRequest {
+    #[schema(value_type = Option<String>)]
+    pub mfa_token: Option<Redacted<String>>,
+    pub username: Option<String>,
+}
+
+/// Options for `navigator.credentials.get()`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LoginStartResponse {
+    pub challenge_id: Uuid,
+    #[schema(value_type = Object)]
+    pub options: RequestChallengeResponse,
+}
+
+/// Authenticator response to a login challenge
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct LoginFinishRequest {
+    pub challenge_id: Uuid,
+    #[schema(value_type = Object)]
+    pub credential: PublicKeyCredential,
+}
+
+/// Ceremony state saved between `register/start` and `register/finish`
+#[derive(Serialize, Deserialize)]
+struct PendingRegistration {
+    name: String,
+    state: PasskeyRegistration,
+}
+
+/// Start registering a passkey
+/// POST /api/v1/auth/webauthn/register/start
+///
+/// Accepts a session or an enrollment token.
+///
+/// # Errors
+///
+/// Returns [`ApiError::InvalidInput`] for invalid input, or an error if the
+/// ceremony can't be started or a query fails.
+#[utoipa::path(
+    post,
+    path = "/auth/webauthn/register/start",
+    tag = "auth",
+    request_body = RegisterStartRequest,
+    responses(
+        (status = 200, description = "Options for the authenticator", body = RegisterStartResponse),
+        (status = 400, description = "Invalid input", body = crate::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn register_start(
+    State(state): State<AppState>,
+    auth: EnrollingUser,
+    Json(req): Json<RegisterStartRequest>,
+) -> Result<Json<RegisterStartResponse>> {
+    req.validate()
+        .map_err(|e| ApiError::InvalidInput(e.to_string()))?;
+
+    let user = db::users::find_by_id(&state.db, auth.id)
+        .await?
+        .ok_or(ApiError::UserNotFound)?;
+
+    // Don't let the same authenticator be registered twice
+    let exclude = db::webauthn::list_passkeys(&state.db, user.id)
+        .await?
+        .iter()
+        .map(|passkey| passkey.cred_id().clone())
+        .collect();
+
+    let (options, registration) = state
+        .webauthn
+        .start_passkey_registration(user.id, &user.username, &user.username, Some(exclude))
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("WebAuthn registration failed: {e}")))?;
+
+    let pending = PendingRegistration {
+        name: req.name,
+        state: registration,
+    };
+    let challenge_id = save_challenge(&state, user.id, PURPOSE_REGISTER, &pending).await?;
+
+    Ok(Json(RegisterStartResponse {
+        challenge_id,
+        options,
+    }))
+}
+
+/// Finish registering a passkey
+/// POST /api/v1/auth/webauthn/register/finish
+///
+/// # Errors
+///
+/// Returns [`ApiError::InvalidToken`] for an unknown, expired or someone else's
+/// challenge, [`ApiError::InvalidSignature`] if the authenticator's response
+/// doesn't verify, [`ApiError::InvalidInput`] if the passkey is already
+/// registered, or an error if a query fails.
+#[utoipa::path(
+    post,
+    path = "/auth/webauthn/register/finish",
+    tag = "auth",
+    request_body = RegisterFinishRequest,
+    responses(
+        (status = 201, description = "Passkey registered"),
+        (status = 400, description = "Invalid input", body = crate::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn register_finish(
+    State(state): State<AppState>,
+    auth: EnrollingUser,
+    Json(req): Json<RegisterFinishRequest>,
+) -> Result<StatusCode> {
+    let (_, _, pending): (_, _, PendingRegistration) =
+        take_challenge(&state, req.challenge_id, |user_id, purpose| {
+            user_id == auth.id && purpose == PURPOSE_REGISTER
+        })
+        .await?;
+
+    let passkey = state
+        .webauthn
+        .finish_passkey_registration(&req.credential, &pending.state)
+        .map_err(|_| ApiError::InvalidSignature)?;
+
+    db::webauthn::insert_passkey(&state.db, auth.id, &pending.name, &passkey)
+        .await
+        .map_err(|e| match e {
+            ApiError::Database(ref err) if db::is_unique_violation(err) => {
+                ApiError::InvalidInput("Passkey is already registered".into())
+            }
+            other => other,
+        })?;
+
+    tracing::info!(user_id = %auth.id, "Passkey registered");
+
+    Ok(StatusCode::CREATED)
+}
+
+/// Start a passkey login
+/// POST /api/v1/auth/webauthn/login/start
+///
+/// # Errors
+///
+/// Returns [`ApiError::InvalidInput`] unless exactly one of `mfa_token` or
+/// `username` is given, [`ApiError::InvalidToken`] for a bad `mfa_token`,
+/// [`ApiError::InvalidCredentials`] for an unknown user or one without
+/// passkeys, or an error if a query fails.
+#[utoipa::path(
+    post,
+    path = "/auth/webauthn/login/start",
+    tag = "auth",
+    request_body = LoginStartRequest,
+    responses(
+        (status = 200, description = "Options for the authenticator", body = LoginStartResponse),
+        (status = 400, description = "Invalid input", body = crate::error::ErrorResponse),
+        (status = 401, description = "Invalid MFA token or unknown user", body = crate::error::ErrorResponse),
+    ),
+)]
+pub async fn login_start(
+    State(state): State<AppState>,
+    Json(req): Json<LoginStartRequest>,
+) -> Result<Json<LoginStartResponse>> {
+    let (user_id, purpose) = match (req.mfa_token, req.username) {
+        (Some(token), None) => {
+            let claims = jwt::decode_token(
+                token.expose(),
+                state.config.jwt_secret.as_bytes(),
+                TokenScope::MfaPending,
+            )?;
+            (claims.sub, PURPOSE_SECOND_FACTOR)
+        }
+        (None, Some(username)) => {
+            let user = db::users::find_by_username(&state.db, &username)
+                .await?
+                .ok_or(ApiError::InvalidCredentials)?;
+            (user.id, PURPOSE_PASSWORDLESS)
+        }
+        _ => {
+            return Err(ApiError::InvalidInput(
+                "Provide exactly one of mfa_token or username".into(),
+            ))
+        }
+    };
+
+    let passkeys = db::webauthn::list_passkeys(&state.db, user_id).await?;
+    if passkeys.is_empty() {
+        return Err(ApiError::InvalidCredentials);
+    }
+
+    let (options, authentication) = state
+        .webauthn
+        .start_passkey_authentication(&passkeys)
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("WebAuthn login failed: {e}")))?;
+
+    let challenge_id = save_challenge(&state, user_id, purpose, &authentication).await?;
+
+    Ok(Json(LoginStartResponse {
+        challenge_id,
+        options,
+    }))
+}
+
+/// Finish a passkey login and issue a session
+/// POST /api/v1/auth/webauthn/login/finish
+///
+/// # Errors
+///
+/// Returns [`ApiError::InvalidToken`] for an unknown or expired challenge,
+/// [`ApiError::InvalidCredentials`] if the authenticator's response doesn't
+/// verify, or an error if signing or a query fails.
+#[utoipa::path(
+    post,
+    path = "/auth/webauthn/login/finish",
+    tag = "auth",
+    request_body = LoginFinishRequest,
+    responses(
+        (status = 200, description = "Logged in", body = AuthResponse),
+        (status = 400, description = "Invalid input", body = crate::error::ErrorResponse),
+        (status = 401, description = "Passkey not accepted", body = crate::error::ErrorResponse),
+    ),
+)]
+pub async fn login_finish(
+    State(state): State<AppState>,
+    Json(req): Json<LoginFinishRequest>,
+) -> Result<Json<AuthResponse>> {
+    let (user_id, purpose, authentication): (_, _, PasskeyAuthentication) =
+        take_challenge(&state, req.challenge_id, |_, purpose| {
+            purpose == PURPOSE_SECOND_FACTOR || purpose == PURPOSE_PASSWORDLESS
+        })
+        .await?;
+
+    let result = state
+        .webauthn
+        .finish_passkey_authentication(&req.credential, &authentication)
+        .map_err(|_| ApiError::InvalidCredentials)?;
+
+    db::webauthn::record_use(&state.db, user_id, &result).await?;
+    db::users::touch_last_active(&state.db, user_id).await?;
+
+    let user = db::users::find_by_id(&state.db, user_id)
+        .await?
+        .ok_or(ApiError::InvalidCredentials)?;
+
+    let amr = if purpose == PURPOSE_SECOND_FACTOR {
+        duress::complete_login(&state, &user).await?;
+        vec![AuthMethod::Password, AuthMethod::HardwareKey]
+    } else {
+        vec![AuthMethod::HardwareKey]
+    };
+
+    Ok(Json(AuthResponse::for_user(&state, user, amr)?))
+}
+
+/// Persist ceremony state and return its challenge ID
+async fn save_challenge<T: Serialize + Sync>(
+    state: &AppState,
+    user_id: Uuid,
+    purpose: &str,
+    ceremony: &T,
+) -> Result<Uuid> {
+    let id = Uuid::new_v4();
+    let ceremony = serde_json::to_value(ceremony).map_err(|e| ApiError::Internal(e.into()))?;
+
+    db::webauthn::insert_challenge(
+        &state.db,
+        id,
+        user_id,
+        purpose,
+        ceremony,
+        Utc::now() + Duration::minutes(CHALLENGE_TTL_MINUTES),
+    )
+    .await?;
+
+    Ok(id)
+}
+
+/// Consume ceremony state, checking who and what it was started for
+///
+/// Unknown, expired, reused and mismatched challenges are all rejected
+/// the same way.
+async fn take_challenge<T: DeserializeOwned + Send + Sync>(
+    state: &AppState,
+    id: Uuid,
+    allowed: impl FnOnce(Uuid, &str) -> bool + Send,
+) -> Result<(Uuid, String, T)> {
+    let (user_id, purpose, ceremony) = db::webauthn::take_challenge(&state.db, id)
+        .await?
+        .filter(|(user_id, purpose, _)| allowed(*user_id, purpose))
+        .ok_or(ApiError::InvalidToken)?;
+
+    let ceremony = serde_json::from_value(ceremony).map_err(|e| ApiError::Internal(e.into()))?;
+
+    Ok((user_id, purpose, ceremony))
+}
@@ -0,0 +1,385 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Webhook subscriptions
+//!
+//! Organizers manage the URLs their event changes are posted to, and
+//! inspect and replay deliveries that failed. Chapter admins can also
+//! subscribe URLs to all of their chapter's events; those subscriptions
+//! belong to whoever created them. See [`crate::webhooks`] for
+//! the delivery format and signing.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+use super::extract::AuthUser;
+use super::organizations::standing;
+use crate::crypto;
+use crate::db::{
+    self,
+    webhooks::{DeadLetter, NewSubscription, WebhookSubscription},
+};
+use crate::error::{ApiError, Result};
+use crate::leveling::ORGANIZER_LEVEL;
+use crate::organizations::Capability;
+use crate::state::AppState;
+use crate::webhooks::{self, EVENT_TYPES};
+
+/// Subscriptions one organizer may have
+const MAX_SUBSCRIPTIONS: i64 = 10;
+
+/// Dead letters listed at once
+const DEAD_LETTER_PAGE: i64 = 100;
+
+/// Subscribe a URL to event changes
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CreateWebhookRequest {
+    #[validate(length(min = 1, max = 2000))]
+    pub url: String,
+
+    /// Any of `event.created`, `event.cancelled`, `event.hidden`,
+    /// `rsvp.changed` and `verification.accepted`
+    #[validate(length(min = 1, max = 5))]
+    pub event_types: Vec<String>,
+
+    /// Send user and organizer IDs instead of scrubbing them
+    #[serde(default)]
+    pub include_pii: bool,
+
+    /// Subscribe to an organization's events instead of the caller's own
+    pub organization_id: Option<Uuid>,
+}
+
+/// A subscription
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WebhookResponse {
+    pub id: Uuid,
+    pub url: String,
+    pub event_types: Vec<String>,
+    pub include_pii: bool,
+    pub organization_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<WebhookSubscription> for WebhookResponse {
+    fn from(subscription: WebhookSubscription) -> Self {
+        Self {
+            id: subscription.id,
+            url: subscription.url,
+            event_types: subscription.event_types,
+            include_pii: subscription.include_pii,
+            organization_id: subscription.organization_id,
+            created_at: subscription.created_at,
+        }
+    }
+}
+
+/// A new subscription, with the secret its deliveries are signed with
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreatedWebhookResponse {
+    #[serde(flatten)]
+    pub webhook: WebhookResponse,
+    /// Shown only once
+    pub secret: String,
+}
+
+/// A delivery that failed every attempt
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DeadLetterResponse {
+    pub id: Uuid,
+    pub event_type: String,
+    pub payload: Value,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub failed_at: DateTime<Utc>,
+}
+
+impl From<DeadLetter> for DeadLetterResponse {
+    fn from(letter: DeadLetter) -> Self {
+        Self {
+            id: letter.id,
+            event_type: letter.event_type,
+            payload: letter.payload,
+            attempts: letter.attempts,
+            last_error: letter.last_error,
+            created_at: letter.created_at,
+            failed_at: letter.failed_at,
+        }
+    }
+}
+
+/// Dead letters queued for delivery again
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReplayResponse {
+    pub replayed: u64,
+}
+
+/// Subscribe a URL to changes to the caller's or an organization's events
+/// POST /api/v1/webhooks
+///
+/// # Errors
+///
+/// Returns [`ApiError::InvalidInput`] for invalid input, an unknown event type,
+/// a disallowed URL or too many webhooks, [`ApiError::Forbidden`] if the caller
+/// can't manage webhooks for the owner, or an error if a query fails.
+#[utoipa::path(
+    post,
+    path = "/webhooks",
+    tag = "webhooks",
+    request_body = CreateWebhookRequest,
+    responses(
+        (status = 201, description = "The subscription, with its secret shown once", body = CreatedWebhookResponse),
+        (status = 400, description = "Invalid input", body = crate::error::ErrorResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 403, description = "Not allowed", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn create_webhook(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(req): Json<CreateWebhookRequest>,
+) -> Result<(StatusCode, Json<CreatedWebhookResponse>)> {
+    req.validate()
+        .map_err(|e| ApiError::InvalidInput(e.to_string()))?;
+    if let Some(unknown) = req
+        .event_types
+        .iter()
+        .find(|t| !EVENT_TYPES.contains(&t.as_str()))
+    {
+        return Err(ApiError::InvalidInput(format!(
+            "Unknown event type: {unknown}"
+        )));
+    }
+    webhooks::check_url(&req.url, &state.config.webhooks).await?;
+
+    // Only organizers have events to report on
+    let user = db::users::find_by_id(&state.db, auth.id)
+        .await?
+        .ok_or(ApiError::UserNotFound)?;
+    let allowed = match req.organization_id {
+        Some(organization_id) => standing(&state, organization_id, user.id)
+            .await?
+            .can(Capability::ManageWebhooks),
+        None => user.current_level >= ORGANIZER_LEVEL,
+    };
+    if !allowed {
+        return Err(ApiError::Forbidden);
+    }
+    if db::webhooks::count_owned(&state.db, user.id).await? >= MAX_SUBSCRIPTIONS {
+        return Err(ApiError::InvalidInput(format!(
+            "At most {MAX_SUBSCRIPTIONS} webhooks are allowed"
+        )));
+    }
+
+    let mut event_types = req.event_types;
+    event_types.sort_unstable();
+    event_types.dedup();
+    let secret = crypto::generate_nonce();
+    let subscription = db::webhooks::create(
+        &state.db,
+        NewSubscription {
+            owner_id: user.id,
+            organization_id: req.organization_id,
+            url: &req.url,
+            secret: &secret,
+            event_types: &event_types,
+            include_pii: req.include_pii,
+        },
+    )
+    .await?;
+
+    tracing::info!(user_id = %user.id, webhook_id = %subscription.id, "Webhook created");
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedWebhookResponse {
+            webhook: subscription.into(),
+            secret,
+        }),
+    ))
+}
+
+/// The caller's subscriptions
+/// GET /api/v1/webhooks
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+#[utoipa::path(
+    get,
+    path = "/webhooks",
+    tag = "webhooks",
+    responses(
+        (status = 200, description = "The caller's subscriptions", body = Vec<WebhookResponse>),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn list_webhooks(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<Vec<WebhookResponse>>> {
+    if auth.sanitized {
+        return Ok(Json(Vec::new()));
+    }
+
+    let subscriptions = db::webhooks::list_owned(&state.db, auth.id).await?;
+
+    Ok(Json(subscriptions.into_iter().map(Into::into).collect()))
+}
+
+/// Unsubscribe, discarding queued and dead deliveries
+/// DELETE /api/v1/webhooks/:id
+///
+/// # Errors
+///
+/// Returns [`ApiError::WebhookNotFound`] unless the caller owns the webhook, or
+/// an error if the query fails.
+#[utoipa::path(
+    delete,
+    path = "/webhooks/{id}",
+    tag = "webhooks",
+    params(
+        ("id" = Uuid, Path, description = "Webhook ID"),
+    ),
+    responses(
+        (status = 204, description = "Unsubscribed"),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 404, description = "No such webhook", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn delete_webhook(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode> {
+    let subscription = find_owned(&state, id, &auth).await?;
+    if !db::webhooks::delete(&state.db, subscription.id, auth.id).await? {
+        return Err(ApiError::WebhookNotFound);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Deliveries that failed every attempt, most recent first
+/// GET /api/v1/webhooks/:id/dead-letters
+///
+/// # Errors
+///
+/// Returns [`ApiError::WebhookNotFound`] unless the caller owns the webhook, or
+/// an error if a query fails.
+#[utoipa::path(
+    get,
+    path = "/webhooks/{id}/dead-letters",
+    tag = "webhooks",
+    params(
+        ("id" = Uuid, Path, description = "Webhook ID"),
+    ),
+    responses(
+        (status = 200, description = "Failed deliveries, most recent first", body = Vec<DeadLetterResponse>),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 404, description = "No such webhook", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn dead_letters(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<DeadLetterResponse>>> {
+    let subscription = find_owned(&state, id, &auth).await?;
+    let letters = db::webhooks::dead_letters(&state.db, subscription.id, DEAD_LETTER_PAGE).await?;
+
+    Ok(Json(letters.into_iter().map(Into::into).collect()))
+}
+
+/// Queue all dead letters for delivery again
+/// POST /api/v1/webhooks/:id/dead-letters/replay
+///
+/// # Errors
+///
+/// Returns [`ApiError::WebhookNotFound`] unless the caller owns the webhook, or
+/// an error if a query fails.
+#[utoipa::path(
+    post,
+    path = "/webhooks/{id}/dead-letters/replay",
+    tag = "webhooks",
+    params(
+        ("id" = Uuid, Path, description = "Webhook ID"),
+    ),
+    responses(
+        (status = 202, description = "Dead letters queued again", body = ReplayResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 404, description = "No such webhook", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn replay_dead_letters(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<(StatusCode, Json<ReplayResponse>)> {
+    let subscription = find_owned(&state, id, &auth).await?;
+    let replayed = db::webhooks::replay(&state.db, subscription.id, None).await?;
+
+    Ok((StatusCode::ACCEPTED, Json(ReplayResponse { replayed })))
+}
+
+/// Queue one dead letter for delivery again
+/// POST /api/v1/webhooks/:id/dead-letters/:letter_id/replay
+///
+/// # Errors
+///
+/// Returns [`ApiError::WebhookNotFound`] unless the caller owns the webhook and
+/// the dead letter, or an error if a query fails.
+#[utoipa::path(
+    post,
+    path = "/webhooks/{id}/dead-letters/{letter_id}/replay",
+    tag = "webhooks",
+    params(
+        ("id" = Uuid, Path, description = "Webhook ID"),
+        ("letter_id" = Uuid, Path, description = "Dead letter ID"),
+    ),
+    responses(
+        (status = 202, description = "Dead letter queued again", body = ReplayResponse),
+        (status = 401, description = "Missing or invalid session", body = crate::error::ErrorResponse),
+        (status = 404, description = "No such webhook or dead letter", body = crate::error::ErrorResponse),
+    ),
+    security(("bearer" = [])),
+)]
+pub async fn replay_dead_letter(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((id, letter_id)): Path<(Uuid, Uuid)>,
+) -> Result<(StatusCode, Json<ReplayResponse>)> {
+    let subscription = find_owned(&state, id, &auth).await?;
+    let replayed = db::webhooks::replay(&state.db, subscription.id, Some(letter_id)).await?;
+    if replayed == 0 {
+        return Err(ApiError::WebhookNotFound);
+    }
+
+    Ok((StatusCode::ACCEPTED, Json(ReplayResponse { replayed })))
+}
+
+/// A subscription of the caller's; under duress they have none
+async fn find_owned(state: &AppState, id: Uuid, auth: &AuthUser) -> Result<WebhookSubscription> {
+    if auth.sanitized {
+        return Err(ApiError::WebhookNotFound);
+    }
+
+    db::webhooks::find_owned(&state.db, id, auth.id)
+        .await?
+        .ok_or(ApiError::WebhookNotFound)
+}
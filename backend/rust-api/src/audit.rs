@@ -0,0 +1,735 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Tamper-evident audit log
+//!
+//! Verifications, level progressions and moderation actions are the
+//! record of who earned what and who changed it, so they are kept as a
+//! single append-only hash chain:
+//!
+//! - Each entry's hash covers its sequence number, the previous entry's
+//!   hash and a canonical encoding of its fields. Editing, deleting or
+//!   reordering an entry breaks every hash after it.
+//! - The database refuses updates and deletes on audit tables. That stops
+//!   accidents; the chain catches anyone who gets around it.
+//! - Checkpoints periodically sign the chain head with an ed25519 key that
+//!   lives outside the database, so the chain can't be silently rebuilt
+//!   from scratch either. Checkpoint signatures can be published.
+//!
+//! Retention may later replace old verifications with tombstones that keep
+//! only their place in the chain (see [`crate::retention`]).
+//!
+//! [`verify_chain`] walks the whole log and reports the first break; the
+//! `verify-audit` binary runs it from the command line.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::db::{
+    self,
+    models::{AdminAction, LevelProgression, Tombstone, Verification},
+};
+use crate::error::{ApiError, Result};
+
+/// Hash that precedes the first entry
+pub const GENESIS_HASH: [u8; 32] = [0; 32];
+
+/// Domain separation for entry hashes
+const ENTRY_DOMAIN: &[u8] = b"civicconnect-audit-entry-v1";
+
+/// Domain separation for checkpoint signatures
+const CHECKPOINT_DOMAIN: &[u8] = b"civicconnect-audit-checkpoint-v1";
+
+/// An entry in the audit chain
+#[derive(Debug, Clone)]
+pub enum AuditEntry {
+    Verification(Verification),
+    LevelProgression(LevelProgression),
+    AdminAction(AdminAction),
+    Tombstone(Tombstone),
+}
+
+impl AuditEntry {
+    /// Position in the chain, if the entry was chained at all
+    #[must_use]
+    pub const fn seq(&self) -> Option<i64> {
+        match self {
+            Self::Verification(v) => v.audit_seq,
+            Self::LevelProgression(p) => p.audit_seq,
+            Self::AdminAction(a) => a.audit_seq,
+            Self::Tombstone(t) => Some(t.audit_seq),
+        }
+    }
+
+    /// Stored hash of the previous entry
+    #[must_use]
+    pub fn prev_hash(&self) -> Option<&[u8]> {
+        match self {
+            Self::Verification(v) => v.prev_hash.as_deref(),
+            Self::LevelProgression(p) => p.prev_hash.as_deref(),
+            Self::AdminAction(a) => a.prev_hash.as_deref(),
+            Self::Tombstone(t) => Some(&t.prev_hash),
+        }
+    }
+
+    /// Stored hash of this entry
+    #[must_use]
+    pub fn entry_hash(&self) -> Option<&[u8]> {
+        match self {
+            Self::Verification(v) => v.entry_hash.as_deref(),
+            Self::LevelProgression(p) => p.entry_hash.as_deref(),
+            Self::AdminAction(a) => a.entry_hash.as_deref(),
+            Self::Tombstone(t) => Some(&t.entry_hash),
+        }
+    }
+
+    /// Table the entry lives in
+    #[must_use]
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Self::Verification(_) => "verification",
+            Self::LevelProgression(_) => "level_progression",
+            Self::AdminAction(_) => "admin_action",
+            Self::Tombstone(_) => "tombstone",
+        }
+    }
+
+    /// Row ID
+    #[must_use]
+    pub const fn id(&self) -> Uuid {
+        match self {
+            Self::Verification(v) => v.id,
+            Self::LevelProgression(p) => p.id,
+            Self::AdminAction(a) => a.id,
+            Self::Tombstone(t) => t.id,
+        }
+    }
+
+    /// Hash of the entry's recorded fields
+    ///
+    /// Chain columns are excluded; timestamps are hashed at the
+    /// microsecond precision Postgres stores. A tombstone gives the hash
+    /// its entry had.
+    #[must_use]
+    pub fn content_hash(&self) -> [u8; 32] {
+        if let Self::Tombstone(t) = self {
+            // A malformed hash can't match, so the link is reported modified
+            return <[u8; 32]>::try_from(t.content_hash.as_slice()).unwrap_or_default();
+        }
+
+        let mut c = Canonical::new(self.kind());
+        match self {
+            Self::Verification(v) => {
+                c.uuid(v.id)
+                    .uuid(v.event_id)
+                    .uuid(v.user_id)
+                    .uuid(v.organizer_id)
+                    .bytes(&v.signature)
+                    .time(v.verified_at)
+                    .int(v.experience_awarded.into())
+                    .str(&v.location_hash);
+
+                // Fraud fields postdate the chain; hashing them only when
+                // set keeps earlier entries verifying
+                if v.status != Verification::VERIFIED
+                    || v.fraud_score != 0
+                    || v.fraud_findings != serde_json::json!([])
+                {
+                    c.str(&v.status)
+                        .int(v.fraud_score.into())
+                        .str(&canonical_json(&v.fraud_findings));
+                }
+            }
+            Self::LevelProgression(p) => {
+                c.uuid(p.id)
+                    .uuid(p.user_id)
+                    .int(p.from_level.into())
+                    .int(p.to_level.into())
+                    .str(&p.reason)
+                    .time(p.progressed_at)
+                    .str(&canonical_json(&p.metadata));
+            }
+            Self::AdminAction(a) => {
+                c.uuid(a.id)
+                    .uuid(a.actor_id)
+                    .str(&a.action)
+                    .uuid(a.target_id)
+                    .str(&a.reason)
+                    .time(a.acted_at)
+                    .str(&canonical_json(&a.metadata));
+            }
+            Self::Tombstone(_) => {}
+        }
+        c.finish()
+    }
+}
+
+/// Hash linking an entry into the chain
+#[must_use]
+pub fn chain_hash(seq: i64, prev_hash: &[u8; 32], content_hash: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(ENTRY_DOMAIN);
+    hasher.update(&seq.to_be_bytes());
+    hasher.update(prev_hash);
+    hasher.update(content_hash);
+    *hasher.finalize().as_bytes()
+}
+
+/// Length-prefixed field encoding, so field boundaries are unambiguous
+struct Canonical(blake3::Hasher);
+
+impl Canonical {
+    fn new(kind: &str) -> Self {
+        let mut c = Self(blake3::Hasher::new());
+        c.str(kind);
+        c
+    }
+
+    fn bytes(&mut self, value: &[u8]) -> &mut Self {
+        self.0.update(&(value.len() as u64).to_be_bytes());
+        self.0.update(value);
+        self
+    }
+
+    fn str(&mut self, value: &str) -> &mut Self {
+        self.bytes(value.as_bytes())
+    }
+
+    fn uuid(&mut self, value: Uuid) -> &mut Self {
+        self.bytes(value.as_bytes())
+    }
+
+    fn int(&mut self, value: i64) -> &mut Self {
+        self.bytes(&value.to_be_bytes())
+    }
+
+    fn time(&mut self, value: DateTime<Utc>) -> &mut Self {
+        self.int(value.timestamp_micros())
+    }
+
+    fn finish(&self) -> [u8; 32] {
+        *self.0.finalize().as_bytes()
+    }
+}
+
+/// JSON with object keys sorted at every level
+///
+/// Postgres `jsonb` doesn't keep key order, so hashes must not depend on it.
+fn canonical_json(value: &serde_json::Value) -> String {
+    fn sorted(value: &serde_json::Value) -> serde_json::Value {
+        match value {
+            serde_json::Value::Object(map) => serde_json::Value::Object(
+                map.iter()
+                    .map(|(k, v)| (k.clone(), sorted(v)))
+                    .collect::<BTreeMap<_, _>>()
+                    .into_iter()
+                    .collect(),
+            ),
+            serde_json::Value::Array(items) => {
+                serde_json::Value::Array(items.iter().map(sorted).collect())
+            }
+            other => other.clone(),
+        }
+    }
+
+    sorted(value).to_string()
+}
+
+/// Truncate a timestamp to the microseconds Postgres stores, so the
+/// hash computed on insert matches the one recomputed on read
+#[must_use]
+pub fn db_timestamp(at: DateTime<Utc>) -> DateTime<Utc> {
+    DateTime::from_timestamp_micros(at.timestamp_micros()).unwrap_or(at)
+}
+
+/// A signed statement of the chain head
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Checkpoint {
+    pub seq: i64,
+    pub entry_hash: Vec<u8>,
+    pub signed_at: DateTime<Utc>,
+    pub signature: Vec<u8>,
+}
+
+/// Bytes covered by a checkpoint signature
+fn checkpoint_message(seq: i64, entry_hash: &[u8], signed_at: DateTime<Utc>) -> Vec<u8> {
+    let mut message = CHECKPOINT_DOMAIN.to_vec();
+    message.extend_from_slice(&seq.to_be_bytes());
+    message.extend_from_slice(entry_hash);
+    message.extend_from_slice(&signed_at.timestamp_micros().to_be_bytes());
+    message
+}
+
+impl Checkpoint {
+    /// Whether the signature is valid under `key`
+    #[must_use]
+    pub fn verify(&self, key: &VerifyingKey) -> bool {
+        let Ok(signature) = Signature::from_slice(&self.signature) else {
+            return false;
+        };
+        let message = checkpoint_message(self.seq, &self.entry_hash, self.signed_at);
+        key.verify(&message, &signature).is_ok()
+    }
+}
+
+/// Signs checkpoints with the server's audit key
+#[derive(Clone)]
+pub struct CheckpointSigner {
+    key: SigningKey,
+}
+
+impl std::fmt::Debug for CheckpointSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CheckpointSigner")
+            .field(
+                "public_key",
+                &hex::encode(self.key.verifying_key().as_bytes()),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+impl CheckpointSigner {
+    /// Create a signer from a 32-byte ed25519 seed
+    #[must_use]
+    pub fn new(seed: &[u8; 32]) -> Self {
+        Self {
+            key: SigningKey::from_bytes(seed),
+        }
+    }
+
+    /// Load the seed from a file containing 32 hex-encoded bytes
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read or doesn't hold a key.
+    pub fn from_key_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            ApiError::Internal(anyhow::anyhow!("Cannot read audit signing key file: {e}"))
+        })?;
+
+        let seed: [u8; 32] = hex::decode(contents.trim())
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| {
+                ApiError::Internal(anyhow::anyhow!(
+                    "Audit signing key file must contain 32 hex-encoded bytes"
+                ))
+            })?;
+
+        Ok(Self::new(&seed))
+    }
+
+    /// Public key that checkpoints verify under
+    #[must_use]
+    pub fn verifying_key(&self) -> VerifyingKey {
+        self.key.verifying_key()
+    }
+
+    /// Sign the chain head as of now
+    #[must_use]
+    pub fn sign(&self, seq: i64, entry_hash: &[u8]) -> Checkpoint {
+        let signed_at = db_timestamp(Utc::now());
+        let signature = self
+            .key
+            .sign(&checkpoint_message(seq, entry_hash, signed_at));
+
+        Checkpoint {
+            seq,
+            entry_hash: entry_hash.to_vec(),
+            signed_at,
+            signature: signature.to_bytes().to_vec(),
+        }
+    }
+}
+
+/// Sign the chain head if it has moved since the last checkpoint
+///
+/// Returns the new checkpoint's sequence number, if one was written.
+///
+/// # Errors
+///
+/// Returns an error if a query fails.
+pub async fn checkpoint(pool: &PgPool, signer: &CheckpointSigner) -> Result<Option<i64>> {
+    let (seq, entry_hash) = db::audit::head(pool).await?;
+    let last = db::audit::latest_checkpoint_seq(pool).await?;
+
+    if seq == 0 || last.is_some_and(|last| last >= seq) {
+        return Ok(None);
+    }
+
+    db::audit::insert_checkpoint(pool, &signer.sign(seq, &entry_hash)).await?;
+    Ok(Some(seq))
+}
+
+/// Write checkpoints every `interval`, forever
+pub async fn run_checkpoints(pool: PgPool, signer: CheckpointSigner, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        match checkpoint(&pool, &signer).await {
+            Ok(Some(seq)) => tracing::info!(seq, "Audit checkpoint signed"),
+            Ok(None) => {}
+            Err(e) => tracing::error!(error = ?e, "Audit checkpoint failed"),
+        }
+    }
+}
+
+/// Why verification stopped
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum ChainBreak {
+    /// A row was written without being chained
+    Unchained { kind: &'static str, id: Uuid },
+    /// Sequence numbers skip (an entry was deleted) or repeat
+    SequenceGap { expected: i64, found: i64 },
+    /// The entry doesn't point at the previous entry's hash
+    PrevHashMismatch { seq: i64 },
+    /// The entry's fields no longer match its hash
+    EntryModified { seq: i64 },
+    /// A signed checkpoint disagrees with the chain
+    CheckpointMismatch { seq: i64 },
+    /// A checkpoint's signature is invalid
+    CheckpointSignatureInvalid { seq: i64 },
+    /// Entries covered by a checkpoint or the head record are missing
+    Truncated { expected_seq: i64, last_seq: i64 },
+}
+
+/// Outcome of walking the audit chain
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditReport {
+    /// Entries whose links were checked
+    pub entries_checked: u64,
+    /// Checkpoints whose signatures were checked
+    pub checkpoints_checked: u64,
+    /// Sequence number of the last intact entry
+    pub last_valid_seq: i64,
+    /// First problem found, if any
+    pub first_break: Option<ChainBreak>,
+}
+
+impl AuditReport {
+    /// Whether the chain verified end to end
+    #[must_use]
+    pub const fn is_intact(&self) -> bool {
+        self.first_break.is_none()
+    }
+}
+
+/// Walks entries in sequence order, checking each link
+pub struct ChainVerifier<'a> {
+    checkpoints: BTreeMap<i64, Checkpoint>,
+    key: Option<&'a VerifyingKey>,
+    prev_hash: [u8; 32],
+    report: AuditReport,
+}
+
+impl<'a> ChainVerifier<'a> {
+    /// Start a walk; checkpoint signatures are checked only if `key` is given
+    #[must_use]
+    pub fn new(checkpoints: Vec<Checkpoint>, key: Option<&'a VerifyingKey>) -> Self {
+        Self {
+            checkpoints: checkpoints.into_iter().map(|c| (c.seq, c)).collect(),
+            key,
+            prev_hash: GENESIS_HASH,
+            report: AuditReport {
+                entries_checked: 0,
+                checkpoints_checked: 0,
+                last_valid_seq: 0,
+                first_break: None,
+            },
+        }
+    }
+
+    /// Check the next entry; returns `false` once the chain is broken
+    pub fn push(&mut self, entry: &AuditEntry) -> bool {
+        if self.report.first_break.is_some() {
+            return false;
+        }
+
+        let expected = self.report.last_valid_seq + 1;
+        let problem = match entry.seq() {
+            None => Some(ChainBreak::Unchained {
+                kind: entry.kind(),
+                id: entry.id(),
+            }),
+            Some(seq) if seq != expected => Some(ChainBreak::SequenceGap {
+                expected,
+                found: seq,
+            }),
+            Some(seq) if entry.prev_hash() != Some(&self.prev_hash[..]) => {
+                Some(ChainBreak::PrevHashMismatch { seq })
+            }
+            Some(seq) => {
+                let hash = chain_hash(seq, &self.prev_hash, &entry.content_hash());
+                if entry.entry_hash() == Some(&hash[..]) {
+                    self.prev_hash = hash;
+                    self.check_checkpoint(seq, &hash)
+                } else {
+                    Some(ChainBreak::EntryModified { seq })
+                }
+            }
+        };
+
+        if problem.is_some() {
+            self.report.first_break = problem;
+            return false;
+        }
+
+        self.report.entries_checked += 1;
+        self.report.last_valid_seq = expected;
+        true
+    }
+
+    fn check_checkpoint(&mut self, seq: i64, hash: &[u8; 32]) -> Option<ChainBreak> {
+        let checkpoint = self.checkpoints.get(&seq)?;
+
+        if let Some(key) = self.key {
+            if !checkpoint.verify(key) {
+                return Some(ChainBreak::CheckpointSignatureInvalid { seq });
+            }
+            self.report.checkpoints_checked += 1;
+        }
+
+        (checkpoint.entry_hash != hash[..]).then_some(ChainBreak::CheckpointMismatch { seq })
+    }
+
+    /// Finish the walk, checking the chain reaches `head_seq` and every
+    /// checkpoint
+    #[must_use]
+    pub fn finish(mut self, head_seq: i64) -> AuditReport {
+        if self.report.first_break.is_none() {
+            let last_seq = self.report.last_valid_seq;
+            let expected_seq = self
+                .checkpoints
+                .keys()
+                .next_back()
+                .copied()
+                .unwrap_or(0)
+                .max(head_seq);
+
+            if expected_seq > last_seq {
+                self.report.first_break = Some(ChainBreak::Truncated {
+                    expected_seq,
+                    last_seq,
+                });
+            }
+        }
+
+        self.report
+    }
+}
+
+/// Walk the whole audit log and report the first break
+///
+/// Entries are read in pages, so this runs in constant memory.
+///
+/// # Errors
+///
+/// Returns an error if a query fails.
+pub async fn verify_chain(pool: &PgPool, key: Option<&VerifyingKey>) -> Result<AuditReport> {
+    let (head_seq, _) = db::audit::head(pool).await?;
+    let mut verifier = ChainVerifier::new(db::audit::checkpoints(pool).await?, key);
+
+    // Rows that bypassed the chain come first, so they are reported first
+    if let Some(entry) = db::audit::first_unchained(pool).await? {
+        verifier.push(&entry);
+        return Ok(verifier.finish(head_seq));
+    }
+
+    let mut after = 0;
+    loop {
+        let page = db::audit::entries_after(pool, after).await?;
+        let Some(last) = page.last().and_then(AuditEntry::seq) else {
+            break;
+        };
+
+        for entry in &page {
+            if !verifier.push(entry) {
+                return Ok(verifier.finish(head_seq));
+            }
+        }
+        after = last;
+    }
+
+    Ok(verifier.finish(head_seq))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn progression(reason: &str) -> LevelProgression {
+        LevelProgression {
+            id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            from_level: 1,
+            to_level: 2,
+            reason: reason.into(),
+            progressed_at: db_timestamp(Utc::now()),
+            metadata: serde_json::json!({ "b": 1, "a": { "d": 2, "c": 3 } }),
+            audit_seq: None,
+            prev_hash: None,
+            entry_hash: None,
+        }
+    }
+
+    /// Chain `entries` in order, as the database layer does
+    fn chain(entries: &mut [LevelProgression]) {
+        let mut prev = GENESIS_HASH;
+        for (seq, entry) in (1..).zip(entries.iter_mut()) {
+            let hash = chain_hash(
+                seq,
+                &prev,
+                &AuditEntry::LevelProgression(entry.clone()).content_hash(),
+            );
+            entry.audit_seq = Some(seq);
+            entry.prev_hash = Some(prev.to_vec());
+            entry.entry_hash = Some(hash.to_vec());
+            prev = hash;
+        }
+    }
+
+    fn verify(
+        entries: &[LevelProgression],
+        checkpoints: Vec<Checkpoint>,
+        key: &VerifyingKey,
+    ) -> AuditReport {
+        let mut verifier = ChainVerifier::new(checkpoints, Some(key));
+        for entry in entries {
+            verifier.push(&AuditEntry::LevelProgression(entry.clone()));
+        }
+        verifier.finish(entries.last().and_then(|e| e.audit_seq).unwrap_or(0))
+    }
+
+    #[test]
+    fn test_intact_chain_verifies() {
+        let signer = CheckpointSigner::new(&[9; 32]);
+        let mut entries: Vec<_> = (0..5).map(|i| progression(&format!("r{i}"))).collect();
+        chain(&mut entries);
+
+        let checkpoint = signer.sign(3, entries[2].entry_hash.as_ref().unwrap());
+        let report = verify(&entries, vec![checkpoint], &signer.verifying_key());
+
+        assert!(report.is_intact(), "{report:?}");
+        assert_eq!(report.entries_checked, 5);
+        assert_eq!(report.checkpoints_checked, 1);
+    }
+
+    #[test]
+    fn test_modified_entry_is_first_break() {
+        let signer = CheckpointSigner::new(&[9; 32]);
+        let mut entries: Vec<_> = (0..5).map(|i| progression(&format!("r{i}"))).collect();
+        chain(&mut entries);
+        entries[2].to_level = 5;
+
+        let report = verify(&entries, vec![], &signer.verifying_key());
+        assert_eq!(
+            report.first_break,
+            Some(ChainBreak::EntryModified { seq: 3 })
+        );
+        assert_eq!(report.last_valid_seq, 2);
+    }
+
+    #[test]
+    fn test_deleted_and_truncated_entries_detected() {
+        let signer = CheckpointSigner::new(&[9; 32]);
+        let mut entries: Vec<_> = (0..5).map(|i| progression(&format!("r{i}"))).collect();
+        chain(&mut entries);
+
+        let mut deleted = entries.clone();
+        deleted.remove(1);
+        assert_eq!(
+            verify(&deleted, vec![], &signer.verifying_key()).first_break,
+            Some(ChainBreak::SequenceGap {
+                expected: 2,
+                found: 3
+            })
+        );
+
+        // Dropping the tail is caught by a later checkpoint
+        let checkpoint = signer.sign(5, entries[4].entry_hash.as_ref().unwrap());
+        assert_eq!(
+            verify(&entries[..3], vec![checkpoint], &signer.verifying_key()).first_break,
+            Some(ChainBreak::Truncated {
+                expected_seq: 5,
+                last_seq: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_rebuilt_chain_fails_checkpoint() {
+        let signer = CheckpointSigner::new(&[9; 32]);
+        let mut entries: Vec<_> = (0..3).map(|i| progression(&format!("r{i}"))).collect();
+        chain(&mut entries);
+        let checkpoint = signer.sign(3, entries[2].entry_hash.as_ref().unwrap());
+
+        // Rewriting an entry and recomputing every hash keeps the links
+        // consistent, but no longer matches the signed head
+        entries[0].reason = "forged".into();
+        chain(&mut entries);
+        assert_eq!(
+            verify(&entries, vec![checkpoint], &signer.verifying_key()).first_break,
+            Some(ChainBreak::CheckpointMismatch { seq: 3 })
+        );
+
+        // Nor can the checkpoint be re-signed without the key
+        let impostor = CheckpointSigner::new(&[8; 32]);
+        let forged = impostor.sign(3, entries[2].entry_hash.as_ref().unwrap());
+        assert_eq!(
+            verify(&entries, vec![forged], &signer.verifying_key()).first_break,
+            Some(ChainBreak::CheckpointSignatureInvalid { seq: 3 })
+        );
+    }
+
+    #[test]
+    fn test_metadata_key_order_ignored() {
+        let entry = progression("r");
+        let mut reordered = entry.clone();
+        reordered.metadata = serde_json::json!({ "a": { "c": 3, "d": 2 }, "b": 1 });
+
+        assert_eq!(
+            AuditEntry::LevelProgression(entry).content_hash(),
+            AuditEntry::LevelProgression(reordered).content_hash()
+        );
+    }
+
+    #[test]
+    fn test_held_verification_hash_covers_fraud_fields() {
+        let verification = Verification {
+            id: Uuid::new_v4(),
+            event_id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            organizer_id: Uuid::new_v4(),
+            signature: vec![1, 2, 3],
+            verified_at: db_timestamp(Utc::now()),
+            experience_awarded: 0,
+            location_hash: "872830828ffffff".into(),
+            status: Verification::HELD_FOR_REVIEW.into(),
+            fraud_score: 100,
+            fraud_findings: serde_json::json!([{ "rule": "impossible_travel" }]),
+            audit_seq: None,
+            prev_hash: None,
+            entry_hash: None,
+        };
+        let hash = |v: &Verification| AuditEntry::Verification(v.clone()).content_hash();
+
+        let mut cleared = verification.clone();
+        cleared.status = Verification::VERIFIED.into();
+        assert_ne!(hash(&verification), hash(&cleared));
+
+        // Clearing every fraud field is still a change
+        cleared.fraud_score = 0;
+        cleared.fraud_findings = serde_json::json!([]);
+        assert_ne!(hash(&verification), hash(&cleared));
+    }
+}
@@ -0,0 +1,70 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Verify the audit chain
+//!
+//! Walks every verification and level progression in order, checks the
+//! hash links and signed checkpoints, and prints a JSON report. Exits
+//! non-zero if the chain is broken.
+//!
+//! Checkpoint signatures are checked against `--public-key <hex>` if
+//! given, otherwise against the configured audit signing key. Without
+//! either, only the hash chain is checked.
+//!
+//! Usage: `verify-audit [--public-key <hex>]`
+
+use std::process::ExitCode;
+
+use anyhow::{bail, Context, Result};
+use ed25519_dalek::VerifyingKey;
+
+use civicconnect_api::{audit, config::Config, db};
+
+#[tokio::main]
+async fn main() -> Result<ExitCode> {
+    dotenvy::dotenv().ok();
+    let config = Config::from_env()?;
+
+    let key = match public_key_arg()? {
+        Some(key) => Some(key),
+        None => config
+            .audit_signing_key_file
+            .as_deref()
+            .map(audit::CheckpointSigner::from_key_file)
+            .transpose()?
+            .map(|signer| signer.verifying_key()),
+    };
+    if key.is_none() {
+        eprintln!("warning: no audit key given; checkpoint signatures not checked");
+    }
+
+    let pool = db::create_pool(&config.database_url).await?;
+    let report = audit::verify_chain(&pool, key.as_ref()).await?;
+
+    println!("{}", serde_json::to_string_pretty(&report)?);
+
+    Ok(if report.is_intact() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+/// Parse `--public-key <hex>`
+fn public_key_arg() -> Result<Option<VerifyingKey>> {
+    let mut args = std::env::args().skip(1);
+    let Some(flag) = args.next() else {
+        return Ok(None);
+    };
+    if flag != "--public-key" {
+        bail!("usage: verify-audit [--public-key <hex>]");
+    }
+
+    let hex_key = args.next().context("--public-key needs a value")?;
+    let bytes: [u8; 32] = hex::decode(hex_key.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .context("public key must be 32 hex-encoded bytes")?;
+
+    Ok(Some(VerifyingKey::from_bytes(&bytes)?))
+}
@@ -0,0 +1,174 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! iCalendar (RFC 5545) rendering
+//!
+//! Events are placed no more precisely than the API shows them: at the
+//! center of their H3 cell (`GEO`), plus the venue text if the organizer
+//! chose to publish one (`LOCATION`). Nothing about attendees or the
+//! organizer's account is included.
+
+use chrono::{DateTime, Utc};
+
+use crate::api::events::EventDetails;
+use crate::location;
+
+/// Product identifier written to every calendar
+const PRODID: &str = "-//CivicConnect//CivicConnect API//EN";
+
+/// Longest content line, in octets, before folding
+const MAX_LINE_OCTETS: usize = 75;
+
+/// Render a calendar containing `events`
+///
+/// `public_url` is used to link each entry back to the event page.
+#[must_use]
+pub fn render(name: &str, events: &[EventDetails], public_url: &str) -> String {
+    let mut out = String::new();
+    line(&mut out, "BEGIN:VCALENDAR");
+    line(&mut out, "VERSION:2.0");
+    line(&mut out, &format!("PRODID:{PRODID}"));
+    line(&mut out, "CALSCALE:GREGORIAN");
+    line(&mut out, &format!("X-WR-CALNAME:{}", escape(name)));
+
+    for event in events {
+        line(&mut out, "BEGIN:VEVENT");
+        line(&mut out, &format!("UID:{}", event.id));
+        line(
+            &mut out,
+            &format!("DTSTAMP:{}", timestamp(event.updated_at)),
+        );
+        line(
+            &mut out,
+            &format!("DTSTART:{}", timestamp(event.start_time)),
+        );
+        line(&mut out, &format!("DTEND:{}", timestamp(event.end_time)));
+        line(&mut out, &format!("SUMMARY:{}", escape(&event.title)));
+        if !event.description.is_empty() {
+            line(
+                &mut out,
+                &format!("DESCRIPTION:{}", escape(&event.description)),
+            );
+        }
+        if let Some(venue) = &event.venue {
+            line(&mut out, &format!("LOCATION:{}", escape(venue)));
+        }
+        if let Some((lat, lng)) = location::cell_center(&event.location_cell) {
+            line(&mut out, &format!("GEO:{lat:.6};{lng:.6}"));
+        }
+        if !event.tags.is_empty() {
+            let tags: Vec<String> = event.tags.iter().map(|t| escape(t)).collect();
+            line(&mut out, &format!("CATEGORIES:{}", tags.join(",")));
+        }
+        line(&mut out, &format!("URL:{public_url}/events/{}", event.id));
+        line(&mut out, "STATUS:CONFIRMED");
+        line(&mut out, "END:VEVENT");
+    }
+
+    line(&mut out, "END:VCALENDAR");
+    out
+}
+
+/// UTC date-time in the RFC 5545 basic format
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Escape a TEXT value
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Append a content line, folded at 75 octets and ended with CRLF
+fn line(out: &mut String, content: &str) {
+    let mut octets = 0;
+    for c in content.chars() {
+        // Continuation lines start with a space, which counts
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            out.push_str("\r\n ");
+            octets = 1;
+        }
+        out.push(c);
+        octets += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn event(venue: Option<&str>) -> EventDetails {
+        let start = DateTime::from_timestamp(1_767_268_800, 0).unwrap();
+        EventDetails {
+            id: Uuid::nil(),
+            title: "Park cleanup; bring gloves, bags".into(),
+            description: "Meet at the gate.\nTools provided.".into(),
+            organizer_id: Uuid::nil(),
+            organizer_username: "organizer".into(),
+            organizer_level: 3,
+            organization_id: None,
+            start_time: start,
+            end_time: start + chrono::Duration::hours(2),
+            location_cell: "87195da49ffffff".into(),
+            venue: venue.map(Into::into),
+            attendee_count: 4,
+            capacity: Some(20),
+            tags: vec!["environment".into()],
+            created_at: start,
+            updated_at: start,
+        }
+    }
+
+    #[test]
+    fn renders_escaped_crlf_calendar() {
+        let ics = render("Events", &[event(None)], "https://civic.example");
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VEVENT\r\nEND:VCALENDAR\r\n"));
+        assert!(ics.contains("\r\nDTSTART:20260101T120000Z\r\n"));
+        assert!(ics.contains("\r\nDTEND:20260101T140000Z\r\n"));
+        assert!(ics.contains("\r\nSUMMARY:Park cleanup\\; bring gloves\\, bags\r\n"));
+        assert!(ics.contains("\r\nDESCRIPTION:Meet at the gate.\\nTools provided.\r\n"));
+        assert!(ics.contains("\r\nURL:https://civic.example/events/"));
+    }
+
+    #[test]
+    fn location_is_cell_center_unless_venue_published() {
+        let ics = render("Events", &[event(None)], "https://civic.example");
+        assert!(ics.contains("\r\nGEO:51."));
+        assert!(!ics.contains("LOCATION:"));
+
+        let ics = render(
+            "Events",
+            &[event(Some("Community hall"))],
+            "https://civic.example",
+        );
+        assert!(ics.contains("\r\nLOCATION:Community hall\r\n"));
+    }
+
+    #[test]
+    fn long_lines_are_folded_within_75_octets() {
+        let mut long = event(None);
+        long.description = "é".repeat(100);
+        let ics = render("Events", &[long], "https://civic.example");
+
+        assert!(ics.split("\r\n").all(|l| l.len() <= MAX_LINE_OCTETS));
+        let unfolded = ics.replace("\r\n ", "");
+        assert!(unfolded.contains(&format!("DESCRIPTION:{}\r\n", "é".repeat(100))));
+    }
+}
@@ -0,0 +1,266 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Server configuration
+//!
+//! Loaded from environment variables (and a `.env` file, if present).
+//! Variables use the `CIVICCONNECT_` prefix with `__` separating nested keys:
+//!
+//! - `CIVICCONNECT_BIND_ADDR`: Server bind address (default: 0.0.0.0:8080)
+//! - `CIVICCONNECT_DATABASE_URL`: Postgres connection string (required)
+//! - `CIVICCONNECT_JWT_SECRET`: HMAC secret for session tokens (required)
+//! - `CIVICCONNECT_BLIND_INDEX_KEYS`: Comma-separated hex email index keys,
+//!   oldest first; append a key to rotate (required)
+//! - `CIVICCONNECT_EMAIL_REINDEX_INTERVAL_SECS`: Seconds between runs
+//!   moving emails to the newest index key (default: 3600)
+//! - `CIVICCONNECT_PUBLIC_URL`: Externally visible base URL, used in links
+//!   and as the passkey relying party origin
+//! - `CIVICCONNECT_RECOVERY_KEY_FILE`: Key file for opt-in recovery emails;
+//!   recovery is disabled if unset
+//! - `CIVICCONNECT_MAIL_SINK_DIR`: Write outbound mail to this directory
+//! - `CIVICCONNECT_WEBAUTHN_REQUIRED_LEVEL`: Users at or above this level
+//!   must log in with a passkey (default: not required)
+//! - `CIVICCONNECT_AUDIT_SIGNING_KEY_FILE`: ed25519 seed for signing audit
+//!   checkpoints; checkpoints are not written if unset
+//! - `CIVICCONNECT_AUDIT_CHECKPOINT_INTERVAL_SECS`: Checkpoint interval
+//!   (default: 3600)
+//! - `CIVICCONNECT_FRAUD__HOLD_THRESHOLD`: Fraud score at which a
+//!   verification is held for review (default: 50)
+//! - `CIVICCONNECT_SCAN__OFFLINE_GRACE_SECS`: How long after a QR code was
+//!   shown a scan queued offline may be uploaded (default: 43200)
+//! - `CIVICCONNECT_SCAN__CLOCK_SKEW_SECS`: Allowed device clock error
+//!   (default: 120)
+//! - `CIVICCONNECT_CREDENTIALS__KEY_FILE`: PEM RSA key blind-signing
+//!   unlinkable attendance credentials; credentials are disabled if unset
+//! - `CIVICCONNECT_REDIS_URL`: Redis for fanning real-time updates out
+//!   across replicas; updates stay on one replica if unset
+//! - `CIVICCONNECT_REALTIME__QR_ROTATION_SECS`: Seconds between QR
+//!   rotation ticks (default: 30)
+//! - `CIVICCONNECT_FEDERATION__KEY_FILE`: PEM RSA key signing ActivityPub
+//!   deliveries; federation is disabled if unset
+//! - `CIVICCONNECT_WEBHOOKS__MAX_ATTEMPTS`: Webhook delivery attempts
+//!   before dead-lettering (default: 8)
+//! - `CIVICCONNECT_SERVER__REQUEST_TIMEOUT_SECS`: Per-request time limit
+//!   (default: 10; 30 for routes that hash passwords)
+//! - `CIVICCONNECT_SERVER__MAX_BODY_BYTES`: Largest request body
+//!   (default: 128 KiB)
+//! - `CIVICCONNECT_SERVER__MAX_CONCURRENT_REQUESTS`: Requests in flight
+//!   before more are shed with 503 (default: 512)
+//! - `CIVICCONNECT_SERVER__SHUTDOWN_GRACE_SECS`: Time in-flight requests
+//!   get to finish on shutdown (default: 30)
+//! - `CIVICCONNECT_IDEMPOTENCY__TTL_SECS`: How long responses to requests
+//!   with an `Idempotency-Key` are kept for replay (default: 86400)
+//! - `CIVICCONNECT_RETENTION__READ_MESSAGE_DAYS`: Days messages are kept
+//!   after being read (default: 30)
+//! - `CIVICCONNECT_RETENTION__VERIFICATION_DAYS`: Days before verifications
+//!   are collapsed into attendance counters (default: 90)
+//! - `CIVICCONNECT_RETENTION__LOCATION_INACTIVE_DAYS`: Days without a
+//!   login before a user's location is cleared (default: 30)
+//! - `CIVICCONNECT_TELEMETRY__LOG_FORMAT`: `json` or `text` (default: json)
+//! - `CIVICCONNECT_TELEMETRY__OTLP_ENDPOINT`: OTLP/HTTP traces endpoint;
+//!   spans are only logged if unset
+//! - `CIVICCONNECT_TELEMETRY__SAMPLE_RATIO`: Fraction of traces exported
+//!   (default: 1.0)
+//! - `CIVICCONNECT_PASSWORD__MEMORY_KIB`: Argon2id memory cost (default: CPR-001)
+//! - `CIVICCONNECT_PASSWORD__MAX_CONCURRENT`: Concurrent hashes allowed
+
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use crate::credentials::CredentialsConfig;
+use crate::crypto::password::PasswordParams;
+use crate::federation::FederationConfig;
+use crate::fraud::FraudConfig;
+use crate::idempotency::IdempotencyConfig;
+use crate::realtime::RealtimeConfig;
+use crate::retention::RetentionConfig;
+use crate::server::ServerConfig;
+use crate::telemetry::TelemetryConfig;
+use crate::verification::ScanConfig;
+use crate::webhooks::WebhookConfig;
+
+/// Environment variable prefix for all settings
+const ENV_PREFIX: &str = "CIVICCONNECT";
+
+/// Top-level server configuration
+#[derive(Debug, Clone, Deserialize)]
+pub struct Config {
+    /// Server bind address
+    #[serde(default = "default_bind_addr")]
+    pub bind_addr: String,
+
+    /// Externally visible base URL (no trailing slash)
+    #[serde(default = "default_public_url")]
+    pub public_url: String,
+
+    /// Postgres connection string
+    pub database_url: String,
+
+    /// Secret used to sign session JWTs
+    pub jwt_secret: String,
+
+    /// Hex-encoded 32-byte email blind index keys, oldest first.
+    /// The last key is current; earlier keys are kept until re-indexing
+    /// has moved every row forward.
+    pub blind_index_keys: Vec<String>,
+
+    /// Seconds between runs moving emails to the current index key
+    #[serde(default = "default_email_reindex_interval_secs")]
+    pub email_reindex_interval_secs: u64,
+
+    /// Session token lifetime in hours
+    #[serde(default = "default_token_ttl_hours")]
+    pub token_ttl_hours: i64,
+
+    /// Password hashing configuration
+    #[serde(default)]
+    pub password: PasswordConfig,
+
+    /// File holding the key for opt-in recovery emails.
+    /// Keep this off the database host and out of its backups.
+    #[serde(default)]
+    pub recovery_key_file: Option<PathBuf>,
+
+    /// Directory that outbound mail is written to (development and tests)
+    #[serde(default)]
+    pub mail_sink_dir: Option<PathBuf>,
+
+    /// Level from which a passkey second factor is mandatory.
+    /// Users at this level without one must enroll before getting a session.
+    #[serde(default)]
+    pub webauthn_required_level: Option<i16>,
+
+    /// File holding the ed25519 seed that signs audit checkpoints.
+    /// Keep this off the database host, like the recovery key.
+    #[serde(default)]
+    pub audit_signing_key_file: Option<PathBuf>,
+
+    /// Seconds between audit checkpoints
+    #[serde(default = "default_audit_checkpoint_interval_secs")]
+    pub audit_checkpoint_interval_secs: u64,
+
+    /// Fraud screening of verifications
+    #[serde(default)]
+    pub fraud: FraudConfig,
+
+    /// Timing of QR scans, including those queued offline
+    #[serde(default)]
+    pub scan: ScanConfig,
+
+    /// Unlinkable attendance credentials
+    #[serde(default)]
+    pub credentials: CredentialsConfig,
+
+    /// Redis connection string, e.g. `redis://cache:6379`
+    #[serde(default)]
+    pub redis_url: Option<String>,
+
+    /// Real-time event updates
+    #[serde(default)]
+    pub realtime: RealtimeConfig,
+
+    /// ActivityPub federation
+    #[serde(default)]
+    pub federation: FederationConfig,
+
+    /// Outbound webhook delivery
+    #[serde(default)]
+    pub webhooks: WebhookConfig,
+
+    /// Request limits and shutdown timing
+    #[serde(default)]
+    pub server: ServerConfig,
+
+    /// Idempotency key retention
+    #[serde(default)]
+    pub idempotency: IdempotencyConfig,
+
+    /// How long personal data is kept
+    #[serde(default)]
+    pub retention: RetentionConfig,
+
+    /// Trace export
+    #[serde(default)]
+    pub telemetry: TelemetryConfig,
+}
+
+/// Password hashing configuration (CPR-001)
+#[derive(Debug, Clone, Deserialize)]
+pub struct PasswordConfig {
+    /// Argon2id cost parameters for new hashes
+    #[serde(flatten)]
+    pub params: PasswordParams,
+
+    /// Argon2id cost parameters for recovery codes, whose random secrets
+    /// need far less than a password
+    #[serde(default = "default_recovery_code_params")]
+    pub recovery_codes: PasswordParams,
+
+    /// Maximum number of hashes computed at once.
+    /// Each hash holds `memory_kib` of RAM for its duration.
+    #[serde(default = "default_max_concurrent_hashes")]
+    pub max_concurrent: usize,
+}
+
+impl Default for PasswordConfig {
+    fn default() -> Self {
+        Self {
+            params: PasswordParams::default(),
+            recovery_codes: default_recovery_code_params(),
+            max_concurrent: default_max_concurrent_hashes(),
+        }
+    }
+}
+
+impl Config {
+    /// Load configuration from the environment
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a required setting is missing or malformed.
+    pub fn from_env() -> anyhow::Result<Self> {
+        let config = config::Config::builder()
+            .add_source(
+                config::Environment::with_prefix(ENV_PREFIX)
+                    .prefix_separator("_")
+                    .separator("__")
+                    .list_separator(",")
+                    .with_list_parse_key("blind_index_keys")
+                    .try_parsing(true),
+            )
+            .build()?
+            .try_deserialize()?;
+
+        Ok(config)
+    }
+}
+
+// Default value functions
+fn default_bind_addr() -> String {
+    "0.0.0.0:8080".to_string()
+}
+
+fn default_public_url() -> String {
+    "http://localhost:8080".to_string()
+}
+
+const fn default_token_ttl_hours() -> i64 {
+    24
+}
+
+const fn default_recovery_code_params() -> PasswordParams {
+    PasswordParams::RECOVERY_CODE
+}
+
+const fn default_max_concurrent_hashes() -> usize {
+    4 // 4 x 512 MiB under CPR-001
+}
+
+const fn default_audit_checkpoint_interval_secs() -> u64 {
+    3600
+}
+
+const fn default_email_reindex_interval_secs() -> u64 {
+    3600
+}
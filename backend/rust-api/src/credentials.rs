@@ -0,0 +1,444 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Unlinkable attendance credentials
+//!
+//! An optional alternative to recording verifications, for deployments
+//! where a seized database mustn't say who attended what. Instead of a
+//! verifications row, a verified attendee gets an RSA blind signature: their
+//! device picks a random token, blinds it, and has the server sign the
+//! blinded value, so the server never sees the token it signed. Later, at a
+//! time of the device's choosing, the user redeems tokens for XP and an
+//! attendance count. The server checks its signature and that the token
+//! wasn't spent before, but can't tell which scan it came from.
+//!
+//! What the server keeps, and for how long:
+//! - Until scans for an event can no longer arrive, a per-event salt and a
+//!   salted hash of each user issued a credential for it, so nobody gets
+//!   two. [`run_purge`] then deletes both, leaving no way back.
+//! - For a day, that a user was issued *a* credential, for the daily limit.
+//! - Hashes of spent tokens, which say nothing about who spent them.
+//!
+//! Fraud screening works from attendance history, which this mode doesn't
+//! keep, so credentials are issued on the signature, time, location and
+//! rate checks alone, and don't enter the audit chain.
+//!
+//! Blinding, done by the client with the key from `GET /credentials/key`
+//! (`n`, `e`, and `k` the modulus length in bytes):
+//!
+//! 1. `token` = 32 random bytes, and `m` its [`message_representative`]
+//! 2. `r` random and coprime to `n`; send `blinded = m · rᵉ mod n` as `k` bytes
+//! 3. the server returns `blind_signature = blindedᵈ mod n`
+//! 4. `signature = blind_signature · r⁻¹ mod n`, which satisfies `signatureᵉ = m`
+
+use std::path::{Path, PathBuf};
+
+use chrono::{Duration, Utc};
+use rand::{rngs::OsRng, RngCore};
+use rsa::hazmat::{rsa_decrypt_and_check, rsa_encrypt};
+use rsa::pkcs1::DecodeRsaPrivateKey;
+use rsa::pkcs8::{DecodePrivateKey, EncodePublicKey, LineEnding};
+use rsa::traits::PublicKeyParts;
+use rsa::{BigUint, RsaPrivateKey};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::crypto;
+use crate::db;
+use crate::error::{ApiError, Result};
+use crate::state::AppState;
+use crate::verification::{self, Scan, TimeLimits, XP_EVENT_ATTENDANCE};
+
+/// Length of a credential token in bytes
+pub const TOKEN_LEN: usize = 32;
+
+/// Smallest signing key accepted, in bytes of modulus
+const MIN_KEY_LEN: usize = 256;
+
+/// Domain separator for [`message_representative`]
+const CONTEXT: &[u8] = b"civicconnect-credential-v1";
+
+/// Attendance credential configuration
+#[derive(Debug, Clone, Deserialize)]
+pub struct CredentialsConfig {
+    /// PEM RSA private key that signs credentials; credentials are
+    /// disabled if unset. Keep it off the database host.
+    #[serde(default)]
+    pub key_file: Option<PathBuf>,
+
+    /// Seconds between purges of claims and issuances no longer needed
+    #[serde(default = "default_purge_interval_secs")]
+    pub purge_interval_secs: u64,
+}
+
+impl Default for CredentialsConfig {
+    fn default() -> Self {
+        Self {
+            key_file: None,
+            purge_interval_secs: default_purge_interval_secs(),
+        }
+    }
+}
+
+const fn default_purge_interval_secs() -> u64 {
+    3600
+}
+
+/// Signs blinded tokens and checks redeemed ones
+pub struct Issuer {
+    key: RsaPrivateKey,
+    public_key_pem: String,
+    key_id: String,
+}
+
+impl std::fmt::Debug for Issuer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Issuer")
+            .field("key_id", &self.key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Issuer {
+    /// Issuer from configuration, if a key file is set
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key file can't be read or holds no usable
+    /// RSA key.
+    pub fn from_config(config: &CredentialsConfig) -> Result<Option<Self>> {
+        config
+            .key_file
+            .as_deref()
+            .map(|path| Self::new(load_key(path)?))
+            .transpose()
+    }
+
+    /// Issuer signing with `key`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key is shorter than 2048 bits.
+    pub fn new(key: RsaPrivateKey) -> Result<Self> {
+        if key.size() < MIN_KEY_LEN {
+            return Err(ApiError::Internal(anyhow::anyhow!(
+                "Credential key must be at least 2048 bits"
+            )));
+        }
+        let public_key_pem = key
+            .to_public_key()
+            .to_public_key_pem(LineEnding::LF)
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!("Cannot encode public key: {e}")))?;
+        let key_id = hex::encode(Sha256::digest(key.n().to_bytes_be()));
+
+        Ok(Self {
+            key,
+            public_key_pem,
+            key_id,
+        })
+    }
+
+    /// Public key, as SPKI PEM
+    #[must_use]
+    pub fn public_key_pem(&self) -> &str {
+        &self.public_key_pem
+    }
+
+    /// Hex SHA-256 of the modulus, so clients can tell when the key changes
+    #[must_use]
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    /// Sign a blinded message representative
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ApiError::InvalidInput`] unless `blinded` is a nonzero
+    /// value below the modulus, encoded in the modulus length.
+    pub fn sign_blinded(&self, blinded: &[u8]) -> Result<Vec<u8>> {
+        let value = BigUint::from_bytes_be(blinded);
+        if blinded.len() != self.key.size() || value >= *self.key.n() || value == BigUint::default()
+        {
+            return Err(ApiError::InvalidInput(
+                "Blinded token must be a value below the modulus".into(),
+            ));
+        }
+
+        let signature = rsa_decrypt_and_check(&self.key, Some(&mut OsRng), &value)
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!("Blind signing failed: {e}")))?;
+
+        Ok(fixed_len(&signature, self.key.size()))
+    }
+
+    /// Whether `signature` is this issuer's on `token`
+    #[must_use]
+    pub fn verify(&self, token: &[u8], signature: &[u8]) -> bool {
+        if token.len() != TOKEN_LEN || signature.len() != self.key.size() {
+            return false;
+        }
+        let signature = BigUint::from_bytes_be(signature);
+        if signature >= *self.key.n() {
+            return false;
+        }
+
+        rsa_encrypt(&self.key, &signature)
+            .is_ok_and(|m| m == message_representative(self.key.n(), token))
+    }
+}
+
+/// Full-domain hash of a token: SHA-256 in counter mode, stretched to the
+/// modulus length and reduced
+#[must_use]
+pub fn message_representative(modulus: &BigUint, token: &[u8]) -> BigUint {
+    let len = modulus.bits().div_ceil(8);
+    let mut bytes = Vec::with_capacity(len + 32);
+    for counter in 0u32.. {
+        if bytes.len() >= len {
+            break;
+        }
+        bytes.extend_from_slice(
+            &Sha256::new()
+                .chain_update(CONTEXT)
+                .chain_update(counter.to_be_bytes())
+                .chain_update(token)
+                .finalize(),
+        );
+    }
+    bytes.truncate(len);
+
+    BigUint::from_bytes_be(&bytes) % modulus
+}
+
+/// Big-endian bytes of `value`, left-padded to `len`
+fn fixed_len(value: &BigUint, len: usize) -> Vec<u8> {
+    let bytes = value.to_bytes_be();
+    let mut padded = vec![0; len.saturating_sub(bytes.len())];
+    padded.extend_from_slice(&bytes);
+    padded
+}
+
+/// Check a scan and sign the attendee's blinded token
+///
+/// Runs the checks [`verification::verify_scan`] does, but records only an
+/// unlinkable claim instead of a verification, and awards nothing until the
+/// credential is redeemed.
+///
+/// # Errors
+///
+/// Returns the first check that failed, [`ApiError::InvalidInput`] if the
+/// blinded token is malformed, or an error if a query fails.
+pub async fn issue(
+    state: &AppState,
+    issuer: &Issuer,
+    user_id: Uuid,
+    scan: &Scan<'_>,
+    limits: &TimeLimits,
+    blinded: &[u8],
+) -> Result<Vec<u8>> {
+    let received_at = Utc::now();
+    let scan_checks = async {
+        let authentic = verification::authenticate_scan(state, scan, received_at, limits).await?;
+        verification::check_history(state, authentic.event.id, user_id, scan.qr_timestamp).await?;
+        Ok(authentic.event)
+    };
+    let event = scan_checks
+        .await
+        .map_err(|e| verification::count_rejection(state, e))?;
+    let blind_signature = issuer.sign_blinded(blinded)?;
+
+    // No scan of the event passes the time checks after this
+    let latest = TimeLimits::queued(&state.config.scan, state.config.realtime.qr_rotation_secs);
+    let discard_after =
+        event.end_time + latest.qr_valid + latest.grace + latest.clock_skew + Duration::hours(1);
+    let mut salt = [0u8; 32];
+    OsRng.fill_bytes(&mut salt);
+
+    let mut tx = state.db.begin().await?;
+    let salt = db::credentials::event_salt(&mut tx, event.id, &salt, discard_after).await?;
+    if !db::credentials::claim(&mut tx, event.id, &claim_tag(&salt, user_id)?).await? {
+        return Err(verification::count_rejection(
+            state,
+            ApiError::AlreadyVerified,
+        ));
+    }
+    db::credentials::record_issuance(&mut tx, user_id).await?;
+    tx.commit().await?;
+
+    state.metrics.verification("credential_issued", "none");
+
+    Ok(blind_signature)
+}
+
+/// Whether a user has been issued a credential for an event whose salt
+/// hasn't been purged yet
+///
+/// # Errors
+///
+/// Returns an error if a query fails.
+pub async fn is_claimed(pool: &PgPool, event_id: Uuid, user_id: Uuid) -> Result<bool> {
+    let Some(salt) = db::credentials::find_salt(pool, event_id).await? else {
+        return Ok(false);
+    };
+
+    db::credentials::is_claimed(pool, event_id, &claim_tag(&salt, user_id)?).await
+}
+
+fn claim_tag(salt: &[u8], user_id: Uuid) -> Result<Vec<u8>> {
+    let key = <[u8; 32]>::try_from(salt)
+        .map_err(|_| ApiError::Internal(anyhow::anyhow!("Malformed credential salt")))?;
+
+    Ok(blake3::keyed_hash(&key, user_id.as_bytes())
+        .as_bytes()
+        .to_vec())
+}
+
+/// Outcome of redeeming credentials
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Redemption {
+    pub redeemed: u32,
+    /// Validly signed, but redeemed before
+    pub already_spent: u32,
+    /// Not signed by this issuer
+    pub invalid: u32,
+    /// XP awarded for the newly redeemed
+    pub xp_awarded: i32,
+    pub total_xp: i32,
+    /// All credentials the user has redeemed
+    pub attendances: i32,
+}
+
+/// Redeem `(token, signature)` pairs for XP and attendance
+///
+/// # Errors
+///
+/// Returns an error if a query fails.
+pub async fn redeem(
+    state: &AppState,
+    issuer: &Issuer,
+    user_id: Uuid,
+    credentials: &[(Vec<u8>, Vec<u8>)],
+) -> Result<Redemption> {
+    let mut redeemed = 0u32;
+    let mut already_spent = 0u32;
+    let mut invalid = 0u32;
+
+    let mut tx = state.db.begin().await?;
+    for (token, signature) in credentials {
+        if !issuer.verify(token, signature) {
+            invalid += 1;
+        } else if db::credentials::spend(&mut tx, &crypto::hash_token(&hex::encode(token))).await? {
+            redeemed += 1;
+        } else {
+            already_spent += 1;
+        }
+    }
+
+    let count = i32::try_from(redeemed).unwrap_or(i32::MAX);
+    let xp_awarded = XP_EVENT_ATTENDANCE.saturating_mul(count);
+    let total_xp = db::users::add_experience(&mut tx, user_id, xp_awarded).await?;
+    let attendances = db::credentials::add_attendances(&mut tx, user_id, count).await?;
+    tx.commit().await?;
+
+    state.metrics.xp_awarded(xp_awarded);
+
+    Ok(Redemption {
+        redeemed,
+        already_spent,
+        invalid,
+        xp_awarded,
+        total_xp,
+        attendances,
+    })
+}
+
+/// Load a PKCS#8 or PKCS#1 PEM private key
+fn load_key(path: &Path) -> Result<RsaPrivateKey> {
+    let pem = std::fs::read_to_string(path)
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Cannot read credential key file: {e}")))?;
+
+    RsaPrivateKey::from_pkcs8_pem(&pem)
+        .or_else(|_| RsaPrivateKey::from_pkcs1_pem(&pem))
+        .map_err(|_| {
+            ApiError::Internal(anyhow::anyhow!(
+                "Credential key file must hold a PEM RSA private key"
+            ))
+        })
+}
+
+/// Delete claims and issuances no longer needed, every `interval`
+pub async fn run_purge(db: PgPool, interval: std::time::Duration) {
+    let mut ticker = tokio::time::interval(interval);
+
+    loop {
+        ticker.tick().await;
+        match db::credentials::purge(&db, Utc::now() - Duration::days(1)).await {
+            Ok(0) => {}
+            Ok(count) => tracing::debug!(count, "Purged credential claims and issuances"),
+            Err(e) => tracing::error!(error = ?e, "Credential purge failed"),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use rsa::traits::PrivateKeyParts;
+
+    use super::*;
+
+    const KEY: &str = include_str!("../tests/fixtures/credentials.pem");
+
+    fn issuer() -> Issuer {
+        Issuer::new(RsaPrivateKey::from_pkcs8_pem(KEY).unwrap()).unwrap()
+    }
+
+    /// Blind `token` as a client would, returning the blinded value and
+    /// the unblinding factor
+    fn blind(issuer: &Issuer, token: &[u8]) -> (Vec<u8>, BigUint) {
+        let key = &issuer.key;
+        let n = key.n();
+        let r = BigUint::from_bytes_be(&[7; 64]);
+        // A client inverts `r` with the extended Euclidean algorithm; the
+        // test knows the factors, so Euler's theorem will do
+        let one = BigUint::from(1u8);
+        let phi = key
+            .primes()
+            .iter()
+            .fold(one.clone(), |phi, p| phi * (p - &one));
+        let r_inv = r.modpow(&(phi - &one), n);
+
+        let m = message_representative(n, token);
+        let blinded = (m * r.modpow(key.e(), n)) % n;
+        (fixed_len(&blinded, key.size()), r_inv)
+    }
+
+    #[test]
+    fn unblinded_signatures_verify() {
+        let issuer = issuer();
+        let token = [42; TOKEN_LEN];
+        let (blinded, r_inv) = blind(&issuer, &token);
+
+        let blind_signature = issuer.sign_blinded(&blinded).unwrap();
+        let signature = (BigUint::from_bytes_be(&blind_signature) * r_inv) % issuer.key.n();
+        let signature = fixed_len(&signature, issuer.key.size());
+
+        // The server signed something other than the token's representative
+        assert_ne!(blind_signature, signature);
+        assert!(issuer.verify(&token, &signature));
+        assert!(!issuer.verify(&[43; TOKEN_LEN], &signature));
+        assert!(!issuer.verify(&token, &blind_signature));
+    }
+
+    #[test]
+    fn blinded_values_must_be_below_the_modulus() {
+        let issuer = issuer();
+        let n = fixed_len(issuer.key.n(), issuer.key.size());
+
+        assert!(issuer.sign_blinded(&n).is_err());
+        assert!(issuer.sign_blinded(&vec![0; issuer.key.size()]).is_err());
+        assert!(issuer.sign_blinded(&[1; 16]).is_err());
+    }
+}
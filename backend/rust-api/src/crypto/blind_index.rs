@@ -0,0 +1,208 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Keyed email blind index (CPR-009)
+//!
+//! Users are looked up by a BLAKE3-keyed hash of their email, so a leaked
+//! database can't be matched against a list of known addresses without
+//! also stealing the server key.
+//!
+//! Key rotation: versions form a chain. Version 0 is the legacy unkeyed
+//! SHA-256 from [`super::hash_email`]; version `n` is
+//! `BLAKE3-keyed(key_n, index_{n-1})`. An index can therefore be moved
+//! forward to the current key without knowing the email, which is what
+//! lets existing rows be re-indexed in the background after a rotation.
+//! A login computes every version of the chain and matches any of them in
+//! a single indexed lookup.
+
+use crate::error::{ApiError, Result};
+
+/// Length of a blind index key in bytes
+pub const KEY_LEN: usize = blake3::KEY_LEN;
+
+/// Versioned set of blind index keys, oldest first
+#[derive(Clone)]
+pub struct BlindIndex {
+    keys: Vec<[u8; KEY_LEN]>,
+}
+
+impl std::fmt::Debug for BlindIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BlindIndex")
+            .field("current_version", &self.current_version())
+            .finish_non_exhaustive()
+    }
+}
+
+impl BlindIndex {
+    /// Create an index from raw keys, oldest first
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no keys are given.
+    pub fn new(keys: Vec<[u8; KEY_LEN]>) -> Result<Self> {
+        if keys.is_empty() {
+            return Err(ApiError::Internal(anyhow::anyhow!(
+                "At least one blind index key is required"
+            )));
+        }
+
+        Ok(Self { keys })
+    }
+
+    /// Create an index from hex-encoded keys, oldest first
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a key is not 32 bytes of hex, or none are given.
+    pub fn from_hex<S: AsRef<str>>(keys: &[S]) -> Result<Self> {
+        let keys = keys
+            .iter()
+            .map(|key| {
+                let bytes = hex::decode(key.as_ref().trim()).map_err(|e| {
+                    ApiError::Internal(anyhow::anyhow!("Invalid blind index key: {e}"))
+                })?;
+                <[u8; KEY_LEN]>::try_from(bytes).map_err(|_| {
+                    ApiError::Internal(anyhow::anyhow!("Blind index keys must be {KEY_LEN} bytes"))
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Self::new(keys)
+    }
+
+    /// Version of the newest key
+    #[must_use]
+    pub fn current_version(&self) -> i16 {
+        i16::try_from(self.keys.len()).unwrap_or(i16::MAX)
+    }
+
+    /// Blind index of an email under the current key
+    #[must_use]
+    pub fn compute(&self, email: &str) -> String {
+        let mut index = legacy_index(email);
+        for key in &self.keys {
+            index = wrap(key, &index);
+        }
+        hex::encode(index)
+    }
+
+    /// Blind index of an email under every key version, oldest first
+    ///
+    /// Used for lookups while rows are still being re-indexed.
+    #[must_use]
+    pub fn candidates(&self, email: &str) -> Vec<(i16, String)> {
+        let mut index = legacy_index(email);
+        let mut out = vec![(0, hex::encode(index))];
+
+        for (version, key) in (1..).zip(&self.keys) {
+            index = wrap(key, &index);
+            out.push((version, hex::encode(index)));
+        }
+
+        out
+    }
+
+    /// Move a stored index from `version` forward to the current key
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the version is unknown or the index is malformed.
+    pub fn rewrap(&self, version: i16, index_hex: &str) -> Result<String> {
+        let start = usize::try_from(version)
+            .ok()
+            .filter(|v| *v <= self.keys.len())
+            .ok_or_else(|| {
+                ApiError::Internal(anyhow::anyhow!("Unknown blind index version {version}"))
+            })?;
+
+        let mut index: [u8; 32] = hex::decode(index_hex)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| ApiError::Internal(anyhow::anyhow!("Malformed blind index")))?;
+
+        for key in &self.keys[start..] {
+            index = wrap(key, &index);
+        }
+
+        Ok(hex::encode(index))
+    }
+}
+
+/// Version 0: unkeyed SHA-256 of the normalized email
+fn legacy_index(email: &str) -> [u8; 32] {
+    use sha2::{Digest, Sha256};
+    Sha256::digest(email.to_lowercase().as_bytes()).into()
+}
+
+/// Advance an index by one key version
+fn wrap(key: &[u8; KEY_LEN], index: &[u8; 32]) -> [u8; 32] {
+    *blake3::keyed_hash(key, index).as_bytes()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn index_with(keys: &[[u8; KEY_LEN]]) -> BlindIndex {
+        BlindIndex::new(keys.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_keyed_and_case_insensitive() {
+        let index = index_with(&[[1; KEY_LEN]]);
+
+        assert_eq!(
+            index.compute("User@Example.COM"),
+            index.compute("user@example.com")
+        );
+        assert_eq!(index.compute("user@example.com").len(), 64);
+
+        // Different key, different index; never the unkeyed hash
+        let other = index_with(&[[2; KEY_LEN]]);
+        assert_ne!(
+            index.compute("user@example.com"),
+            other.compute("user@example.com")
+        );
+        assert_ne!(
+            index.compute("user@example.com"),
+            super::super::hash_email("user@example.com")
+        );
+    }
+
+    #[test]
+    fn test_rewrap_matches_fresh_index() {
+        let old = index_with(&[[1; KEY_LEN]]);
+        let rotated = index_with(&[[1; KEY_LEN], [2; KEY_LEN]]);
+        let email = "organizer@example.org";
+
+        let stored = old.compute(email);
+        assert_eq!(rotated.rewrap(1, &stored).unwrap(), rotated.compute(email));
+
+        // Legacy unkeyed rows can be brought forward too
+        let legacy = super::super::hash_email(email);
+        assert_eq!(rotated.rewrap(0, &legacy).unwrap(), rotated.compute(email));
+
+        assert!(rotated.rewrap(3, &stored).is_err());
+    }
+
+    #[test]
+    fn test_candidates_cover_all_versions() {
+        let rotated = index_with(&[[1; KEY_LEN], [2; KEY_LEN]]);
+        let email = "organizer@example.org";
+
+        let candidates = rotated.candidates(email);
+        assert_eq!(candidates.len(), 3);
+        assert_eq!(candidates[0], (0, super::super::hash_email(email)));
+        assert_eq!(candidates[2], (2, rotated.compute(email)));
+    }
+
+    #[test]
+    fn test_rejects_bad_keys() {
+        assert!(BlindIndex::from_hex::<&str>(&[]).is_err());
+        assert!(BlindIndex::from_hex(&["abcd"]).is_err());
+        assert!(BlindIndex::from_hex(&["zz".repeat(32)]).is_err());
+        assert!(BlindIndex::from_hex(&["ab".repeat(32)]).is_ok());
+    }
+}
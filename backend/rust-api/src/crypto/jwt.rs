@@ -0,0 +1,161 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Session tokens (HS256 JWT)
+//!
+//! Claims carry only the user ID, validity window, scope and how the user
+//! authenticated - no username, email or level, so a leaked token reveals
+//! nothing beyond an opaque ID.
+//!
+//! Scopes keep partial logins apart from sessions: a token issued after
+//! the password step of a multi-factor login is only accepted by the
+//! second-factor endpoints, never as a session.
+
+use chrono::{Duration, Utc};
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::{ApiError, Result};
+
+/// What a token may be used for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenScope {
+    /// Full session
+    #[default]
+    Session,
+    /// Password verified; a second factor is still needed
+    MfaPending,
+    /// Password verified; only second-factor enrollment is allowed
+    Enrollment,
+}
+
+/// Authentication method references (RFC 8176)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AuthMethod {
+    /// Password
+    #[serde(rename = "pwd")]
+    Password,
+    /// Hardware-backed key (passkey)
+    #[serde(rename = "hwk")]
+    HardwareKey,
+    /// One-time password (TOTP or recovery code)
+    #[serde(rename = "otp")]
+    OneTimePassword,
+}
+
+/// JWT claims for an authenticated session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    /// User ID
+    pub sub: Uuid,
+    /// Issued at (unix seconds)
+    pub iat: i64,
+    /// Expiry (unix seconds)
+    pub exp: i64,
+    /// Token scope
+    #[serde(default)]
+    pub scope: TokenScope,
+    /// Methods used to authenticate
+    #[serde(default)]
+    pub amr: Vec<AuthMethod>,
+}
+
+/// Issue a token for a user
+///
+/// # Errors
+///
+/// Returns an error if the token cannot be encoded.
+pub fn issue_token(
+    user_id: Uuid,
+    scope: TokenScope,
+    amr: Vec<AuthMethod>,
+    secret: &[u8],
+    ttl: Duration,
+) -> Result<String> {
+    let now = Utc::now();
+    let claims = Claims {
+        sub: user_id,
+        iat: now.timestamp(),
+        exp: (now + ttl).timestamp(),
+        scope,
+        amr,
+    };
+
+    encode(
+        &Header::new(Algorithm::HS256),
+        &claims,
+        &EncodingKey::from_secret(secret),
+    )
+    .map_err(|e| ApiError::Internal(anyhow::anyhow!("Token encoding failed: {e}")))
+}
+
+/// Decode and validate a token of the expected scope
+///
+/// # Errors
+///
+/// Returns [`ApiError::Unauthorized`] if the token is malformed, expired,
+/// signed with a different secret or issued for another scope.
+pub fn decode_token(token: &str, secret: &[u8], scope: TokenScope) -> Result<Claims> {
+    decode::<Claims>(
+        token,
+        &DecodingKey::from_secret(secret),
+        &Validation::new(Algorithm::HS256),
+    )
+    .map(|data| data.claims)
+    .ok()
+    .filter(|claims| claims.scope == scope)
+    .ok_or(ApiError::Unauthorized)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn session(user_id: Uuid, ttl: Duration) -> String {
+        issue_token(
+            user_id,
+            TokenScope::Session,
+            vec![AuthMethod::Password],
+            b"test-secret",
+            ttl,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_token_roundtrip() {
+        let user_id = Uuid::new_v4();
+        let token = session(user_id, Duration::hours(1));
+
+        let claims = decode_token(&token, b"test-secret", TokenScope::Session).unwrap();
+        assert_eq!(claims.sub, user_id);
+        assert_eq!(claims.amr, vec![AuthMethod::Password]);
+
+        // Wrong secret should fail
+        assert!(decode_token(&token, b"other-secret", TokenScope::Session).is_err());
+    }
+
+    #[test]
+    fn test_expired_token_rejected() {
+        let token = session(Uuid::new_v4(), Duration::hours(-1));
+        assert!(decode_token(&token, b"test-secret", TokenScope::Session).is_err());
+    }
+
+    #[test]
+    fn test_scope_enforced() {
+        let token = issue_token(
+            Uuid::new_v4(),
+            TokenScope::MfaPending,
+            vec![AuthMethod::Password],
+            b"test-secret",
+            Duration::minutes(5),
+        )
+        .unwrap();
+
+        assert!(decode_token(&token, b"test-secret", TokenScope::Session).is_err());
+        assert!(decode_token(&token, b"test-secret", TokenScope::MfaPending).is_ok());
+    }
+}
@@ -4,40 +4,25 @@
 //! Cryptographic operations
 //!
 //! Security-critical module for:
-//! - Password hashing (Argon2)
+//! - Password hashing (Argon2id, CPR-001)
+//! - Email blind index (keyed BLAKE3, CPR-009)
+//! - Recovery email encryption (XChaCha20-Poly1305, CPR-006)
+//! - TOTP second factor (RFC 6238) and one-time recovery codes
 //! - Digital signatures (ed25519)
 //! - JWT token generation
 
-use argon2::{
-    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
-    Argon2,
-};
+pub mod blind_index;
+pub mod jwt;
+pub mod password;
+pub mod recovery_code;
+pub mod totp;
+pub mod vault;
+
+use argon2::password_hash::rand_core::OsRng;
 use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
 use rand::RngCore;
 
-use crate::error::{ApiError, Result};
-
-/// Hash a password using Argon2id
-pub fn hash_password(password: &str) -> Result<String> {
-    let salt = SaltString::generate(&mut OsRng);
-    let argon2 = Argon2::default();
-
-    let hash = argon2
-        .hash_password(password.as_bytes(), &salt)
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Password hashing failed: {}", e)))?;
-
-    Ok(hash.to_string())
-}
-
-/// Verify a password against a hash
-pub fn verify_password(password: &str, hash: &str) -> Result<bool> {
-    let parsed_hash = PasswordHash::new(hash)
-        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Invalid password hash: {}", e)))?;
-
-    Ok(Argon2::default()
-        .verify_password(password.as_bytes(), &parsed_hash)
-        .is_ok())
-}
+pub use password::{hash_password, verify_password, PasswordParams};
 
 /// Generate a new ed25519 keypair
 pub fn generate_keypair() -> (SigningKey, VerifyingKey) {
@@ -47,11 +32,13 @@ pub fn generate_keypair() -> (SigningKey, VerifyingKey) {
 }
 
 /// Sign a message with ed25519
+#[must_use]
 pub fn sign_message(signing_key: &SigningKey, message: &[u8]) -> Signature {
     signing_key.sign(message)
 }
 
 /// Verify an ed25519 signature
+#[must_use]
 pub fn verify_signature(
     verifying_key: &VerifyingKey,
     message: &[u8],
@@ -61,13 +48,29 @@ pub fn verify_signature(
 }
 
 /// Generate a random nonce (32 bytes, hex encoded)
+#[must_use]
 pub fn generate_nonce() -> String {
     let mut nonce = [0u8; 32];
     OsRng.fill_bytes(&mut nonce);
     hex::encode(nonce)
 }
 
-/// Hash email for zero-knowledge storage
+/// Hash a bearer token for storage (SHA-256, hex encoded)
+///
+/// Tokens are 256-bit random values, so an unsalted hash is enough to make
+/// a stolen table useless.
+#[must_use]
+pub fn hash_token(token: &str) -> String {
+    use sha2::{Digest, Sha256};
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Unkeyed SHA-256 of the normalized email
+///
+/// This is version 0 of the blind index chain and is trivially reversible
+/// with a list of candidate addresses. Store [`blind_index::BlindIndex`]
+/// output instead; this remains only to match and re-index legacy rows.
+#[must_use]
 pub fn hash_email(email: &str) -> String {
     use sha2::{Digest, Sha256};
     let mut hasher = Sha256::new();
@@ -76,13 +79,19 @@ pub fn hash_email(email: &str) -> String {
 }
 
 #[cfg(test)]
+#[allow(clippy::unwrap_used)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_password_hashing() {
         let password = "SecurePassword123!";
-        let hash = hash_password(password).unwrap();
+        let params = PasswordParams {
+            memory_kib: 1024,
+            iterations: 2,
+            lanes: 1,
+        };
+        let hash = hash_password(password, &params).unwrap();
 
         assert!(verify_password(password, &hash).unwrap());
         assert!(!verify_password("WrongPassword", &hash).unwrap());
@@ -0,0 +1,386 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Password hashing (CPR-001)
+//!
+//! Argon2id with configurable cost parameters. Hashes are stored as PHC
+//! strings (`$argon2id$v=19$m=...,t=...,p=...$salt$hash`), which record the
+//! algorithm, version and parameters used. That lets a stored hash be
+//! compared with the current policy and upgraded on the next successful
+//! login, without forcing a password reset.
+//!
+//! Argon2id is memory-hard by design (512 MiB per hash under CPR-001), so
+//! [`PasswordHasher`] runs hashing on tokio's blocking pool behind a
+//! semaphore. A burst of logins queues instead of exhausting memory or
+//! starving the async runtime.
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use argon2::{
+    password_hash::{
+        rand_core::OsRng, PasswordHash, PasswordHasher as _, PasswordVerifier, SaltString,
+    },
+    Algorithm, Argon2, Params, Version,
+};
+use prometheus::HistogramVec;
+use serde::Deserialize;
+use tokio::sync::Semaphore;
+
+use crate::error::{ApiError, Result};
+
+/// Argon2id cost parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct PasswordParams {
+    /// Memory cost in KiB
+    #[serde(default = "default_memory_kib")]
+    pub memory_kib: u32,
+    /// Number of passes over memory
+    #[serde(default = "default_iterations")]
+    pub iterations: u32,
+    /// Degree of parallelism
+    #[serde(default = "default_lanes")]
+    pub lanes: u32,
+}
+
+impl PasswordParams {
+    /// CPR-001: Argon2id, 512 MiB, 8 iterations, 4 lanes
+    pub const CPR_001: Self = Self {
+        memory_kib: 512 * 1024,
+        iterations: 8,
+        lanes: 4,
+    };
+
+    /// Argon2id, 19 MiB, 2 iterations, 1 lane: the default for recovery
+    /// codes, a batch of which must hash within one request
+    pub const RECOVERY_CODE: Self = Self {
+        memory_kib: 19 * 1024,
+        iterations: 2,
+        lanes: 1,
+    };
+
+    /// Build an Argon2id context for these parameters
+    fn argon2(&self) -> Result<Argon2<'static>> {
+        let params = Params::new(self.memory_kib, self.iterations, self.lanes, None)
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!("Invalid Argon2 parameters: {e}")))?;
+
+        Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+    }
+
+    /// Whether `other` is at least as strong as `self` on every axis
+    const fn is_satisfied_by(&self, other: &Self) -> bool {
+        other.memory_kib >= self.memory_kib
+            && other.iterations >= self.iterations
+            && other.lanes >= self.lanes
+    }
+}
+
+impl Default for PasswordParams {
+    fn default() -> Self {
+        Self::CPR_001
+    }
+}
+
+const fn default_memory_kib() -> u32 {
+    PasswordParams::CPR_001.memory_kib
+}
+
+const fn default_iterations() -> u32 {
+    PasswordParams::CPR_001.iterations
+}
+
+const fn default_lanes() -> u32 {
+    PasswordParams::CPR_001.lanes
+}
+
+/// Outcome of checking a password against a stored hash
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasswordCheck {
+    /// Password does not match
+    Mismatch,
+    /// Password matches and the hash meets current policy
+    Valid,
+    /// Password matches but the hash predates current policy
+    ValidNeedsRehash,
+}
+
+impl PasswordCheck {
+    /// Whether the password matched
+    #[must_use]
+    pub const fn is_valid(self) -> bool {
+        !matches!(self, Self::Mismatch)
+    }
+}
+
+/// Hash a password with the given Argon2id parameters
+///
+/// This is CPU- and memory-heavy; async code should go through
+/// [`PasswordHasher::hash`] instead.
+///
+/// # Errors
+///
+/// Returns an error if the parameters are out of range.
+pub fn hash_password(password: &str, params: &PasswordParams) -> Result<String> {
+    let salt = SaltString::generate(&mut OsRng);
+
+    let hash = params
+        .argon2()?
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Password hashing failed: {e}")))?;
+
+    Ok(hash.to_string())
+}
+
+/// Verify a password against a hash
+///
+/// The hash's own parameters are used, so hashes created under an older
+/// policy still verify.
+///
+/// # Errors
+///
+/// Returns an error if the stored hash is not a valid PHC string.
+pub fn verify_password(password: &str, hash: &str) -> Result<bool> {
+    let parsed_hash = PasswordHash::new(hash)
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Invalid password hash: {e}")))?;
+
+    Ok(Argon2::default()
+        .verify_password(password.as_bytes(), &parsed_hash)
+        .is_ok())
+}
+
+/// Whether a stored hash is weaker than `policy` and should be replaced
+///
+/// A hash needs rehashing if it is not Argon2id v19, or if any of its cost
+/// parameters is below the policy. Hashes stronger than the policy are kept.
+///
+/// # Errors
+///
+/// Returns an error if the stored hash is not a valid PHC string.
+pub fn needs_rehash(hash: &str, policy: &PasswordParams) -> Result<bool> {
+    let parsed_hash = PasswordHash::new(hash)
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Invalid password hash: {e}")))?;
+
+    if Algorithm::try_from(parsed_hash.algorithm).ok() != Some(Algorithm::Argon2id)
+        || parsed_hash.version != Some(Version::V0x13.into())
+    {
+        return Ok(true);
+    }
+
+    let Ok(params) = Params::try_from(&parsed_hash) else {
+        return Ok(true);
+    };
+
+    let stored = PasswordParams {
+        memory_kib: params.m_cost(),
+        iterations: params.t_cost(),
+        lanes: params.p_cost(),
+    };
+
+    Ok(!policy.is_satisfied_by(&stored))
+}
+
+/// Bounded password hashing service
+///
+/// Runs Argon2id on the blocking thread pool, allowing at most
+/// `max_concurrent` hashes in flight. Further requests wait for a permit.
+#[derive(Debug, Clone)]
+pub struct PasswordHasher {
+    params: PasswordParams,
+    permits: Arc<Semaphore>,
+    /// Time per operation, labelled `hash` or `verify`
+    latency: Option<HistogramVec>,
+}
+
+impl PasswordHasher {
+    /// Create a hasher for the given policy
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the parameters are out of range.
+    pub fn new(params: PasswordParams, max_concurrent: usize) -> Result<Self> {
+        // Fail at startup rather than on the first login
+        params.argon2()?;
+
+        Ok(Self {
+            params,
+            permits: Arc::new(Semaphore::new(max_concurrent.max(1))),
+            latency: None,
+        })
+    }
+
+    /// Record how long each hash takes in `latency`
+    #[must_use]
+    pub fn with_latency(mut self, latency: HistogramVec) -> Self {
+        self.latency = Some(latency);
+        self
+    }
+
+    /// Current hashing policy
+    #[must_use]
+    pub const fn params(&self) -> &PasswordParams {
+        &self.params
+    }
+
+    /// Hash a password under the current policy
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if hashing fails or the blocking task panics.
+    pub async fn hash(&self, password: String) -> Result<String> {
+        let params = self.params;
+        self.run("hash", move || hash_password(&password, &params))
+            .await
+    }
+
+    /// Verify a password and report whether its hash should be upgraded
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the stored hash is malformed.
+    pub async fn verify(&self, password: String, hash: String) -> Result<PasswordCheck> {
+        let policy = self.params;
+        self.run("verify", move || {
+            if !verify_password(&password, &hash)? {
+                return Ok(PasswordCheck::Mismatch);
+            }
+
+            if needs_rehash(&hash, &policy)? {
+                Ok(PasswordCheck::ValidNeedsRehash)
+            } else {
+                Ok(PasswordCheck::Valid)
+            }
+        })
+        .await
+    }
+
+    /// Spend the same effort as a real verification, for unknown accounts
+    ///
+    /// Keeps login timing independent of whether the account exists.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if hashing fails.
+    pub async fn verify_dummy(&self, password: String) -> Result<PasswordCheck> {
+        // Timed as a verification, so metrics don't tell unknown accounts apart
+        let params = self.params;
+        self.run("verify", move || hash_password(&password, &params))
+            .await?;
+        Ok(PasswordCheck::Mismatch)
+    }
+
+    async fn run<T, F>(&self, operation: &str, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> Result<T> + Send + 'static,
+    {
+        let _permit = self
+            .permits
+            .acquire()
+            .await
+            .map_err(|e| ApiError::Internal(e.into()))?;
+
+        let started = Instant::now();
+        let result = tokio::task::spawn_blocking(f)
+            .await
+            .map_err(|e| ApiError::Internal(e.into()))?;
+        if let Some(latency) = &self.latency {
+            latency
+                .with_label_values(&[operation])
+                .observe(started.elapsed().as_secs_f64());
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    /// Cheap parameters so tests don't allocate 512 MiB per hash
+    const TEST_PARAMS: PasswordParams = PasswordParams {
+        memory_kib: 1024,
+        iterations: 2,
+        lanes: 1,
+    };
+
+    #[test]
+    fn test_hash_records_params() {
+        let hash = hash_password("SecurePassword123!", &TEST_PARAMS).unwrap();
+
+        assert!(hash.starts_with("$argon2id$v=19$m=1024,t=2,p=1$"));
+        assert!(!needs_rehash(&hash, &TEST_PARAMS).unwrap());
+    }
+
+    #[test]
+    fn test_needs_rehash_when_policy_stronger() {
+        let hash = hash_password("SecurePassword123!", &TEST_PARAMS).unwrap();
+
+        let stronger = PasswordParams {
+            iterations: 3,
+            ..TEST_PARAMS
+        };
+        assert!(needs_rehash(&hash, &stronger).unwrap());
+        assert!(needs_rehash(&hash, &PasswordParams::CPR_001).unwrap());
+
+        // A hash stronger than policy is left alone
+        let weaker = PasswordParams {
+            memory_kib: 512,
+            ..TEST_PARAMS
+        };
+        assert!(!needs_rehash(&hash, &weaker).unwrap());
+    }
+
+    #[test]
+    fn test_needs_rehash_for_other_algorithms() {
+        // argon2i hash of "password" (RFC 9106 style PHC string)
+        let argon2i = Argon2::new(
+            Algorithm::Argon2i,
+            Version::V0x13,
+            Params::new(1024, 2, 1, None).unwrap(),
+        )
+        .hash_password(b"password", &SaltString::generate(&mut OsRng))
+        .unwrap()
+        .to_string();
+
+        assert!(verify_password("password", &argon2i).unwrap());
+        assert!(needs_rehash(&argon2i, &TEST_PARAMS).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_hasher_verify_outcomes() {
+        let hasher = PasswordHasher::new(TEST_PARAMS, 2).unwrap();
+        let hash = hasher.hash("SecurePassword123!".into()).await.unwrap();
+
+        assert_eq!(
+            hasher
+                .verify("SecurePassword123!".into(), hash.clone())
+                .await
+                .unwrap(),
+            PasswordCheck::Valid
+        );
+        assert_eq!(
+            hasher
+                .verify("WrongPassword".into(), hash.clone())
+                .await
+                .unwrap(),
+            PasswordCheck::Mismatch
+        );
+
+        let upgraded = PasswordHasher::new(
+            PasswordParams {
+                iterations: 3,
+                ..TEST_PARAMS
+            },
+            2,
+        )
+        .unwrap();
+        assert_eq!(
+            upgraded
+                .verify("SecurePassword123!".into(), hash)
+                .await
+                .unwrap(),
+            PasswordCheck::ValidNeedsRehash
+        );
+    }
+}
@@ -0,0 +1,103 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! One-time recovery codes for a lost second factor
+//!
+//! A code looks like `3f9a-1c0e7b52d4a8`: a short selector, used to find
+//! the stored row, and a 48-bit secret that is stored only as an Argon2id
+//! hash. The selector means redeeming a code costs one hash verification
+//! rather than one per outstanding code.
+
+use rand::RngCore;
+
+/// Codes issued per batch
+pub const CODES_PER_BATCH: usize = 10;
+
+/// A freshly generated recovery code
+#[derive(Clone)]
+pub struct RecoveryCode {
+    /// Lookup part, stored in plaintext
+    pub selector: String,
+    /// Secret part, stored hashed
+    pub secret: String,
+}
+
+impl RecoveryCode {
+    /// Generate a random code
+    #[must_use]
+    pub fn generate() -> Self {
+        let mut bytes = [0u8; 8];
+        rand::thread_rng().fill_bytes(&mut bytes);
+
+        Self {
+            selector: hex::encode(&bytes[..2]),
+            secret: hex::encode(&bytes[2..]),
+        }
+    }
+
+    /// Split a user-entered code into selector and secret
+    #[must_use]
+    pub fn parse(code: &str) -> Option<Self> {
+        let code = code.trim().to_ascii_lowercase();
+        let (selector, secret) = code.split_once('-')?;
+
+        let is_hex =
+            |s: &str, len: usize| s.len() == len && s.bytes().all(|b| b.is_ascii_hexdigit());
+        if !is_hex(selector, 4) || !is_hex(secret, 12) {
+            return None;
+        }
+
+        Some(Self {
+            selector: selector.to_string(),
+            secret: secret.to_string(),
+        })
+    }
+
+    /// The code as shown to the user
+    #[must_use]
+    pub fn display(&self) -> String {
+        format!("{}-{}", self.selector, self.secret)
+    }
+}
+
+/// Generate a batch of codes with distinct selectors
+#[must_use]
+pub fn generate_batch() -> Vec<RecoveryCode> {
+    let mut codes: Vec<RecoveryCode> = Vec::with_capacity(CODES_PER_BATCH);
+    while codes.len() < CODES_PER_BATCH {
+        let code = RecoveryCode::generate();
+        if codes.iter().all(|c| c.selector != code.selector) {
+            codes.push(code);
+        }
+    }
+    codes
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_roundtrip() {
+        let code = RecoveryCode::generate();
+        let parsed = RecoveryCode::parse(&code.display().to_uppercase()).unwrap();
+
+        assert_eq!(parsed.selector, code.selector);
+        assert_eq!(parsed.secret, code.secret);
+
+        assert!(RecoveryCode::parse("3f9a1c0e7b52d4a8").is_none());
+        assert!(RecoveryCode::parse("3f9a-1c0e").is_none());
+        assert!(RecoveryCode::parse("zz9a-1c0e7b52d4a8").is_none());
+    }
+
+    #[test]
+    fn test_batch_selectors_distinct() {
+        let batch = generate_batch();
+        assert_eq!(batch.len(), CODES_PER_BATCH);
+
+        for (i, a) in batch.iter().enumerate() {
+            assert!(batch[i + 1..].iter().all(|b| b.selector != a.selector));
+        }
+    }
+}
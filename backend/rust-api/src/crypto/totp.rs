@@ -0,0 +1,190 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Time-based one-time passwords (RFC 6238)
+//!
+//! HMAC-SHA1, 6 digits, 30-second steps: the defaults every authenticator
+//! app supports. Codes from one step either side of the current one are
+//! accepted to tolerate clock drift. Verification returns the matched
+//! step so callers can refuse any step at or before the last one used,
+//! which stops a code from being replayed within its window.
+
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha1::Sha1;
+use subtle::ConstantTimeEq;
+
+/// Secret length in bytes (160 bits, as recommended by RFC 4226)
+pub const SECRET_LEN: usize = 20;
+
+/// Step length in seconds
+pub const STEP_SECONDS: i64 = 30;
+
+/// Digits per code
+pub const DIGITS: u32 = 6;
+
+/// Steps either side of the current one that are accepted
+pub const WINDOW: i64 = 1;
+
+/// Generate a new random secret
+#[must_use]
+pub fn generate_secret() -> [u8; SECRET_LEN] {
+    let mut secret = [0u8; SECRET_LEN];
+    rand::thread_rng().fill_bytes(&mut secret);
+    secret
+}
+
+/// Secret in the base32 form authenticator apps accept for manual entry
+#[must_use]
+pub fn encode_secret(secret: &[u8]) -> String {
+    BASE32_NOPAD.encode(secret)
+}
+
+/// `otpauth://` URI for provisioning an authenticator app by QR code
+#[must_use]
+pub fn provisioning_uri(secret: &[u8], issuer: &str, account: &str) -> String {
+    let issuer = url_encode(issuer);
+    format!(
+        "otpauth://totp/{issuer}:{account}?secret={secret}&issuer={issuer}\
+         &algorithm=SHA1&digits={DIGITS}&period={STEP_SECONDS}",
+        account = url_encode(account),
+        secret = encode_secret(secret),
+    )
+}
+
+/// Time step containing a unix timestamp
+#[must_use]
+pub const fn step_at(unix_seconds: i64) -> i64 {
+    unix_seconds.div_euclid(STEP_SECONDS)
+}
+
+/// Code for a given step, zero-padded to [`DIGITS`]
+#[must_use]
+pub fn code_at(secret: &[u8], step: i64) -> String {
+    format!(
+        "{:0width$}",
+        hotp(secret, step, DIGITS),
+        width = DIGITS as usize
+    )
+}
+
+/// Check a code against the steps around `now`
+///
+/// Returns the matched step, but only if it is later than `last_step`,
+/// so a code that has already been accepted is never accepted again.
+#[must_use]
+pub fn verify(secret: &[u8], code: &str, unix_seconds: i64, last_step: Option<i64>) -> Option<i64> {
+    let code = code.trim();
+    if code.len() != DIGITS as usize || !code.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let current = step_at(unix_seconds);
+    let mut matched = None;
+
+    // Check every step, so timing doesn't reveal which one matched
+    for step in (current - WINDOW)..=(current + WINDOW) {
+        let expected = code_at(secret, step);
+        if bool::from(expected.as_bytes().ct_eq(code.as_bytes())) {
+            matched = Some(step);
+        }
+    }
+
+    matched.filter(|step| last_step.map_or(true, |last| *step > last))
+}
+
+/// HOTP value (RFC 4226) truncated to `digits`
+fn hotp(secret: &[u8], counter: i64, digits: u32) -> u32 {
+    // HMAC accepts keys of any length
+    #[allow(clippy::expect_used)]
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC accepts any key length");
+    mac.update(&counter.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+
+    // Dynamic truncation
+    let offset = usize::from(digest[digest.len() - 1] & 0x0f);
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+
+    binary % 10u32.pow(digits)
+}
+
+/// Percent-encode a label component for an `otpauth://` URI
+fn url_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                char::from(b).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// RFC 6238 appendix B SHA-1 secret
+    const RFC_SECRET: &[u8] = b"12345678901234567890";
+
+    #[test]
+    fn test_rfc_6238_vectors() {
+        // (time, 8-digit code) from RFC 6238 appendix B
+        let vectors = [
+            (59, 94_287_082),
+            (1_111_111_109, 7_081_804),
+            (1_111_111_111, 14_050_471),
+            (1_234_567_890, 89_005_924),
+            (2_000_000_000, 69_279_037),
+        ];
+
+        for (time, expected) in vectors {
+            assert_eq!(hotp(RFC_SECRET, step_at(time), 8), expected);
+            assert_eq!(
+                code_at(RFC_SECRET, step_at(time)),
+                format!("{:06}", expected % 1_000_000)
+            );
+        }
+    }
+
+    #[test]
+    fn test_window_and_replay() {
+        let now = 1_234_567_890;
+        let current = step_at(now);
+
+        // Adjacent steps are accepted, further ones are not
+        let previous = code_at(RFC_SECRET, current - 1);
+        assert_eq!(verify(RFC_SECRET, &previous, now, None), Some(current - 1));
+        let stale = code_at(RFC_SECRET, current - 2);
+        assert_eq!(verify(RFC_SECRET, &stale, now, None), None);
+
+        // A step at or before the last one used is refused
+        let code = code_at(RFC_SECRET, current);
+        assert_eq!(
+            verify(RFC_SECRET, &code, now, Some(current - 1)),
+            Some(current)
+        );
+        assert_eq!(verify(RFC_SECRET, &code, now, Some(current)), None);
+        assert_eq!(verify(RFC_SECRET, &previous, now, Some(current)), None);
+
+        assert_eq!(verify(RFC_SECRET, "12345", now, None), None);
+        assert_eq!(verify(RFC_SECRET, "abcdef", now, None), None);
+    }
+
+    #[test]
+    fn test_provisioning_uri() {
+        let uri = provisioning_uri(RFC_SECRET, "CivicConnect", "alice smith");
+
+        assert!(uri.starts_with("otpauth://totp/CivicConnect:alice%20smith?"));
+        assert!(uri.contains("secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ"));
+        assert!(uri.contains("issuer=CivicConnect"));
+        assert!(uri.contains("period=30"));
+    }
+}
@@ -0,0 +1,162 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Recovery email encryption (XChaCha20-Poly1305, CPR-006)
+//!
+//! Users may opt in to storing their email for account recovery. It is
+//! encrypted under a key read from a separate file, which is meant to live
+//! outside the database's backup and access path (a mounted secret, HSM
+//! export, etc.). A dump of the database alone yields only ciphertext.
+//!
+//! Ciphertexts are bound to the owning user ID as associated data, so a
+//! blob copied onto another account row fails to decrypt.
+
+use std::path::Path;
+
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+use uuid::Uuid;
+
+use crate::error::{ApiError, Result};
+
+/// `XChaCha20` nonce length in bytes
+const NONCE_LEN: usize = 24;
+
+/// Encrypts and decrypts recovery emails
+#[derive(Clone)]
+pub struct EmailVault {
+    cipher: XChaCha20Poly1305,
+}
+
+impl std::fmt::Debug for EmailVault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EmailVault").finish_non_exhaustive()
+    }
+}
+
+impl EmailVault {
+    /// Create a vault from a raw 32-byte key
+    #[must_use]
+    pub fn new(key: &[u8; 32]) -> Self {
+        Self {
+            cipher: XChaCha20Poly1305::new(key.into()),
+        }
+    }
+
+    /// Load the key from a file containing 32 hex-encoded bytes
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read or doesn't hold a key.
+    pub fn from_key_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            ApiError::Internal(anyhow::anyhow!("Cannot read recovery key file: {e}"))
+        })?;
+
+        let key: [u8; 32] = hex::decode(contents.trim())
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| {
+                ApiError::Internal(anyhow::anyhow!(
+                    "Recovery key file must contain 32 hex-encoded bytes"
+                ))
+            })?;
+
+        Ok(Self::new(&key))
+    }
+
+    /// Encrypt an email for the given user
+    ///
+    /// Output is `nonce || ciphertext`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if encryption fails.
+    pub fn encrypt(&self, user_id: Uuid, email: &str) -> Result<Vec<u8>> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: email.as_bytes(),
+                    aad: user_id.as_bytes(),
+                },
+            )
+            .map_err(|_| ApiError::Internal(anyhow::anyhow!("Email encryption failed")))?;
+
+        let mut out = nonce.to_vec();
+        out.extend_from_slice(&ciphertext);
+        Ok(out)
+    }
+
+    /// Decrypt an email stored for the given user
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the blob is truncated, was encrypted for another
+    /// user or under another key, or has been tampered with.
+    pub fn decrypt(&self, user_id: Uuid, blob: &[u8]) -> Result<String> {
+        if blob.len() < NONCE_LEN {
+            return Err(ApiError::Internal(anyhow::anyhow!(
+                "Encrypted email is truncated"
+            )));
+        }
+
+        let (nonce, ciphertext) = blob.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(
+                XNonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: user_id.as_bytes(),
+                },
+            )
+            .map_err(|_| ApiError::Internal(anyhow::anyhow!("Email decryption failed")))?;
+
+        String::from_utf8(plaintext).map_err(|e| ApiError::Internal(e.into()))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_roundtrip() {
+        let vault = EmailVault::new(&[7; 32]);
+        let user_id = Uuid::new_v4();
+
+        let blob = vault.encrypt(user_id, "organizer@example.org").unwrap();
+        assert_eq!(
+            vault.decrypt(user_id, &blob).unwrap(),
+            "organizer@example.org"
+        );
+
+        // Fresh nonce each time
+        assert_ne!(
+            blob,
+            vault.encrypt(user_id, "organizer@example.org").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_decrypt_rejects_other_user_key_or_tampering() {
+        let vault = EmailVault::new(&[7; 32]);
+        let user_id = Uuid::new_v4();
+        let blob = vault.encrypt(user_id, "organizer@example.org").unwrap();
+
+        assert!(vault.decrypt(Uuid::new_v4(), &blob).is_err());
+        assert!(EmailVault::new(&[8; 32]).decrypt(user_id, &blob).is_err());
+
+        let mut tampered = blob.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(vault.decrypt(user_id, &tampered).is_err());
+
+        assert!(vault.decrypt(user_id, &blob[..10]).is_err());
+    }
+}
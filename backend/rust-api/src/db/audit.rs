@@ -0,0 +1,499 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Audit chain queries
+//!
+//! Verifications, level progressions and moderation actions are only ever
+//! written through [`append_verification`], [`append_level_progression`]
+//! and [`append_admin_action`], which chain them (see [`crate::audit`]).
+//! Retention removes old verifications only through
+//! [`tombstone_verification`].
+
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use super::models::{AdminAction, LevelProgression, Tombstone, Verification};
+use crate::audit::{self, AuditEntry, Checkpoint};
+use crate::error::{ApiError, Result};
+
+/// Entries read per page while verifying
+const PAGE_SIZE: i64 = 1000;
+
+/// A verification to record
+#[derive(Debug, Clone)]
+pub struct NewVerification<'a> {
+    pub event_id: Uuid,
+    pub user_id: Uuid,
+    pub organizer_id: Uuid,
+    pub signature: &'a [u8],
+    /// When the attendee was there, by the signed code or token
+    pub verified_at: DateTime<Utc>,
+    pub experience_awarded: i32,
+    pub location_hash: &'a str,
+    pub status: &'a str,
+    pub fraud_score: i16,
+    pub fraud_findings: serde_json::Value,
+}
+
+/// A level change to record
+#[derive(Debug, Clone)]
+pub struct NewLevelProgression<'a> {
+    pub user_id: Uuid,
+    pub from_level: i16,
+    pub to_level: i16,
+    pub reason: &'a str,
+    pub metadata: serde_json::Value,
+}
+
+/// A moderation action to record
+#[derive(Debug, Clone)]
+pub struct NewAdminAction<'a> {
+    pub actor_id: Uuid,
+    /// One of the [`AdminAction`] constants
+    pub action: &'a str,
+    pub target_id: Uuid,
+    pub reason: &'a str,
+    pub metadata: serde_json::Value,
+}
+
+/// Append a verification to the audit chain
+///
+/// Run inside the caller's transaction so the entry commits (or not)
+/// together with whatever it records. Appends are serialized on the
+/// chain head.
+///
+/// # Errors
+///
+/// Returns an error if a query fails, including the unique violation for
+/// a repeat verification of the same event.
+pub async fn append_verification(
+    conn: &mut PgConnection,
+    new: NewVerification<'_>,
+) -> Result<Verification> {
+    let mut entry = Verification {
+        id: Uuid::new_v4(),
+        event_id: new.event_id,
+        user_id: new.user_id,
+        organizer_id: new.organizer_id,
+        signature: new.signature.to_vec(),
+        verified_at: audit::db_timestamp(new.verified_at),
+        experience_awarded: new.experience_awarded,
+        location_hash: new.location_hash.to_string(),
+        status: new.status.to_string(),
+        fraud_score: new.fraud_score,
+        fraud_findings: new.fraud_findings,
+        audit_seq: None,
+        prev_hash: None,
+        entry_hash: None,
+    };
+    let (seq, prev_hash, entry_hash) = link(conn, &AuditEntry::Verification(entry.clone())).await?;
+
+    sqlx::query(
+        "INSERT INTO verifications
+             (id, event_id, user_id, organizer_id, signature, verified_at,
+              experience_awarded, location_hash, status, fraud_score, fraud_findings,
+              audit_seq, prev_hash, entry_hash)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)",
+    )
+    .bind(entry.id)
+    .bind(entry.event_id)
+    .bind(entry.user_id)
+    .bind(entry.organizer_id)
+    .bind(&entry.signature)
+    .bind(entry.verified_at)
+    .bind(entry.experience_awarded)
+    .bind(&entry.location_hash)
+    .bind(&entry.status)
+    .bind(entry.fraud_score)
+    .bind(&entry.fraud_findings)
+    .bind(seq)
+    .bind(&prev_hash[..])
+    .bind(&entry_hash[..])
+    .execute(&mut *conn)
+    .await?;
+
+    entry.audit_seq = Some(seq);
+    entry.prev_hash = Some(prev_hash.to_vec());
+    entry.entry_hash = Some(entry_hash.to_vec());
+    Ok(entry)
+}
+
+/// Append a level change to the audit chain
+///
+/// Run inside the caller's transaction, as for [`append_verification`].
+///
+/// # Errors
+///
+/// Returns an error if a query fails.
+pub async fn append_level_progression(
+    conn: &mut PgConnection,
+    new: NewLevelProgression<'_>,
+) -> Result<LevelProgression> {
+    let mut entry = LevelProgression {
+        id: Uuid::new_v4(),
+        user_id: new.user_id,
+        from_level: new.from_level,
+        to_level: new.to_level,
+        reason: new.reason.to_string(),
+        progressed_at: audit::db_timestamp(Utc::now()),
+        metadata: new.metadata,
+        audit_seq: None,
+        prev_hash: None,
+        entry_hash: None,
+    };
+    let (seq, prev_hash, entry_hash) =
+        link(conn, &AuditEntry::LevelProgression(entry.clone())).await?;
+
+    sqlx::query(
+        "INSERT INTO level_progressions
+             (id, user_id, from_level, to_level, reason, progressed_at, metadata,
+              audit_seq, prev_hash, entry_hash)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+    )
+    .bind(entry.id)
+    .bind(entry.user_id)
+    .bind(entry.from_level)
+    .bind(entry.to_level)
+    .bind(&entry.reason)
+    .bind(entry.progressed_at)
+    .bind(&entry.metadata)
+    .bind(seq)
+    .bind(&prev_hash[..])
+    .bind(&entry_hash[..])
+    .execute(&mut *conn)
+    .await?;
+
+    entry.audit_seq = Some(seq);
+    entry.prev_hash = Some(prev_hash.to_vec());
+    entry.entry_hash = Some(entry_hash.to_vec());
+    Ok(entry)
+}
+
+/// Append a moderation action to the audit chain
+///
+/// Run inside the transaction that makes the change, as for
+/// [`append_verification`].
+///
+/// # Errors
+///
+/// Returns an error if a query fails, including the unique violation for
+/// a second review of the same verification.
+pub async fn append_admin_action(
+    conn: &mut PgConnection,
+    new: NewAdminAction<'_>,
+) -> Result<AdminAction> {
+    let mut entry = AdminAction {
+        id: Uuid::new_v4(),
+        actor_id: new.actor_id,
+        action: new.action.to_string(),
+        target_id: new.target_id,
+        reason: new.reason.to_string(),
+        acted_at: audit::db_timestamp(Utc::now()),
+        metadata: new.metadata,
+        audit_seq: None,
+        prev_hash: None,
+        entry_hash: None,
+    };
+    let (seq, prev_hash, entry_hash) = link(conn, &AuditEntry::AdminAction(entry.clone())).await?;
+
+    sqlx::query(
+        "INSERT INTO admin_actions
+             (id, actor_id, action, target_id, reason, acted_at, metadata,
+              audit_seq, prev_hash, entry_hash)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+    )
+    .bind(entry.id)
+    .bind(entry.actor_id)
+    .bind(&entry.action)
+    .bind(entry.target_id)
+    .bind(&entry.reason)
+    .bind(entry.acted_at)
+    .bind(&entry.metadata)
+    .bind(seq)
+    .bind(&prev_hash[..])
+    .bind(&entry_hash[..])
+    .execute(&mut *conn)
+    .await?;
+
+    entry.audit_seq = Some(seq);
+    entry.prev_hash = Some(prev_hash.to_vec());
+    entry.entry_hash = Some(entry_hash.to_vec());
+    Ok(entry)
+}
+
+/// Lock the chain head and advance it past `entry`
+///
+/// Returns the entry's sequence number, previous hash and own hash. The
+/// lock is held until the caller's transaction ends.
+async fn link(conn: &mut PgConnection, entry: &AuditEntry) -> Result<(i64, [u8; 32], [u8; 32])> {
+    let (head_seq, head_hash): (i64, Vec<u8>) =
+        sqlx::query_as("SELECT seq, entry_hash FROM audit_chain_head FOR UPDATE")
+            .fetch_one(&mut *conn)
+            .await?;
+
+    let prev_hash: [u8; 32] = head_hash
+        .try_into()
+        .map_err(|_| ApiError::Internal(anyhow::anyhow!("Malformed audit chain head")))?;
+    let seq = head_seq + 1;
+    let entry_hash = audit::chain_hash(seq, &prev_hash, &entry.content_hash());
+
+    sqlx::query("UPDATE audit_chain_head SET seq = $1, entry_hash = $2")
+        .bind(seq)
+        .bind(&entry_hash[..])
+        .execute(&mut *conn)
+        .await?;
+
+    Ok((seq, prev_hash, entry_hash))
+}
+
+/// Replace a chained verification with its tombstone
+///
+/// The tombstone keeps the entry's place in the chain, so the chain still
+/// verifies once the row is gone. Run inside a transaction with whatever
+/// the row is collapsed into.
+///
+/// # Errors
+///
+/// Returns an error if the verification was never chained or a query
+/// fails.
+pub async fn tombstone_verification(
+    conn: &mut PgConnection,
+    verification: &Verification,
+) -> Result<()> {
+    let (Some(seq), Some(prev_hash), Some(entry_hash)) = (
+        verification.audit_seq,
+        verification.prev_hash.as_deref(),
+        verification.entry_hash.as_deref(),
+    ) else {
+        return Err(ApiError::Internal(anyhow::anyhow!(
+            "Verification {} is not in the audit chain",
+            verification.id
+        )));
+    };
+    let content_hash = AuditEntry::Verification(verification.clone()).content_hash();
+
+    sqlx::query(
+        "INSERT INTO audit_tombstones (audit_seq, id, prev_hash, content_hash, entry_hash)
+         VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(seq)
+    .bind(verification.id)
+    .bind(prev_hash)
+    .bind(&content_hash[..])
+    .bind(entry_hash)
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query("DELETE FROM verifications WHERE id = $1")
+        .bind(verification.id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Current chain head: last sequence number and its hash
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn head(pool: &PgPool) -> Result<(i64, Vec<u8>)> {
+    let head = sqlx::query_as("SELECT seq, entry_hash FROM audit_chain_head")
+        .fetch_one(pool)
+        .await?;
+
+    Ok(head)
+}
+
+/// Sequence number of the newest checkpoint
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn latest_checkpoint_seq(pool: &PgPool) -> Result<Option<i64>> {
+    let seq = sqlx::query_scalar("SELECT MAX(seq) FROM audit_checkpoints")
+        .fetch_one(pool)
+        .await?;
+
+    Ok(seq)
+}
+
+/// Store a signed checkpoint
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn insert_checkpoint(pool: &PgPool, checkpoint: &Checkpoint) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO audit_checkpoints (seq, entry_hash, signed_at, signature)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT (seq) DO NOTHING",
+    )
+    .bind(checkpoint.seq)
+    .bind(&checkpoint.entry_hash)
+    .bind(checkpoint.signed_at)
+    .bind(&checkpoint.signature)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// All checkpoints, oldest first
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn checkpoints(pool: &PgPool) -> Result<Vec<Checkpoint>> {
+    let checkpoints = sqlx::query_as::<_, Checkpoint>(
+        "SELECT seq, entry_hash, signed_at, signature FROM audit_checkpoints ORDER BY seq",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(checkpoints)
+}
+
+/// All entry kinds as one row shape
+const ENTRIES: &str = "
+    SELECT audit_seq, prev_hash, entry_hash, 'verification' AS kind, id, user_id,
+           event_id, organizer_id, signature, verified_at AS recorded_at,
+           experience_awarded, location_hash, status, fraud_score, fraud_findings,
+           NULL::smallint AS from_level, NULL::smallint AS to_level,
+           NULL::text AS reason, NULL::jsonb AS metadata,
+           NULL::text AS action, NULL::uuid AS target_id, NULL::bytea AS content_hash
+    FROM verifications
+    UNION ALL
+    SELECT audit_seq, prev_hash, entry_hash, 'level_progression', id, user_id,
+           NULL, NULL, NULL, progressed_at,
+           NULL, NULL, NULL, NULL, NULL,
+           from_level, to_level, reason, metadata,
+           NULL, NULL, NULL
+    FROM level_progressions
+    UNION ALL
+    SELECT audit_seq, prev_hash, entry_hash, 'admin_action', id, actor_id,
+           NULL, NULL, NULL, acted_at,
+           NULL, NULL, NULL, NULL, NULL,
+           NULL, NULL, reason, metadata,
+           action, target_id, NULL
+    FROM admin_actions
+    UNION ALL
+    SELECT audit_seq, prev_hash, entry_hash, 'tombstone', id, NULL,
+           NULL, NULL, NULL, pruned_at,
+           NULL, NULL, NULL, NULL, NULL,
+           NULL, NULL, NULL, NULL,
+           NULL, NULL, content_hash
+    FROM audit_tombstones";
+
+#[derive(sqlx::FromRow)]
+struct EntryRow {
+    audit_seq: Option<i64>,
+    prev_hash: Option<Vec<u8>>,
+    entry_hash: Option<Vec<u8>>,
+    kind: String,
+    id: Uuid,
+    user_id: Option<Uuid>,
+    event_id: Option<Uuid>,
+    organizer_id: Option<Uuid>,
+    signature: Option<Vec<u8>>,
+    recorded_at: DateTime<Utc>,
+    experience_awarded: Option<i32>,
+    location_hash: Option<String>,
+    status: Option<String>,
+    fraud_score: Option<i16>,
+    fraud_findings: Option<serde_json::Value>,
+    from_level: Option<i16>,
+    to_level: Option<i16>,
+    reason: Option<String>,
+    metadata: Option<serde_json::Value>,
+    action: Option<String>,
+    target_id: Option<Uuid>,
+    content_hash: Option<Vec<u8>>,
+}
+
+impl From<EntryRow> for AuditEntry {
+    fn from(row: EntryRow) -> Self {
+        match row.kind.as_str() {
+            "verification" => Self::Verification(Verification {
+                id: row.id,
+                event_id: row.event_id.unwrap_or_default(),
+                user_id: row.user_id.unwrap_or_default(),
+                organizer_id: row.organizer_id.unwrap_or_default(),
+                signature: row.signature.unwrap_or_default(),
+                verified_at: row.recorded_at,
+                experience_awarded: row.experience_awarded.unwrap_or_default(),
+                location_hash: row.location_hash.unwrap_or_default(),
+                status: row.status.unwrap_or_default(),
+                fraud_score: row.fraud_score.unwrap_or_default(),
+                fraud_findings: row.fraud_findings.unwrap_or_default(),
+                audit_seq: row.audit_seq,
+                prev_hash: row.prev_hash,
+                entry_hash: row.entry_hash,
+            }),
+            "level_progression" => Self::LevelProgression(LevelProgression {
+                id: row.id,
+                user_id: row.user_id.unwrap_or_default(),
+                from_level: row.from_level.unwrap_or_default(),
+                to_level: row.to_level.unwrap_or_default(),
+                reason: row.reason.unwrap_or_default(),
+                progressed_at: row.recorded_at,
+                metadata: row.metadata.unwrap_or_default(),
+                audit_seq: row.audit_seq,
+                prev_hash: row.prev_hash,
+                entry_hash: row.entry_hash,
+            }),
+            "tombstone" => Self::Tombstone(Tombstone {
+                id: row.id,
+                audit_seq: row.audit_seq.unwrap_or_default(),
+                prev_hash: row.prev_hash.unwrap_or_default(),
+                content_hash: row.content_hash.unwrap_or_default(),
+                entry_hash: row.entry_hash.unwrap_or_default(),
+            }),
+            _ => Self::AdminAction(AdminAction {
+                id: row.id,
+                actor_id: row.user_id.unwrap_or_default(),
+                action: row.action.unwrap_or_default(),
+                target_id: row.target_id.unwrap_or_default(),
+                reason: row.reason.unwrap_or_default(),
+                acted_at: row.recorded_at,
+                metadata: row.metadata.unwrap_or_default(),
+                audit_seq: row.audit_seq,
+                prev_hash: row.prev_hash,
+                entry_hash: row.entry_hash,
+            }),
+        }
+    }
+}
+
+/// Any entry written outside the chain
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn first_unchained(pool: &PgPool) -> Result<Option<AuditEntry>> {
+    let row = sqlx::query_as::<_, EntryRow>(&format!(
+        "SELECT * FROM ({ENTRIES}) e WHERE audit_seq IS NULL ORDER BY recorded_at LIMIT 1"
+    ))
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(AuditEntry::from))
+}
+
+/// The next page of chained entries after `seq`, in order
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn entries_after(pool: &PgPool, seq: i64) -> Result<Vec<AuditEntry>> {
+    let rows = sqlx::query_as::<_, EntryRow>(&format!(
+        "SELECT * FROM ({ENTRIES}) e WHERE audit_seq > $1 ORDER BY audit_seq LIMIT $2"
+    ))
+    .bind(seq)
+    .bind(PAGE_SIZE)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(AuditEntry::from).collect())
+}
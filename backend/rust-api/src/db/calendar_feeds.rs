@@ -0,0 +1,68 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Calendar feed token queries
+//!
+//! Only SHA-256 hashes of feed tokens are stored. Each user has at most
+//! one feed; issuing a new token revokes the old one.
+
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::Result;
+
+/// Set a user's feed token, replacing any existing one
+///
+/// Returns when the feed was created.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn replace(pool: &PgPool, user_id: Uuid, token_hash: &str) -> Result<DateTime<Utc>> {
+    let created_at = sqlx::query_scalar(
+        "INSERT INTO calendar_feeds (user_id, token_hash) VALUES ($1, $2)
+         ON CONFLICT (user_id) DO UPDATE SET token_hash = $2, created_at = now()
+         RETURNING created_at",
+    )
+    .bind(user_id)
+    .bind(token_hash)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(created_at)
+}
+
+/// Revoke a user's feed
+///
+/// Idempotent; returns `false` if there was no feed.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn revoke(pool: &PgPool, user_id: Uuid) -> Result<bool> {
+    let result = sqlx::query("DELETE FROM calendar_feeds WHERE user_id = $1")
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Owner of a feed token, if the token is current and the owner is active
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn owner(pool: &PgPool, token_hash: &str) -> Result<Option<Uuid>> {
+    let user_id = sqlx::query_scalar(
+        "SELECT f.user_id FROM calendar_feeds f
+         JOIN users u ON u.id = f.user_id
+         WHERE f.token_hash = $1 AND u.suspended_at IS NULL",
+    )
+    .bind(token_hash)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(user_id)
+}
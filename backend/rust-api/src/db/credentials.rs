@@ -0,0 +1,173 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Attendance credential queries
+//!
+//! See [`crate::credentials`] for what is kept and for how long.
+
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::error::Result;
+
+/// An event's claim salt, creating it with `salt` if there is none
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn event_salt(
+    conn: &mut PgConnection,
+    event_id: Uuid,
+    salt: &[u8],
+    discard_after: DateTime<Utc>,
+) -> Result<Vec<u8>> {
+    // The no-op update makes RETURNING yield an existing row's salt
+    let salt = sqlx::query_scalar(
+        "INSERT INTO credential_event_salts (event_id, salt, discard_after)
+         VALUES ($1, $2, $3)
+         ON CONFLICT (event_id) DO UPDATE SET event_id = EXCLUDED.event_id
+         RETURNING salt",
+    )
+    .bind(event_id)
+    .bind(salt)
+    .bind(discard_after)
+    .fetch_one(conn)
+    .await?;
+
+    Ok(salt)
+}
+
+/// An event's claim salt, if any credential has been issued for it
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn find_salt(pool: &PgPool, event_id: Uuid) -> Result<Option<Vec<u8>>> {
+    let salt = sqlx::query_scalar("SELECT salt FROM credential_event_salts WHERE event_id = $1")
+        .bind(event_id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(salt)
+}
+
+/// Record a claim, returning whether it is new
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn claim(conn: &mut PgConnection, event_id: Uuid, tag: &[u8]) -> Result<bool> {
+    let result = sqlx::query(
+        "INSERT INTO credential_claims (event_id, tag) VALUES ($1, $2)
+         ON CONFLICT DO NOTHING",
+    )
+    .bind(event_id)
+    .bind(tag)
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Whether a claim exists
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn is_claimed(pool: &PgPool, event_id: Uuid, tag: &[u8]) -> Result<bool> {
+    let claimed = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM credential_claims WHERE event_id = $1 AND tag = $2)",
+    )
+    .bind(event_id)
+    .bind(tag)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(claimed)
+}
+
+/// Note that a user was issued a credential
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn record_issuance(conn: &mut PgConnection, user_id: Uuid) -> Result<()> {
+    sqlx::query("INSERT INTO credential_issuances (user_id) VALUES ($1)")
+        .bind(user_id)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Credentials a user has been issued since `since`
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn issued_since(pool: &PgPool, user_id: Uuid, since: DateTime<Utc>) -> Result<i64> {
+    let count = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM credential_issuances WHERE user_id = $1 AND issued_at > $2",
+    )
+    .bind(user_id)
+    .bind(since)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(count)
+}
+
+/// Mark a token spent, returning whether it wasn't already
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn spend(conn: &mut PgConnection, token_hash: &str) -> Result<bool> {
+    let result = sqlx::query(
+        "INSERT INTO spent_credentials (token_hash) VALUES ($1) ON CONFLICT DO NOTHING",
+    )
+    .bind(token_hash)
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Add redeemed credentials to a user's attendance count, returning the
+/// new count
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn add_attendances(conn: &mut PgConnection, user_id: Uuid, count: i32) -> Result<i32> {
+    let total = sqlx::query_scalar(
+        "UPDATE users SET credential_attendances = credential_attendances + $2, updated_at = now()
+         WHERE id = $1
+         RETURNING credential_attendances",
+    )
+    .bind(user_id)
+    .bind(count)
+    .fetch_one(conn)
+    .await?;
+
+    Ok(total)
+}
+
+/// Delete salts and claims for events past `discard_after`, and issuances
+/// older than `issued_before`, returning how many rows went
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn purge(pool: &PgPool, issued_before: DateTime<Utc>) -> Result<u64> {
+    let salts = sqlx::query("DELETE FROM credential_event_salts WHERE discard_after <= now()")
+        .execute(pool)
+        .await?;
+    let issuances = sqlx::query("DELETE FROM credential_issuances WHERE issued_at <= $1")
+        .bind(issued_before)
+        .execute(pool)
+        .await?;
+
+    Ok(salts.rows_affected() + issuances.rows_affected())
+}
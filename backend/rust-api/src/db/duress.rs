@@ -0,0 +1,250 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Duress mode queries
+//!
+//! See [`crate::duress`] for what duress mode does.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::error::Result;
+
+/// Someone alerted when a user logs in under duress
+#[derive(Debug, Clone, Serialize, sqlx::FromRow, ToSchema)]
+pub struct TrustedContact {
+    pub user_id: Uuid,
+    pub username: String,
+    pub added_at: DateTime<Utc>,
+}
+
+/// A contact to alert, with their opt-in recovery email if they have one
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct AlertRecipient {
+    pub id: Uuid,
+    pub email_encrypted: Option<Vec<u8>>,
+}
+
+/// A trusted contact's login under duress, as seen by the contact
+#[derive(Debug, Clone, Serialize, sqlx::FromRow, ToSchema)]
+pub struct DuressAlert {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub username: String,
+    pub raised_at: DateTime<Utc>,
+}
+
+/// Set or clear a user's duress password
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn set_password(
+    pool: &PgPool,
+    user_id: Uuid,
+    password_hash: Option<&str>,
+    notify: bool,
+) -> Result<()> {
+    sqlx::query(
+        "UPDATE users SET duress_password_hash = $2, duress_notify = $3, updated_at = now()
+         WHERE id = $1",
+    )
+    .bind(user_id)
+    .bind(password_hash)
+    .bind(notify)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Record a successful password login at `at`
+///
+/// A duress login enters duress mode and revokes every session issued
+/// before `at`. A login with the real password leaves duress mode, again
+/// revoking sessions, so none from under duress survive. Otherwise
+/// nothing changes. Returns whether this login entered duress mode.
+///
+/// Every login runs the same single statement, so the two passwords take
+/// the same time.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn record_login(
+    pool: &PgPool,
+    user_id: Uuid,
+    under_duress: bool,
+    at: DateTime<Utc>,
+) -> Result<bool> {
+    let entered = sqlx::query_scalar(
+        "UPDATE users u
+         SET duress_at = CASE WHEN $2 THEN COALESCE(old.duress_at, $3) END,
+             sessions_valid_after = CASE
+                 WHEN $2 OR old.duress_at IS NOT NULL THEN $3
+                 ELSE old.sessions_valid_after
+             END
+         FROM (SELECT id, duress_at, sessions_valid_after FROM users WHERE id = $1 FOR UPDATE) old
+         WHERE u.id = old.id
+         RETURNING $2 AND old.duress_at IS NULL",
+    )
+    .bind(user_id)
+    .bind(under_duress)
+    .bind(at)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(entered)
+}
+
+/// Note which password began a login that still needs a second factor
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn set_pending(pool: &PgPool, user_id: Uuid, under_duress: bool) -> Result<()> {
+    sqlx::query("UPDATE users SET pending_duress = $2 WHERE id = $1")
+        .bind(user_id)
+        .bind(under_duress)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Take which password began a pending login, if one is pending
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn take_pending(pool: &PgPool, user_id: Uuid) -> Result<Option<bool>> {
+    let pending = sqlx::query_scalar(
+        "UPDATE users u SET pending_duress = NULL
+         FROM (SELECT id, pending_duress FROM users WHERE id = $1 FOR UPDATE) old
+         WHERE u.id = old.id
+         RETURNING old.pending_duress",
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(pending.flatten())
+}
+
+/// A user's trusted contacts, by username
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn contacts(pool: &PgPool, user_id: Uuid) -> Result<Vec<TrustedContact>> {
+    let contacts = sqlx::query_as::<_, TrustedContact>(
+        "SELECT u.id AS user_id, u.username, t.created_at AS added_at
+         FROM trusted_contacts t
+         JOIN users u ON u.id = t.contact_id
+         WHERE t.user_id = $1
+         ORDER BY u.username",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(contacts)
+}
+
+/// Add a trusted contact; idempotent
+///
+/// Returns `false` if there is no such active contact.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn add_contact(pool: &PgPool, user_id: Uuid, contact_id: Uuid) -> Result<bool> {
+    let exists = sqlx::query_scalar(
+        "WITH contact AS (SELECT id FROM users WHERE id = $2 AND suspended_at IS NULL),
+              added AS (
+                  INSERT INTO trusted_contacts (user_id, contact_id)
+                  SELECT $1, id FROM contact
+                  ON CONFLICT DO NOTHING
+              )
+         SELECT EXISTS (SELECT 1 FROM contact)",
+    )
+    .bind(user_id)
+    .bind(contact_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(exists)
+}
+
+/// Remove a trusted contact; idempotent
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn remove_contact(pool: &PgPool, user_id: Uuid, contact_id: Uuid) -> Result<()> {
+    sqlx::query("DELETE FROM trusted_contacts WHERE user_id = $1 AND contact_id = $2")
+        .bind(user_id)
+        .bind(contact_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Record an alert for each of a user's active trusted contacts
+///
+/// Returns who was alerted, to email those who opted in to recovery.
+///
+/// # Errors
+///
+/// Returns an error if a query fails.
+pub async fn raise_alerts(pool: &PgPool, user_id: Uuid) -> Result<Vec<AlertRecipient>> {
+    let mut tx = pool.begin().await?;
+
+    let recipients = sqlx::query_as::<_, AlertRecipient>(
+        "SELECT u.id, u.email_encrypted
+         FROM trusted_contacts t
+         JOIN users u ON u.id = t.contact_id
+         WHERE t.user_id = $1 AND u.suspended_at IS NULL",
+    )
+    .bind(user_id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    for recipient in &recipients {
+        sqlx::query("INSERT INTO duress_alerts (id, contact_id, user_id) VALUES ($1, $2, $3)")
+            .bind(Uuid::new_v4())
+            .bind(recipient.id)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(recipients)
+}
+
+/// Alerts for a contact, newest first
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn alerts_for(pool: &PgPool, contact_id: Uuid) -> Result<Vec<DuressAlert>> {
+    let alerts = sqlx::query_as::<_, DuressAlert>(
+        "SELECT a.id, a.user_id, u.username, a.raised_at
+         FROM duress_alerts a
+         JOIN users u ON u.id = a.user_id
+         WHERE a.contact_id = $1
+         ORDER BY a.raised_at DESC
+         LIMIT 100",
+    )
+    .bind(contact_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(alerts)
+}
@@ -0,0 +1,344 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Event queries
+
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use super::models::Event;
+use crate::error::Result;
+
+/// An event with its organizer and RSVP count, for display
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct EventListing {
+    #[sqlx(flatten)]
+    pub event: Event,
+    pub organizer_username: String,
+    pub organizer_level: i16,
+    pub rsvps: i64,
+}
+
+/// Someone helping run an event
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct CoOrganizer {
+    pub user_id: Uuid,
+    pub username: String,
+    pub added_by: Uuid,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Selects [`EventListing`] columns; append a `WHERE` clause
+const LISTING: &str = "SELECT e.*, u.username AS organizer_username,
+            u.current_level AS organizer_level,
+            (SELECT COUNT(*) FROM event_rsvps r WHERE r.event_id = e.id) AS rsvps
+     FROM events e
+     JOIN users u ON u.id = e.organizer_id";
+
+/// Hide or restore an event, returning it
+///
+/// Idempotent; returns `None` if there is no such event.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn set_hidden(conn: &mut PgConnection, id: Uuid, hidden: bool) -> Result<Option<Event>> {
+    let event = sqlx::query_as::<_, Event>(
+        "UPDATE events
+         SET hidden_at = CASE WHEN $2 THEN COALESCE(hidden_at, now()) END, updated_at = now()
+         WHERE id = $1
+         RETURNING *",
+    )
+    .bind(id)
+    .bind(hidden)
+    .fetch_optional(conn)
+    .await?;
+
+    Ok(event)
+}
+
+/// Look up a visible event, locking it against concurrent RSVPs
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn find_visible_for_update(conn: &mut PgConnection, id: Uuid) -> Result<Option<Event>> {
+    let event = sqlx::query_as::<_, Event>(
+        "SELECT * FROM events WHERE id = $1 AND hidden_at IS NULL FOR UPDATE",
+    )
+    .bind(id)
+    .fetch_optional(conn)
+    .await?;
+
+    Ok(event)
+}
+
+/// Number of RSVPs to an event
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn rsvp_count(conn: &mut PgConnection, event_id: Uuid) -> Result<i64> {
+    let count = sqlx::query_scalar("SELECT COUNT(*) FROM event_rsvps WHERE event_id = $1")
+        .bind(event_id)
+        .fetch_one(conn)
+        .await?;
+
+    Ok(count)
+}
+
+/// RSVP to an event
+///
+/// Idempotent; returns `false` if the user had already RSVP'd.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn add_rsvp(conn: &mut PgConnection, event_id: Uuid, user_id: Uuid) -> Result<bool> {
+    let result = sqlx::query(
+        "INSERT INTO event_rsvps (event_id, user_id) VALUES ($1, $2)
+         ON CONFLICT DO NOTHING",
+    )
+    .bind(event_id)
+    .bind(user_id)
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Withdraw an RSVP
+///
+/// Idempotent; returns `false` if there was no RSVP.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn remove_rsvp(conn: &mut PgConnection, event_id: Uuid, user_id: Uuid) -> Result<bool> {
+    let result = sqlx::query("DELETE FROM event_rsvps WHERE event_id = $1 AND user_id = $2")
+        .bind(event_id)
+        .bind(user_id)
+        .execute(conn)
+        .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Move an event into an organization, or out of one with `None`
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn set_organization(
+    conn: &mut PgConnection,
+    id: Uuid,
+    organization_id: Option<Uuid>,
+) -> Result<()> {
+    sqlx::query("UPDATE events SET organization_id = $2, updated_at = now() WHERE id = $1")
+        .bind(id)
+        .bind(organization_id)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Add a co-organizer
+///
+/// Idempotent; returns `false` if they already were one.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn add_co_organizer(
+    conn: &mut PgConnection,
+    event_id: Uuid,
+    user_id: Uuid,
+    added_by: Uuid,
+) -> Result<bool> {
+    let result = sqlx::query(
+        "INSERT INTO event_co_organizers (event_id, user_id, added_by) VALUES ($1, $2, $3)
+         ON CONFLICT DO NOTHING",
+    )
+    .bind(event_id)
+    .bind(user_id)
+    .bind(added_by)
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Remove a co-organizer
+///
+/// Idempotent; returns `false` if they weren't one.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn remove_co_organizer(
+    conn: &mut PgConnection,
+    event_id: Uuid,
+    user_id: Uuid,
+) -> Result<bool> {
+    let result =
+        sqlx::query("DELETE FROM event_co_organizers WHERE event_id = $1 AND user_id = $2")
+            .bind(event_id)
+            .bind(user_id)
+            .execute(conn)
+            .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// An event's co-organizers, in the order they were added
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn co_organizers(pool: &PgPool, event_id: Uuid) -> Result<Vec<CoOrganizer>> {
+    let co_organizers = sqlx::query_as::<_, CoOrganizer>(
+        "SELECT c.user_id, u.username, c.added_by, c.created_at
+         FROM event_co_organizers c
+         JOIN users u ON u.id = c.user_id
+         WHERE c.event_id = $1
+         ORDER BY c.created_at",
+    )
+    .bind(event_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(co_organizers)
+}
+
+/// Signing key of someone running an event, as organizer or co-organizer
+///
+/// Returns `None` if they don't run it, are suspended, or have no key.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn organizer_signing_key(
+    pool: &PgPool,
+    event_id: Uuid,
+    user_id: Uuid,
+) -> Result<Option<Vec<u8>>> {
+    let key = sqlx::query_scalar(
+        "SELECT u.signing_key FROM users u
+         WHERE u.id = $2 AND u.signing_key IS NOT NULL AND u.suspended_at IS NULL
+           AND (EXISTS (SELECT 1 FROM events e WHERE e.id = $1 AND e.organizer_id = u.id)
+                OR EXISTS (SELECT 1 FROM event_co_organizers c
+                           WHERE c.event_id = $1 AND c.user_id = u.id))",
+    )
+    .bind(event_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(key)
+}
+
+/// Events whose updates a user receives: those they RSVP'd to, organize
+/// or co-organize
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn followed_by(pool: &PgPool, user_id: Uuid) -> Result<Vec<Uuid>> {
+    let ids = sqlx::query_scalar(
+        "SELECT event_id FROM event_rsvps WHERE user_id = $1
+         UNION
+         SELECT id FROM events WHERE organizer_id = $1 AND hidden_at IS NULL
+         UNION
+         SELECT c.event_id FROM event_co_organizers c
+         JOIN events e ON e.id = c.event_id
+         WHERE c.user_id = $1 AND e.hidden_at IS NULL",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(ids)
+}
+
+/// Visible events in progress at `at`
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn in_progress(pool: &PgPool, at: DateTime<Utc>) -> Result<Vec<Uuid>> {
+    let ids = sqlx::query_scalar(
+        "SELECT id FROM events
+         WHERE start_time <= $1 AND end_time > $1 AND hidden_at IS NULL",
+    )
+    .bind(at)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(ids)
+}
+
+/// Look up a visible event for display
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn find_listing(pool: &PgPool, id: Uuid) -> Result<Option<EventListing>> {
+    let listing = sqlx::query_as::<_, EventListing>(&format!(
+        "{LISTING} WHERE e.id = $1 AND e.hidden_at IS NULL"
+    ))
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(listing)
+}
+
+/// Visible events a user RSVP'd to that ended after `since`, soonest first
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn rsvp_listings(
+    pool: &PgPool,
+    user_id: Uuid,
+    since: DateTime<Utc>,
+) -> Result<Vec<EventListing>> {
+    let listings = sqlx::query_as::<_, EventListing>(&format!(
+        "{LISTING}
+         JOIN event_rsvps mine ON mine.event_id = e.id AND mine.user_id = $1
+         WHERE e.hidden_at IS NULL AND e.end_time > $2
+         ORDER BY e.start_time"
+    ))
+    .bind(user_id)
+    .bind(since)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(listings)
+}
+
+/// An organizer's visible events, latest first
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn organized_listings(
+    pool: &PgPool,
+    organizer_id: Uuid,
+    limit: i64,
+) -> Result<Vec<EventListing>> {
+    let listings = sqlx::query_as::<_, EventListing>(&format!(
+        "{LISTING}
+         WHERE e.organizer_id = $1 AND e.hidden_at IS NULL
+         ORDER BY e.start_time DESC
+         LIMIT $2"
+    ))
+    .bind(organizer_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(listings)
+}
@@ -0,0 +1,176 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! ActivityPub follow relationship queries
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::models::User;
+use crate::error::Result;
+use crate::leveling::ORGANIZER_LEVEL;
+
+/// A remote actor following one of our organizers
+#[derive(Debug, Clone)]
+pub struct NewFollower<'a> {
+    pub user_id: Uuid,
+    pub actor_uri: &'a str,
+    pub inbox: &'a str,
+    pub shared_inbox: Option<&'a str>,
+    pub follow_id: &'a str,
+}
+
+/// An active organizer, who is published as an actor
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn find_actor(pool: &PgPool, user_id: Uuid) -> Result<Option<User>> {
+    let user = sqlx::query_as::<_, User>(
+        "SELECT * FROM users
+         WHERE id = $1 AND current_level >= $2 AND suspended_at IS NULL",
+    )
+    .bind(user_id)
+    .bind(ORGANIZER_LEVEL)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(user)
+}
+
+/// An actor by username, as looked up by WebFinger
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn find_actor_by_username(pool: &PgPool, username: &str) -> Result<Option<User>> {
+    let user = sqlx::query_as::<_, User>(
+        "SELECT * FROM users
+         WHERE username = $1 AND current_level >= $2 AND suspended_at IS NULL",
+    )
+    .bind(username)
+    .bind(ORGANIZER_LEVEL)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(user)
+}
+
+/// Record a follower, replacing an earlier follow by the same actor
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn add_follower(pool: &PgPool, follower: NewFollower<'_>) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO federation_followers (user_id, actor_uri, inbox, shared_inbox, follow_id)
+         VALUES ($1, $2, $3, $4, $5)
+         ON CONFLICT (user_id, actor_uri) DO UPDATE
+         SET inbox = $3, shared_inbox = $4, follow_id = $5",
+    )
+    .bind(follower.user_id)
+    .bind(follower.actor_uri)
+    .bind(follower.inbox)
+    .bind(follower.shared_inbox)
+    .bind(follower.follow_id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Remove a follower; returns `false` if they weren't following
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn remove_follower(pool: &PgPool, user_id: Uuid, actor_uri: &str) -> Result<bool> {
+    let result =
+        sqlx::query("DELETE FROM federation_followers WHERE user_id = $1 AND actor_uri = $2")
+            .bind(user_id)
+            .bind(actor_uri)
+            .execute(pool)
+            .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Number of followers an organizer has
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn follower_count(pool: &PgPool, user_id: Uuid) -> Result<i64> {
+    let count = sqlx::query_scalar("SELECT COUNT(*) FROM federation_followers WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+
+    Ok(count)
+}
+
+/// Distinct inboxes to deliver an organizer's activities to
+///
+/// Followers on the same server share one delivery where the server
+/// advertises a shared inbox.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn follower_inboxes(pool: &PgPool, user_id: Uuid) -> Result<Vec<String>> {
+    let inboxes = sqlx::query_scalar(
+        "SELECT DISTINCT COALESCE(shared_inbox, inbox) FROM federation_followers
+         WHERE user_id = $1",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(inboxes)
+}
+
+/// Record a Follow we sent, replacing any earlier one to the same actor
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn add_following(
+    pool: &PgPool,
+    user_id: Uuid,
+    actor_uri: &str,
+    follow_id: &str,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO federation_following (user_id, actor_uri, follow_id)
+         VALUES ($1, $2, $3)
+         ON CONFLICT (user_id, actor_uri) DO UPDATE
+         SET follow_id = $3, created_at = now(), accepted_at = NULL",
+    )
+    .bind(user_id)
+    .bind(actor_uri)
+    .bind(follow_id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Mark a Follow we sent as accepted by the actor it was sent to
+///
+/// Returns `false` if there is no such pending or accepted Follow.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn accept_following(pool: &PgPool, follow_id: &str, actor_uri: &str) -> Result<bool> {
+    let result = sqlx::query(
+        "UPDATE federation_following SET accepted_at = COALESCE(accepted_at, now())
+         WHERE follow_id = $1 AND actor_uri = $2",
+    )
+    .bind(follow_id)
+    .bind(actor_uri)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
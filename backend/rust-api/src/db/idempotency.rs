@@ -0,0 +1,121 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Idempotency key queries
+//!
+//! Rows are keyed by a hash of the caller's scope and key; responses are
+//! stored encrypted. See [`crate::idempotency`].
+
+use sqlx::PgPool;
+
+use crate::error::Result;
+
+/// A key's first request and, once it finished, its response
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct IdempotencyRecord {
+    pub request_hash: String,
+    /// `None` while the first request is still running
+    pub status: Option<i16>,
+    /// Encrypted response headers and body
+    pub response: Option<Vec<u8>>,
+}
+
+/// Claim a key for a new request
+///
+/// Succeeds if the key is unused, expired, or was claimed more than
+/// `abandon_secs` ago by a request that never finished. Returns whether
+/// the claim succeeded.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn claim(
+    pool: &PgPool,
+    key_hash: &str,
+    request_hash: &str,
+    ttl_secs: f64,
+    abandon_secs: f64,
+) -> Result<bool> {
+    let claimed = sqlx::query_scalar::<_, bool>(
+        "INSERT INTO idempotency_keys (key_hash, request_hash, expires_at)
+         VALUES ($1, $2, now() + make_interval(secs => $3))
+         ON CONFLICT (key_hash) DO UPDATE
+         SET request_hash = EXCLUDED.request_hash, status = NULL, response = NULL,
+             created_at = now(), expires_at = EXCLUDED.expires_at
+         WHERE idempotency_keys.expires_at <= now()
+            OR (idempotency_keys.status IS NULL
+                AND idempotency_keys.created_at < now() - make_interval(secs => $4))
+         RETURNING true",
+    )
+    .bind(key_hash)
+    .bind(request_hash)
+    .bind(ttl_secs)
+    .bind(abandon_secs)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(claimed.is_some())
+}
+
+/// The unexpired record for a key
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn find(pool: &PgPool, key_hash: &str) -> Result<Option<IdempotencyRecord>> {
+    let record = sqlx::query_as::<_, IdempotencyRecord>(
+        "SELECT request_hash, status, response FROM idempotency_keys
+         WHERE key_hash = $1 AND expires_at > now()",
+    )
+    .bind(key_hash)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(record)
+}
+
+/// Store the response to a claimed key's request
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn complete(pool: &PgPool, key_hash: &str, status: i16, response: &[u8]) -> Result<()> {
+    sqlx::query(
+        "UPDATE idempotency_keys SET status = $2, response = $3
+         WHERE key_hash = $1 AND status IS NULL",
+    )
+    .bind(key_hash)
+    .bind(status)
+    .bind(response)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Give up a claim without storing a response, so a retry runs afresh
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn release(pool: &PgPool, key_hash: &str) -> Result<()> {
+    sqlx::query("DELETE FROM idempotency_keys WHERE key_hash = $1 AND status IS NULL")
+        .bind(key_hash)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Delete expired keys, returning how many there were
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn purge_expired(pool: &PgPool) -> Result<u64> {
+    let result = sqlx::query("DELETE FROM idempotency_keys WHERE expires_at <= now()")
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
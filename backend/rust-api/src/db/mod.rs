@@ -3,14 +3,35 @@
 
 //! Database operations using sqlx
 //!
-//! PostgreSQL with PostGIS for spatial queries
+//! `PostgreSQL` with `PostGIS` for spatial queries
+
+pub mod audit;
+pub mod calendar_feeds;
+pub mod credentials;
+pub mod duress;
+pub mod events;
+pub mod federation;
+pub mod idempotency;
+pub mod organizations;
+pub mod password_resets;
+pub mod reports;
+pub mod retention;
+pub mod totp;
+pub mod users;
+pub mod verifications;
+pub mod webauthn;
+pub mod webhooks;
 
 use sqlx::postgres::PgPoolOptions;
 use sqlx::PgPool;
 
-use crate::error::Result;
+use crate::error::{ApiError, Result};
 
 /// Create database connection pool
+///
+/// # Errors
+///
+/// Returns an error if the database can't be reached.
 pub async fn create_pool(database_url: &str) -> Result<PgPool> {
     let pool = PgPoolOptions::new()
         .max_connections(10)
@@ -20,18 +41,59 @@ pub async fn create_pool(database_url: &str) -> Result<PgPool> {
     Ok(pool)
 }
 
+/// Apply pending schema migrations from `migrations/`
+///
+/// # Errors
+///
+/// Returns an error if a migration fails or the applied history diverges.
+pub async fn run_migrations(pool: &PgPool) -> Result<()> {
+    sqlx::migrate!("./migrations")
+        .run(pool)
+        .await
+        .map_err(|e| ApiError::Internal(e.into()))
+}
+
+/// Number of migrations in `migrations/` not yet successfully applied
+///
+/// # Errors
+///
+/// Returns an error if the migrations table can't be read.
+pub async fn pending_migrations(pool: &PgPool) -> Result<usize> {
+    let applied: Vec<i64> =
+        sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success")
+            .fetch_all(pool)
+            .await?;
+
+    Ok(sqlx::migrate!("./migrations")
+        .iter()
+        .filter(|migration| !applied.contains(&migration.version))
+        .count())
+}
+
+/// Whether an error is a unique-constraint violation
+pub(crate) fn is_unique_violation(err: &sqlx::Error) -> bool {
+    err.as_database_error()
+        .is_some_and(sqlx::error::DatabaseError::is_unique_violation)
+}
+
 /// Database models
 pub mod models {
     use chrono::{DateTime, Utc};
     use serde::{Deserialize, Serialize};
     use sqlx::FromRow;
+    use utoipa::ToSchema;
     use uuid::Uuid;
 
     /// User database model
     #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
     pub struct User {
         pub id: Uuid,
+        /// Keyed blind index of the email (see `crypto::blind_index`)
         pub email_hash: String,
+        /// Blind index key version that produced `email_hash`
+        pub email_key_version: i16,
+        /// Opt-in recovery email, encrypted (see `crypto::vault`)
+        pub email_encrypted: Option<Vec<u8>>,
         pub username: String,
         pub password_hash: String,
         pub current_level: i16,
@@ -41,6 +103,39 @@ pub struct User {
         pub updated_at: DateTime<Utc>,
         pub last_active: DateTime<Utc>,
         pub is_verified: bool,
+        /// Moderation role, granted separately from earned level
+        pub role: Role,
+        pub suspended_at: Option<DateTime<Utc>>,
+        /// Second password that logs in under duress (see `crate::duress`)
+        pub duress_password_hash: Option<String>,
+        /// Whether trusted contacts are alerted when it is used
+        pub duress_notify: bool,
+        /// When the account entered duress mode, if it is in it
+        pub duress_at: Option<DateTime<Utc>>,
+    }
+
+    /// Moderation role, in increasing order of privilege
+    #[derive(
+        Debug,
+        Clone,
+        Copy,
+        PartialEq,
+        Eq,
+        PartialOrd,
+        Ord,
+        sqlx::Type,
+        Serialize,
+        Deserialize,
+        ToSchema,
+    )]
+    #[sqlx(type_name = "text", rename_all = "lowercase")]
+    #[serde(rename_all = "lowercase")]
+    pub enum Role {
+        Member,
+        /// Reviews held verifications and hides events
+        Moderator,
+        /// Also suspends users, reverses XP and grants roles
+        Admin,
     }
 
     /// Event database model
@@ -58,6 +153,12 @@ pub struct Event {
         pub tags: Vec<String>,
         pub created_at: DateTime<Utc>,
         pub updated_at: DateTime<Utc>,
+        /// Set while hidden by a moderator
+        pub hidden_at: Option<DateTime<Utc>>,
+        /// Venue text the organizer chose to publish
+        pub venue: Option<String>,
+        /// Organization the event is held by, if any
+        pub organization_id: Option<Uuid>,
     }
 
     /// Verification audit log entry
@@ -71,6 +172,22 @@ pub struct Verification {
         pub verified_at: DateTime<Utc>,
         pub experience_awarded: i32,
         pub location_hash: String,
+        /// [`Verification::VERIFIED`] or [`Verification::HELD_FOR_REVIEW`]
+        pub status: String,
+        /// Fraud score and findings at the time of verification (see `crate::fraud`)
+        pub fraud_score: i16,
+        pub fraud_findings: serde_json::Value,
+        /// Position in the audit chain (see `crate::audit`)
+        pub audit_seq: Option<i64>,
+        pub prev_hash: Option<Vec<u8>>,
+        pub entry_hash: Option<Vec<u8>>,
+    }
+
+    impl Verification {
+        /// Attendance accepted and XP awarded
+        pub const VERIFIED: &'static str = "verified";
+        /// Flagged by fraud screening; no XP until reviewed
+        pub const HELD_FOR_REVIEW: &'static str = "held_for_review";
     }
 
     /// Message (encrypted content)
@@ -106,5 +223,183 @@ pub struct LevelProgression {
         pub reason: String,
         pub progressed_at: DateTime<Utc>,
         pub metadata: serde_json::Value,
+        /// Position in the audit chain (see `crate::audit`)
+        pub audit_seq: Option<i64>,
+        pub prev_hash: Option<Vec<u8>>,
+        pub entry_hash: Option<Vec<u8>>,
+    }
+
+    /// Moderation action audit entry
+    #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+    pub struct AdminAction {
+        pub id: Uuid,
+        /// Moderator or admin who acted
+        pub actor_id: Uuid,
+        /// One of the `AdminAction` constants
+        pub action: String,
+        /// Verification, event or user acted on
+        pub target_id: Uuid,
+        pub reason: String,
+        pub acted_at: DateTime<Utc>,
+        pub metadata: serde_json::Value,
+        /// Position in the audit chain (see `crate::audit`)
+        pub audit_seq: Option<i64>,
+        pub prev_hash: Option<Vec<u8>>,
+        pub entry_hash: Option<Vec<u8>>,
+    }
+
+    impl AdminAction {
+        pub const RELEASE_VERIFICATION: &'static str = "release_verification";
+        pub const REJECT_VERIFICATION: &'static str = "reject_verification";
+        pub const HIDE_EVENT: &'static str = "hide_event";
+        pub const RESTORE_EVENT: &'static str = "restore_event";
+        pub const SUSPEND_USER: &'static str = "suspend_user";
+        pub const UNSUSPEND_USER: &'static str = "unsuspend_user";
+        pub const REVERSE_XP: &'static str = "reverse_xp";
+        pub const SET_ROLE: &'static str = "set_role";
+        pub const TRIAGE_REPORT: &'static str = "triage_report";
+    }
+
+    /// What is left of an audit entry removed by retention (see
+    /// `crate::retention`)
+    #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+    pub struct Tombstone {
+        /// ID of the removed entry
+        pub id: Uuid,
+        pub audit_seq: i64,
+        pub prev_hash: Vec<u8>,
+        /// The removed entry's content hash, so its link still checks
+        pub content_hash: Vec<u8>,
+        pub entry_hash: Vec<u8>,
+    }
+
+    /// Abuse report
+    #[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+    pub struct Report {
+        pub id: Uuid,
+        /// Never shown to the reported party
+        pub reporter_id: Uuid,
+        pub target_type: ReportTarget,
+        pub target_id: Uuid,
+        pub category: ReportCategory,
+        pub description: String,
+        /// Reporter-decrypted message content, for message reports
+        pub evidence: Option<String>,
+        pub status: ReportStatus,
+        pub created_at: DateTime<Utc>,
+        pub updated_at: DateTime<Utc>,
+    }
+
+    /// What a report is about
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type, Serialize, Deserialize, ToSchema)]
+    #[sqlx(type_name = "text", rename_all = "snake_case")]
+    #[serde(rename_all = "snake_case")]
+    pub enum ReportTarget {
+        User,
+        Event,
+        Message,
+    }
+
+    /// Kind of abuse reported
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type, Serialize, Deserialize, ToSchema)]
+    #[sqlx(type_name = "text", rename_all = "snake_case")]
+    #[serde(rename_all = "snake_case")]
+    pub enum ReportCategory {
+        Harassment,
+        Threat,
+        Spam,
+        Impersonation,
+        UnsafeEvent,
+        Other,
+    }
+
+    /// Triage state of a report
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type, Serialize, Deserialize, ToSchema)]
+    #[sqlx(type_name = "text", rename_all = "snake_case")]
+    #[serde(rename_all = "snake_case")]
+    pub enum ReportStatus {
+        /// Waiting for a moderator
+        Open,
+        /// A moderator is looking into it
+        Triaged,
+        /// Closed with action taken
+        Actioned,
+        /// Closed with no action
+        Dismissed,
+    }
+
+    impl ReportStatus {
+        /// Whether a report in this state can move to `next`
+        ///
+        /// Reports move forward only; closed reports stay closed, and a
+        /// new report can be filed if the abuse continues.
+        #[must_use]
+        pub const fn can_become(self, next: Self) -> bool {
+            matches!(
+                (self, next),
+                (Self::Open, Self::Triaged | Self::Actioned | Self::Dismissed)
+                    | (Self::Triaged, Self::Actioned | Self::Dismissed)
+            )
+        }
+
+        /// Whether the report is still being handled
+        #[must_use]
+        pub const fn is_open(self) -> bool {
+            matches!(self, Self::Open | Self::Triaged)
+        }
+    }
+
+    /// Organization (chapter)
+    #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+    pub struct Organization {
+        pub id: Uuid,
+        pub name: String,
+        /// URL-safe unique name
+        pub slug: String,
+        pub description: String,
+        pub created_by: Uuid,
+        pub created_at: DateTime<Utc>,
+        pub updated_at: DateTime<Utc>,
+    }
+
+    /// Role in an organization, in increasing order of privilege
+    #[derive(
+        Debug,
+        Clone,
+        Copy,
+        PartialEq,
+        Eq,
+        PartialOrd,
+        Ord,
+        sqlx::Type,
+        Serialize,
+        Deserialize,
+        ToSchema,
+    )]
+    #[sqlx(type_name = "text", rename_all = "lowercase")]
+    #[serde(rename_all = "lowercase")]
+    pub enum OrganizationRole {
+        Member,
+        /// Runs the chapter day to day: invites, members, events
+        Admin,
+        /// Also appoints admins and owners
+        Owner,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::models::ReportStatus;
+
+    #[test]
+    fn test_report_status_moves_forward_only() {
+        assert!(ReportStatus::Open.can_become(ReportStatus::Triaged));
+        assert!(ReportStatus::Open.can_become(ReportStatus::Dismissed));
+        assert!(ReportStatus::Triaged.can_become(ReportStatus::Actioned));
+
+        assert!(!ReportStatus::Triaged.can_become(ReportStatus::Open));
+        assert!(!ReportStatus::Open.can_become(ReportStatus::Open));
+        assert!(!ReportStatus::Actioned.can_become(ReportStatus::Dismissed));
+        assert!(!ReportStatus::Dismissed.can_become(ReportStatus::Triaged));
     }
 }
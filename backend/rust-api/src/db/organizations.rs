@@ -0,0 +1,386 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Organization, membership and invitation queries
+
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use super::models::{AdminAction, Organization, OrganizationRole, Verification};
+use crate::error::Result;
+
+/// Fields for a new organization
+#[derive(Debug, Clone)]
+pub struct NewOrganization<'a> {
+    pub name: &'a str,
+    pub slug: &'a str,
+    pub description: &'a str,
+    pub created_by: Uuid,
+}
+
+/// A member, for the member list
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct MemberListing {
+    pub user_id: Uuid,
+    pub username: String,
+    pub current_level: i16,
+    pub role: OrganizationRole,
+    pub joined_at: DateTime<Utc>,
+}
+
+/// One of a user's memberships
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Membership {
+    #[sqlx(flatten)]
+    pub organization: Organization,
+    pub role: OrganizationRole,
+}
+
+/// Fields for a new invitation
+#[derive(Debug, Clone)]
+pub struct NewInvitation<'a> {
+    pub organization_id: Uuid,
+    pub token_hash: &'a str,
+    pub role: OrganizationRole,
+    pub created_by: Uuid,
+    pub max_uses: i32,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// An invitation link
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Invitation {
+    pub id: Uuid,
+    pub organization_id: Uuid,
+    pub role: OrganizationRole,
+    pub created_by: Uuid,
+    pub max_uses: i32,
+    pub uses: i32,
+    pub expires_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Chapter-wide counts
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Stats {
+    pub members: i64,
+    pub admins: i64,
+    pub owners: i64,
+    pub members_joined: i64,
+    pub events: i64,
+    pub upcoming_events: i64,
+    pub rsvps: i64,
+    pub verified_attendance: i64,
+}
+
+/// Create an organization, with its creator as owner
+///
+/// # Errors
+///
+/// Returns an error if the query fails, including a unique violation if
+/// the slug is taken.
+pub async fn create(conn: &mut PgConnection, new: NewOrganization<'_>) -> Result<Organization> {
+    let organization = sqlx::query_as::<_, Organization>(
+        "INSERT INTO organizations (id, name, slug, description, created_by)
+         VALUES ($1, $2, $3, $4, $5)
+         RETURNING *",
+    )
+    .bind(Uuid::new_v4())
+    .bind(new.name)
+    .bind(new.slug)
+    .bind(new.description)
+    .bind(new.created_by)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    set_role(
+        conn,
+        organization.id,
+        new.created_by,
+        OrganizationRole::Owner,
+    )
+    .await?;
+
+    Ok(organization)
+}
+
+/// Look up an organization
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn find(pool: &PgPool, id: Uuid) -> Result<Option<Organization>> {
+    let organization =
+        sqlx::query_as::<_, Organization>("SELECT * FROM organizations WHERE id = $1")
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
+
+    Ok(organization)
+}
+
+/// Lock an organization against concurrent membership changes
+///
+/// Returns `false` if there is no such organization.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn lock(conn: &mut PgConnection, id: Uuid) -> Result<bool> {
+    let found =
+        sqlx::query_scalar::<_, Uuid>("SELECT id FROM organizations WHERE id = $1 FOR UPDATE")
+            .bind(id)
+            .fetch_optional(conn)
+            .await?;
+
+    Ok(found.is_some())
+}
+
+/// A user's role in an organization, if they're a member
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn role_of(
+    conn: &mut PgConnection,
+    organization_id: Uuid,
+    user_id: Uuid,
+) -> Result<Option<OrganizationRole>> {
+    let role = sqlx::query_scalar(
+        "SELECT role FROM organization_members WHERE organization_id = $1 AND user_id = $2",
+    )
+    .bind(organization_id)
+    .bind(user_id)
+    .fetch_optional(conn)
+    .await?;
+
+    Ok(role)
+}
+
+/// Add a member or change their role
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn set_role(
+    conn: &mut PgConnection,
+    organization_id: Uuid,
+    user_id: Uuid,
+    role: OrganizationRole,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO organization_members (organization_id, user_id, role) VALUES ($1, $2, $3)
+         ON CONFLICT (organization_id, user_id) DO UPDATE SET role = $3",
+    )
+    .bind(organization_id)
+    .bind(user_id)
+    .bind(role)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Remove a member; returns `false` if they weren't one
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn remove_member(
+    conn: &mut PgConnection,
+    organization_id: Uuid,
+    user_id: Uuid,
+) -> Result<bool> {
+    let result =
+        sqlx::query("DELETE FROM organization_members WHERE organization_id = $1 AND user_id = $2")
+            .bind(organization_id)
+            .bind(user_id)
+            .execute(conn)
+            .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Number of owners an organization has
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn owner_count(conn: &mut PgConnection, organization_id: Uuid) -> Result<i64> {
+    let count = sqlx::query_scalar(
+        "SELECT count(*) FROM organization_members WHERE organization_id = $1 AND role = 'owner'",
+    )
+    .bind(organization_id)
+    .fetch_one(conn)
+    .await?;
+
+    Ok(count)
+}
+
+/// Number of members an organization has
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn member_count(pool: &PgPool, organization_id: Uuid) -> Result<i64> {
+    let count =
+        sqlx::query_scalar("SELECT count(*) FROM organization_members WHERE organization_id = $1")
+            .bind(organization_id)
+            .fetch_one(pool)
+            .await?;
+
+    Ok(count)
+}
+
+/// An organization's members, by role then join date
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn members(pool: &PgPool, organization_id: Uuid) -> Result<Vec<MemberListing>> {
+    let members = sqlx::query_as::<_, MemberListing>(
+        "SELECT m.user_id, u.username, u.current_level, m.role, m.joined_at
+         FROM organization_members m
+         JOIN users u ON u.id = m.user_id
+         WHERE m.organization_id = $1
+         ORDER BY CASE m.role WHEN 'owner' THEN 0 WHEN 'admin' THEN 1 ELSE 2 END, m.joined_at",
+    )
+    .bind(organization_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(members)
+}
+
+/// The organizations a user belongs to, by name
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn memberships(pool: &PgPool, user_id: Uuid) -> Result<Vec<Membership>> {
+    let memberships = sqlx::query_as::<_, Membership>(
+        "SELECT o.*, m.role
+         FROM organization_members m
+         JOIN organizations o ON o.id = m.organization_id
+         WHERE m.user_id = $1
+         ORDER BY o.name",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(memberships)
+}
+
+/// Store an invitation
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn create_invitation(pool: &PgPool, new: NewInvitation<'_>) -> Result<Invitation> {
+    let invitation = sqlx::query_as::<_, Invitation>(
+        "INSERT INTO organization_invitations
+             (id, organization_id, token_hash, role, created_by, max_uses, expires_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)
+         RETURNING id, organization_id, role, created_by, max_uses, uses, expires_at,
+                   revoked_at, created_at",
+    )
+    .bind(Uuid::new_v4())
+    .bind(new.organization_id)
+    .bind(new.token_hash)
+    .bind(new.role)
+    .bind(new.created_by)
+    .bind(new.max_uses)
+    .bind(new.expires_at)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(invitation)
+}
+
+/// Use up an invitation, if it's still valid
+///
+/// Returns the invitation with the use counted, or `None` if it doesn't
+/// exist, was revoked, has expired or has no uses left.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn redeem_invitation(
+    conn: &mut PgConnection,
+    token_hash: &str,
+) -> Result<Option<Invitation>> {
+    let invitation = sqlx::query_as::<_, Invitation>(
+        "UPDATE organization_invitations SET uses = uses + 1
+         WHERE token_hash = $1 AND revoked_at IS NULL AND expires_at > now() AND uses < max_uses
+         RETURNING id, organization_id, role, created_by, max_uses, uses, expires_at,
+                   revoked_at, created_at",
+    )
+    .bind(token_hash)
+    .fetch_optional(conn)
+    .await?;
+
+    Ok(invitation)
+}
+
+/// Revoke an invitation; returns `false` if there's no such live invitation
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn revoke_invitation(pool: &PgPool, organization_id: Uuid, id: Uuid) -> Result<bool> {
+    let result = sqlx::query(
+        "UPDATE organization_invitations SET revoked_at = now()
+         WHERE id = $1 AND organization_id = $2 AND revoked_at IS NULL",
+    )
+    .bind(id)
+    .bind(organization_id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Chapter-wide counts, with joins and events since `since`
+///
+/// Attendance counts released and collapsed verifications, as
+/// [`count_verified`](super::verifications::count_verified) does.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn stats(pool: &PgPool, organization_id: Uuid, since: DateTime<Utc>) -> Result<Stats> {
+    let stats = sqlx::query_as::<_, Stats>(
+        "WITH org_events AS (
+             SELECT id, start_time, archived_attendance FROM events
+             WHERE organization_id = $1 AND hidden_at IS NULL AND start_time >= $2
+         )
+         SELECT
+             (SELECT count(*) FROM organization_members WHERE organization_id = $1) AS members,
+             (SELECT count(*) FROM organization_members
+              WHERE organization_id = $1 AND role = 'admin') AS admins,
+             (SELECT count(*) FROM organization_members
+              WHERE organization_id = $1 AND role = 'owner') AS owners,
+             (SELECT count(*) FROM organization_members
+              WHERE organization_id = $1 AND joined_at >= $2) AS members_joined,
+             (SELECT count(*) FROM org_events) AS events,
+             (SELECT count(*) FROM org_events WHERE start_time > now()) AS upcoming_events,
+             (SELECT count(*) FROM event_rsvps r JOIN org_events e ON e.id = r.event_id) AS rsvps,
+             ((SELECT count(*) FROM verifications v JOIN org_events e ON e.id = v.event_id
+              WHERE v.status = $3 OR EXISTS (
+                  SELECT 1 FROM admin_actions a WHERE a.target_id = v.id AND a.action = $4
+              ))
+              + (SELECT COALESCE(SUM(archived_attendance), 0) FROM org_events)
+             )::bigint AS verified_attendance",
+    )
+    .bind(organization_id)
+    .bind(since)
+    .bind(Verification::VERIFIED)
+    .bind(AdminAction::RELEASE_VERIFICATION)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(stats)
+}
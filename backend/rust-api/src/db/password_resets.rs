@@ -0,0 +1,103 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Password reset token queries
+//!
+//! Only SHA-256 hashes of tokens are stored, so a database dump can't be
+//! used to reset anyone's password.
+
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::Result;
+
+/// Store a new reset token for a user
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn insert(
+    pool: &PgPool,
+    user_id: Uuid,
+    token_hash: &str,
+    expires_at: DateTime<Utc>,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO password_reset_tokens (token_hash, user_id, expires_at)
+         VALUES ($1, $2, $3)",
+    )
+    .bind(token_hash)
+    .bind(user_id)
+    .bind(expires_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Whether a token is currently usable (unused and unexpired)
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn is_valid(pool: &PgPool, token_hash: &str) -> Result<bool> {
+    let valid = sqlx::query_scalar(
+        "SELECT EXISTS (
+             SELECT 1 FROM password_reset_tokens
+             WHERE token_hash = $1 AND used_at IS NULL AND expires_at > now()
+         )",
+    )
+    .bind(token_hash)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(valid)
+}
+
+/// Reset a password with a token, if the token is valid
+///
+/// Marks the token used, sets the new hash, revokes the user's sessions
+/// and discards their other outstanding tokens in one transaction, so a
+/// token works at most once and a stolen session doesn't outlive it.
+/// Returns the user ID, or `None` if the token is unknown, used or expired.
+///
+/// # Errors
+///
+/// Returns an error if a query fails.
+pub async fn consume(pool: &PgPool, token_hash: &str, password_hash: &str) -> Result<Option<Uuid>> {
+    let mut tx = pool.begin().await?;
+
+    let user_id: Option<Uuid> = sqlx::query_scalar(
+        "UPDATE password_reset_tokens SET used_at = now()
+         WHERE token_hash = $1 AND used_at IS NULL AND expires_at > now()
+         RETURNING user_id",
+    )
+    .bind(token_hash)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some(user_id) = user_id else {
+        return Ok(None);
+    };
+
+    // Tokens carry whole seconds, like `crate::duress::record_login`
+    sqlx::query(
+        "UPDATE users
+         SET password_hash = $2, sessions_valid_after = date_trunc('second', now()),
+             updated_at = now()
+         WHERE id = $1",
+    )
+    .bind(user_id)
+    .bind(password_hash)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query("DELETE FROM password_reset_tokens WHERE user_id = $1 AND used_at IS NULL")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(Some(user_id))
+}
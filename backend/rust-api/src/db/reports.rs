@@ -0,0 +1,198 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Abuse report queries
+
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use super::models::{Report, ReportCategory, ReportStatus, ReportTarget};
+use crate::error::Result;
+
+/// Fields for a new report
+#[derive(Debug, Clone)]
+pub struct NewReport<'a> {
+    pub reporter_id: Uuid,
+    pub target_type: ReportTarget,
+    pub target_id: Uuid,
+    pub category: ReportCategory,
+    pub description: &'a str,
+    pub evidence: Option<&'a str>,
+}
+
+/// A report in the moderation queue
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct QueuedReport {
+    #[sqlx(flatten)]
+    pub report: Report,
+    /// Unresolved reports against the same target, from anyone
+    pub reports_on_target: i64,
+}
+
+/// Whether `reporter_id` can report the target
+///
+/// Users and events must exist; messages must have been sent to the
+/// reporter.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn target_reportable(
+    pool: &PgPool,
+    reporter_id: Uuid,
+    target_type: ReportTarget,
+    target_id: Uuid,
+) -> Result<bool> {
+    let query = match target_type {
+        ReportTarget::User => "SELECT EXISTS (SELECT 1 FROM users WHERE id = $1)",
+        ReportTarget::Event => "SELECT EXISTS (SELECT 1 FROM events WHERE id = $1)",
+        ReportTarget::Message => {
+            "SELECT EXISTS (SELECT 1 FROM messages WHERE id = $1 AND recipient_id = $2)"
+        }
+    };
+
+    let reportable = sqlx::query_scalar(query)
+        .bind(target_id)
+        .bind(reporter_id)
+        .fetch_one(pool)
+        .await?;
+
+    Ok(reportable)
+}
+
+/// Reports filed by a user since `since`
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn count_since(
+    conn: &mut PgConnection,
+    reporter_id: Uuid,
+    since: DateTime<Utc>,
+) -> Result<i64> {
+    let count = sqlx::query_scalar(
+        "SELECT count(*) FROM reports WHERE reporter_id = $1 AND created_at > $2",
+    )
+    .bind(reporter_id)
+    .bind(since)
+    .fetch_one(conn)
+    .await?;
+
+    Ok(count)
+}
+
+/// The reporter's unresolved report on a target, if any
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn find_unresolved(
+    conn: &mut PgConnection,
+    reporter_id: Uuid,
+    target_type: ReportTarget,
+    target_id: Uuid,
+) -> Result<Option<Report>> {
+    let report = sqlx::query_as::<_, Report>(
+        "SELECT * FROM reports
+         WHERE reporter_id = $1 AND target_type = $2 AND target_id = $3
+           AND status IN ('open', 'triaged')",
+    )
+    .bind(reporter_id)
+    .bind(target_type)
+    .bind(target_id)
+    .fetch_optional(conn)
+    .await?;
+
+    Ok(report)
+}
+
+/// File a report
+///
+/// # Errors
+///
+/// Returns a database error if the reporter already has an unresolved
+/// report on the target.
+pub async fn insert(conn: &mut PgConnection, new: NewReport<'_>) -> Result<Report> {
+    let report = sqlx::query_as::<_, Report>(
+        "INSERT INTO reports
+             (id, reporter_id, target_type, target_id, category, description, evidence)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)
+         RETURNING *",
+    )
+    .bind(Uuid::new_v4())
+    .bind(new.reporter_id)
+    .bind(new.target_type)
+    .bind(new.target_id)
+    .bind(new.category)
+    .bind(new.description)
+    .bind(new.evidence)
+    .fetch_one(conn)
+    .await?;
+
+    Ok(report)
+}
+
+/// Look up a report
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn find_by_id(pool: &PgPool, id: Uuid) -> Result<Option<Report>> {
+    let report = sqlx::query_as::<_, Report>("SELECT * FROM reports WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(report)
+}
+
+/// Reports in a given state, most-reported targets first
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn queue(pool: &PgPool, status: ReportStatus, limit: i64) -> Result<Vec<QueuedReport>> {
+    let reports = sqlx::query_as::<_, QueuedReport>(
+        "SELECT r.*,
+                (SELECT count(*) FROM reports o
+                 WHERE o.target_type = r.target_type AND o.target_id = r.target_id
+                   AND o.status IN ('open', 'triaged')) AS reports_on_target
+         FROM reports r
+         WHERE r.status = $1
+         ORDER BY reports_on_target DESC, r.created_at
+         LIMIT $2",
+    )
+    .bind(status)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(reports)
+}
+
+/// Move a report from `from` to `to`
+///
+/// Returns `false` if the report was no longer in `from`, e.g. because
+/// another moderator got there first.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn set_status(
+    conn: &mut PgConnection,
+    id: Uuid,
+    from: ReportStatus,
+    to: ReportStatus,
+) -> Result<bool> {
+    let result = sqlx::query(
+        "UPDATE reports SET status = $3, updated_at = now() WHERE id = $1 AND status = $2",
+    )
+    .bind(id)
+    .bind(from)
+    .bind(to)
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
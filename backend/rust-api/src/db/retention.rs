@@ -0,0 +1,114 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Retention queries
+//!
+//! See [`crate::retention`] for what is removed and when.
+
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use super::models::{AdminAction, Verification};
+use crate::error::Result;
+
+/// A verification past retention
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Expired {
+    #[sqlx(flatten)]
+    pub verification: Verification,
+    /// Whether it counts as attendance: verified, or held and released
+    pub counts: bool,
+}
+
+/// Delete messages read before `read_before`
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn delete_read_messages(pool: &PgPool, read_before: DateTime<Utc>) -> Result<u64> {
+    let result = sqlx::query("DELETE FROM messages WHERE read_at < $1")
+        .bind(read_before)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Clear the location of users inactive since `inactive_since`
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn clear_locations(pool: &PgPool, inactive_since: DateTime<Utc>) -> Result<u64> {
+    let result = sqlx::query(
+        "UPDATE users SET location_hash = NULL, updated_at = now()
+         WHERE location_hash IS NOT NULL AND last_active < $1",
+    )
+    .bind(inactive_since)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Lock up to `limit` chained verifications from before `verified_before`
+/// that no longer await review, oldest first
+///
+/// Rows another replica has locked are skipped.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn lock_expired_verifications(
+    conn: &mut PgConnection,
+    verified_before: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<Expired>> {
+    let expired = sqlx::query_as::<_, Expired>(
+        "SELECT v.*,
+                v.status = $2 OR EXISTS (
+                    SELECT 1 FROM admin_actions a WHERE a.target_id = v.id AND a.action = $3
+                ) AS counts
+         FROM verifications v
+         WHERE v.verified_at < $1
+           AND v.audit_seq IS NOT NULL
+           AND (v.status = $2 OR EXISTS (
+               SELECT 1 FROM admin_actions a WHERE a.target_id = v.id AND a.action IN ($3, $4)
+           ))
+         ORDER BY v.verified_at
+         LIMIT $5
+         FOR UPDATE SKIP LOCKED",
+    )
+    .bind(verified_before)
+    .bind(Verification::VERIFIED)
+    .bind(AdminAction::RELEASE_VERIFICATION)
+    .bind(AdminAction::REJECT_VERIFICATION)
+    .bind(limit)
+    .fetch_all(conn)
+    .await?;
+
+    Ok(expired)
+}
+
+/// Count one collapsed attendance towards a user and an event
+///
+/// # Errors
+///
+/// Returns an error if a query fails.
+pub async fn archive_attendance(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    event_id: Uuid,
+) -> Result<()> {
+    sqlx::query("UPDATE users SET archived_attendances = archived_attendances + 1 WHERE id = $1")
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("UPDATE events SET archived_attendance = archived_attendance + 1 WHERE id = $1")
+        .bind(event_id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
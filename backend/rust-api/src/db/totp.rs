@@ -0,0 +1,266 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! TOTP secret and recovery code queries
+
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::error::Result;
+
+/// A user's TOTP enrollment
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct TotpCredential {
+    pub user_id: Uuid,
+    pub secret: Vec<u8>,
+    /// Set once the user has entered a valid code
+    pub confirmed_at: Option<DateTime<Utc>>,
+    /// Last time step accepted, to refuse replays
+    pub last_used_step: Option<i64>,
+    /// Consecutive failed codes since the last success
+    pub failed_attempts: i16,
+    pub last_failed_at: Option<DateTime<Utc>>,
+}
+
+/// A user's TOTP enrollment, confirmed or pending
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn find(pool: &PgPool, user_id: Uuid) -> Result<Option<TotpCredential>> {
+    let credential = sqlx::query_as::<_, TotpCredential>(
+        "SELECT user_id, secret, confirmed_at, last_used_step, failed_attempts, last_failed_at
+         FROM totp_credentials WHERE user_id = $1",
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(credential)
+}
+
+/// Whether a user has a confirmed TOTP secret
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn is_enabled(pool: &PgPool, user_id: Uuid) -> Result<bool> {
+    let enabled = sqlx::query_scalar(
+        "SELECT EXISTS (
+             SELECT 1 FROM totp_credentials
+             WHERE user_id = $1 AND confirmed_at IS NOT NULL
+         )",
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(enabled)
+}
+
+/// Start (or restart) enrollment with a new pending secret
+///
+/// Returns `false` without changing anything if TOTP is already confirmed.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn upsert_pending(pool: &PgPool, user_id: Uuid, secret: &[u8]) -> Result<bool> {
+    let result = sqlx::query(
+        "INSERT INTO totp_credentials (user_id, secret) VALUES ($1, $2)
+         ON CONFLICT (user_id) DO UPDATE
+             SET secret = EXCLUDED.secret, last_used_step = NULL,
+                 failed_attempts = 0, last_failed_at = NULL, created_at = now()
+             WHERE totp_credentials.confirmed_at IS NULL",
+    )
+    .bind(user_id)
+    .bind(secret)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Record an accepted code, confirming the secret if still pending
+///
+/// Any `recovery_codes` replace the user's in the same transaction, so a
+/// secret is never confirmed without the codes shown for it. Returns
+/// `false`, changing nothing, if the step is not later than the last one
+/// used, which means the same code was accepted concurrently.
+///
+/// # Errors
+///
+/// Returns an error if a query fails.
+pub async fn record_success(
+    pool: &PgPool,
+    user_id: Uuid,
+    step: i64,
+    recovery_codes: Option<&[(String, String)]>,
+) -> Result<bool> {
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query(
+        "UPDATE totp_credentials
+         SET last_used_step = $2, failed_attempts = 0,
+             confirmed_at = COALESCE(confirmed_at, now())
+         WHERE user_id = $1 AND (last_used_step IS NULL OR last_used_step < $2)",
+    )
+    .bind(user_id)
+    .bind(step)
+    .execute(&mut *tx)
+    .await?;
+    if result.rows_affected() != 1 {
+        return Ok(false);
+    }
+
+    if let Some(codes) = recovery_codes {
+        store_recovery_codes(&mut tx, user_id, codes).await?;
+    }
+
+    tx.commit().await?;
+    Ok(true)
+}
+
+/// Count a rejected code
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn record_failure(pool: &PgPool, user_id: Uuid) -> Result<()> {
+    sqlx::query(
+        "UPDATE totp_credentials
+         SET failed_attempts = failed_attempts + 1, last_failed_at = now()
+         WHERE user_id = $1",
+    )
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Remove TOTP and any recovery codes
+///
+/// # Errors
+///
+/// Returns an error if a query fails.
+pub async fn delete(pool: &PgPool, user_id: Uuid) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM totp_credentials WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM mfa_recovery_codes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Replace a user's recovery codes with a new batch of
+/// `(selector, code_hash)` pairs
+///
+/// # Errors
+///
+/// Returns an error if a query fails.
+pub async fn replace_recovery_codes(
+    pool: &PgPool,
+    user_id: Uuid,
+    codes: &[(String, String)],
+) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    store_recovery_codes(&mut tx, user_id, codes).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Replace recovery codes within a transaction
+async fn store_recovery_codes(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    codes: &[(String, String)],
+) -> Result<()> {
+    let (selectors, hashes): (Vec<&str>, Vec<&str>) = codes
+        .iter()
+        .map(|(selector, hash)| (selector.as_str(), hash.as_str()))
+        .unzip();
+
+    sqlx::query("DELETE FROM mfa_recovery_codes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query(
+        "INSERT INTO mfa_recovery_codes (user_id, selector, code_hash)
+         SELECT $1, * FROM UNNEST($2::text[], $3::text[])",
+    )
+    .bind(user_id)
+    .bind(&selectors)
+    .bind(&hashes)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Hash of an unused recovery code
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn find_recovery_code(
+    pool: &PgPool,
+    user_id: Uuid,
+    selector: &str,
+) -> Result<Option<String>> {
+    let hash = sqlx::query_scalar(
+        "SELECT code_hash FROM mfa_recovery_codes
+         WHERE user_id = $1 AND selector = $2 AND used_at IS NULL",
+    )
+    .bind(user_id)
+    .bind(selector)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(hash)
+}
+
+/// Mark a recovery code used
+///
+/// Returns `false` if it had already been used, e.g. by a concurrent
+/// request.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn consume_recovery_code(pool: &PgPool, user_id: Uuid, selector: &str) -> Result<bool> {
+    let result = sqlx::query(
+        "UPDATE mfa_recovery_codes SET used_at = now()
+         WHERE user_id = $1 AND selector = $2 AND used_at IS NULL",
+    )
+    .bind(user_id)
+    .bind(selector)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Number of unused recovery codes
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn remaining_recovery_codes(pool: &PgPool, user_id: Uuid) -> Result<i64> {
+    let count = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM mfa_recovery_codes WHERE user_id = $1 AND used_at IS NULL",
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(count)
+}
@@ -0,0 +1,436 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! User queries
+
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use super::models::{Role, User};
+use crate::crypto::blind_index::BlindIndex;
+use crate::error::Result;
+
+/// Rows re-indexed per transaction by [`reindex_emails`]
+const REINDEX_BATCH_SIZE: i64 = 500;
+
+/// Look up a user by email via the blind index
+///
+/// Matches the email under every key version in one indexed query. A row
+/// still on an older key is moved to the current key on the way out.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn find_by_email(pool: &PgPool, index: &BlindIndex, email: &str) -> Result<Option<User>> {
+    let (versions, hashes): (Vec<i16>, Vec<String>) = index.candidates(email).into_iter().unzip();
+
+    // Accounts duplicated before registration checked every version
+    // resolve to the one on the newest key
+    let user = sqlx::query_as::<_, User>(
+        "SELECT users.* FROM users
+         JOIN UNNEST($1::smallint[], $2::text[]) AS c (version, hash)
+           ON users.email_key_version = c.version AND users.email_hash = c.hash
+         ORDER BY users.email_key_version DESC
+         LIMIT 1",
+    )
+    .bind(&versions)
+    .bind(&hashes)
+    .fetch_optional(pool)
+    .await?;
+
+    let Some(mut user) = user else {
+        return Ok(None);
+    };
+
+    if user.email_key_version < index.current_version() {
+        user.email_hash = index.compute(email);
+        user.email_key_version = index.current_version();
+        set_email_index(pool, user.id, &user.email_hash, user.email_key_version).await?;
+    }
+
+    Ok(Some(user))
+}
+
+/// Fields for a new account
+pub struct NewUser<'a> {
+    pub id: Uuid,
+    /// Blind index of the email under every key version, oldest first
+    /// (see [`BlindIndex::candidates`]); the last is stored
+    pub email_candidates: &'a [(i16, String)],
+    /// Opt-in recovery email, already encrypted for `id`
+    pub email_encrypted: Option<&'a [u8]>,
+    pub username: &'a str,
+    pub password_hash: &'a str,
+}
+
+/// Create a new user at level 0
+///
+/// Returns `None` if the email is already registered under any key
+/// version, so a rotation can't let one email hold two accounts.
+///
+/// # Errors
+///
+/// Returns a database error if the username is taken, or the email was
+/// registered concurrently.
+pub async fn insert(pool: &PgPool, new: NewUser<'_>) -> Result<Option<User>> {
+    let (versions, hashes): (Vec<i16>, Vec<String>) = new.email_candidates.iter().cloned().unzip();
+    let (Some(version), Some(hash)) = (versions.last(), hashes.last()) else {
+        return Ok(None);
+    };
+
+    let user = sqlx::query_as::<_, User>(
+        "INSERT INTO users
+             (id, email_hash, email_key_version, email_encrypted, username, password_hash)
+         SELECT $1, $2, $3, $4, $5, $6
+         WHERE NOT EXISTS (
+             SELECT 1 FROM users
+             JOIN UNNEST($7::smallint[], $8::text[]) AS c (version, hash)
+               ON users.email_key_version = c.version AND users.email_hash = c.hash
+         )
+         RETURNING *",
+    )
+    .bind(new.id)
+    .bind(hash)
+    .bind(version)
+    .bind(new.email_encrypted)
+    .bind(new.username)
+    .bind(new.password_hash)
+    .bind(&versions)
+    .bind(&hashes)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(user)
+}
+
+/// Replace a user's password hash (e.g. after a policy upgrade)
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn update_password_hash(pool: &PgPool, id: Uuid, password_hash: &str) -> Result<()> {
+    sqlx::query("UPDATE users SET password_hash = $2, updated_at = now() WHERE id = $1")
+        .bind(id)
+        .bind(password_hash)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Look up a user by ID
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn find_by_id(pool: &PgPool, id: Uuid) -> Result<Option<User>> {
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(user)
+}
+
+/// Look up a user by username
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn find_by_username(pool: &PgPool, username: &str) -> Result<Option<User>> {
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE username = $1")
+        .bind(username)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(user)
+}
+
+/// Set or clear a user's encrypted recovery email
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn set_recovery_email(pool: &PgPool, id: Uuid, encrypted: Option<&[u8]>) -> Result<()> {
+    sqlx::query("UPDATE users SET email_encrypted = $2, updated_at = now() WHERE id = $1")
+        .bind(id)
+        .bind(encrypted)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Set or clear a user's device signing key (ed25519 public key)
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn set_signing_key(pool: &PgPool, id: Uuid, key: Option<&[u8]>) -> Result<()> {
+    sqlx::query("UPDATE users SET signing_key = $2, updated_at = now() WHERE id = $1")
+        .bind(id)
+        .bind(key)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// An active user's device signing key, if they registered one
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn signing_key(pool: &PgPool, id: Uuid) -> Result<Option<Vec<u8>>> {
+    let key = sqlx::query_scalar(
+        "SELECT signing_key FROM users
+         WHERE id = $1 AND signing_key IS NOT NULL AND suspended_at IS NULL",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(key)
+}
+
+/// Record activity for reputation decay tracking
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn touch_last_active(pool: &PgPool, id: Uuid) -> Result<()> {
+    sqlx::query("UPDATE users SET last_active = now() WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Add to a user's XP, returning the new total
+///
+/// Run inside the transaction that records why the XP was earned.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn add_experience(conn: &mut PgConnection, id: Uuid, xp: i32) -> Result<i32> {
+    let total = sqlx::query_scalar(
+        "UPDATE users SET experience_points = experience_points + $2, updated_at = now()
+         WHERE id = $1
+         RETURNING experience_points",
+    )
+    .bind(id)
+    .bind(xp)
+    .fetch_one(conn)
+    .await?;
+
+    Ok(total)
+}
+
+/// What a session token is checked against on each request
+#[derive(Debug, Clone, Copy, sqlx::FromRow)]
+pub struct SessionState {
+    /// Sessions issued before this are revoked
+    pub sessions_valid_after: Option<DateTime<Utc>>,
+    /// Whether the account is in duress mode
+    pub under_duress: bool,
+}
+
+/// Session state of a user that exists and is not suspended
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn session_state(pool: &PgPool, id: Uuid) -> Result<Option<SessionState>> {
+    let state = sqlx::query_as::<_, SessionState>(
+        "SELECT sessions_valid_after, duress_at IS NOT NULL AS under_duress
+         FROM users WHERE id = $1 AND suspended_at IS NULL",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(state)
+}
+
+/// Suspend or reinstate a user
+///
+/// Idempotent; returns `false` if there is no such user.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn set_suspended(conn: &mut PgConnection, id: Uuid, suspended: bool) -> Result<bool> {
+    let result = sqlx::query(
+        "UPDATE users
+         SET suspended_at = CASE WHEN $2 THEN COALESCE(suspended_at, now()) END, updated_at = now()
+         WHERE id = $1",
+    )
+    .bind(id)
+    .bind(suspended)
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Change a user's moderation role
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn set_role(conn: &mut PgConnection, id: Uuid, role: Role) -> Result<()> {
+    sqlx::query("UPDATE users SET role = $2, updated_at = now() WHERE id = $1")
+        .bind(id)
+        .bind(role)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Lock a user's row for a change of level or XP
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn find_for_update(conn: &mut PgConnection, id: Uuid) -> Result<Option<User>> {
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1 FOR UPDATE")
+        .bind(id)
+        .fetch_optional(conn)
+        .await?;
+
+    Ok(user)
+}
+
+/// Set a user's level and XP
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn set_progress(conn: &mut PgConnection, id: Uuid, level: i16, xp: i32) -> Result<()> {
+    sqlx::query(
+        "UPDATE users SET current_level = $2, experience_points = $3, updated_at = now()
+         WHERE id = $1",
+    )
+    .bind(id)
+    .bind(level)
+    .bind(xp)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Outcome of a [`reindex_emails`] run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Reindexed {
+    /// Rows moved to the current key
+    pub moved: u64,
+    /// Rows left on an older key because another account already holds
+    /// their email under the current key
+    pub conflicts: u64,
+}
+
+/// Move every row on an older blind index key to the current key
+///
+/// Runs in small batches with `SKIP LOCKED`, so it is safe to run
+/// alongside logins and from several replicas at once. A row whose email
+/// another account already holds under the current key is skipped rather
+/// than failing the batch; it stays on its old key, where logins still
+/// find it, and is tried again on the next run.
+///
+/// # Errors
+///
+/// Returns an error if a query fails or a stored index is malformed.
+pub async fn reindex_emails(pool: &PgPool, index: &BlindIndex) -> Result<Reindexed> {
+    let mut reindexed = Reindexed::default();
+    let mut after = Uuid::nil();
+
+    loop {
+        let mut tx = pool.begin().await?;
+
+        let stale: Vec<(Uuid, String, i16)> = sqlx::query_as(
+            "SELECT id, email_hash, email_key_version FROM users
+             WHERE email_key_version < $1 AND id > $2
+             ORDER BY id
+             LIMIT $3
+             FOR UPDATE SKIP LOCKED",
+        )
+        .bind(index.current_version())
+        .bind(after)
+        .bind(REINDEX_BATCH_SIZE)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let Some((last, _, _)) = stale.last() else {
+            tx.commit().await?;
+            return Ok(reindexed);
+        };
+        after = *last;
+
+        for (id, email_hash, version) in &stale {
+            let moved = sqlx::query(
+                "UPDATE users SET email_hash = $2, email_key_version = $3
+                 WHERE id = $1 AND NOT EXISTS (SELECT 1 FROM users WHERE email_hash = $2)",
+            )
+            .bind(id)
+            .bind(index.rewrap(*version, email_hash)?)
+            .bind(index.current_version())
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+            if moved == 1 {
+                reindexed.moved += 1;
+            } else {
+                reindexed.conflicts += 1;
+            }
+        }
+
+        tx.commit().await?;
+    }
+}
+
+/// Re-index emails every `interval`, so rows skipped or missed by one run
+/// are picked up by the next
+pub async fn run_reindex(db: PgPool, index: BlindIndex, interval: std::time::Duration) {
+    let mut ticker = tokio::time::interval(interval);
+
+    loop {
+        ticker.tick().await;
+        match reindex_emails(&db, &index).await {
+            Ok(Reindexed {
+                moved: 0,
+                conflicts: 0,
+            }) => {}
+            Ok(Reindexed {
+                moved,
+                conflicts: 0,
+            }) => tracing::info!(count = moved, "Re-indexed email blind index"),
+            Ok(Reindexed { moved, conflicts }) => tracing::warn!(
+                count = moved,
+                conflicts,
+                "Re-indexed email blind index; some emails belong to two accounts"
+            ),
+            Err(e) => tracing::error!(error = ?e, "Email re-index failed"),
+        }
+    }
+}
+
+async fn set_email_index(pool: &PgPool, id: Uuid, email_hash: &str, version: i16) -> Result<()> {
+    // Left for re-indexing to report if the email has a second account
+    sqlx::query(
+        "UPDATE users SET email_hash = $2, email_key_version = $3
+         WHERE id = $1 AND NOT EXISTS (SELECT 1 FROM users WHERE email_hash = $2)",
+    )
+    .bind(id)
+    .bind(email_hash)
+    .bind(version)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,122 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Verification queries
+//!
+//! Verifications are written through [`super::audit::append_verification`];
+//! these are reads.
+
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::models::{AdminAction, Verification};
+use crate::error::Result;
+
+/// Look up a verification
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn find_by_id(pool: &PgPool, id: Uuid) -> Result<Option<Verification>> {
+    let verification =
+        sqlx::query_as::<_, Verification>("SELECT * FROM verifications WHERE id = $1")
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
+
+    Ok(verification)
+}
+
+/// Held verifications not yet reviewed, oldest first
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn awaiting_review(pool: &PgPool, limit: i64) -> Result<Vec<Verification>> {
+    let verifications = sqlx::query_as::<_, Verification>(
+        "SELECT * FROM verifications v
+         WHERE v.status = $1
+           AND NOT EXISTS (
+               SELECT 1 FROM admin_actions a
+               WHERE a.target_id = v.id AND a.action IN ($2, $3)
+           )
+         ORDER BY v.verified_at
+         LIMIT $4",
+    )
+    .bind(Verification::HELD_FOR_REVIEW)
+    .bind(AdminAction::RELEASE_VERIFICATION)
+    .bind(AdminAction::REJECT_VERIFICATION)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(verifications)
+}
+
+/// Attendances at an event that count: verified, or held and released,
+/// plus those since collapsed by retention
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn count_verified(pool: &PgPool, event_id: Uuid) -> Result<i64> {
+    let count = sqlx::query_scalar(
+        "SELECT COUNT(*) + (SELECT COALESCE(MAX(archived_attendance), 0) FROM events WHERE id = $1)
+         FROM verifications v
+         WHERE v.event_id = $1
+           AND (v.status = $2 OR EXISTS (
+               SELECT 1 FROM admin_actions a
+               WHERE a.target_id = v.id AND a.action = $3
+           ))",
+    )
+    .bind(event_id)
+    .bind(Verification::VERIFIED)
+    .bind(AdminAction::RELEASE_VERIFICATION)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(count)
+}
+
+/// Verifications a user made strictly between `after` and `before`, of
+/// any status
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn count_between(
+    pool: &PgPool,
+    user_id: Uuid,
+    after: DateTime<Utc>,
+    before: DateTime<Utc>,
+) -> Result<i64> {
+    let count = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM verifications
+         WHERE user_id = $1 AND verified_at > $2 AND verified_at < $3",
+    )
+    .bind(user_id)
+    .bind(after)
+    .bind(before)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(count)
+}
+
+/// Whether a user already has a verification for an event
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn exists(pool: &PgPool, event_id: Uuid, user_id: Uuid) -> Result<bool> {
+    let exists = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM verifications WHERE event_id = $1 AND user_id = $2)",
+    )
+    .bind(event_id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(exists)
+}
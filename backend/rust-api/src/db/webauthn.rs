@@ -0,0 +1,150 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Passkey credential and ceremony queries
+
+use chrono::{DateTime, Utc};
+use sqlx::{types::Json, PgPool};
+use uuid::Uuid;
+use webauthn_rs::prelude::{AuthenticationResult, Passkey};
+
+use crate::error::Result;
+
+/// A user's registered passkeys
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn list_passkeys(pool: &PgPool, user_id: Uuid) -> Result<Vec<Passkey>> {
+    let rows: Vec<Json<Passkey>> = sqlx::query_scalar(
+        "SELECT passkey FROM webauthn_credentials WHERE user_id = $1 ORDER BY created_at",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|Json(passkey)| passkey).collect())
+}
+
+/// Whether a user has any passkey registered
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn has_passkey(pool: &PgPool, user_id: Uuid) -> Result<bool> {
+    let exists =
+        sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM webauthn_credentials WHERE user_id = $1)")
+            .bind(user_id)
+            .fetch_one(pool)
+            .await?;
+
+    Ok(exists)
+}
+
+/// Store a newly registered passkey
+///
+/// # Errors
+///
+/// Returns a database error if the credential ID is already registered.
+pub async fn insert_passkey(
+    pool: &PgPool,
+    user_id: Uuid,
+    name: &str,
+    passkey: &Passkey,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO webauthn_credentials (credential_id, user_id, name, passkey)
+         VALUES ($1, $2, $3, $4)",
+    )
+    .bind(passkey.cred_id().as_ref())
+    .bind(user_id)
+    .bind(name)
+    .bind(Json(passkey))
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Apply the signature counter and backup state from a successful assertion
+///
+/// # Errors
+///
+/// Returns an error if a query fails.
+pub async fn record_use(pool: &PgPool, user_id: Uuid, result: &AuthenticationResult) -> Result<()> {
+    let credential_id: &[u8] = result.cred_id().as_ref();
+    let row: Option<Json<Passkey>> = sqlx::query_scalar(
+        "SELECT passkey FROM webauthn_credentials WHERE credential_id = $1 AND user_id = $2",
+    )
+    .bind(credential_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    let Some(Json(mut passkey)) = row else {
+        return Ok(());
+    };
+    passkey.update_credential(result);
+
+    sqlx::query(
+        "UPDATE webauthn_credentials SET passkey = $3, last_used_at = now()
+         WHERE credential_id = $1 AND user_id = $2",
+    )
+    .bind(credential_id)
+    .bind(user_id)
+    .bind(Json(&passkey))
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Store ceremony state between the start and finish requests
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn insert_challenge(
+    pool: &PgPool,
+    id: Uuid,
+    user_id: Uuid,
+    purpose: &str,
+    state: serde_json::Value,
+    expires_at: DateTime<Utc>,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO webauthn_challenges (id, user_id, purpose, state, expires_at)
+         VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(id)
+    .bind(user_id)
+    .bind(purpose)
+    .bind(state)
+    .bind(expires_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Take (and delete) unexpired ceremony state, so each challenge is
+/// answered at most once. Returns the user ID, purpose and state.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn take_challenge(
+    pool: &PgPool,
+    id: Uuid,
+) -> Result<Option<(Uuid, String, serde_json::Value)>> {
+    let row = sqlx::query_as(
+        "DELETE FROM webauthn_challenges
+         WHERE id = $1 AND expires_at > now()
+         RETURNING user_id, purpose, state",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row)
+}
@@ -0,0 +1,375 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Webhook subscription and delivery queue queries
+
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::error::Result;
+
+/// A URL subscribed to an owner's event changes
+#[derive(Clone, sqlx::FromRow)]
+pub struct WebhookSubscription {
+    pub id: Uuid,
+    pub owner_id: Uuid,
+    pub url: String,
+    pub secret: String,
+    pub event_types: Vec<String>,
+    pub include_pii: bool,
+    pub created_at: DateTime<Utc>,
+    /// Chapter whose events this hears about, instead of the owner's own
+    pub organization_id: Option<Uuid>,
+}
+
+impl std::fmt::Debug for WebhookSubscription {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebhookSubscription")
+            .field("id", &self.id)
+            .field("owner_id", &self.owner_id)
+            .field("organization_id", &self.organization_id)
+            .field("url", &self.url)
+            .field("event_types", &self.event_types)
+            .field("include_pii", &self.include_pii)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Fields for a new subscription
+#[derive(Debug, Clone)]
+pub struct NewSubscription<'a> {
+    pub owner_id: Uuid,
+    pub organization_id: Option<Uuid>,
+    pub url: &'a str,
+    pub secret: &'a str,
+    pub event_types: &'a [String],
+    pub include_pii: bool,
+}
+
+/// A subscription a change should be delivered to
+#[derive(Debug, Clone, Copy, sqlx::FromRow)]
+pub struct Subscriber {
+    pub id: Uuid,
+    pub include_pii: bool,
+}
+
+/// A delivery claimed for an attempt, with where to send it
+#[derive(Clone, sqlx::FromRow)]
+pub struct DueDelivery {
+    pub id: Uuid,
+    pub subscription_id: Uuid,
+    pub event_type: String,
+    pub payload: Value,
+    /// Attempts so far, including this one
+    pub attempts: i32,
+    pub created_at: DateTime<Utc>,
+    pub url: String,
+    pub secret: String,
+}
+
+impl std::fmt::Debug for DueDelivery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DueDelivery")
+            .field("id", &self.id)
+            .field("event_type", &self.event_type)
+            .field("attempts", &self.attempts)
+            .field("url", &self.url)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A delivery that failed every attempt
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct DeadLetter {
+    pub id: Uuid,
+    pub subscription_id: Uuid,
+    pub event_type: String,
+    pub payload: Value,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub failed_at: DateTime<Utc>,
+}
+
+/// Create a subscription
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn create(pool: &PgPool, new: NewSubscription<'_>) -> Result<WebhookSubscription> {
+    let subscription = sqlx::query_as::<_, WebhookSubscription>(
+        "INSERT INTO webhook_subscriptions
+             (id, owner_id, url, secret, event_types, include_pii, organization_id)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)
+         RETURNING *",
+    )
+    .bind(Uuid::new_v4())
+    .bind(new.owner_id)
+    .bind(new.url)
+    .bind(new.secret)
+    .bind(new.event_types)
+    .bind(new.include_pii)
+    .bind(new.organization_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(subscription)
+}
+
+/// Number of subscriptions a user owns
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn count_owned(pool: &PgPool, owner_id: Uuid) -> Result<i64> {
+    let count =
+        sqlx::query_scalar("SELECT count(*) FROM webhook_subscriptions WHERE owner_id = $1")
+            .bind(owner_id)
+            .fetch_one(pool)
+            .await?;
+
+    Ok(count)
+}
+
+/// A user's subscriptions, oldest first
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn list_owned(pool: &PgPool, owner_id: Uuid) -> Result<Vec<WebhookSubscription>> {
+    let subscriptions = sqlx::query_as::<_, WebhookSubscription>(
+        "SELECT * FROM webhook_subscriptions WHERE owner_id = $1 ORDER BY created_at",
+    )
+    .bind(owner_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(subscriptions)
+}
+
+/// A subscription, if `owner_id` owns it
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn find_owned(
+    pool: &PgPool,
+    id: Uuid,
+    owner_id: Uuid,
+) -> Result<Option<WebhookSubscription>> {
+    let subscription = sqlx::query_as::<_, WebhookSubscription>(
+        "SELECT * FROM webhook_subscriptions WHERE id = $1 AND owner_id = $2",
+    )
+    .bind(id)
+    .bind(owner_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(subscription)
+}
+
+/// Delete a subscription with its queued and dead deliveries
+///
+/// Returns `false` if `owner_id` has no such subscription.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn delete(pool: &PgPool, id: Uuid, owner_id: Uuid) -> Result<bool> {
+    let result = sqlx::query("DELETE FROM webhook_subscriptions WHERE id = $1 AND owner_id = $2")
+        .bind(id)
+        .bind(owner_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Subscriptions to `event_type` for an event
+///
+/// That's the organizer's personal subscriptions and, for a chapter
+/// event, the chapter's.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn subscribers(
+    conn: &mut PgConnection,
+    event_id: Uuid,
+    event_type: &str,
+) -> Result<Vec<Subscriber>> {
+    let subscribers = sqlx::query_as::<_, Subscriber>(
+        "SELECT s.id, s.include_pii
+         FROM webhook_subscriptions s
+         JOIN events e ON e.id = $1
+         WHERE $2 = ANY (s.event_types)
+           AND ((s.organization_id IS NULL AND s.owner_id = e.organizer_id)
+                OR s.organization_id = e.organization_id)",
+    )
+    .bind(event_id)
+    .bind(event_type)
+    .fetch_all(conn)
+    .await?;
+
+    Ok(subscribers)
+}
+
+/// Queue a delivery for its first attempt
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn enqueue(
+    conn: &mut PgConnection,
+    subscription_id: Uuid,
+    event_type: &str,
+    payload: &Value,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO webhook_deliveries (id, subscription_id, event_type, payload)
+         VALUES ($1, $2, $3, $4)",
+    )
+    .bind(Uuid::new_v4())
+    .bind(subscription_id)
+    .bind(event_type)
+    .bind(payload)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Claim up to `limit` due deliveries for an attempt
+///
+/// Claimed deliveries count the attempt and aren't due again for
+/// `lease_secs`, so another replica won't send them meanwhile and a
+/// crash mid-attempt only delays them.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn claim_due(pool: &PgPool, limit: i64, lease_secs: f64) -> Result<Vec<DueDelivery>> {
+    let deliveries = sqlx::query_as::<_, DueDelivery>(
+        "WITH due AS (
+             SELECT id FROM webhook_deliveries
+             WHERE next_attempt_at <= now()
+             ORDER BY next_attempt_at
+             LIMIT $1
+             FOR UPDATE SKIP LOCKED
+         )
+         UPDATE webhook_deliveries d
+         SET attempts = d.attempts + 1, next_attempt_at = now() + make_interval(secs => $2)
+         FROM due, webhook_subscriptions s
+         WHERE d.id = due.id AND s.id = d.subscription_id
+         RETURNING d.id, d.subscription_id, d.event_type, d.payload, d.attempts, d.created_at,
+                   s.url, s.secret",
+    )
+    .bind(limit)
+    .bind(lease_secs)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(deliveries)
+}
+
+/// Remove a delivery that succeeded
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn delivered(pool: &PgPool, id: Uuid) -> Result<()> {
+    sqlx::query("DELETE FROM webhook_deliveries WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Schedule another attempt at a failed delivery
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn retry_at(pool: &PgPool, id: Uuid, at: DateTime<Utc>, error: &str) -> Result<()> {
+    sqlx::query(
+        "UPDATE webhook_deliveries SET next_attempt_at = $2, last_error = $3 WHERE id = $1",
+    )
+    .bind(id)
+    .bind(at)
+    .bind(error)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Move a delivery that failed its last attempt to the dead-letter table
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn dead_letter(pool: &PgPool, id: Uuid, error: &str) -> Result<()> {
+    sqlx::query(
+        "WITH failed AS (DELETE FROM webhook_deliveries WHERE id = $1 RETURNING *)
+         INSERT INTO webhook_dead_letters
+             (id, subscription_id, event_type, payload, attempts, last_error, created_at)
+         SELECT id, subscription_id, event_type, payload, attempts, $2, created_at FROM failed",
+    )
+    .bind(id)
+    .bind(error)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// A subscription's dead letters, most recent first
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn dead_letters(
+    pool: &PgPool,
+    subscription_id: Uuid,
+    limit: i64,
+) -> Result<Vec<DeadLetter>> {
+    let letters = sqlx::query_as::<_, DeadLetter>(
+        "SELECT * FROM webhook_dead_letters WHERE subscription_id = $1
+         ORDER BY failed_at DESC LIMIT $2",
+    )
+    .bind(subscription_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(letters)
+}
+
+/// Queue dead letters for delivery again, with fresh retries
+///
+/// Replays one letter, or all of the subscription's if `letter_id` is
+/// `None`. Returns how many were queued.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn replay(pool: &PgPool, subscription_id: Uuid, letter_id: Option<Uuid>) -> Result<u64> {
+    let result = sqlx::query(
+        "WITH replayed AS (
+             DELETE FROM webhook_dead_letters
+             WHERE subscription_id = $1 AND ($2::uuid IS NULL OR id = $2)
+             RETURNING *
+         )
+         INSERT INTO webhook_deliveries (id, subscription_id, event_type, payload, created_at)
+         SELECT id, subscription_id, event_type, payload, created_at FROM replayed",
+    )
+    .bind(subscription_id)
+    .bind(letter_id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,191 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Duress mode
+//!
+//! An organizer forced to unlock their account can log in with a second,
+//! duress password instead. The login looks and takes the same as a
+//! normal one, and then:
+//!
+//! - Every other session is revoked.
+//! - The account is shown sanitized: its organizations, co-organizers,
+//!   webhooks, followed events, trusted contacts, alerts, messages and
+//!   mentorships appear empty; it can't manage organizations, issue a
+//!   calendar feed, enroll passkeys or change TOTP; and duress settings
+//!   can't be changed, nor the recovery email removed. Handlers that
+//!   touch any of these must honor [`AuthUser::sanitized`](crate::api::extract::AuthUser::sanitized).
+//! - If the user opted in, trusted contacts get an alert in the app, and
+//!   by email if they stored a recovery email. This happens after the
+//!   response, so it doesn't slow the login down.
+//!
+//! Nothing in the token marks a duress session; the state is kept on the
+//! server. Logging in with the real password leaves duress mode and
+//! revokes the sanitized sessions.
+//!
+//! For accounts with a second factor, neither password changes anything
+//! until that factor succeeds, so a password alone can't end duress mode
+//! or revoke sessions.
+
+use chrono::{Duration, DurationRound, Utc};
+use uuid::Uuid;
+
+use crate::crypto::password::{PasswordCheck, PasswordHasher};
+use crate::db::{self, models::User};
+use crate::error::Result;
+use crate::mail::Mail;
+use crate::state::AppState;
+
+/// Which password a login matched
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoginPassword {
+    /// The real password
+    Real(PasswordCheck),
+    /// The duress password
+    Duress,
+    /// Neither
+    Mismatch,
+}
+
+/// Check a login password against both of a user's passwords
+///
+/// Always spends two hashes, whether or not a duress password is set, so
+/// timing doesn't tell the passwords apart. Unknown accounts should
+/// spend the same with [`check_unknown`].
+///
+/// # Errors
+///
+/// Returns an error if a stored hash is malformed or hashing fails.
+pub async fn check(
+    passwords: &PasswordHasher,
+    user: &User,
+    password: &str,
+) -> Result<LoginPassword> {
+    let real = passwords
+        .verify(password.to_owned(), user.password_hash.clone())
+        .await?;
+    let duress = match &user.duress_password_hash {
+        Some(hash) => passwords.verify(password.to_owned(), hash.clone()).await?,
+        None => passwords.verify_dummy(password.to_owned()).await?,
+    };
+
+    Ok(if real.is_valid() {
+        LoginPassword::Real(real)
+    } else if duress.is_valid() {
+        LoginPassword::Duress
+    } else {
+        LoginPassword::Mismatch
+    })
+}
+
+/// Spend the same effort as [`check`], for unknown accounts
+///
+/// # Errors
+///
+/// Returns an error if hashing fails.
+pub async fn check_unknown(passwords: &PasswordHasher, password: &str) -> Result<()> {
+    passwords.verify_dummy(password.to_owned()).await?;
+    passwords.verify_dummy(password.to_owned()).await?;
+    Ok(())
+}
+
+/// Record a successful password login, entering or leaving duress mode
+///
+/// For logins without a second factor; see [`begin_login`] for the rest.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn record_login(state: &AppState, user: &User, password: LoginPassword) -> Result<()> {
+    record(state, user, password == LoginPassword::Duress).await
+}
+
+/// Note the password step of a login that still needs a second factor
+///
+/// Both passwords run the same statement, so they still take the same
+/// time.
+///
+/// # Errors
+///
+/// Returns an error if the query fails.
+pub async fn begin_login(state: &AppState, user: &User, password: LoginPassword) -> Result<()> {
+    db::duress::set_pending(&state.db, user.id, password == LoginPassword::Duress).await
+}
+
+/// Record a login whose second factor just succeeded, entering or leaving
+/// duress mode as its password step asked
+///
+/// Does nothing if no password step is pending, as after a passwordless
+/// login.
+///
+/// # Errors
+///
+/// Returns an error if a query fails.
+pub async fn complete_login(state: &AppState, user: &User) -> Result<()> {
+    match db::duress::take_pending(&state.db, user.id).await? {
+        Some(under_duress) => record(state, user, under_duress).await,
+        None => Ok(()),
+    }
+}
+
+/// Enter or leave duress mode; contacts are alerted in the background
+/// when the user enters it and opted in to alerts
+async fn record(state: &AppState, user: &User, under_duress: bool) -> Result<()> {
+    // Tokens carry whole seconds, so a session issued in this second
+    // must still count as after the revocation
+    let now = Utc::now();
+    let at = now.duration_trunc(Duration::seconds(1)).unwrap_or(now);
+
+    let entered = db::duress::record_login(&state.db, user.id, under_duress, at).await?;
+    if entered && user.duress_notify {
+        tokio::spawn(alert_contacts(
+            state.clone(),
+            user.id,
+            user.username.clone(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Alert a user's trusted contacts that they logged in under duress
+async fn alert_contacts(state: AppState, user_id: Uuid, username: String) {
+    let recipients = match db::duress::raise_alerts(&state.db, user_id).await {
+        Ok(recipients) => recipients,
+        Err(e) => {
+            tracing::error!(error = ?e, user_id = %user_id, "Failed to raise duress alerts");
+            return;
+        }
+    };
+
+    let Some(vault) = &state.email_vault else {
+        return;
+    };
+    for recipient in recipients {
+        let Some(encrypted) = &recipient.email_encrypted else {
+            continue;
+        };
+        let sent = match vault.decrypt(recipient.id, encrypted) {
+            Ok(to) => {
+                state
+                    .mailer
+                    .send(Mail {
+                        to,
+                        subject: "A CivicConnect contact may need help".into(),
+                        body: format!(
+                            "{username} listed you as a trusted contact on CivicConnect, \
+                             and has just signed in with their duress password.\n\n\
+                             They may be being forced to open their account. Their \
+                             sessions have been ended and their network is hidden.\n\
+                             Reach out to them the way you agreed on, not through \
+                             CivicConnect."
+                        ),
+                    })
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = sent {
+            tracing::error!(error = ?e, contact_id = %recipient.id, "Failed to send duress alert");
+        }
+    }
+}
@@ -1,17 +1,21 @@
 // SPDX-License-Identifier: MPL-2.0
 // SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
 
-//! Error types for the CivicConnect API
+//! Error types for the `CivicConnect` API
 
 use axum::{
-    http::StatusCode,
+    http::{header, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use serde::Serialize;
 use thiserror::Error;
+use utoipa::ToSchema;
 
-/// Result type alias using ApiError
+/// Seconds a shed request is told to wait before retrying
+pub const RETRY_AFTER_SECS: u64 = 2;
+
+/// Result type alias using [`ApiError`]
 pub type Result<T> = std::result::Result<T, ApiError>;
 
 /// API error types
@@ -20,12 +24,33 @@ pub enum ApiError {
     #[error("Invalid credentials")]
     InvalidCredentials,
 
+    #[error("Email or username already registered")]
+    AlreadyRegistered,
+
     #[error("User not found")]
     UserNotFound,
 
     #[error("Event not found")]
     EventNotFound,
 
+    #[error("Verification not found")]
+    VerificationNotFound,
+
+    #[error("Report not found")]
+    ReportNotFound,
+
+    #[error("Webhook not found")]
+    WebhookNotFound,
+
+    #[error("Organization not found")]
+    OrganizationNotFound,
+
+    #[error("Invitation not found")]
+    InvitationNotFound,
+
+    #[error("Slug already taken")]
+    SlugTaken,
+
     #[error("Invalid input: {0}")]
     InvalidInput(String),
 
@@ -35,6 +60,9 @@ pub enum ApiError {
     #[error("Already verified")]
     AlreadyVerified,
 
+    #[error("Event is full")]
+    EventFull,
+
     #[error("Invalid signature")]
     InvalidSignature,
 
@@ -44,12 +72,36 @@ pub enum ApiError {
     #[error("Outside location")]
     OutsideLocation,
 
+    #[error("Invalid or expired token")]
+    InvalidToken,
+
     #[error("Unauthorized")]
     Unauthorized,
 
     #[error("Forbidden")]
     Forbidden,
 
+    #[error("Account suspended")]
+    AccountSuspended,
+
+    #[error("Server busy; retry shortly")]
+    Overloaded,
+
+    #[error("Request took too long")]
+    TimedOut,
+
+    #[error("Request body too large")]
+    PayloadTooLarge,
+
+    #[error("Idempotency key already used for a different request")]
+    IdempotencyKeyReused,
+
+    #[error("A request with this idempotency key is still in progress")]
+    IdempotencyKeyInProgress,
+
+    #[error("Remote server error: {0}")]
+    FederationFailed(String),
+
     #[error("Internal server error")]
     Internal(#[from] anyhow::Error),
 
@@ -58,35 +110,70 @@ pub enum ApiError {
 }
 
 /// Error response body
-#[derive(Serialize)]
-struct ErrorResponse {
-    error: String,
-    code: &'static str,
+#[derive(Serialize, ToSchema)]
+pub struct ErrorResponse {
+    pub error: String,
+    /// Stable machine-readable code, e.g. `EVENT_NOT_FOUND`
+    pub code: &'static str,
 }
 
-impl IntoResponse for ApiError {
-    fn into_response(self) -> Response {
-        let (status, code) = match &self {
+impl ApiError {
+    /// HTTP status and stable code for this error
+    const fn status_and_code(&self) -> (StatusCode, &'static str) {
+        match self {
             Self::InvalidCredentials => (StatusCode::UNAUTHORIZED, "INVALID_CREDENTIALS"),
+            Self::AlreadyRegistered => (StatusCode::CONFLICT, "ALREADY_REGISTERED"),
+            Self::SlugTaken => (StatusCode::CONFLICT, "SLUG_TAKEN"),
             Self::UserNotFound => (StatusCode::NOT_FOUND, "USER_NOT_FOUND"),
             Self::EventNotFound => (StatusCode::NOT_FOUND, "EVENT_NOT_FOUND"),
+            Self::VerificationNotFound => (StatusCode::NOT_FOUND, "VERIFICATION_NOT_FOUND"),
+            Self::ReportNotFound => (StatusCode::NOT_FOUND, "REPORT_NOT_FOUND"),
+            Self::WebhookNotFound => (StatusCode::NOT_FOUND, "WEBHOOK_NOT_FOUND"),
+            Self::OrganizationNotFound => (StatusCode::NOT_FOUND, "ORGANIZATION_NOT_FOUND"),
+            Self::InvitationNotFound => (StatusCode::NOT_FOUND, "INVITATION_NOT_FOUND"),
             Self::InvalidInput(_) => (StatusCode::BAD_REQUEST, "INVALID_INPUT"),
             Self::RateLimited => (StatusCode::TOO_MANY_REQUESTS, "RATE_LIMITED"),
             Self::AlreadyVerified => (StatusCode::CONFLICT, "ALREADY_VERIFIED"),
+            Self::EventFull => (StatusCode::CONFLICT, "EVENT_FULL"),
             Self::InvalidSignature => (StatusCode::BAD_REQUEST, "INVALID_SIGNATURE"),
             Self::OutsideTimeWindow => (StatusCode::BAD_REQUEST, "OUTSIDE_TIME_WINDOW"),
             Self::OutsideLocation => (StatusCode::BAD_REQUEST, "OUTSIDE_LOCATION"),
+            Self::InvalidToken => (StatusCode::BAD_REQUEST, "INVALID_TOKEN"),
             Self::Unauthorized => (StatusCode::UNAUTHORIZED, "UNAUTHORIZED"),
             Self::Forbidden => (StatusCode::FORBIDDEN, "FORBIDDEN"),
+            Self::AccountSuspended => (StatusCode::FORBIDDEN, "ACCOUNT_SUSPENDED"),
+            Self::Overloaded => (StatusCode::SERVICE_UNAVAILABLE, "OVERLOADED"),
+            Self::TimedOut => (StatusCode::SERVICE_UNAVAILABLE, "TIMED_OUT"),
+            Self::PayloadTooLarge => (StatusCode::PAYLOAD_TOO_LARGE, "PAYLOAD_TOO_LARGE"),
+            Self::IdempotencyKeyReused => {
+                (StatusCode::UNPROCESSABLE_ENTITY, "IDEMPOTENCY_KEY_REUSED")
+            }
+            Self::IdempotencyKeyInProgress => (StatusCode::CONFLICT, "IDEMPOTENCY_KEY_IN_PROGRESS"),
+            Self::FederationFailed(_) => (StatusCode::BAD_GATEWAY, "FEDERATION_FAILED"),
             Self::Internal(_) => (StatusCode::INTERNAL_SERVER_ERROR, "INTERNAL_ERROR"),
             Self::Database(_) => (StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR"),
-        };
+        }
+    }
+}
+
+impl From<&ApiError> for ErrorResponse {
+    fn from(err: &ApiError) -> Self {
+        Self {
+            error: err.to_string(),
+            code: err.status_and_code().1,
+        }
+    }
+}
 
-        let body = Json(ErrorResponse {
-            error: self.to_string(),
-            code,
-        });
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = self.status_and_code().0;
+        let body = Json(ErrorResponse::from(&self));
 
+        if matches!(self, Self::Overloaded | Self::IdempotencyKeyInProgress) {
+            let retry_after = [(header::RETRY_AFTER, RETRY_AFTER_SECS.to_string())];
+            return (status, retry_after, body).into_response();
+        }
         (status, body).into_response()
     }
 }
@@ -0,0 +1,159 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Inbox processing
+//!
+//! Every activity must be signed by its `actor`: the signature's key is
+//! fetched from the key ID's actor document, which must name the key as
+//! its own and be the actor the activity claims. Only then is the activity
+//! acted on.
+//!
+//! Handled: `Follow` of the inbox's owner (answered with `Accept`),
+//! `Undo` of such a follow, and `Accept` of a follow we sent. Anything
+//! else is accepted and ignored, as the protocol expects.
+
+use std::sync::Arc;
+
+use axum::http::HeaderMap;
+use serde_json::{json, Value};
+
+use super::signature::{self, SignatureHeader};
+use super::{Federation, RemoteActor};
+use crate::db::{self, federation::NewFollower, models::User};
+use crate::error::{ApiError, Result};
+use crate::state::AppState;
+
+/// A signed request to an actor's inbox
+#[derive(Debug, Clone, Copy)]
+pub struct Delivery<'a> {
+    /// Request path, as signed in `(request-target)`
+    pub path: &'a str,
+    pub headers: &'a HeaderMap,
+    pub body: &'a [u8],
+}
+
+impl<'a> Delivery<'a> {
+    fn header(&self, name: &str) -> Option<&'a str> {
+        self.headers.get(name).and_then(|value| value.to_str().ok())
+    }
+}
+
+/// Authenticate and act on an activity delivered to `owner`'s inbox
+///
+/// # Errors
+///
+/// Returns [`ApiError::Unauthorized`] if the request isn't validly signed
+/// by the activity's actor, [`ApiError::InvalidInput`] if it isn't an
+/// activity, or an error if a query fails.
+pub async fn receive(
+    state: &AppState,
+    federation: &Arc<Federation>,
+    owner: &User,
+    delivery: Delivery<'_>,
+) -> Result<()> {
+    let sender = authenticate(federation, delivery).await?;
+
+    let activity: Value = serde_json::from_slice(delivery.body)
+        .map_err(|_| ApiError::InvalidInput("Body is not JSON".into()))?;
+    if id_of(&activity["actor"]) != Some(sender.id.as_str()) {
+        return Err(ApiError::Unauthorized);
+    }
+    let our_actor = federation.actor_uri(owner.id);
+
+    match activity["type"].as_str() {
+        Some("Follow") if id_of(&activity["object"]) == Some(our_actor.as_str()) => {
+            let follow_id = id_of(&activity)
+                .ok_or_else(|| ApiError::InvalidInput("Follow has no ID".into()))?;
+            db::federation::add_follower(
+                &state.db,
+                NewFollower {
+                    user_id: owner.id,
+                    actor_uri: &sender.id,
+                    inbox: &sender.inbox,
+                    shared_inbox: sender.shared_inbox.as_deref(),
+                    follow_id,
+                },
+            )
+            .await?;
+            tracing::info!(user_id = %owner.id, follower = %sender.id, "Federated follow");
+
+            accept(federation, owner, &sender, &activity);
+        }
+        Some("Undo") if activity["object"]["type"] == "Follow" => {
+            db::federation::remove_follower(&state.db, owner.id, &sender.id).await?;
+        }
+        Some("Accept") => {
+            if let Some(follow_id) = id_of(&activity["object"]) {
+                db::federation::accept_following(&state.db, follow_id, &sender.id).await?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Check the request is signed by the actor the key belongs to
+async fn authenticate(federation: &Federation, delivery: Delivery<'_>) -> Result<RemoteActor> {
+    let header = delivery.header("signature").ok_or(ApiError::Unauthorized)?;
+    let header = SignatureHeader::parse(header)?;
+
+    let sender = federation.fetch_actor(&header.key_id).await.map_err(|e| {
+        tracing::debug!(error = %e, key_id = %header.key_id, "Cannot fetch signing key");
+        ApiError::Unauthorized
+    })?;
+    if sender.key_id != header.key_id {
+        return Err(ApiError::Unauthorized);
+    }
+
+    signature::verify_post(
+        &header,
+        &sender.public_key_pem,
+        delivery.path,
+        |name| delivery.header(name),
+        delivery.body,
+    )?;
+    Ok(sender)
+}
+
+/// Answer a follow, in the background
+fn accept(federation: &Arc<Federation>, owner: &User, to: &RemoteActor, follow: &Value) {
+    let activity = json!({
+        "@context": "https://www.w3.org/ns/activitystreams",
+        "id": federation.activity_id(owner.id),
+        "type": "Accept",
+        "actor": federation.actor_uri(owner.id),
+        "object": follow,
+    });
+    let (federation, sender, inbox) = (Arc::clone(federation), owner.id, to.inbox.clone());
+
+    tokio::spawn(async move {
+        if let Err(e) = federation.deliver(sender, &inbox, &activity).await {
+            tracing::warn!(error = %e, %inbox, "Delivering Accept failed");
+        }
+    });
+}
+
+/// The ID of an object given inline or by reference
+fn id_of(value: &Value) -> Option<&str> {
+    value.as_str().or_else(|| value["id"].as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn objects_are_identified_inline_or_by_reference() {
+        assert_eq!(
+            id_of(&json!("https://a.example/u")),
+            Some("https://a.example/u")
+        );
+        assert_eq!(
+            id_of(&json!({ "id": "https://a.example/u", "type": "Person" })),
+            Some("https://a.example/u")
+        );
+        assert_eq!(id_of(&json!({ "type": "Person" })), None);
+        assert_eq!(id_of(&Value::Null), None);
+    }
+}
@@ -0,0 +1,505 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! ActivityPub federation
+//!
+//! Organizers (users at [`ORGANIZER_LEVEL`](crate::leveling::ORGANIZER_LEVEL))
+//! are published as `Person` actors, discoverable by WebFinger as
+//! `username@host`, and their visible events as ActivityStreams `Event`
+//! objects. Remote actors - a Mastodon account, a Mobilizon group - can
+//! follow an organizer and are sent a `Create` for each event and a
+//! `Delete` when one is taken down. Organizers can follow remote actors in
+//! turn, and the inbox handles `Follow`, `Undo` of a follow, and `Accept`.
+//!
+//! All deliveries are signed with HTTP signatures (see [`signature`]),
+//! and inbox requests must be signed by the actor they claim to be from.
+//! One instance key signs for every actor; each actor document publishes
+//! it as that actor's key. Remote actors are fetched only from public
+//! addresses (see [`crate::outbound`]), and their inboxes must be on the
+//! same server.
+//!
+//! Privacy: events federate with the same coarse location the API shows,
+//! and no attendee information leaves the server. Follower lists are not
+//! published, only their size.
+//!
+//! Federation is off unless a key file is configured.
+
+pub mod inbox;
+pub mod signature;
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use rsa::pkcs1::DecodeRsaPrivateKey;
+use rsa::pkcs8::{DecodePrivateKey, EncodePublicKey, LineEnding};
+use rsa::RsaPrivateKey;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use url::Url;
+use uuid::Uuid;
+
+use crate::db::{self, events::EventListing, models::User};
+use crate::error::{ApiError, Result};
+use crate::location;
+use crate::outbound;
+use crate::state::AppState;
+
+/// Media type of ActivityPub documents
+pub const ACTIVITY_JSON: &str = "application/activity+json";
+
+/// Addressing for public objects
+pub const PUBLIC: &str = "https://www.w3.org/ns/activitystreams#Public";
+
+const ACTIVITYSTREAMS: &str = "https://www.w3.org/ns/activitystreams";
+const SECURITY: &str = "https://w3id.org/security/v1";
+
+/// Remote requests give up after this long
+const REMOTE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Largest actor document we'll read
+const MAX_DOCUMENT_BYTES: usize = 256 * 1024;
+
+/// Federation configuration
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FederationConfig {
+    /// PEM RSA private key that signs for every actor; federation is
+    /// disabled if unset. Keep it off the database host.
+    #[serde(default)]
+    pub key_file: Option<PathBuf>,
+
+    /// Talk to remote servers over plain HTTP (development and tests only)
+    #[serde(default)]
+    pub allow_http: bool,
+
+    /// Talk to loopback and private addresses (development and tests only)
+    #[serde(default)]
+    pub allow_private_networks: bool,
+}
+
+/// A remote actor, as described by its actor document
+#[derive(Debug, Clone)]
+pub struct RemoteActor {
+    pub id: String,
+    pub inbox: String,
+    pub shared_inbox: Option<String>,
+    pub key_id: String,
+    pub public_key_pem: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ActorDocument {
+    id: String,
+    inbox: String,
+    #[serde(default)]
+    endpoints: Option<Endpoints>,
+    public_key: PublicKeyDocument,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Endpoints {
+    shared_inbox: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PublicKeyDocument {
+    id: String,
+    owner: String,
+    public_key_pem: String,
+}
+
+/// Signs, builds and delivers ActivityPub documents
+pub struct Federation {
+    key: RsaPrivateKey,
+    public_key_pem: String,
+    public_url: String,
+    allow_http: bool,
+    allow_private_networks: bool,
+    client: reqwest::Client,
+}
+
+impl std::fmt::Debug for Federation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Federation")
+            .field("public_url", &self.public_url)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Federation {
+    /// Federation as configured, or `None` if no key file is set
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key file can't be read or holds no RSA key.
+    pub fn from_config(config: &FederationConfig, public_url: &str) -> Result<Option<Self>> {
+        config
+            .key_file
+            .as_deref()
+            .map(|path| Self::new(load_key(path)?, public_url, config))
+            .transpose()
+    }
+
+    /// Federation signing with `key`, for actors under `public_url`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP client can't be built.
+    pub fn new(key: RsaPrivateKey, public_url: &str, config: &FederationConfig) -> Result<Self> {
+        let public_key_pem = key
+            .to_public_key()
+            .to_public_key_pem(LineEnding::LF)
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!("Cannot encode public key: {e}")))?;
+        let client = outbound::client_builder(config.allow_private_networks)
+            .timeout(REMOTE_TIMEOUT)
+            .user_agent(format!(
+                "CivicConnect/{} (+{public_url})",
+                env!("CARGO_PKG_VERSION")
+            ))
+            .build()
+            .map_err(|e| ApiError::Internal(e.into()))?;
+
+        Ok(Self {
+            key,
+            public_key_pem,
+            public_url: public_url.to_string(),
+            allow_http: config.allow_http,
+            allow_private_networks: config.allow_private_networks,
+            client,
+        })
+    }
+
+    /// Host part of the public URL, as used in `acct:` URIs
+    #[must_use]
+    pub fn host(&self) -> Option<String> {
+        let url = Url::parse(&self.public_url).ok()?;
+        let host = url.host_str()?;
+        Some(
+            url.port()
+                .map_or_else(|| host.to_string(), |port| format!("{host}:{port}")),
+        )
+    }
+
+    /// An organizer's actor URI
+    #[must_use]
+    pub fn actor_uri(&self, user_id: Uuid) -> String {
+        format!("{}/ap/actors/{user_id}", self.public_url)
+    }
+
+    /// An event's object URI
+    #[must_use]
+    pub fn event_uri(&self, event_id: Uuid) -> String {
+        format!("{}/ap/events/{event_id}", self.public_url)
+    }
+
+    /// A fresh activity ID under an actor
+    #[must_use]
+    pub fn activity_id(&self, user_id: Uuid) -> String {
+        format!("{}/activities/{}", self.actor_uri(user_id), Uuid::new_v4())
+    }
+
+    /// An organizer's actor document
+    #[must_use]
+    pub fn actor(&self, user: &User) -> Value {
+        let id = self.actor_uri(user.id);
+        json!({
+            "@context": [ACTIVITYSTREAMS, SECURITY],
+            "id": id,
+            "type": "Person",
+            "preferredUsername": user.username,
+            "name": user.username,
+            "url": format!("{}/users/{}", self.public_url, user.id),
+            "inbox": format!("{id}/inbox"),
+            "outbox": format!("{id}/outbox"),
+            "followers": format!("{id}/followers"),
+            "publicKey": {
+                "id": format!("{id}#main-key"),
+                "owner": id,
+                "publicKeyPem": self.public_key_pem,
+            },
+        })
+    }
+
+    /// An event as an ActivityStreams `Event`
+    #[must_use]
+    pub fn event(&self, listing: &EventListing) -> Value {
+        let event = &listing.event;
+        let actor = self.actor_uri(event.organizer_id);
+
+        let mut location = json!({
+            "type": "Place",
+            "name": event.venue.as_deref().unwrap_or("Approximate area"),
+        });
+        if let Some((lat, lng)) = location::cell_center(&event.location_hash) {
+            location["latitude"] = json!(lat);
+            location["longitude"] = json!(lng);
+        }
+
+        json!({
+            "@context": ACTIVITYSTREAMS,
+            "id": self.event_uri(event.id),
+            "type": "Event",
+            "name": event.title,
+            "content": event.description,
+            "startTime": event.start_time,
+            "endTime": event.end_time,
+            "location": location,
+            "attributedTo": actor,
+            "to": [PUBLIC],
+            "cc": [format!("{actor}/followers")],
+            "url": format!("{}/events/{}", self.public_url, event.id),
+            "published": event.created_at,
+            "updated": event.updated_at,
+            "tag": event.tags.iter().map(|tag| json!({
+                "type": "Hashtag",
+                "name": format!("#{tag}"),
+            })).collect::<Vec<_>>(),
+        })
+    }
+
+    /// `Create` activity announcing an event
+    #[must_use]
+    pub fn create(&self, listing: &EventListing) -> Value {
+        let mut object = self.event(listing);
+        if let Some(map) = object.as_object_mut() {
+            map.remove("@context");
+        }
+        let actor = self.actor_uri(listing.event.organizer_id);
+
+        json!({
+            "@context": ACTIVITYSTREAMS,
+            "id": format!("{}/create", self.event_uri(listing.event.id)),
+            "type": "Create",
+            "actor": actor,
+            "published": listing.event.created_at,
+            "to": [PUBLIC],
+            "cc": [format!("{actor}/followers")],
+            "object": object,
+        })
+    }
+
+    /// `Delete` activity retracting an event
+    #[must_use]
+    pub fn delete(&self, organizer_id: Uuid, event_id: Uuid) -> Value {
+        let actor = self.actor_uri(organizer_id);
+        json!({
+            "@context": ACTIVITYSTREAMS,
+            "id": self.activity_id(organizer_id),
+            "type": "Delete",
+            "actor": actor,
+            "to": [PUBLIC],
+            "cc": [format!("{actor}/followers")],
+            "object": {
+                "id": self.event_uri(event_id),
+                "type": "Tombstone",
+            },
+        })
+    }
+
+    /// Parse a remote URL, refusing schemes we don't talk to and private
+    /// addresses
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ApiError::InvalidInput`] for anything but `https` URLs
+    /// (or `http`, if allowed), or for a non-public IP address.
+    pub fn remote_url(&self, uri: &str) -> Result<Url> {
+        let url = Url::parse(uri).map_err(|_| ApiError::InvalidInput("Invalid URL".into()))?;
+        match url.scheme() {
+            "https" => {}
+            "http" if self.allow_http => {}
+            _ => return Err(ApiError::InvalidInput("Remote URLs must use HTTPS".into())),
+        }
+        if !self.allow_private_networks {
+            outbound::check_host(&url)?;
+        }
+        Ok(url)
+    }
+
+    /// Fetch and check a remote actor's document
+    ///
+    /// `uri` may be a key ID; any fragment is dropped. The actor's inboxes
+    /// must be on its own server, so it can't have us post elsewhere.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ApiError::InvalidInput`] for a URL we don't fetch, or
+    /// [`ApiError::FederationFailed`] if the document can't be fetched,
+    /// is too large or doesn't describe an actor with a key.
+    pub async fn fetch_actor(&self, uri: &str) -> Result<RemoteActor> {
+        let mut url = self.remote_url(uri)?;
+        url.set_fragment(None);
+
+        let response = self
+            .client
+            .get(url.clone())
+            .header(reqwest::header::ACCEPT, ACTIVITY_JSON)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| ApiError::FederationFailed(e.to_string()))?;
+        let body = read_limited(response, MAX_DOCUMENT_BYTES).await?;
+        let document: ActorDocument =
+            serde_json::from_slice(&body).map_err(|e| ApiError::FederationFailed(e.to_string()))?;
+
+        if document.id != url.as_str() || document.public_key.owner != document.id {
+            return Err(ApiError::FederationFailed(
+                "Actor document does not match its URL".into(),
+            ));
+        }
+        let inboxes = std::iter::once(&document.inbox).chain(
+            document
+                .endpoints
+                .as_ref()
+                .and_then(|e| e.shared_inbox.as_ref()),
+        );
+        for inbox in inboxes {
+            if self.remote_url(inbox)?.origin() != url.origin() {
+                return Err(ApiError::FederationFailed(
+                    "Actor inbox is on another server".into(),
+                ));
+            }
+        }
+
+        Ok(RemoteActor {
+            id: document.id,
+            inbox: document.inbox,
+            shared_inbox: document.endpoints.and_then(|e| e.shared_inbox),
+            key_id: document.public_key.id,
+            public_key_pem: document.public_key.public_key_pem,
+        })
+    }
+
+    /// Sign and deliver an activity from one of our actors
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ApiError::FederationFailed`] if the inbox can't be reached
+    /// or refuses the activity.
+    pub async fn deliver(&self, sender: Uuid, inbox: &str, activity: &Value) -> Result<()> {
+        let url = self.remote_url(inbox)?;
+        let host = &url[url::Position::BeforeHost..url::Position::AfterPort];
+        let path = &url[url::Position::BeforePath..url::Position::AfterQuery];
+        let body = serde_json::to_vec(activity).map_err(|e| ApiError::Internal(e.into()))?;
+
+        let key_id = format!("{}#main-key", self.actor_uri(sender));
+        let signed = signature::sign_post(&self.key, &key_id, host, path, &body);
+
+        self.client
+            .post(url.clone())
+            .header(reqwest::header::CONTENT_TYPE, ACTIVITY_JSON)
+            .header(reqwest::header::DATE, signed.date)
+            .header("Digest", signed.digest)
+            .header("Signature", signed.signature)
+            .body(body)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| ApiError::FederationFailed(e.to_string()))?;
+
+        Ok(())
+    }
+}
+
+/// Read a response body, giving up once it passes `limit` bytes
+async fn read_limited(mut response: reqwest::Response, limit: usize) -> Result<Vec<u8>> {
+    let too_large = || ApiError::FederationFailed("Remote document is too large".into());
+    if response
+        .content_length()
+        .is_some_and(|length| length > limit as u64)
+    {
+        return Err(too_large());
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| ApiError::FederationFailed(e.to_string()))?
+    {
+        if body.len() + chunk.len() > limit {
+            return Err(too_large());
+        }
+        body.extend_from_slice(&chunk);
+    }
+
+    Ok(body)
+}
+
+/// Load a PKCS#8 or PKCS#1 PEM private key
+fn load_key(path: &Path) -> Result<RsaPrivateKey> {
+    let pem = std::fs::read_to_string(path)
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Cannot read federation key file: {e}")))?;
+
+    RsaPrivateKey::from_pkcs8_pem(&pem)
+        .or_else(|_| RsaPrivateKey::from_pkcs1_pem(&pem))
+        .map_err(|_| {
+            ApiError::Internal(anyhow::anyhow!(
+                "Federation key file must hold a PEM RSA private key"
+            ))
+        })
+}
+
+/// Deliver an activity to all of an organizer's followers, in the background
+pub fn deliver_to_followers(state: &AppState, sender: Uuid, activity: Value) {
+    let Some(federation) = state.federation.clone() else {
+        return;
+    };
+    let db = state.db.clone();
+
+    tokio::spawn(async move {
+        let inboxes = match db::federation::follower_inboxes(&db, sender).await {
+            Ok(inboxes) => inboxes,
+            Err(e) => {
+                tracing::error!(error = ?e, "Loading follower inboxes failed");
+                return;
+            }
+        };
+        for inbox in inboxes {
+            if let Err(e) = federation.deliver(sender, &inbox, &activity).await {
+                tracing::warn!(error = %e, %inbox, "ActivityPub delivery failed");
+            }
+        }
+    });
+}
+
+/// Send a visible event to its organizer's followers
+///
+/// Does nothing if federation is off, the event is hidden or its organizer
+/// isn't published as an actor.
+///
+/// # Errors
+///
+/// Returns an error if a query fails.
+pub async fn publish_event(state: &AppState, event_id: Uuid) -> Result<()> {
+    let Some(federation) = &state.federation else {
+        return Ok(());
+    };
+    let Some(listing) = db::events::find_listing(&state.db, event_id).await? else {
+        return Ok(());
+    };
+    if db::federation::find_actor(&state.db, listing.event.organizer_id)
+        .await?
+        .is_none()
+    {
+        return Ok(());
+    }
+
+    let activity = federation.create(&listing);
+    deliver_to_followers(state, listing.event.organizer_id, activity);
+    Ok(())
+}
+
+/// Tell an organizer's followers an event is gone
+///
+/// Does nothing if federation is off.
+pub fn retract_event(state: &AppState, organizer_id: Uuid, event_id: Uuid) {
+    let Some(federation) = &state.federation else {
+        return;
+    };
+
+    let activity = federation.delete(organizer_id, event_id);
+    deliver_to_followers(state, organizer_id, activity);
+}
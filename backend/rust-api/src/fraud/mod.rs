@@ -0,0 +1,260 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Fraud screening for attendance verification
+//!
+//! Every verification that passes the signature, time and location checks
+//! is scored by a [`FraudEngine`] before it is recorded. The engine runs a
+//! set of [`FraudRule`]s, each of which may report a [`Finding`] with a
+//! score; scores add up, and a total at or above the hold threshold means
+//! the verification is recorded as held for review and awards no XP.
+//!
+//! Rules are pluggable: [`FraudEngine::standard`] installs the built-in
+//! heuristics from [`rules`], and deployments can add their own with
+//! [`FraudEngine::with_rule`].
+//!
+//! Privacy: findings are stored with the verification and end up in front
+//! of moderators, so they describe patterns (distances, counts), never
+//! other users' identities or locations.
+
+pub mod rules;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::Result;
+
+/// Highest total score; rule scores are added and capped here
+pub const MAX_SCORE: u16 = 100;
+
+/// A verification about to be recorded
+#[derive(Debug, Clone)]
+pub struct Attempt {
+    pub user_id: Uuid,
+    pub event_id: Uuid,
+    pub organizer_id: Uuid,
+    /// H3 cell the attendee reported
+    pub location_cell: String,
+    pub at: DateTime<Utc>,
+    /// Whether this is a check-in from an organizer's signed manifest,
+    /// where a whole queue of attendees checks in at once
+    pub attested: bool,
+}
+
+/// A rule's reason for suspicion
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Finding {
+    /// Name of the rule that fired
+    pub rule: String,
+    /// Contribution to the total score
+    pub score: u16,
+    /// Human-readable explanation for reviewers
+    pub detail: String,
+}
+
+/// A fraud heuristic
+#[async_trait]
+pub trait FraudRule: Send + Sync {
+    /// Stable identifier, recorded with findings
+    fn name(&self) -> &'static str;
+
+    /// Inspect an attempt; `None` means nothing suspicious
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a query fails.
+    async fn evaluate(&self, db: &PgPool, attempt: &Attempt) -> Result<Option<Finding>>;
+}
+
+/// Outcome of screening one attempt
+#[derive(Debug, Clone, Default)]
+pub struct Assessment {
+    /// Sum of finding scores, capped at [`MAX_SCORE`]
+    pub score: u16,
+    pub findings: Vec<Finding>,
+    /// Whether the score reached the hold threshold
+    pub held: bool,
+}
+
+/// Fraud screening configuration
+#[derive(Debug, Clone, Deserialize)]
+pub struct FraudConfig {
+    /// Total score at which a verification is held for review
+    #[serde(default = "default_hold_threshold")]
+    pub hold_threshold: u16,
+
+    /// Fastest plausible travel between verifications, in km/h
+    #[serde(default = "default_max_travel_kmh")]
+    pub max_travel_kmh: f64,
+
+    /// Past events two accounts must have verified at together to count
+    /// as verifying in lockstep
+    #[serde(default = "default_cluster_min_shared_events")]
+    pub cluster_min_shared_events: i64,
+
+    /// Lockstep accounts, already verified at this event, that make a
+    /// cluster
+    #[serde(default = "default_cluster_min_accounts")]
+    pub cluster_min_accounts: i64,
+
+    /// Verifications per minute at one event beyond which attendance is
+    /// implausible
+    #[serde(default = "default_max_scans_per_minute")]
+    pub max_scans_per_minute: i64,
+}
+
+impl Default for FraudConfig {
+    fn default() -> Self {
+        Self {
+            hold_threshold: default_hold_threshold(),
+            max_travel_kmh: default_max_travel_kmh(),
+            cluster_min_shared_events: default_cluster_min_shared_events(),
+            cluster_min_accounts: default_cluster_min_accounts(),
+            max_scans_per_minute: default_max_scans_per_minute(),
+        }
+    }
+}
+
+const fn default_hold_threshold() -> u16 {
+    50
+}
+
+const fn default_max_travel_kmh() -> f64 {
+    200.0 // Faster than any road or rail trip between events
+}
+
+const fn default_cluster_min_shared_events() -> i64 {
+    3
+}
+
+const fn default_cluster_min_accounts() -> i64 {
+    3
+}
+
+const fn default_max_scans_per_minute() -> i64 {
+    30
+}
+
+/// Runs fraud rules and decides whether to hold a verification
+#[derive(Clone)]
+pub struct FraudEngine {
+    rules: Vec<Arc<dyn FraudRule>>,
+    hold_threshold: u16,
+}
+
+impl std::fmt::Debug for FraudEngine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FraudEngine")
+            .field(
+                "rules",
+                &self.rules.iter().map(|r| r.name()).collect::<Vec<_>>(),
+            )
+            .field("hold_threshold", &self.hold_threshold)
+            .finish()
+    }
+}
+
+impl FraudEngine {
+    /// An engine with no rules
+    #[must_use]
+    pub fn new(hold_threshold: u16) -> Self {
+        Self {
+            rules: Vec::new(),
+            hold_threshold,
+        }
+    }
+
+    /// An engine with the built-in rules
+    #[must_use]
+    pub fn standard(config: &FraudConfig) -> Self {
+        Self::new(config.hold_threshold)
+            .with_rule(rules::ImpossibleTravel {
+                max_speed_kmh: config.max_travel_kmh,
+            })
+            .with_rule(rules::AccountCluster {
+                min_shared_events: config.cluster_min_shared_events,
+                min_accounts: config.cluster_min_accounts,
+            })
+            .with_rule(rules::ImplausibleAttendance {
+                max_scans_per_minute: config.max_scans_per_minute,
+            })
+    }
+
+    /// Add a rule
+    #[must_use]
+    pub fn with_rule(mut self, rule: impl FraudRule + 'static) -> Self {
+        self.rules.push(Arc::new(rule));
+        self
+    }
+
+    /// Score an attempt against every rule
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a rule fails; attempts are never waved through
+    /// unscreened.
+    pub async fn assess(&self, db: &PgPool, attempt: &Attempt) -> Result<Assessment> {
+        let mut findings = Vec::new();
+        for rule in &self.rules {
+            if let Some(finding) = rule.evaluate(db, attempt).await? {
+                findings.push(finding);
+            }
+        }
+
+        Ok(self.decide(findings))
+    }
+
+    /// Combine findings into an assessment
+    fn decide(&self, findings: Vec<Finding>) -> Assessment {
+        let score = findings
+            .iter()
+            .fold(0u16, |total, f| total.saturating_add(f.score))
+            .min(MAX_SCORE);
+
+        Assessment {
+            score,
+            held: !findings.is_empty() && score >= self.hold_threshold,
+            findings,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finding(score: u16) -> Finding {
+        Finding {
+            rule: "test".into(),
+            score,
+            detail: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_scores_add_up_to_threshold() {
+        let engine = FraudEngine::new(50);
+
+        assert!(!engine.decide(vec![]).held);
+        assert!(!engine.decide(vec![finding(30)]).held);
+
+        let combined = engine.decide(vec![finding(30), finding(30)]);
+        assert!(combined.held);
+        assert_eq!(combined.score, 60);
+
+        let capped = engine.decide(vec![finding(100), finding(100)]);
+        assert_eq!(capped.score, MAX_SCORE);
+    }
+
+    #[test]
+    fn test_zero_threshold_needs_a_finding() {
+        let engine = FraudEngine::new(0);
+        assert!(!engine.decide(vec![]).held);
+        assert!(engine.decide(vec![finding(1)]).held);
+    }
+}
@@ -0,0 +1,250 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Built-in fraud rules
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+use super::{Attempt, Finding, FraudRule};
+use crate::error::Result;
+use crate::location;
+
+/// Distance allowed for free, covering the size of two resolution 7 cells
+/// (roughly 1.2 km edge each) and rounding at their centres
+const CELL_SLACK_KM: f64 = 10.0;
+
+/// How close in time two verifications at one event must be to count as
+/// made together
+const LOCKSTEP_SECONDS: i32 = 120;
+
+/// How far back account clustering looks
+const CLUSTER_WINDOW_DAYS: i32 = 30;
+
+/// Attendee verified at two places further apart than they could travel
+#[derive(Debug, Clone)]
+pub struct ImpossibleTravel {
+    pub max_speed_kmh: f64,
+}
+
+/// Speed needed to get between two cells in the given time, in km/h
+///
+/// Returns `None` if either cell is invalid. Simultaneous verifications
+/// at distinct places yield infinity.
+#[must_use]
+pub fn implied_speed_kmh(
+    from_cell: &str,
+    from_at: DateTime<Utc>,
+    to_cell: &str,
+    to_at: DateTime<Utc>,
+) -> Option<f64> {
+    let distance =
+        (location::approximate_distance_km(from_cell, to_cell)? - CELL_SLACK_KM).max(0.0);
+    if distance == 0.0 {
+        return Some(0.0);
+    }
+
+    #[allow(clippy::cast_precision_loss)] // Seconds between verifications
+    let hours = (to_at - from_at).num_seconds().abs() as f64 / 3600.0;
+
+    Some(if hours == 0.0 {
+        f64::INFINITY
+    } else {
+        distance / hours
+    })
+}
+
+#[async_trait]
+impl FraudRule for ImpossibleTravel {
+    fn name(&self) -> &'static str {
+        "impossible_travel"
+    }
+
+    async fn evaluate(&self, db: &PgPool, attempt: &Attempt) -> Result<Option<Finding>> {
+        let previous: Option<(String, DateTime<Utc>)> = sqlx::query_as(
+            "SELECT location_hash, verified_at FROM verifications
+             WHERE user_id = $1
+             ORDER BY verified_at DESC
+             LIMIT 1",
+        )
+        .bind(attempt.user_id)
+        .fetch_optional(db)
+        .await?;
+
+        let Some((cell, at)) = previous else {
+            return Ok(None);
+        };
+
+        let Some(speed) = implied_speed_kmh(&cell, at, &attempt.location_cell, attempt.at) else {
+            return Ok(None);
+        };
+
+        Ok((speed > self.max_speed_kmh).then(|| Finding {
+            rule: self.name().into(),
+            score: 100,
+            detail: if speed.is_finite() {
+                format!("Previous verification implies travel at {speed:.0} km/h")
+            } else {
+                "Verified at another place at the same moment".into()
+            },
+        }))
+    }
+}
+
+/// Attendee belongs to a group of accounts that keep verifying together
+///
+/// One person scanning a stack of phones, or a ring of accounts farming
+/// XP, shows up as the same accounts verifying within moments of each
+/// other event after event.
+#[derive(Debug, Clone)]
+pub struct AccountCluster {
+    /// Past events an account must share in lockstep to be a partner
+    pub min_shared_events: i64,
+    /// Partners already verified at this event that make a cluster
+    pub min_accounts: i64,
+}
+
+#[async_trait]
+impl FraudRule for AccountCluster {
+    fn name(&self) -> &'static str {
+        "account_cluster"
+    }
+
+    async fn evaluate(&self, db: &PgPool, attempt: &Attempt) -> Result<Option<Finding>> {
+        // Check-ins are collected a queue at a time, so attendees who come
+        // together are uploaded in lockstep
+        if attempt.attested {
+            return Ok(None);
+        }
+
+        let (partners,): (i64,) = sqlx::query_as(
+            "WITH partners AS (
+                 SELECT other.user_id
+                 FROM verifications mine
+                 JOIN verifications other
+                   ON other.event_id = mine.event_id
+                  AND other.user_id <> mine.user_id
+                  AND other.verified_at BETWEEN mine.verified_at - make_interval(secs => $4)
+                                            AND mine.verified_at + make_interval(secs => $4)
+                 WHERE mine.user_id = $1
+                   AND mine.verified_at > $3 - make_interval(days => $5)
+                 GROUP BY other.user_id
+                 HAVING count(DISTINCT mine.event_id) >= $6
+             )
+             SELECT count(*) FROM verifications v
+             JOIN partners p ON p.user_id = v.user_id
+             WHERE v.event_id = $2
+               AND v.verified_at > $3 - make_interval(secs => $4)",
+        )
+        .bind(attempt.user_id)
+        .bind(attempt.event_id)
+        .bind(attempt.at)
+        .bind(f64::from(LOCKSTEP_SECONDS))
+        .bind(CLUSTER_WINDOW_DAYS)
+        .bind(self.min_shared_events)
+        .fetch_one(db)
+        .await?;
+
+        Ok((partners >= self.min_accounts).then(|| Finding {
+            rule: self.name().into(),
+            score: 50,
+            detail: format!(
+                "{partners} accounts that repeatedly verify in lockstep with this one \
+                 verified here within {LOCKSTEP_SECONDS} seconds"
+            ),
+        }))
+    }
+}
+
+/// Organizer is minting more attendance than the event could have
+#[derive(Debug, Clone)]
+pub struct ImplausibleAttendance {
+    /// Verifications per minute at one event beyond which scans are
+    /// implausible
+    pub max_scans_per_minute: i64,
+}
+
+#[async_trait]
+impl FraudRule for ImplausibleAttendance {
+    fn name(&self) -> &'static str {
+        "implausible_attendance"
+    }
+
+    async fn evaluate(&self, db: &PgPool, attempt: &Attempt) -> Result<Option<Finding>> {
+        if attempt.user_id == attempt.organizer_id {
+            return Ok(Some(Finding {
+                rule: self.name().into(),
+                score: 100,
+                detail: "Organizer verified their own attendance".into(),
+            }));
+        }
+
+        let (capacity, total, last_minute): (Option<i32>, i64, i64) = sqlx::query_as(
+            "SELECT e.capacity,
+                    count(v.id),
+                    count(v.id) FILTER (WHERE v.verified_at > $2 - interval '1 minute')
+             FROM events e
+             LEFT JOIN verifications v ON v.event_id = e.id
+             WHERE e.id = $1
+             GROUP BY e.id",
+        )
+        .bind(attempt.event_id)
+        .bind(attempt.at)
+        .fetch_optional(db)
+        .await?
+        .unwrap_or((None, 0, 0));
+
+        if capacity.is_some_and(|capacity| total >= i64::from(capacity)) {
+            return Ok(Some(Finding {
+                rule: self.name().into(),
+                score: 60,
+                detail: format!("Event is already at capacity ({total} verified)"),
+            }));
+        }
+
+        // A manifest's check-ins arrive as fast as the organizer's device
+        // collected them; each carries its attendee's own signature instead
+        let too_fast = !attempt.attested && last_minute >= self.max_scans_per_minute;
+        Ok(too_fast.then(|| Finding {
+            rule: self.name().into(),
+            score: 60,
+            detail: format!("{last_minute} verifications at this event in the last minute"),
+        }))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    /// Resolution 7 cells in London and Paris, about 340 km apart
+    const LONDON: &str = "87195da49ffffff";
+    const PARIS: &str = "871fb4662ffffff";
+
+    #[test]
+    fn test_implied_speed() {
+        let at = Utc::now();
+
+        // Same cell is never travel
+        assert_eq!(implied_speed_kmh(LONDON, at, LONDON, at), Some(0.0));
+
+        let one_hour = implied_speed_kmh(LONDON, at, PARIS, at + Duration::hours(1)).unwrap();
+        assert!((250.0..400.0).contains(&one_hour), "{one_hour}");
+
+        let one_day = implied_speed_kmh(LONDON, at, PARIS, at + Duration::days(1)).unwrap();
+        assert!(one_day < 20.0);
+
+        // Order doesn't matter
+        let backwards = implied_speed_kmh(PARIS, at + Duration::hours(1), LONDON, at).unwrap();
+        assert!((backwards - one_hour).abs() < f64::EPSILON);
+
+        assert_eq!(
+            implied_speed_kmh(LONDON, at, PARIS, at),
+            Some(f64::INFINITY)
+        );
+        assert_eq!(implied_speed_kmh("bogus", at, PARIS, at), None);
+    }
+}
@@ -0,0 +1,406 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Safe retries of POST and PUT requests
+//!
+//! A client on a flaky connection sends an `Idempotency-Key` header (a
+//! fresh random value, such as a UUID, per logical request) and repeats
+//! it on every retry. The first request with a key runs and its response
+//! is kept for [`IdempotencyConfig::ttl_secs`]; retries get that response
+//! again, marked `Idempotent-Replayed: true`, instead of creating a
+//! duplicate or failing with `ALREADY_VERIFIED`. Reusing a key with a
+//! different method, path or body fails with 422, and retrying while the
+//! first request is still running fails with 409.
+//!
+//! Keys are scoped to the `Authorization` header, so one account can't
+//! replay another's responses. Server errors and rate limiting aren't
+//! kept, so a retry after one runs afresh.
+//!
+//! Stored responses can hold session tokens and secrets shown once, so
+//! neither the key nor the response is stored in the clear: rows are
+//! found by a hash of scope and key, and responses are encrypted under a
+//! key derived from the same (XChaCha20-Poly1305). Only a client holding
+//! the key can have its response decrypted.
+
+use std::time::Duration;
+
+use axum::{
+    body::{to_bytes, Body, Bytes},
+    extract::{Request, State},
+    http::{header, request::Parts, HeaderName, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use tracing::Instrument;
+
+use crate::db;
+use crate::error::{ApiError, Result};
+use crate::state::AppState;
+
+/// Request header carrying the client's key
+pub const IDEMPOTENCY_KEY: HeaderName = HeaderName::from_static("idempotency-key");
+
+/// Response header marking a replayed response
+pub const IDEMPOTENT_REPLAYED: HeaderName = HeaderName::from_static("idempotent-replayed");
+
+/// Longest key accepted
+const MAX_KEY_LEN: usize = 255;
+
+/// `XChaCha20` nonce length in bytes
+const NONCE_LEN: usize = 24;
+
+/// Idempotency key retention
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdempotencyConfig {
+    /// Seconds a response is kept for replay
+    #[serde(default = "default_ttl_secs")]
+    pub ttl_secs: u64,
+
+    /// Seconds between purges of expired keys
+    #[serde(default = "default_purge_interval_secs")]
+    pub purge_interval_secs: u64,
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        Self {
+            ttl_secs: default_ttl_secs(),
+            purge_interval_secs: default_purge_interval_secs(),
+        }
+    }
+}
+
+const fn default_ttl_secs() -> u64 {
+    24 * 60 * 60
+}
+
+const fn default_purge_interval_secs() -> u64 {
+    60 * 60
+}
+
+/// Replay or run a POST or PUT carrying an `Idempotency-Key`
+///
+/// Other requests pass straight through.
+pub async fn idempotent(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    if !matches!(*request.method(), Method::POST | Method::PUT) {
+        return next.run(request).await;
+    }
+    let Some(key) = request.headers().get(&IDEMPOTENCY_KEY).cloned() else {
+        return next.run(request).await;
+    };
+
+    run_once(state, &key, request, next)
+        .await
+        .unwrap_or_else(IntoResponse::into_response)
+}
+
+async fn run_once(
+    state: AppState,
+    key: &HeaderValue,
+    request: Request,
+    next: Next,
+) -> Result<Response> {
+    let key = key
+        .to_str()
+        .ok()
+        .filter(|key| !key.is_empty() && key.len() <= MAX_KEY_LEN)
+        .ok_or_else(|| {
+            ApiError::InvalidInput(format!(
+                "Idempotency-Key must be 1 to {MAX_KEY_LEN} visible ASCII characters"
+            ))
+        })?;
+
+    let (parts, body) = request.into_parts();
+    let body = to_bytes(body, state.config.server.max_body_bytes)
+        .await
+        .map_err(|_| ApiError::PayloadTooLarge)?;
+    let secrets = KeySecrets::derive(&parts, key);
+    let request_hash = secrets.request_hash(&parts, &body);
+
+    let limits = &state.config.server;
+    // Past the longest time limit, a claim without a response was left by
+    // a replica that died mid-request
+    let abandon_secs = 2 * limits.request_timeout_secs.max(limits.hashing_timeout_secs);
+    #[allow(clippy::cast_precision_loss)]
+    let claimed = db::idempotency::claim(
+        &state.db,
+        &secrets.lookup,
+        &request_hash,
+        state.config.idempotency.ttl_secs as f64,
+        abandon_secs as f64,
+    )
+    .await?;
+
+    if !claimed {
+        return match db::idempotency::find(&state.db, &secrets.lookup).await? {
+            Some(record) if record.request_hash != request_hash => {
+                Err(ApiError::IdempotencyKeyReused)
+            }
+            Some(db::idempotency::IdempotencyRecord {
+                status: Some(status),
+                response: Some(response),
+                ..
+            }) => secrets.replay(status, &response),
+            _ => Err(ApiError::IdempotencyKeyInProgress),
+        };
+    }
+
+    // Run to completion even if the client hangs up, so that its retry
+    // finds the outcome instead of a claim that's never settled
+    let request = Request::from_parts(parts, Body::from(body));
+    let db = state.db.clone();
+    let task = tokio::spawn(
+        async move {
+            let response = next.run(request).await;
+            secrets.settle(&db, response).await
+        }
+        .instrument(tracing::Span::current()),
+    );
+
+    task.await
+        .map_err(|e| ApiError::Internal(anyhow::anyhow!("Idempotent request failed: {e}")))
+}
+
+/// Response headers and body, as encrypted for storage
+#[derive(Serialize, Deserialize)]
+struct StoredResponse {
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+/// Values derived from the caller's scope and key
+struct KeySecrets {
+    /// Row key
+    lookup: String,
+    /// Key for request hashes and response encryption
+    secret: [u8; 32],
+}
+
+impl KeySecrets {
+    fn derive(parts: &Parts, key: &str) -> Self {
+        let scope = Sha256::digest(
+            parts
+                .headers
+                .get(header::AUTHORIZATION)
+                .map_or(&b""[..], HeaderValue::as_bytes),
+        );
+        let derive = |purpose: &[u8]| -> [u8; 32] {
+            Sha256::new()
+                .chain_update(b"civicconnect idempotency ")
+                .chain_update(purpose)
+                .chain_update([0])
+                .chain_update(scope)
+                .chain_update(key.as_bytes())
+                .finalize()
+                .into()
+        };
+
+        Self {
+            lookup: hex::encode(derive(b"lookup")),
+            secret: derive(b"secret"),
+        }
+    }
+
+    /// Keyed hash identifying the request a key was first used for
+    fn request_hash(&self, parts: &Parts, body: &[u8]) -> String {
+        hex::encode(
+            Sha256::new()
+                .chain_update(self.secret)
+                .chain_update(parts.method.as_str())
+                .chain_update([0])
+                .chain_update(parts.uri.to_string())
+                .chain_update([0])
+                .chain_update(body)
+                .finalize(),
+        )
+    }
+
+    fn cipher(&self) -> XChaCha20Poly1305 {
+        XChaCha20Poly1305::new((&self.secret).into())
+    }
+
+    /// Store the response, or give up the claim if it shouldn't be kept
+    async fn settle(self, db: &PgPool, response: Response) -> Response {
+        let (parts, body) = response.into_parts();
+        let body = match to_bytes(body, usize::MAX).await {
+            Ok(body) => body,
+            Err(e) => {
+                self.release(db).await;
+                return ApiError::Internal(anyhow::anyhow!("Response body failed: {e}"))
+                    .into_response();
+            }
+        };
+
+        let transient =
+            parts.status.is_server_error() || parts.status == StatusCode::TOO_MANY_REQUESTS;
+        match self.seal(&parts.headers, &body) {
+            Some(sealed) if !transient => {
+                let status = i16::try_from(parts.status.as_u16()).unwrap_or(i16::MAX);
+                if let Err(e) = db::idempotency::complete(db, &self.lookup, status, &sealed).await {
+                    tracing::error!(error = ?e, "Storing idempotent response failed");
+                }
+            }
+            _ => self.release(db).await,
+        }
+
+        Response::from_parts(parts, Body::from(body))
+    }
+
+    async fn release(&self, db: &PgPool) {
+        if let Err(e) = db::idempotency::release(db, &self.lookup).await {
+            tracing::error!(error = ?e, "Releasing idempotency key failed");
+        }
+    }
+
+    /// `nonce || ciphertext` of the response, or `None` if its body isn't
+    /// text
+    fn seal(&self, headers: &axum::http::HeaderMap, body: &Bytes) -> Option<Vec<u8>> {
+        let stored = StoredResponse {
+            headers: headers
+                .iter()
+                .filter_map(|(name, value)| {
+                    Some((name.as_str().to_string(), value.to_str().ok()?.to_string()))
+                })
+                .collect(),
+            body: String::from_utf8(body.to_vec()).ok()?,
+        };
+        let plaintext = serde_json::to_vec(&stored).ok()?;
+
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher()
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: &plaintext,
+                    aad: self.lookup.as_bytes(),
+                },
+            )
+            .ok()?;
+
+        let mut out = nonce.to_vec();
+        out.extend_from_slice(&ciphertext);
+        Some(out)
+    }
+
+    /// The stored response, marked as a replay
+    fn replay(&self, status: i16, sealed: &[u8]) -> Result<Response> {
+        let corrupt =
+            || ApiError::Internal(anyhow::anyhow!("Stored idempotent response is corrupt"));
+        if sealed.len() < NONCE_LEN {
+            return Err(corrupt());
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher()
+            .decrypt(
+                XNonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: self.lookup.as_bytes(),
+                },
+            )
+            .map_err(|_| corrupt())?;
+        let stored: StoredResponse = serde_json::from_slice(&plaintext).map_err(|_| corrupt())?;
+
+        let status = u16::try_from(status)
+            .ok()
+            .and_then(|status| StatusCode::from_u16(status).ok())
+            .ok_or_else(corrupt)?;
+        let mut response = Response::builder().status(status);
+        for (name, value) in &stored.headers {
+            response = response.header(name, value);
+        }
+        response
+            .header(IDEMPOTENT_REPLAYED, "true")
+            .body(Body::from(stored.body))
+            .map_err(|_| corrupt())
+    }
+}
+
+/// Delete expired keys every `interval`
+pub async fn run_purge(db: PgPool, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+
+    loop {
+        ticker.tick().await;
+        match db::idempotency::purge_expired(&db).await {
+            Ok(0) => {}
+            Ok(count) => tracing::debug!(count, "Purged expired idempotency keys"),
+            Err(e) => tracing::error!(error = ?e, "Idempotency key purge failed"),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn parts(authorization: Option<&str>) -> Parts {
+        let mut request = Request::post("/api/v1/verify/scan");
+        if let Some(authorization) = authorization {
+            request = request.header(header::AUTHORIZATION, authorization);
+        }
+        request.body(()).unwrap().into_parts().0
+    }
+
+    #[test]
+    fn keys_are_scoped_to_the_caller() {
+        let alice = KeySecrets::derive(&parts(Some("Bearer alice")), "key-1");
+        let bob = KeySecrets::derive(&parts(Some("Bearer bob")), "key-1");
+        let anonymous = KeySecrets::derive(&parts(None), "key-1");
+        assert_ne!(alice.lookup, bob.lookup);
+        assert_ne!(alice.lookup, anonymous.lookup);
+        assert_eq!(
+            alice.lookup,
+            KeySecrets::derive(&parts(Some("Bearer alice")), "key-1").lookup
+        );
+        assert!(!alice.lookup.contains("key-1"));
+    }
+
+    #[test]
+    fn responses_open_only_with_the_same_key() {
+        let secrets = KeySecrets::derive(&parts(None), "key-1");
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        );
+        let sealed = secrets
+            .seal(&headers, &Bytes::from_static(br#"{"token":"secret"}"#))
+            .unwrap();
+        assert!(!sealed.windows(6).any(|window| window == b"secret"));
+
+        let replayed = secrets.replay(201, &sealed).unwrap();
+        assert_eq!(replayed.status(), StatusCode::CREATED);
+        assert_eq!(replayed.headers()[header::CONTENT_TYPE], "application/json");
+        assert_eq!(replayed.headers()[IDEMPOTENT_REPLAYED], "true");
+
+        let other = KeySecrets::derive(&parts(None), "key-2");
+        assert!(other.replay(201, &sealed).is_err());
+    }
+
+    #[test]
+    fn request_hashes_cover_method_path_and_body() {
+        let secrets = KeySecrets::derive(&parts(None), "key-1");
+        let scan = parts(None);
+        let hash = secrets.request_hash(&scan, b"{}");
+        assert_eq!(hash, secrets.request_hash(&scan, b"{}"));
+        assert_ne!(hash, secrets.request_hash(&scan, b"{ }"));
+
+        let checkins = Request::post("/api/v1/verify/checkins")
+            .body(())
+            .unwrap()
+            .into_parts()
+            .0;
+        assert_ne!(hash, secrets.request_hash(&checkins, b"{}"));
+    }
+}
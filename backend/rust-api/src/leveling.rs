@@ -0,0 +1,39 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Level thresholds (mirrors `Civicconnect.Leveling` in the Ada core)
+//!
+//! XP alone never promotes: higher levels also need events attended,
+//! events organized, endorsements and mentees. XP does cap the level,
+//! though, so losing XP can demote.
+
+/// XP needed for each level, 0 to 5
+pub const LEVEL_THRESHOLDS: [i32; 6] = [0, 100, 500, 1_500, 4_000, 10_000];
+
+/// Level at which users organize events (and may be made moderators)
+pub const ORGANIZER_LEVEL: i16 = 3;
+
+/// Highest level `xp` is enough for
+#[must_use]
+pub fn max_level_for(xp: i32) -> i16 {
+    (1..)
+        .zip(&LEVEL_THRESHOLDS[1..])
+        .take_while(|(_, threshold)| xp >= **threshold)
+        .last()
+        .map_or(0, |(level, _)| level)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_max_level_for() {
+        assert_eq!(max_level_for(0), 0);
+        assert_eq!(max_level_for(99), 0);
+        assert_eq!(max_level_for(100), 1);
+        assert_eq!(max_level_for(1_499), 2);
+        assert_eq!(max_level_for(1_500), 3);
+        assert_eq!(max_level_for(1_000_000), 5);
+    }
+}
@@ -1,15 +1,36 @@
 // SPDX-License-Identifier: MPL-2.0
 // SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
 
-//! CivicConnect API Library
+//! `CivicConnect` API Library
 //!
-//! Core types and functionality for the CivicConnect REST API.
+//! Core types and functionality for the `CivicConnect` REST API.
 
 pub mod api;
+pub mod audit;
+pub mod calendar;
+pub mod config;
+pub mod credentials;
 pub mod crypto;
 pub mod db;
+pub mod duress;
 pub mod error;
+pub mod federation;
+pub mod fraud;
+pub mod idempotency;
+pub mod leveling;
 pub mod location;
+pub mod mail;
+pub mod metrics;
+pub mod organizations;
+pub mod outbound;
+pub mod realtime;
+pub mod retention;
+pub mod routes;
+pub mod server;
+pub mod state;
+pub mod telemetry;
+pub mod verification;
+pub mod webhooks;
 
 /// Re-export commonly used types
 pub use error::{ApiError, Result};
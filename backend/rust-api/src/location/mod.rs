@@ -11,13 +11,14 @@
 //! H3 resolution 7 = ~5km hexagon diameter
 //! Good balance of privacy vs. discovery usefulness
 
-use h3o::{CellIndex, Resolution};
+use h3o::{CellIndex, LatLng, Resolution};
 
 /// H3 resolution for location storage
 /// Resolution 7 = approximately 5km hexagon diameter
 pub const LOCATION_RESOLUTION: Resolution = Resolution::Seven;
 
 /// Validate H3 cell ID format
+#[must_use]
 pub fn is_valid_cell(cell_str: &str) -> bool {
     if cell_str.len() != 15 {
         return false;
@@ -30,10 +31,10 @@ pub fn is_valid_cell(cell_str: &str) -> bool {
 /// Ring 0 = just the cell itself
 /// Ring 1 = cell + 6 immediate neighbors
 /// Ring 2 = cell + 6 neighbors + 12 outer neighbors
+#[must_use]
 pub fn get_neighbors(cell_str: &str, rings: u32) -> Vec<String> {
-    let cell = match cell_str.parse::<CellIndex>() {
-        Ok(c) => c,
-        Err(_) => return vec![cell_str.to_string()],
+    let Ok(cell) = cell_str.parse::<CellIndex>() else {
+        return vec![cell_str.to_string()];
     };
 
     // Collect cells within the specified number of rings
@@ -52,14 +53,26 @@ pub fn get_neighbors(cell_str: &str, rings: u32) -> Vec<String> {
     cells
 }
 
+/// Center of a cell as (latitude, longitude) in degrees
+///
+/// This is as precise as a published location gets: it says which
+/// ~5km hexagon an event is in, not where in it.
+#[must_use]
+pub fn cell_center(cell_str: &str) -> Option<(f64, f64)> {
+    let cell = cell_str.parse::<CellIndex>().ok()?;
+    let center = LatLng::from(cell);
+    Some((center.lat(), center.lng()))
+}
+
 /// Calculate approximate distance between two cells in kilometers
 /// This is a rough estimate based on cell center distance
+#[must_use]
 pub fn approximate_distance_km(cell1: &str, cell2: &str) -> Option<f64> {
     let c1 = cell1.parse::<CellIndex>().ok()?;
     let c2 = cell2.parse::<CellIndex>().ok()?;
 
-    let ll1 = c1.to_lat_lng();
-    let ll2 = c2.to_lat_lng();
+    let ll1 = LatLng::from(c1);
+    let ll2 = LatLng::from(c2);
 
     // Haversine formula
     let r = 6371.0; // Earth's radius in km
@@ -69,7 +82,8 @@ pub fn approximate_distance_km(cell1: &str, cell2: &str) -> Option<f64> {
     let dlat = (ll2.lat() - ll1.lat()).to_radians();
     let dlon = (ll2.lng() - ll1.lng()).to_radians();
 
-    let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+    let a =
+        (lat1.cos() * lat2.cos()).mul_add((dlon / 2.0).sin().powi(2), (dlat / 2.0).sin().powi(2));
     let c = 2.0 * a.sqrt().asin();
 
     Some(r * c)
@@ -0,0 +1,86 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Outbound mail
+//!
+//! Sending goes through the [`Mailer`] trait so the transport can be
+//! swapped per deployment. [`FileMailer`] writes each message to a
+//! directory and is what tests and local development use.
+//!
+//! Privacy: mail bodies contain recipient addresses and single-use tokens.
+//! Mailers must never log them.
+
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::error::{ApiError, Result};
+
+/// A plain-text email
+#[derive(Clone)]
+pub struct Mail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Mail transport
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    /// Deliver a message
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the message could not be handed off.
+    async fn send(&self, mail: Mail) -> Result<()>;
+}
+
+/// Writes each message as a file in a directory
+#[derive(Debug, Clone)]
+pub struct FileMailer {
+    dir: PathBuf,
+}
+
+impl FileMailer {
+    /// Create a mailer that writes into `dir`
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, mail: Mail) -> Result<()> {
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .map_err(|e| ApiError::Internal(e.into()))?;
+
+        let name = format!(
+            "{}-{}.eml",
+            Utc::now().format("%Y%m%dT%H%M%S%.6f"),
+            Uuid::new_v4()
+        );
+        let contents = format!(
+            "To: {}\nSubject: {}\n\n{}\n",
+            mail.to, mail.subject, mail.body
+        );
+
+        tokio::fs::write(self.dir.join(name), contents)
+            .await
+            .map_err(|e| ApiError::Internal(e.into()))
+    }
+}
+
+/// Drops every message; used when no transport is configured
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NullMailer;
+
+#[async_trait]
+impl Mailer for NullMailer {
+    async fn send(&self, _mail: Mail) -> Result<()> {
+        tracing::warn!("No mailer configured; outbound mail dropped");
+        Ok(())
+    }
+}
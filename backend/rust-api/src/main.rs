@@ -1,99 +1,114 @@
 // SPDX-License-Identifier: MPL-2.0
 // SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
 
-//! CivicConnect REST API Server
+//! `CivicConnect` REST API Server
 //!
 //! High-performance API layer for the civic organizing platform.
 //! Handles HTTP requests, cryptographic operations, and location services.
 
-use std::net::SocketAddr;
+use std::time::Duration;
 
 use anyhow::Result;
-use axum::{
-    http::{header, Method},
-    routing::{get, post},
-    Router,
+
+use civicconnect_api::{
+    audit::{self, CheckpointSigner},
+    config::Config,
+    credentials, db, idempotency, realtime, retention, routes, server,
+    state::AppState,
+    telemetry,
+    webhooks::{self, Dispatcher},
 };
-use tower_http::{cors::CorsLayer, trace::TraceLayer};
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
-
-mod api;
-mod crypto;
-mod db;
-mod error;
-mod location;
-
-/// Application state shared across handlers
-#[derive(Clone)]
-pub struct AppState {
-    // Database pool will be added here
-    // Redis connection will be added here
-}
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize tracing/logging
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "civicconnect_api=debug,tower_http=debug".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
-
     // Load environment variables
     dotenvy::dotenv().ok();
+    let config = Config::from_env()?;
+
+    // Initialize tracing/logging, exporting spans if configured
+    let tracer = telemetry::init(&config.telemetry)?;
 
     tracing::info!("Starting CivicConnect API server");
 
-    // Build application routes
-    let app = create_router();
+    // Connect to the database and bring the schema up to date
+    let pool = db::create_pool(&config.database_url).await?;
+    db::run_migrations(&pool).await?;
 
     // Bind to address
-    let addr = SocketAddr::from(([0, 0, 0, 0], 8080));
-    tracing::info!("Listening on {}", addr);
+    let addr = config.bind_addr.clone();
 
-    // Start server
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    // Periodically sign the audit chain head
+    if let Some(path) = config.audit_signing_key_file.as_deref() {
+        let signer = CheckpointSigner::from_key_file(path)?;
+        let interval = Duration::from_secs(config.audit_checkpoint_interval_secs.max(1));
+        tokio::spawn(audit::run_checkpoints(pool.clone(), signer, interval));
+    } else {
+        tracing::warn!("No audit signing key configured; checkpoints disabled");
+    }
 
-    Ok(())
-}
+    let state = AppState::new(config, pool)?;
 
-/// Create the application router with all routes
-fn create_router() -> Router {
-    // CORS configuration - restrict in production
-    let cors = CorsLayer::new()
-        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
-        .allow_headers([header::CONTENT_TYPE, header::AUTHORIZATION])
-        .allow_origin(tower_http::cors::Any); // TODO: Restrict in production
-
-    Router::new()
-        // Health check
-        .route("/health", get(api::health::health_check))
-        // API v1 routes
-        .nest("/api/v1", api_v1_routes())
-        // Middleware
-        .layer(TraceLayer::new_for_http())
-        .layer(cors)
-}
+    // Relay real-time updates from other replicas, and tick QR rotations
+    if state.config.redis_url.is_some() {
+        tokio::spawn(realtime::run_fanout(state.realtime.clone()));
+    } else {
+        tracing::warn!("No Redis configured; real-time updates stay on this replica");
+    }
+    tokio::spawn(realtime::run_qr_ticks(
+        state.db.clone(),
+        state.realtime.clone(),
+        Duration::from_secs(state.config.realtime.qr_rotation_secs.max(1)),
+    ));
+
+    // Deliver queued webhooks
+    let dispatcher = Dispatcher::new(state.config.webhooks.clone())?;
+    tokio::spawn(webhooks::run_deliveries(state.db.clone(), dispatcher));
+
+    // Forget idempotency keys past their retention
+    tokio::spawn(idempotency::run_purge(
+        state.db.clone(),
+        Duration::from_secs(state.config.idempotency.purge_interval_secs.max(1)),
+    ));
 
-/// API v1 routes
-fn api_v1_routes() -> Router {
-    Router::new()
-        // Authentication
-        .route("/auth/register", post(api::auth::register))
-        .route("/auth/login", post(api::auth::login))
-        // Users
-        .route("/users/me", get(api::users::get_current_user))
-        .route("/users/:id", get(api::users::get_user))
-        // Events
-        .route("/events", get(api::events::list_events))
-        .route("/events", post(api::events::create_event))
-        .route("/events/:id", get(api::events::get_event))
-        // Verification
-        .route("/verify/qr", post(api::verify::generate_qr))
-        .route("/verify/scan", post(api::verify::verify_attendance))
-        // Location (privacy-preserving)
-        .route("/location/nearby", get(api::location::nearby_events))
+    // Remove personal data past its retention period
+    tokio::spawn(retention::run_purge(
+        state.db.clone(),
+        state.metrics.clone(),
+        state.config.retention.clone(),
+    ));
+
+    // Forget who was issued credentials once that can no longer matter
+    if state.credentials.is_some() {
+        tokio::spawn(credentials::run_purge(
+            state.db.clone(),
+            Duration::from_secs(state.config.credentials.purge_interval_secs.max(1)),
+        ));
+    }
+
+    // Move any rows on an older email index key to the current key
+    tokio::spawn(db::users::run_reindex(
+        state.db.clone(),
+        state.email_index.clone(),
+        Duration::from_secs(state.config.email_reindex_interval_secs.max(1)),
+    ));
+
+    // Build application routes
+    let limits = state.config.server.clone();
+    let draining = state.draining.clone();
+    let app = routes::create_router(state);
+
+    tracing::info!("Listening on {}", addr);
+
+    // Start server, draining on SIGTERM
+    let listener = tokio::net::TcpListener::bind(&addr).await?;
+    server::serve(listener, app, &limits, draining, server::shutdown_signal()).await?;
+
+    // Flush spans still queued for export
+    if let Some(tracer) = tracer {
+        if let Err(e) = tracer.shutdown() {
+            tracing::error!(error = %e, "Trace export shutdown failed");
+        }
+    }
+
+    Ok(())
 }
@@ -0,0 +1,280 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Prometheus metrics
+//!
+//! Exposed at `/metrics` (see [`crate::api::metrics`]):
+//!
+//! - `civicconnect_http_requests_total` and
+//!   `civicconnect_http_request_duration_seconds`: rate, errors and
+//!   duration per route template and method
+//! - `civicconnect_db_pool_connections`: pool connections by state, and
+//!   `civicconnect_db_pool_max_connections`
+//! - `civicconnect_password_hash_duration_seconds`: Argon2id time, not
+//!   counting time queued for a permit
+//! - `civicconnect_verifications_total`: verification outcomes, with the
+//!   rejection reason or the fraud rule that held them
+//! - `civicconnect_xp_awarded_total`: XP awarded for attendance
+//! - `civicconnect_retention_purged_total`: rows removed or cleared by
+//!   retention, per policy, and
+//!   `civicconnect_retention_last_run_timestamp_seconds`
+//!
+//! Labels are bounded sets: route templates rather than paths, error and
+//! rule names rather than messages. Nothing identifies a user.
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+use sqlx::PgPool;
+
+use crate::error::{ApiError, Result};
+use crate::retention::Purged;
+
+/// Buckets for request durations, in seconds
+const REQUEST_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+/// Buckets for Argon2id, which takes around a second under CPR-001
+const HASH_BUCKETS: &[f64] = &[0.05, 0.1, 0.25, 0.5, 1.0, 2.0, 4.0, 8.0];
+
+/// This replica's metrics
+pub struct Metrics {
+    registry: Registry,
+    requests: IntCounterVec,
+    request_duration: HistogramVec,
+    pool_connections: IntGaugeVec,
+    pool_max_connections: IntGauge,
+    password_hashing: HistogramVec,
+    verifications: IntCounterVec,
+    xp_awarded: IntCounter,
+    retention_purged: IntCounterVec,
+    retention_last_run: IntGauge,
+}
+
+impl Metrics {
+    /// Register every metric in a fresh registry
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a metric definition is invalid.
+    pub fn new() -> Result<Self> {
+        let registry =
+            Registry::new_custom(Some("civicconnect".into()), None).map_err(|e| internal(&e))?;
+
+        let requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests by route and status"),
+            &["method", "route", "status"],
+        )
+        .map_err(|e| internal(&e))?;
+        let request_duration = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "HTTP request duration")
+                .buckets(REQUEST_BUCKETS.to_vec()),
+            &["method", "route"],
+        )
+        .map_err(|e| internal(&e))?;
+        let pool_connections = IntGaugeVec::new(
+            Opts::new("db_pool_connections", "Database pool connections by state"),
+            &["state"],
+        )
+        .map_err(|e| internal(&e))?;
+        let pool_max_connections =
+            IntGauge::new("db_pool_max_connections", "Database pool connection limit")
+                .map_err(|e| internal(&e))?;
+        let password_hashing = HistogramVec::new(
+            HistogramOpts::new(
+                "password_hash_duration_seconds",
+                "Argon2id time per operation",
+            )
+            .buckets(HASH_BUCKETS.to_vec()),
+            &["operation"],
+        )
+        .map_err(|e| internal(&e))?;
+        let verifications = IntCounterVec::new(
+            Opts::new("verifications_total", "Attendance verification outcomes"),
+            &["outcome", "reason"],
+        )
+        .map_err(|e| internal(&e))?;
+        let xp_awarded = IntCounter::new("xp_awarded_total", "XP awarded for attendance")
+            .map_err(|e| internal(&e))?;
+        let retention_purged = IntCounterVec::new(
+            Opts::new(
+                "retention_purged_total",
+                "Rows removed or cleared by retention",
+            ),
+            &["policy"],
+        )
+        .map_err(|e| internal(&e))?;
+        let retention_last_run = IntGauge::new(
+            "retention_last_run_timestamp_seconds",
+            "When retention last completed",
+        )
+        .map_err(|e| internal(&e))?;
+
+        for collector in [
+            Box::new(requests.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(request_duration.clone()),
+            Box::new(pool_connections.clone()),
+            Box::new(pool_max_connections.clone()),
+            Box::new(password_hashing.clone()),
+            Box::new(verifications.clone()),
+            Box::new(xp_awarded.clone()),
+            Box::new(retention_purged.clone()),
+            Box::new(retention_last_run.clone()),
+        ] {
+            registry.register(collector).map_err(|e| internal(&e))?;
+        }
+
+        Ok(Self {
+            registry,
+            requests,
+            request_duration,
+            pool_connections,
+            pool_max_connections,
+            password_hashing,
+            verifications,
+            xp_awarded,
+            retention_purged,
+            retention_last_run,
+        })
+    }
+
+    /// Argon2id timings, for [`crate::crypto::password::PasswordHasher`]
+    #[must_use]
+    pub fn password_hashing(&self) -> HistogramVec {
+        self.password_hashing.clone()
+    }
+
+    /// Count a verification outcome: `verified`, `held` (with the fraud
+    /// rule as reason), `released`, or `rejected`
+    pub fn verification(&self, outcome: &str, reason: &str) {
+        self.verifications
+            .with_label_values(&[outcome, reason])
+            .inc();
+    }
+
+    /// Count a verification refused by the checks before recording
+    pub fn verification_rejected(&self, err: &ApiError) {
+        let reason = match err {
+            ApiError::AlreadyVerified => "already_verified",
+            ApiError::InvalidSignature => "invalid_signature",
+            ApiError::OutsideTimeWindow => "outside_time_window",
+            ApiError::OutsideLocation => "outside_location",
+            ApiError::RateLimited => "rate_limited",
+            _ => "other",
+        };
+        self.verification("rejected", reason);
+    }
+
+    /// Count XP awarded
+    pub fn xp_awarded(&self, xp: i32) {
+        self.xp_awarded.inc_by(u64::try_from(xp).unwrap_or(0));
+    }
+
+    /// Count a completed retention purge
+    pub fn retention(&self, purged: &Purged) {
+        for (policy, count) in [
+            ("read_messages", purged.messages),
+            ("verifications", purged.verifications),
+            ("locations", purged.locations),
+        ] {
+            self.retention_purged
+                .with_label_values(&[policy])
+                .inc_by(count);
+        }
+        self.retention_last_run.set(chrono::Utc::now().timestamp());
+    }
+
+    /// Current values in the Prometheus text format
+    #[must_use]
+    pub fn render(&self, pool: &PgPool) -> String {
+        let size = i64::from(pool.size());
+        let idle = i64::try_from(pool.num_idle()).unwrap_or(i64::MAX);
+        self.pool_connections.with_label_values(&["idle"]).set(idle);
+        self.pool_connections
+            .with_label_values(&["in_use"])
+            .set((size - idle).max(0));
+        self.pool_max_connections
+            .set(i64::from(pool.options().get_max_connections()));
+
+        let mut out = Vec::new();
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut out) {
+            tracing::error!(error = %e, "Metrics encoding failed");
+        }
+        String::from_utf8(out).unwrap_or_default()
+    }
+}
+
+/// Record each request's route, status and duration
+pub async fn track_requests(
+    State(metrics): State<Arc<Metrics>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or_else(|| "unmatched".to_string(), |path| path.as_str().to_string());
+    let method = request.method().clone();
+    let started = Instant::now();
+
+    let response = next.run(request).await;
+
+    metrics
+        .request_duration
+        .with_label_values(&[method.as_str(), &route])
+        .observe(started.elapsed().as_secs_f64());
+    metrics
+        .requests
+        .with_label_values(&[method.as_str(), &route, response.status().as_str()])
+        .inc();
+    response
+}
+
+fn internal(err: &prometheus::Error) -> ApiError {
+    ApiError::Internal(anyhow::anyhow!("Metrics registration failed: {err}"))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn metrics_render_with_their_prefix() {
+        let metrics = Metrics::new().unwrap();
+        metrics.verification("held", "impossible_travel");
+        metrics.verification_rejected(&ApiError::OutsideLocation);
+        metrics.xp_awarded(25);
+        metrics.retention(&Purged {
+            messages: 3,
+            ..Purged::default()
+        });
+
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .max_connections(7)
+            .connect_lazy("postgres://localhost:1/unused")
+            .unwrap();
+        let text = metrics.render(&pool);
+
+        assert!(text.contains(
+            r#"civicconnect_verifications_total{outcome="held",reason="impossible_travel"} 1"#
+        ));
+        assert!(text.contains(
+            r#"civicconnect_verifications_total{outcome="rejected",reason="outside_location"} 1"#
+        ));
+        assert!(text.contains("civicconnect_xp_awarded_total 25"));
+        assert!(text.contains(r#"civicconnect_retention_purged_total{policy="read_messages"} 3"#));
+        assert!(text.contains(r#"civicconnect_retention_purged_total{policy="locations"} 0"#));
+        assert!(text.contains("civicconnect_db_pool_max_connections 7"));
+    }
+}
@@ -0,0 +1,133 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Organization (chapter) permissions
+//!
+//! What someone may do in a chapter depends on both their chapter role and
+//! their platform level. Roles say who runs the chapter; levels say who has
+//! earned trust on the platform. So admins and owners can do anything
+//! day-to-day, but only members who are organizers in their own right
+//! ([`ORGANIZER_LEVEL`]) can create chapter events, and only organizers can
+//! be made admins or owners.
+//!
+//! Owners appoint admins and owners; admins manage ordinary members.
+
+use crate::db::models::OrganizationRole;
+use crate::leveling::ORGANIZER_LEVEL;
+
+/// Something a chapter member might do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    /// Hold events under the chapter's name
+    CreateEvents,
+    /// Reassign or add co-organizers to other people's chapter events
+    ManageEvents,
+    /// Issue and revoke invitation links
+    Invite,
+    /// Change members' roles and remove members
+    ManageMembers,
+    /// See chapter-wide counts
+    ViewAnalytics,
+    /// Subscribe webhooks to chapter events
+    ManageWebhooks,
+}
+
+/// Whether a member with `role` and platform `level` has `capability`
+#[must_use]
+pub const fn can(role: OrganizationRole, level: i16, capability: Capability) -> bool {
+    let runs_chapter = matches!(role, OrganizationRole::Admin | OrganizationRole::Owner);
+    match capability {
+        Capability::CreateEvents => runs_chapter || level >= ORGANIZER_LEVEL,
+        Capability::ManageEvents
+        | Capability::Invite
+        | Capability::ManageMembers
+        | Capability::ViewAnalytics
+        | Capability::ManageWebhooks => runs_chapter,
+    }
+}
+
+/// Whether someone at platform `level` may hold `role`
+#[must_use]
+pub const fn can_hold(role: OrganizationRole, level: i16) -> bool {
+    matches!(role, OrganizationRole::Member) || level >= ORGANIZER_LEVEL
+}
+
+/// Whether `actor` may move someone from role `from` to role `to`
+///
+/// `None` means not a member, so this covers inviting and removing too.
+/// Owners may make any change; admins only add and remove ordinary
+/// members. It doesn't check [`can_hold`] or that a chapter keeps an
+/// owner.
+#[must_use]
+pub const fn can_change_role(
+    actor: OrganizationRole,
+    from: Option<OrganizationRole>,
+    to: Option<OrganizationRole>,
+) -> bool {
+    match actor {
+        OrganizationRole::Owner => true,
+        OrganizationRole::Admin => {
+            matches!(from, None | Some(OrganizationRole::Member))
+                && matches!(to, None | Some(OrganizationRole::Member))
+        }
+        OrganizationRole::Member => false,
+    }
+}
+
+/// Whether `slug` is lowercase letters and digits in dash-separated words
+#[must_use]
+pub fn is_valid_slug(slug: &str) -> bool {
+    (3..=50).contains(&slug.len())
+        && slug.split('-').all(|word| {
+            !word.is_empty()
+                && word
+                    .bytes()
+                    .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use OrganizationRole::{Admin, Member, Owner};
+
+    #[test]
+    fn chapter_events_need_a_chapter_role_or_organizer_level() {
+        assert!(can(Admin, 0, Capability::CreateEvents));
+        assert!(can(Member, ORGANIZER_LEVEL, Capability::CreateEvents));
+        assert!(!can(Member, ORGANIZER_LEVEL - 1, Capability::CreateEvents));
+
+        // Level alone doesn't let a member run the chapter
+        assert!(!can(Member, 5, Capability::Invite));
+        assert!(!can(Member, 5, Capability::ViewAnalytics));
+        assert!(can(Admin, 0, Capability::ManageMembers));
+    }
+
+    #[test]
+    fn only_organizers_lead_chapters() {
+        assert!(can_hold(Member, 0));
+        assert!(!can_hold(Admin, ORGANIZER_LEVEL - 1));
+        assert!(can_hold(Owner, ORGANIZER_LEVEL));
+    }
+
+    #[test]
+    fn admins_manage_members_and_owners_manage_admins() {
+        assert!(can_change_role(Admin, None, Some(Member)));
+        assert!(can_change_role(Admin, Some(Member), None));
+        assert!(!can_change_role(Admin, Some(Member), Some(Admin)));
+        assert!(!can_change_role(Admin, Some(Admin), None));
+        assert!(can_change_role(Owner, Some(Admin), Some(Owner)));
+        assert!(!can_change_role(Member, None, Some(Member)));
+    }
+
+    #[test]
+    fn slugs_are_dash_separated_words() {
+        assert!(is_valid_slug("east-side-tenants"));
+        assert!(is_valid_slug("ward7"));
+        assert!(!is_valid_slug("ab"));
+        assert!(!is_valid_slug("East-Side"));
+        assert!(!is_valid_slug("east--side"));
+        assert!(!is_valid_slug("-east"));
+        assert!(!is_valid_slug("east_side"));
+    }
+}
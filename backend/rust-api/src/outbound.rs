@@ -0,0 +1,212 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Requests to URLs chosen by users and remote servers
+//!
+//! Federated actors and webhook subscribers name the URLs we fetch and
+//! post to, so they could point us at our own loopback interface, the
+//! private network behind us or a cloud metadata service. Clients built by
+//! [`client_builder`] resolve names only to public addresses, and
+//! [`check_host`] refuses IP literals, which are never resolved. Both run
+//! on every request, so a name that was public when accepted and now
+//! resolves inward is still refused.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use url::{Host, Url};
+
+use crate::error::{ApiError, Result};
+
+/// Whether an address is on the public internet
+///
+/// Loopback, private, link-local, shared (CGNAT), documentation,
+/// benchmarking, multicast and reserved ranges are not, nor are IPv6
+/// addresses that embed one of them.
+#[must_use]
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => is_public_v6(ip),
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    !(a == 0
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        || ip.is_broadcast()
+        // Shared address space (RFC 6598)
+        || (a == 100 && (64..128).contains(&b))
+        // IETF protocol assignments
+        || (a == 192 && b == 0 && c == 0)
+        // Benchmarking (RFC 2544)
+        || (a == 198 && (b == 18 || b == 19))
+        // Reserved
+        || a >= 240)
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    if let Some(v4) = ip.to_ipv4_mapped() {
+        return is_public_v4(v4);
+    }
+
+    let segments = ip.segments();
+    // NAT64 (RFC 6052) reaches the embedded IPv4 address
+    if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+        let [.., a, b, c, d] = ip.octets();
+        return is_public_v4(Ipv4Addr::new(a, b, c, d));
+    }
+
+    !(ip.is_loopback()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        // Unique local
+        || (segments[0] & 0xfe00) == 0xfc00
+        // Link-local
+        || (segments[0] & 0xffc0) == 0xfe80
+        // Documentation
+        || (segments[0] == 0x2001 && segments[1] == 0x0db8))
+}
+
+/// Refuse a URL whose host is an IP literal outside the public internet
+///
+/// # Errors
+///
+/// Returns [`ApiError::InvalidInput`] for a non-public address.
+pub fn check_host(url: &Url) -> Result<()> {
+    let ip = match url.host() {
+        Some(Host::Ipv4(ip)) => IpAddr::V4(ip),
+        Some(Host::Ipv6(ip)) => IpAddr::V6(ip),
+        _ => return Ok(()),
+    };
+
+    if is_public(ip) {
+        Ok(())
+    } else {
+        Err(ApiError::InvalidInput(
+            "URL must not point to a private address".into(),
+        ))
+    }
+}
+
+/// Refuse a URL whose host is, or currently resolves to, a non-public
+/// address
+///
+/// For early feedback when a URL is saved. A host that doesn't resolve
+/// yet is allowed; requests are checked again when they are made.
+///
+/// # Errors
+///
+/// Returns [`ApiError::InvalidInput`] for a non-public address.
+pub async fn check_resolved(url: &Url) -> Result<()> {
+    check_host(url)?;
+
+    let Some(Host::Domain(domain)) = url.host() else {
+        return Ok(());
+    };
+    let Ok(mut addrs) = tokio::net::lookup_host((domain, 0)).await else {
+        return Ok(());
+    };
+
+    if addrs.all(|addr| is_public(addr.ip())) {
+        Ok(())
+    } else {
+        Err(ApiError::InvalidInput(
+            "URL must not point to a private address".into(),
+        ))
+    }
+}
+
+/// Resolves names to their public addresses only
+#[derive(Debug, Clone, Copy)]
+pub struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|addr| is_public(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{} has no public address", name.as_str()).into());
+            }
+
+            let addrs: Addrs = Box::new(addrs.into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+/// A client builder that follows no redirects and, unless
+/// `allow_private_networks`, resolves names to public addresses only
+pub fn client_builder(allow_private_networks: bool) -> reqwest::ClientBuilder {
+    let builder = reqwest::Client::builder().redirect(reqwest::redirect::Policy::none());
+    if allow_private_networks {
+        builder
+    } else {
+        builder.dns_resolver(Arc::new(PublicResolver))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn public(ip: &str) -> bool {
+        is_public(ip.parse().unwrap())
+    }
+
+    #[test]
+    fn internal_addresses_are_not_public() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "255.255.255.255",
+            "::1",
+            "::",
+            "fc00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+            "::ffff:169.254.169.254",
+            "64:ff9b::a00:1",
+        ] {
+            assert!(!public(ip), "{ip}");
+        }
+    }
+
+    #[test]
+    fn internet_addresses_are_public() {
+        for ip in [
+            "1.1.1.1",
+            "93.184.216.34",
+            "2606:4700:4700::1111",
+            "::ffff:1.1.1.1",
+        ] {
+            assert!(public(ip), "{ip}");
+        }
+    }
+
+    #[test]
+    fn ip_literal_hosts_are_checked() {
+        let check = |url: &str| check_host(&Url::parse(url).unwrap());
+
+        assert!(check("http://169.254.169.254/latest/meta-data").is_err());
+        assert!(check("http://[::1]:8080/").is_err());
+        assert!(check("https://1.1.1.1/").is_ok());
+        assert!(check("https://hooks.example/").is_ok());
+    }
+}
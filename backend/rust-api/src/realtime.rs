@@ -0,0 +1,299 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Real-time event updates
+//!
+//! Users following an event (they RSVP'd to it or organize it) can hold
+//! open a server-sent event stream (see [`crate::api::stream`]) and hear
+//! about cancellations, capacity changes, live attendance counts and QR
+//! rotation ticks as they happen.
+//!
+//! Fan-out: with `redis_url` set, updates are published to a Redis
+//! channel and every replica's [`run_fanout`] task relays that channel
+//! into its local [`Hub`], so a client connected to any replica sees
+//! updates made on any other. Without Redis, updates only reach clients
+//! of the replica that produced them, which is fine for a single instance.
+//!
+//! QR rotation ticks are derived from the clock rather than published:
+//! each replica's [`run_qr_ticks`] emits them locally for events in
+//! progress, and epochs agree across replicas as long as their clocks do.
+//!
+//! Privacy: updates carry counts, never who RSVP'd or verified.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use redis::aio::ConnectionManager;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tokio::sync::{broadcast, OnceCell};
+use uuid::Uuid;
+
+use crate::db;
+
+/// Redis channel updates are published on
+pub const CHANNEL: &str = "civicconnect:event-updates";
+
+/// A change to an event, as sent to followers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EventUpdate {
+    /// The event is cancelled or was taken down by moderators
+    Cancelled { event_id: Uuid },
+    /// RSVPs changed how many places are left
+    Capacity {
+        event_id: Uuid,
+        capacity: Option<i32>,
+        rsvps: i64,
+    },
+    /// Verified attendance so far
+    Attendance { event_id: Uuid, verified: i64 },
+    /// Organizers should display a fresh QR code
+    QrRotation { event_id: Uuid, epoch: i64 },
+}
+
+impl EventUpdate {
+    /// The event this update is about
+    #[must_use]
+    pub const fn event_id(&self) -> Uuid {
+        match self {
+            Self::Cancelled { event_id }
+            | Self::Capacity { event_id, .. }
+            | Self::Attendance { event_id, .. }
+            | Self::QrRotation { event_id, .. } => *event_id,
+        }
+    }
+
+    /// Name of the update, used as the SSE event name
+    #[must_use]
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Self::Cancelled { .. } => "cancelled",
+            Self::Capacity { .. } => "capacity",
+            Self::Attendance { .. } => "attendance",
+            Self::QrRotation { .. } => "qr_rotation",
+        }
+    }
+}
+
+/// Real-time update configuration
+#[derive(Debug, Clone, Deserialize)]
+pub struct RealtimeConfig {
+    /// Seconds between QR rotation ticks
+    #[serde(default = "default_qr_rotation_secs")]
+    pub qr_rotation_secs: u64,
+
+    /// Updates buffered per replica before slow subscribers miss some
+    #[serde(default = "default_buffer")]
+    pub buffer: usize,
+}
+
+impl Default for RealtimeConfig {
+    fn default() -> Self {
+        Self {
+            qr_rotation_secs: default_qr_rotation_secs(),
+            buffer: default_buffer(),
+        }
+    }
+}
+
+const fn default_qr_rotation_secs() -> u64 {
+    30
+}
+
+const fn default_buffer() -> usize {
+    1024
+}
+
+/// QR rotation epoch containing `at`
+#[must_use]
+pub fn qr_epoch(at: DateTime<Utc>, rotation_secs: u64) -> i64 {
+    at.timestamp()
+        .div_euclid(i64::try_from(rotation_secs.max(1)).unwrap_or(i64::MAX))
+}
+
+/// Delivers updates to this replica's subscribers
+pub struct Hub {
+    local: broadcast::Sender<EventUpdate>,
+    redis: Option<redis::Client>,
+    publisher: OnceCell<ConnectionManager>,
+}
+
+impl Hub {
+    /// A hub that fans out through `redis`, or delivers locally if `None`
+    #[must_use]
+    pub fn new(config: &RealtimeConfig, redis: Option<redis::Client>) -> Self {
+        let (local, _) = broadcast::channel(config.buffer.max(1));
+        Self {
+            local,
+            redis,
+            publisher: OnceCell::new(),
+        }
+    }
+
+    /// Receive every update delivered to this replica
+    #[must_use]
+    pub fn subscribe(&self) -> broadcast::Receiver<EventUpdate> {
+        self.local.subscribe()
+    }
+
+    /// Send an update to followers on every replica
+    ///
+    /// Best effort: if Redis is unreachable the update is delivered to
+    /// this replica only, and the failure is logged.
+    pub async fn publish(&self, update: EventUpdate) {
+        let Some(client) = &self.redis else {
+            self.publish_local(update);
+            return;
+        };
+
+        let sent = async {
+            let conn = self
+                .publisher
+                .get_or_try_init(|| ConnectionManager::new(client.clone()))
+                .await?;
+            let payload = serde_json::to_string(&update).map_err(|e| {
+                redis::RedisError::from((
+                    redis::ErrorKind::TypeError,
+                    "unserializable update",
+                    e.to_string(),
+                ))
+            })?;
+            redis::cmd("PUBLISH")
+                .arg(CHANNEL)
+                .arg(payload)
+                .query_async::<_, ()>(&mut conn.clone())
+                .await
+        }
+        .await;
+
+        if let Err(e) = sent {
+            tracing::warn!(error = %e, "Redis publish failed; delivering locally");
+            self.publish_local(update);
+        }
+    }
+
+    /// Deliver an update to this replica's subscribers only
+    pub fn publish_local(&self, update: EventUpdate) {
+        // No subscribers is not an error
+        let _ = self.local.send(update);
+    }
+
+    /// Ping Redis over the publishing connection, or `None` without Redis
+    pub async fn ping(&self) -> Option<redis::RedisResult<()>> {
+        let client = self.redis.as_ref()?;
+        let pinged = async {
+            let conn = self
+                .publisher
+                .get_or_try_init(|| ConnectionManager::new(client.clone()))
+                .await?;
+            redis::cmd("PING")
+                .query_async::<_, ()>(&mut conn.clone())
+                .await
+        }
+        .await;
+        Some(pinged)
+    }
+}
+
+/// Relay updates from Redis into the local hub, reconnecting on failure
+///
+/// Returns immediately if the hub has no Redis client.
+pub async fn run_fanout(hub: Arc<Hub>) {
+    let Some(client) = hub.redis.clone() else {
+        return;
+    };
+
+    loop {
+        if let Err(e) = relay(&client, &hub).await {
+            tracing::error!(error = %e, "Redis fan-out disconnected");
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+}
+
+async fn relay(client: &redis::Client, hub: &Hub) -> redis::RedisResult<()> {
+    let mut pubsub = client.get_async_connection().await?.into_pubsub();
+    pubsub.subscribe(CHANNEL).await?;
+    tracing::info!("Subscribed to event updates on Redis");
+
+    let mut messages = pubsub.on_message();
+    while let Some(message) = messages.next().await {
+        let payload: String = message.get_payload()?;
+        match serde_json::from_str(&payload) {
+            Ok(update) => hub.publish_local(update),
+            Err(e) => tracing::warn!(error = %e, "Ignoring malformed event update"),
+        }
+    }
+
+    Ok(())
+}
+
+/// Emit a QR rotation tick for every event in progress, every `interval`
+pub async fn run_qr_ticks(db: PgPool, hub: Arc<Hub>, interval: Duration) {
+    let rotation_secs = interval.as_secs().max(1);
+    let mut ticker = tokio::time::interval(interval);
+
+    loop {
+        ticker.tick().await;
+        let now = Utc::now();
+        match db::events::in_progress(&db, now).await {
+            Ok(events) => {
+                let epoch = qr_epoch(now, rotation_secs);
+                for event_id in events {
+                    hub.publish_local(EventUpdate::QrRotation { event_id, epoch });
+                }
+            }
+            Err(e) => tracing::error!(error = ?e, "QR rotation tick failed"),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn updates_are_tagged_by_type() {
+        let event_id = Uuid::nil();
+        let update = EventUpdate::Attendance {
+            event_id,
+            verified: 12,
+        };
+
+        let json = serde_json::to_value(&update).unwrap();
+        assert_eq!(json["type"], update.kind());
+        assert_eq!(json["verified"], 12);
+        assert_eq!(serde_json::from_value::<EventUpdate>(json).unwrap(), update);
+        assert_eq!(update.event_id(), event_id);
+    }
+
+    #[test]
+    fn qr_epochs_advance_once_per_rotation() {
+        let start = DateTime::from_timestamp(1_700_000_020, 0).unwrap();
+
+        assert_eq!(
+            qr_epoch(start, 30),
+            qr_epoch(start + chrono::Duration::seconds(19), 30)
+        );
+        assert_eq!(
+            qr_epoch(start, 30) + 1,
+            qr_epoch(start + chrono::Duration::seconds(20), 30)
+        );
+    }
+
+    #[tokio::test]
+    async fn without_redis_updates_are_delivered_locally() {
+        let hub = Hub::new(&RealtimeConfig::default(), None);
+        let mut updates = hub.subscribe();
+        let update = EventUpdate::Cancelled {
+            event_id: Uuid::new_v4(),
+        };
+
+        hub.publish(update.clone()).await;
+        assert_eq!(updates.recv().await.unwrap(), update);
+    }
+}
@@ -0,0 +1,172 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Data retention
+//!
+//! So a seized database holds as little as possible, [`run_purge`]
+//! regularly removes what the service no longer needs:
+//!
+//! - Messages, [`RetentionConfig::read_message_days`] after they were
+//!   read. Unread messages are kept.
+//! - Verifications, after [`RetentionConfig::verification_days`]. They are
+//!   collapsed into per-user and per-event attendance counters, and each
+//!   leaves a tombstone so the audit chain still verifies (see
+//!   [`crate::audit`]). Held verifications wait until they are reviewed.
+//!   Fraud screening only sees the history that remains.
+//! - Users' coarse locations, after
+//!   [`RetentionConfig::location_inactive_days`] without a login.
+//!
+//! Each run is counted in `civicconnect_retention_purged_total`, and
+//! `civicconnect_retention_last_run_timestamp_seconds` shows it is running.
+
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+use sqlx::PgPool;
+
+use crate::db;
+use crate::error::Result;
+use crate::metrics::Metrics;
+
+/// Verifications collapsed per transaction
+const BATCH_SIZE: i64 = 500;
+
+/// Retention periods
+#[derive(Debug, Clone, Deserialize)]
+pub struct RetentionConfig {
+    /// Days a message is kept after being read
+    #[serde(default = "default_read_message_days")]
+    pub read_message_days: u32,
+
+    /// Days before a verification is collapsed into counters
+    #[serde(default = "default_verification_days")]
+    pub verification_days: u32,
+
+    /// Days without a login before a user's location is cleared
+    #[serde(default = "default_location_inactive_days")]
+    pub location_inactive_days: u32,
+
+    /// Seconds between purges
+    #[serde(default = "default_purge_interval_secs")]
+    pub purge_interval_secs: u64,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            read_message_days: default_read_message_days(),
+            verification_days: default_verification_days(),
+            location_inactive_days: default_location_inactive_days(),
+            purge_interval_secs: default_purge_interval_secs(),
+        }
+    }
+}
+
+const fn default_read_message_days() -> u32 {
+    30
+}
+
+const fn default_verification_days() -> u32 {
+    90
+}
+
+const fn default_location_inactive_days() -> u32 {
+    30
+}
+
+const fn default_purge_interval_secs() -> u64 {
+    3600
+}
+
+/// Rows removed or cleared by one purge
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Purged {
+    pub messages: u64,
+    pub verifications: u64,
+    pub locations: u64,
+}
+
+impl Purged {
+    /// Whether anything was removed
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.messages == 0 && self.verifications == 0 && self.locations == 0
+    }
+}
+
+/// Apply every retention period as of `now`
+///
+/// # Errors
+///
+/// Returns an error if a query fails. Verifications collapsed in earlier
+/// batches stay collapsed.
+pub async fn purge(pool: &PgPool, config: &RetentionConfig, now: DateTime<Utc>) -> Result<Purged> {
+    let days = |days: u32| now - Duration::days(i64::from(days));
+
+    let messages =
+        db::retention::delete_read_messages(pool, days(config.read_message_days)).await?;
+    let locations =
+        db::retention::clear_locations(pool, days(config.location_inactive_days)).await?;
+    let verifications = collapse_verifications(pool, days(config.verification_days)).await?;
+
+    Ok(Purged {
+        messages,
+        verifications,
+        locations,
+    })
+}
+
+/// Collapse verifications from before `verified_before` into counters
+async fn collapse_verifications(pool: &PgPool, verified_before: DateTime<Utc>) -> Result<u64> {
+    let mut collapsed = 0;
+
+    loop {
+        let mut tx = pool.begin().await?;
+        let expired =
+            db::retention::lock_expired_verifications(&mut tx, verified_before, BATCH_SIZE).await?;
+        for row in &expired {
+            let verification = &row.verification;
+            if row.counts {
+                db::retention::archive_attendance(
+                    &mut tx,
+                    verification.user_id,
+                    verification.event_id,
+                )
+                .await?;
+            }
+            db::audit::tombstone_verification(&mut tx, verification).await?;
+        }
+        tx.commit().await?;
+
+        collapsed += expired.len() as u64;
+        if expired.len() < usize::try_from(BATCH_SIZE).unwrap_or(usize::MAX) {
+            return Ok(collapsed);
+        }
+    }
+}
+
+/// Apply retention periods every `purge_interval_secs`
+pub async fn run_purge(db: PgPool, metrics: Arc<Metrics>, config: RetentionConfig) {
+    let mut ticker = tokio::time::interval(std::time::Duration::from_secs(
+        config.purge_interval_secs.max(1),
+    ));
+
+    loop {
+        ticker.tick().await;
+        match purge(&db, &config, Utc::now()).await {
+            Ok(purged) => {
+                metrics.retention(&purged);
+                if !purged.is_empty() {
+                    tracing::debug!(
+                        messages = purged.messages,
+                        verifications = purged.verifications,
+                        locations = purged.locations,
+                        "Applied retention periods"
+                    );
+                }
+            }
+            Err(e) => tracing::error!(error = ?e, "Retention purge failed"),
+        }
+    }
+}
@@ -0,0 +1,312 @@
+//! HTTP routing
+
+use std::time::Duration;
+
+use axum::{
+    error_handling::HandleErrorLayer,
+    extract::DefaultBodyLimit,
+    http::{header, Method},
+    middleware,
+    routing::{delete, get, post, put},
+    Router,
+};
+use tower::limit::GlobalConcurrencyLimitLayer;
+use tower::ServiceBuilder;
+use tower_http::{compression::CompressionLayer, cors::CorsLayer, trace::TraceLayer};
+
+use crate::api;
+use crate::idempotency;
+use crate::metrics;
+use crate::server;
+use crate::state::AppState;
+use crate::telemetry::RouteSpan;
+
+/// Create the application router with all routes
+pub fn create_router(state: AppState) -> Router {
+    // CORS configuration - restrict in production
+    let cors = CorsLayer::new()
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
+        .allow_headers([
+            header::CONTENT_TYPE,
+            header::AUTHORIZATION,
+            idempotency::IDEMPOTENCY_KEY,
+        ])
+        .expose_headers([idempotency::IDEMPOTENT_REPLAYED])
+        .allow_origin(tower_http::cors::Any); // TODO: Restrict in production
+
+    let limits = &state.config.server;
+    let api_v1 = api_v1_routes()
+        .layer(middleware::from_fn_with_state(
+            Duration::from_secs(limits.request_timeout_secs),
+            server::time_limit,
+        ))
+        .merge(hashing_routes().layer(middleware::from_fn_with_state(
+            Duration::from_secs(limits.hashing_timeout_secs),
+            server::time_limit,
+        )))
+        // Outside the time limits, so a timed-out request's key is freed
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            idempotency::idempotent,
+        ));
+
+    let mut router = Router::new()
+        // API v1 routes
+        .nest("/api/v1", api_v1);
+
+    if state.federation.is_some() {
+        router = router.merge(federation_routes());
+    }
+
+    router
+        // Shed load past the concurrency cap; health probes and metrics
+        // are exempt
+        .layer(
+            ServiceBuilder::new()
+                .layer(HandleErrorLayer::new(server::shed))
+                .load_shed()
+                .layer(GlobalConcurrencyLimitLayer::new(
+                    limits.max_concurrent_requests.max(1),
+                )),
+        )
+        // Health checks
+        .route("/health", get(api::health::health_check))
+        .route("/health/live", get(api::health::live))
+        .route("/health/ready", get(api::health::ready))
+        // Prometheus scrape endpoint
+        .route("/metrics", get(api::metrics::metrics))
+        // Middleware
+        .layer(middleware::from_fn_with_state(
+            state.metrics.clone(),
+            metrics::track_requests,
+        ))
+        .layer(DefaultBodyLimit::max(limits.max_body_bytes))
+        .layer(CompressionLayer::new())
+        .layer(TraceLayer::new_for_http().make_span_with(RouteSpan))
+        .layer(cors)
+        .with_state(state)
+}
+
+/// API v1 routes, apart from [`hashing_routes`]
+fn api_v1_routes() -> Router<AppState> {
+    Router::new()
+        // OpenAPI document
+        .route("/openapi.json", get(api::openapi::openapi_json))
+        // Passkeys (WebAuthn)
+        .route(
+            "/auth/webauthn/register/start",
+            post(api::webauthn::register_start),
+        )
+        .route(
+            "/auth/webauthn/register/finish",
+            post(api::webauthn::register_finish),
+        )
+        .route(
+            "/auth/webauthn/login/start",
+            post(api::webauthn::login_start),
+        )
+        .route(
+            "/auth/webauthn/login/finish",
+            post(api::webauthn::login_finish),
+        )
+        // TOTP second factor
+        .route("/auth/totp/enroll", post(api::totp::enroll))
+        .route("/auth/totp/verify", post(api::totp::verify))
+        .route("/auth/totp", delete(api::totp::disable))
+        // Account recovery (opt-in)
+        .route(
+            "/auth/password-reset/request",
+            post(api::recovery::request_reset),
+        )
+        // Users
+        .route("/users/me", get(api::users::get_current_user))
+        .route(
+            "/users/me/recovery-email",
+            put(api::recovery::enable_recovery).delete(api::recovery::disable_recovery),
+        )
+        .route(
+            "/users/me/calendar-feed",
+            put(api::calendar::issue_feed).delete(api::calendar::revoke_feed),
+        )
+        .route(
+            "/users/me/signing-key",
+            put(api::verify::set_signing_key).delete(api::verify::remove_signing_key),
+        )
+        // Duress mode
+        .merge(duress_routes())
+        .route("/users/:id", get(api::users::get_user))
+        // Events
+        .route("/events", get(api::events::list_events))
+        .route("/events", post(api::events::create_event))
+        .route("/events/stream", get(api::stream::event_updates))
+        .route("/events/:id", get(api::events::get_event))
+        .route(
+            "/events/:id/rsvp",
+            put(api::events::rsvp).delete(api::events::cancel_rsvp),
+        )
+        .route("/calendar/:token", get(api::calendar::feed))
+        // Organizations
+        .merge(organization_routes())
+        // Verification
+        .route("/verify/scan", post(api::verify::verify_attendance))
+        .route("/verify/scan/batch", post(api::verify::verify_batch))
+        .route("/verify/checkins", post(api::verify::upload_checkins))
+        // Unlinkable attendance credentials
+        .route("/credentials/key", get(api::credentials::key))
+        .route("/credentials/attendance", post(api::credentials::issue))
+        .route("/credentials/redeem", post(api::credentials::redeem))
+        // Abuse reports
+        .route("/reports", post(api::reports::create_report))
+        // Location (privacy-preserving)
+        .route("/location/nearby", get(api::location::nearby_events))
+        // Moderation
+        .route(
+            "/admin/verifications/held",
+            get(api::admin::held_verifications),
+        )
+        .route(
+            "/admin/verifications/:id/review",
+            post(api::admin::review_verification),
+        )
+        .route("/admin/events/:id/hide", post(api::admin::hide_event))
+        .route("/admin/events/:id/restore", post(api::admin::restore_event))
+        .route("/admin/users/:id/suspend", post(api::admin::suspend_user))
+        .route(
+            "/admin/users/:id/unsuspend",
+            post(api::admin::unsuspend_user),
+        )
+        .route(
+            "/admin/users/:id/xp-reversals",
+            post(api::admin::reverse_experience),
+        )
+        .route("/admin/users/:id/role", put(api::admin::set_role))
+        .route("/admin/reports", get(api::admin::report_queue))
+        .route("/admin/reports/:id/status", post(api::admin::triage_report))
+        // Webhooks
+        .route(
+            "/webhooks",
+            get(api::webhooks::list_webhooks).post(api::webhooks::create_webhook),
+        )
+        .route("/webhooks/:id", delete(api::webhooks::delete_webhook))
+        .route(
+            "/webhooks/:id/dead-letters",
+            get(api::webhooks::dead_letters),
+        )
+        .route(
+            "/webhooks/:id/dead-letters/replay",
+            post(api::webhooks::replay_dead_letters),
+        )
+        .route(
+            "/webhooks/:id/dead-letters/:letter_id/replay",
+            post(api::webhooks::replay_dead_letter),
+        )
+}
+
+/// API v1 routes that hash a password or recovery code, which get
+/// longer to finish
+fn hashing_routes() -> Router<AppState> {
+    Router::new()
+        .route("/auth/register", post(api::auth::register))
+        .route("/auth/login", post(api::auth::login))
+        .route("/auth/totp/confirm", post(api::totp::confirm))
+        .route(
+            "/auth/totp/recovery-codes",
+            post(api::totp::regenerate_recovery_codes),
+        )
+        .route("/auth/mfa/recovery", post(api::totp::redeem_recovery_code))
+        .route(
+            "/auth/password-reset/confirm",
+            post(api::recovery::confirm_reset),
+        )
+        .route(
+            "/users/me/duress-password",
+            put(api::duress::set_duress_password),
+        )
+}
+
+/// Duress mode settings and alerts, under API v1, apart from setting the
+/// duress password (a [`hashing_routes`] route)
+fn duress_routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/users/me/duress-password",
+            delete(api::duress::remove_duress_password),
+        )
+        .route(
+            "/users/me/trusted-contacts",
+            get(api::duress::list_trusted_contacts),
+        )
+        .route(
+            "/users/me/trusted-contacts/:user_id",
+            put(api::duress::add_trusted_contact).delete(api::duress::remove_trusted_contact),
+        )
+        .route("/users/me/duress-alerts", get(api::duress::duress_alerts))
+}
+
+/// Organization (chapter) and co-organizer routes, under API v1
+fn organization_routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/users/me/organizations",
+            get(api::organizations::my_organizations),
+        )
+        .route(
+            "/events/:id/organization",
+            put(api::events::set_organization),
+        )
+        .route(
+            "/events/:id/co-organizers",
+            get(api::events::list_co_organizers),
+        )
+        .route(
+            "/events/:id/co-organizers/:user_id",
+            put(api::events::add_co_organizer).delete(api::events::remove_co_organizer),
+        )
+        .route(
+            "/organizations",
+            post(api::organizations::create_organization),
+        )
+        .route(
+            "/organizations/:id",
+            get(api::organizations::get_organization),
+        )
+        .route(
+            "/organizations/:id/members",
+            get(api::organizations::list_members),
+        )
+        .route(
+            "/organizations/:id/members/:user_id",
+            put(api::organizations::set_member_role).delete(api::organizations::remove_member),
+        )
+        .route(
+            "/organizations/:id/invitations",
+            post(api::organizations::create_invitation),
+        )
+        .route(
+            "/organizations/:id/invitations/:invitation_id",
+            delete(api::organizations::revoke_invitation),
+        )
+        .route(
+            "/organizations/:id/analytics",
+            get(api::organizations::analytics),
+        )
+        .route(
+            "/invitations/:token/accept",
+            post(api::organizations::accept_invitation),
+        )
+}
+
+/// ActivityPub routes, at the root where remote servers expect them
+fn federation_routes() -> Router<AppState> {
+    Router::new()
+        .route("/.well-known/webfinger", get(api::federation::webfinger))
+        .route("/ap/actors/:id", get(api::federation::actor))
+        .route("/ap/actors/:id/inbox", post(api::federation::inbox))
+        .route("/ap/actors/:id/outbox", get(api::federation::outbox))
+        .route("/ap/actors/:id/followers", get(api::federation::followers))
+        .route("/ap/events/:id", get(api::federation::event))
+        .route(
+            "/api/v1/federation/following",
+            post(api::federation::follow),
+        )
+}
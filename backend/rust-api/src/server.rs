@@ -0,0 +1,188 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Serving under load and shutting down cleanly
+//!
+//! Requests are bounded three ways, all configured by [`ServerConfig`]:
+//! a time limit per route group, a maximum body size, and a cap on
+//! requests in flight. Past the cap, requests are shed at once with 503
+//! and `Retry-After` rather than queued, so an overloaded replica recovers
+//! instead of building a backlog it will time out on anyway.
+//!
+//! Shutdown: on SIGTERM or Ctrl-C, readiness starts failing, and after
+//! `drain_delay_secs` (time for load balancers to notice) the listener
+//! closes and in-flight requests finish. Connections still open after
+//! `shutdown_grace_secs`, such as event streams, are dropped.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    BoxError, Router,
+};
+use serde::Deserialize;
+use tokio::net::TcpListener;
+use tokio::sync::Notify;
+
+use crate::error::ApiError;
+
+/// Request limits and shutdown timing
+#[derive(Debug, Clone, Deserialize)]
+pub struct ServerConfig {
+    /// Seconds a request may take before it fails with 503
+    #[serde(default = "default_request_timeout_secs")]
+    pub request_timeout_secs: u64,
+
+    /// Seconds allowed for requests that hash a password or recovery
+    /// code, which may queue behind `password.max_concurrent`
+    #[serde(default = "default_hashing_timeout_secs")]
+    pub hashing_timeout_secs: u64,
+
+    /// Largest request body accepted, in bytes
+    #[serde(default = "default_max_body_bytes")]
+    pub max_body_bytes: usize,
+
+    /// Requests handled at once before further ones are shed
+    #[serde(default = "default_max_concurrent_requests")]
+    pub max_concurrent_requests: usize,
+
+    /// Seconds readiness fails before the listener closes on shutdown
+    #[serde(default = "default_drain_delay_secs")]
+    pub drain_delay_secs: u64,
+
+    /// Seconds in-flight requests get to finish once the listener closes
+    #[serde(default = "default_shutdown_grace_secs")]
+    pub shutdown_grace_secs: u64,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            request_timeout_secs: default_request_timeout_secs(),
+            hashing_timeout_secs: default_hashing_timeout_secs(),
+            max_body_bytes: default_max_body_bytes(),
+            max_concurrent_requests: default_max_concurrent_requests(),
+            drain_delay_secs: default_drain_delay_secs(),
+            shutdown_grace_secs: default_shutdown_grace_secs(),
+        }
+    }
+}
+
+const fn default_request_timeout_secs() -> u64 {
+    10
+}
+
+const fn default_hashing_timeout_secs() -> u64 {
+    30
+}
+
+/// Room for a full check-in manifest (see
+/// [`ScanConfig::max_manifest`](crate::verification::ScanConfig::max_manifest)),
+/// at under 400 bytes a token
+const fn default_max_body_bytes() -> usize {
+    128 * 1024
+}
+
+const fn default_max_concurrent_requests() -> usize {
+    512
+}
+
+const fn default_drain_delay_secs() -> u64 {
+    5
+}
+
+const fn default_shutdown_grace_secs() -> u64 {
+    30
+}
+
+/// Fail requests that outlast the limit in the middleware's state
+///
+/// The limit covers producing a response, not streaming its body, so
+/// event streams aren't cut off.
+pub async fn time_limit(State(limit): State<Duration>, request: Request, next: Next) -> Response {
+    tokio::time::timeout(limit, next.run(request))
+        .await
+        .unwrap_or_else(|_| ApiError::TimedOut.into_response())
+}
+
+/// Turn errors from the load-shedding stack into responses
+pub async fn shed(err: BoxError) -> ApiError {
+    if err.is::<tower::load_shed::error::Overloaded>() {
+        ApiError::Overloaded
+    } else {
+        ApiError::Internal(anyhow::anyhow!("Request limit failed: {err}"))
+    }
+}
+
+/// Serve `app` until `signal` resolves, then drain as described above
+///
+/// # Errors
+///
+/// Returns an error if accepting connections fails.
+pub async fn serve(
+    listener: TcpListener,
+    app: Router,
+    config: &ServerConfig,
+    draining: Arc<AtomicBool>,
+    signal: impl Future<Output = ()> + Send + 'static,
+) -> std::io::Result<()> {
+    let drain_delay = Duration::from_secs(config.drain_delay_secs);
+    let grace = Duration::from_secs(config.shutdown_grace_secs);
+    let closed = Arc::new(Notify::new());
+
+    let close = closed.clone();
+    let shutdown = async move {
+        signal.await;
+        draining.store(true, Ordering::Relaxed);
+        tracing::info!(?drain_delay, "Shutting down; draining");
+        tokio::time::sleep(drain_delay).await;
+        tracing::info!("Closing listener");
+        close.notify_one();
+    };
+
+    tokio::select! {
+        served = axum::serve(listener, app).with_graceful_shutdown(shutdown) => served,
+        () = async {
+            closed.notified().await;
+            tokio::time::sleep(grace).await;
+        } => {
+            tracing::warn!(?grace, "Dropping connections still open after the grace period");
+            Ok(())
+        }
+    }
+}
+
+/// Resolve on SIGTERM or Ctrl-C
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!(error = %e, "Can't listen for Ctrl-C");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "Can't listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        () = ctrl_c => {}
+        () = terminate => {}
+    }
+}
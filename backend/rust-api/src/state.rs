@@ -0,0 +1,124 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Application state shared across handlers
+
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+use sqlx::PgPool;
+use webauthn_rs::{prelude::Url, Webauthn, WebauthnBuilder};
+
+use crate::config::Config;
+use crate::credentials::Issuer;
+use crate::crypto::{blind_index::BlindIndex, password::PasswordHasher, vault::EmailVault};
+use crate::error::{ApiError, Result};
+use crate::federation::Federation;
+use crate::fraud::FraudEngine;
+use crate::mail::{FileMailer, Mailer, NullMailer};
+use crate::metrics::Metrics;
+use crate::realtime::Hub;
+
+/// Application state shared across handlers
+#[derive(Clone)]
+pub struct AppState {
+    pub db: PgPool,
+    pub config: Arc<Config>,
+    pub passwords: PasswordHasher,
+    /// Cheaper hashing for the secret part of recovery codes
+    pub recovery_codes: PasswordHasher,
+    pub email_index: BlindIndex,
+    /// Present only when a recovery key file is configured
+    pub email_vault: Option<EmailVault>,
+    pub mailer: Arc<dyn Mailer>,
+    /// Passkey relying party for `public_url`
+    pub webauthn: Arc<Webauthn>,
+    /// Screens verifications before they award XP
+    pub fraud: Arc<FraudEngine>,
+    /// Real-time updates, fanned out through Redis if configured
+    pub realtime: Arc<Hub>,
+    /// Present only when a federation key file is configured
+    pub federation: Option<Arc<Federation>>,
+    /// Present only when a credential key file is configured
+    pub credentials: Option<Arc<Issuer>>,
+    /// Prometheus metrics for this replica
+    pub metrics: Arc<Metrics>,
+    /// Set once shutdown starts, so readiness fails while requests drain
+    pub draining: Arc<AtomicBool>,
+}
+
+impl AppState {
+    /// Build state from configuration and an open pool
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the password policy, blind index keys,
+    /// recovery key file, public URL, Redis URL, federation key file or credential key file are
+    /// invalid.
+    pub fn new(config: Config, db: PgPool) -> Result<Self> {
+        let metrics = Arc::new(Metrics::new()?);
+        let passwords =
+            PasswordHasher::new(config.password.params, config.password.max_concurrent)?
+                .with_latency(metrics.password_hashing());
+        let recovery_codes = PasswordHasher::new(
+            config.password.recovery_codes,
+            config.password.max_concurrent,
+        )?;
+        let email_index = BlindIndex::from_hex(&config.blind_index_keys)?;
+        let email_vault = config
+            .recovery_key_file
+            .as_deref()
+            .map(EmailVault::from_key_file)
+            .transpose()?;
+        let mailer: Arc<dyn Mailer> = match &config.mail_sink_dir {
+            Some(dir) => Arc::new(FileMailer::new(dir)),
+            None => Arc::new(NullMailer),
+        };
+        let webauthn = Arc::new(relying_party(&config.public_url)?);
+        let fraud = Arc::new(FraudEngine::standard(&config.fraud));
+        let redis = config
+            .redis_url
+            .as_deref()
+            .map(redis::Client::open)
+            .transpose()
+            .map_err(|e| ApiError::Internal(anyhow::anyhow!("Invalid Redis URL: {e}")))?;
+        let realtime = Arc::new(Hub::new(&config.realtime, redis));
+        let federation =
+            Federation::from_config(&config.federation, &config.public_url)?.map(Arc::new);
+        let credentials = Issuer::from_config(&config.credentials)?.map(Arc::new);
+
+        Ok(Self {
+            db,
+            config: Arc::new(config),
+            passwords,
+            recovery_codes,
+            email_index,
+            email_vault,
+            mailer,
+            webauthn,
+            fraud,
+            realtime,
+            federation,
+            credentials,
+            metrics,
+            draining: Arc::new(AtomicBool::new(false)),
+        })
+    }
+}
+
+/// Passkey relying party whose ID is the public URL's host
+fn relying_party(public_url: &str) -> Result<Webauthn> {
+    let invalid = |e: &dyn std::fmt::Display| {
+        ApiError::Internal(anyhow::anyhow!("Invalid WebAuthn relying party: {e}"))
+    };
+
+    let origin = Url::parse(public_url).map_err(|e| invalid(&e))?;
+    let rp_id = origin
+        .host_str()
+        .ok_or_else(|| invalid(&"public URL has no host"))?
+        .to_string();
+
+    WebauthnBuilder::new(&rp_id, &origin)
+        .and_then(|builder| builder.rp_name("CivicConnect").build())
+        .map_err(|e| invalid(&e))
+}
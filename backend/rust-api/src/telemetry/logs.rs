@@ -0,0 +1,197 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Log output with personal data redacted
+//!
+//! Every field of every event and span passes through
+//! [`scrub::scrub_field`] on its way out: fields named for secrets are
+//! withheld whatever their value, and emails, tokens and cells in other
+//! fields (including the message) are replaced. Wrapping a value in
+//! [`Redacted`](super::redact::Redacted) keeps it out even when it's
+//! logged under an innocuous name.
+
+use std::fmt;
+use std::io::Write as _;
+
+use chrono::{SecondsFormat, Utc};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use tracing::field::{Field, Visit};
+use tracing::{span, Event, Subscriber};
+use tracing_subscriber::fmt::{format::Writer, FormatFields, MakeWriter};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+use super::scrub::{self, REDACTED};
+
+/// Log line format
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// One JSON object per line, for log collectors
+    #[default]
+    Json,
+    /// Human-readable lines, for development
+    Text,
+}
+
+/// Layer writing each event as a line of JSON
+///
+/// ```json
+/// {"timestamp":"…","level":"INFO","target":"…","fields":{…},"spans":[{"name":"request",…}]}
+/// ```
+#[derive(Debug)]
+pub struct JsonLogLayer<W> {
+    make_writer: W,
+}
+
+impl<W> JsonLogLayer<W> {
+    /// Write lines to `make_writer`, e.g. [`std::io::stdout`]
+    pub const fn new(make_writer: W) -> Self {
+        Self { make_writer }
+    }
+}
+
+/// A span's redacted fields, kept in its extensions
+struct SpanFields(Map<String, Value>);
+
+impl<S, W> Layer<S> for JsonLogLayer<W>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + 'static,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else { return };
+        let mut fields = Map::new();
+        attrs.record(&mut JsonVisitor(&mut fields));
+        span.extensions_mut().insert(SpanFields(fields));
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else { return };
+        let mut extensions = span.extensions_mut();
+        if let Some(SpanFields(fields)) = extensions.get_mut::<SpanFields>() {
+            values.record(&mut JsonVisitor(fields));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let mut fields = Map::new();
+        event.record(&mut JsonVisitor(&mut fields));
+
+        let spans: Vec<Value> = ctx
+            .event_scope(event)
+            .into_iter()
+            .flat_map(tracing_subscriber::registry::Scope::from_root)
+            .map(|span| {
+                let mut object = Map::new();
+                object.insert("name".into(), span.name().into());
+                if let Some(SpanFields(fields)) = span.extensions().get::<SpanFields>() {
+                    object.extend(fields.clone());
+                }
+                Value::Object(object)
+            })
+            .collect();
+
+        let line = serde_json::json!({
+            "timestamp": Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true),
+            "level": metadata.level().as_str(),
+            "target": metadata.target(),
+            "fields": fields,
+            "spans": spans,
+        });
+
+        let mut buf = line.to_string();
+        buf.push('\n');
+        // Nowhere to report a failure to log
+        let _ = self.make_writer.make_writer().write_all(buf.as_bytes());
+    }
+}
+
+/// Records fields into a JSON object, redacting as it goes
+struct JsonVisitor<'a>(&'a mut Map<String, Value>);
+
+impl JsonVisitor<'_> {
+    fn insert(&mut self, field: &Field, value: impl Into<Value>) {
+        let value = if scrub::is_sensitive_field(field.name()) {
+            REDACTED.into()
+        } else {
+            value.into()
+        };
+        self.0.insert(field.name().to_string(), value);
+    }
+}
+
+impl Visit for JsonVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, scrub::scrub(value).into_owned());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.record_str(field, &format!("{value:?}"));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, value);
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, value);
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, value);
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, value);
+    }
+}
+
+/// Field formatter for [`LogFormat::Text`], with the same redaction
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RedactingFields;
+
+impl<'writer> FormatFields<'writer> for RedactingFields {
+    fn format_fields<R: tracing_subscriber::field::RecordFields>(
+        &self,
+        writer: Writer<'writer>,
+        fields: R,
+    ) -> fmt::Result {
+        let mut visitor = TextVisitor {
+            writer,
+            empty: true,
+            result: Ok(()),
+        };
+        fields.record(&mut visitor);
+        visitor.result
+    }
+}
+
+/// Writes `message key=value …`, redacting as it goes
+struct TextVisitor<'a> {
+    writer: Writer<'a>,
+    empty: bool,
+    result: fmt::Result,
+}
+
+impl Visit for TextVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if self.result.is_err() {
+            return;
+        }
+        let value = scrub::scrub_field(field.name(), value);
+        let separator = if self.empty { "" } else { " " };
+        self.empty = false;
+        self.result = if field.name() == "message" {
+            write!(self.writer, "{separator}{value}")
+        } else {
+            write!(self.writer, "{separator}{}={value}", field.name())
+        };
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.record_str(field, &format!("{value:?}"));
+    }
+}
@@ -0,0 +1,201 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Logging and trace export
+//!
+//! Logs go to stdout, as JSON lines by default, with personal data
+//! redacted by [`logs`]. With `otlp_endpoint` set, spans are also exported
+//! over OTLP/HTTP to a collector, after [`scrub`] has removed anything
+//! that looks like an email address, token or location cell.
+//!
+//! Request spans name the matched route template (`/calendar/:token`),
+//! never the request URI, so secrets in paths and query strings aren't
+//! recorded in the first place.
+
+pub mod logs;
+pub mod redact;
+pub mod scrub;
+
+use std::time::Duration;
+
+use axum::{extract::MatchedPath, http::Request};
+use opentelemetry::trace::{Status, TracerProvider as _};
+use opentelemetry::{KeyValue, Value};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider, SpanData, SpanExporter};
+use opentelemetry_sdk::Resource;
+use serde::Deserialize;
+use tower_http::trace::MakeSpan;
+use tracing::Span;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+
+use self::logs::{JsonLogLayer, LogFormat, RedactingFields};
+
+/// Trace export configuration
+#[derive(Debug, Clone, Deserialize)]
+pub struct TelemetryConfig {
+    /// Log line format
+    #[serde(default)]
+    pub log_format: LogFormat,
+
+    /// OTLP/HTTP traces endpoint, e.g. `http://collector:4318/v1/traces`;
+    /// spans aren't exported if unset
+    #[serde(default)]
+    pub otlp_endpoint: Option<String>,
+
+    /// `service.name` reported to the collector
+    #[serde(default = "default_service_name")]
+    pub service_name: String,
+
+    /// Fraction of new traces exported, from 0 to 1
+    #[serde(default = "default_sample_ratio")]
+    pub sample_ratio: f64,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            log_format: LogFormat::default(),
+            otlp_endpoint: None,
+            service_name: default_service_name(),
+            sample_ratio: default_sample_ratio(),
+        }
+    }
+}
+
+fn default_service_name() -> String {
+    "civicconnect-api".to_string()
+}
+
+const fn default_sample_ratio() -> f64 {
+    1.0
+}
+
+/// Install the global subscriber: redacted stdout logs, plus OTLP
+/// export if configured
+///
+/// Returns the tracer provider, to be shut down (flushing queued spans)
+/// on exit.
+///
+/// # Errors
+///
+/// Returns an error if the OTLP exporter can't be built.
+pub fn init(config: &TelemetryConfig) -> anyhow::Result<Option<SdkTracerProvider>> {
+    let provider = config
+        .otlp_endpoint
+        .as_deref()
+        .map(|endpoint| tracer_provider(config, endpoint))
+        .transpose()?;
+    let otel = provider.as_ref().map(|provider| {
+        tracing_opentelemetry::layer().with_tracer(provider.tracer("civicconnect-api"))
+    });
+    let (json, text) = match config.log_format {
+        LogFormat::Json => (Some(JsonLogLayer::new(std::io::stdout)), None),
+        LogFormat::Text => (
+            None,
+            Some(tracing_subscriber::fmt::layer().fmt_fields(RedactingFields)),
+        ),
+    };
+
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| "civicconnect_api=debug,tower_http=debug".into()),
+        )
+        .with(json)
+        .with(text)
+        .with(otel)
+        .init();
+
+    Ok(provider)
+}
+
+fn tracer_provider(config: &TelemetryConfig, endpoint: &str) -> anyhow::Result<SdkTracerProvider> {
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(endpoint)
+        .with_timeout(Duration::from_secs(10))
+        .build()?;
+
+    Ok(SdkTracerProvider::builder()
+        .with_batch_exporter(ScrubbingExporter(exporter))
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            config.sample_ratio.clamp(0.0, 1.0),
+        ))))
+        .with_resource(
+            Resource::builder()
+                .with_service_name(config.service_name.clone())
+                .build(),
+        )
+        .build())
+}
+
+/// Request spans carrying the route template instead of the URI
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RouteSpan;
+
+impl<B> MakeSpan<B> for RouteSpan {
+    fn make_span(&mut self, request: &Request<B>) -> Span {
+        let route = request
+            .extensions()
+            .get::<MatchedPath>()
+            .map_or("unmatched", MatchedPath::as_str);
+
+        tracing::debug_span!(
+            "request",
+            method = %request.method(),
+            route,
+            version = ?request.version(),
+        )
+    }
+}
+
+/// Span exporter that scrubs spans before passing them on
+#[derive(Debug)]
+pub struct ScrubbingExporter<E>(pub E);
+
+impl<E: SpanExporter> SpanExporter for ScrubbingExporter<E> {
+    async fn export(&self, mut batch: Vec<SpanData>) -> OTelSdkResult {
+        for span in &mut batch {
+            scrub_span(span);
+        }
+        self.0.export(batch).await
+    }
+
+    fn shutdown_with_timeout(&mut self, timeout: Duration) -> OTelSdkResult {
+        self.0.shutdown_with_timeout(timeout)
+    }
+
+    fn force_flush(&mut self) -> OTelSdkResult {
+        self.0.force_flush()
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.0.set_resource(resource);
+    }
+}
+
+/// Scrub a span's attributes, events and status in place
+pub fn scrub_span(span: &mut SpanData) {
+    scrub_attributes(&mut span.attributes);
+    for event in &mut span.events.events {
+        event.name = scrub::scrub(&event.name).into_owned().into();
+        scrub_attributes(&mut event.attributes);
+    }
+    if let Status::Error { description } = &mut span.status {
+        *description = scrub::scrub(description).into_owned().into();
+    }
+}
+
+fn scrub_attributes(attributes: &mut [KeyValue]) {
+    for attribute in attributes {
+        if scrub::is_sensitive_field(attribute.key.as_str()) {
+            attribute.value = Value::from(scrub::REDACTED);
+        } else if let Value::String(text) = &attribute.value {
+            if let std::borrow::Cow::Owned(scrubbed) = scrub::scrub(text.as_str()) {
+                attribute.value = Value::from(scrubbed);
+            }
+        }
+    }
+}
@@ -0,0 +1,94 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Values that never appear in logs
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use validator::{ValidateEmail, ValidateLength};
+
+use super::scrub::REDACTED;
+
+/// A password, email, token or other secret
+///
+/// `Debug` and `Display` print `[redacted]`, so a request logged whole
+/// doesn't leak its secrets. Serialization is transparent: the wrapped
+/// value goes over the wire unchanged. Use [`Redacted::expose`] where the
+/// value itself is needed.
+#[derive(Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Redacted<T>(T);
+
+impl<T> Redacted<T> {
+    /// Wrap a secret
+    pub const fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    /// The secret itself
+    pub const fn expose(&self) -> &T {
+        &self.0
+    }
+
+    /// Unwrap the secret
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for Redacted<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T> fmt::Debug for Redacted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl<T> fmt::Display for Redacted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl<T: ValidateEmail> ValidateEmail for Redacted<T> {
+    fn as_email_string(&self) -> Option<std::borrow::Cow<'_, str>> {
+        self.0.as_email_string()
+    }
+}
+
+impl<T: ValidateLength<u64>> ValidateLength<u64> for Redacted<T> {
+    fn length(&self) -> Option<u64> {
+        self.0.length()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formatting_hides_the_value() {
+        let password = Redacted::new("correct horse battery staple".to_string());
+        assert_eq!(format!("{password:?}"), REDACTED);
+        assert_eq!(password.to_string(), REDACTED);
+        assert_eq!(password.expose(), "correct horse battery staple");
+    }
+
+    #[test]
+    fn serialization_is_transparent() {
+        let email: Redacted<String> = serde_json::from_str(r#""alice@example.org""#).unwrap();
+        assert_eq!(email.expose(), "alice@example.org");
+        assert_eq!(
+            serde_json::to_string(&email).unwrap(),
+            r#""alice@example.org""#
+        );
+        assert!(email.validate_email());
+        assert!(!Redacted::new("short".to_string()).validate_length(Some(12), None, None));
+    }
+}
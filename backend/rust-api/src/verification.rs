@@ -0,0 +1,794 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Checking scans and recording verified attendance
+//!
+//! Organizers' devices sign the QR codes they display with the key they
+//! registered (see [`qr_message`]), and attendees' devices scan them. A
+//! scan is checked against that signed timestamp rather than when it
+//! reaches the server, so scans queued on a device with no signal can be
+//! uploaded later: the device's own scan time has to agree with the code,
+//! and the upload has to arrive within [`ScanConfig::offline_grace_secs`]
+//! of the code being shown.
+//!
+//! Where attendees have no data, the flow can run in reverse: attendees'
+//! devices sign check-in tokens (see [`checkin_message`]) with their own
+//! registered keys, the organizer's device collects them, and later
+//! uploads them in a [`Manifest`] it signs. Tokens are checked like scans,
+//! with the attendee's signed time in place of the code's.
+//!
+//! Once a scan has passed the signature, time and location checks, it is
+//! screened for fraud (see [`crate::fraud`]) and recorded in the audit
+//! chain, timed by its signed timestamp like the checks were. Clean
+//! verifications award XP straight away; flagged ones are recorded as held
+//! for review with no XP, so a moderator can release or reject them later
+//! without anything having to be clawed back.
+
+use std::fmt::Write as _;
+
+use chrono::{DateTime, Duration, Utc};
+use ed25519_dalek::{Signature, VerifyingKey};
+use serde::Deserialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::credentials;
+use crate::crypto;
+use crate::db::{
+    self,
+    audit::NewVerification,
+    models::{Event, Verification},
+};
+use crate::error::{ApiError, Result};
+use crate::fraud::{Assessment, Attempt};
+use crate::location;
+use crate::realtime::EventUpdate;
+use crate::state::AppState;
+use crate::webhooks::{self, WebhookEvent};
+
+/// XP for attending an event (matches `XP_Event_Attendance` in the spec)
+pub const XP_EVENT_ATTENDANCE: i32 = 25;
+
+/// Verifications a user may have recorded in any 24 hours
+pub const MAX_VERIFICATIONS_PER_DAY: i64 = 3;
+
+/// H3 rings around an event's cell that count as being there
+pub const GEOFENCE_RINGS: u32 = 1;
+
+/// QR rotations a code stays scannable for: its own and the next, so a
+/// code replaced mid-scan still works
+const QR_ROTATIONS_VALID: u64 = 2;
+
+/// Offline scan limits
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScanConfig {
+    /// Seconds after a QR code was shown that a queued scan of it may
+    /// still be uploaded
+    #[serde(default = "default_offline_grace_secs")]
+    pub offline_grace_secs: u64,
+
+    /// Seconds devices' clocks may disagree with the server's
+    #[serde(default = "default_clock_skew_secs")]
+    pub clock_skew_secs: u64,
+
+    /// Most scans accepted in one batch
+    #[serde(default = "default_max_batch")]
+    pub max_batch: usize,
+
+    /// Most check-in tokens accepted in one manifest
+    #[serde(default = "default_max_manifest")]
+    pub max_manifest: usize,
+}
+
+impl Default for ScanConfig {
+    fn default() -> Self {
+        Self {
+            offline_grace_secs: default_offline_grace_secs(),
+            clock_skew_secs: default_clock_skew_secs(),
+            max_batch: default_max_batch(),
+            max_manifest: default_max_manifest(),
+        }
+    }
+}
+
+const fn default_offline_grace_secs() -> u64 {
+    12 * 3600
+}
+
+const fn default_clock_skew_secs() -> u64 {
+    120
+}
+
+const fn default_max_batch() -> usize {
+    20
+}
+
+const fn default_max_manifest() -> usize {
+    200
+}
+
+/// A scanned QR code, as the attendee's device saw it
+#[derive(Debug, Clone)]
+pub struct Scan<'a> {
+    pub event_id: Uuid,
+    pub organizer_id: Uuid,
+    /// When the organizer's device signed the code
+    pub qr_timestamp: DateTime<Utc>,
+    pub nonce: &'a str,
+    /// Organizer's signature over [`qr_message`], hex encoded
+    pub signature: &'a str,
+    /// H3 cell the attendee reported
+    pub location_cell: &'a str,
+    /// When the attendee's device scanned the code, by its own clock
+    pub scanned_at: DateTime<Utc>,
+}
+
+/// How far apart a scan's timestamps may be
+#[derive(Debug, Clone, Copy)]
+pub struct TimeLimits {
+    /// How long a code or check-in token stays valid after it is signed
+    pub qr_valid: Duration,
+    /// Allowed disagreement between any two clocks
+    pub clock_skew: Duration,
+    /// How long after a code stops being valid a scan of it may arrive
+    pub grace: Duration,
+}
+
+impl TimeLimits {
+    /// Limits for a scan uploaded as it happens
+    #[must_use]
+    pub fn live(config: &ScanConfig, qr_rotation_secs: u64) -> Self {
+        Self {
+            grace: Duration::zero(),
+            ..Self::queued(config, qr_rotation_secs)
+        }
+    }
+
+    /// Limits for a scan queued on the device and uploaded later
+    #[must_use]
+    pub fn queued(config: &ScanConfig, qr_rotation_secs: u64) -> Self {
+        Self {
+            qr_valid: seconds(QR_ROTATIONS_VALID.saturating_mul(qr_rotation_secs.max(1))),
+            clock_skew: seconds(config.clock_skew_secs),
+            grace: seconds(config.offline_grace_secs),
+        }
+    }
+}
+
+fn seconds(secs: u64) -> Duration {
+    Duration::seconds(i64::try_from(secs).unwrap_or(i64::MAX / 1_000))
+}
+
+/// Bytes an organizer's device signs for a QR code
+///
+/// The timestamp is signed to the second.
+#[must_use]
+pub fn qr_message(
+    event_id: Uuid,
+    organizer_id: Uuid,
+    timestamp: DateTime<Utc>,
+    nonce: &str,
+) -> Vec<u8> {
+    format!(
+        "civicconnect-qr-v1\n{event_id}\n{organizer_id}\n{}\n{nonce}",
+        timestamp.timestamp()
+    )
+    .into_bytes()
+}
+
+/// Bytes an attendee's device signs for a check-in token
+///
+/// The timestamp is signed to the second.
+#[must_use]
+pub fn checkin_message(
+    event_id: Uuid,
+    user_id: Uuid,
+    issued_at: DateTime<Utc>,
+    nonce: &str,
+) -> Vec<u8> {
+    format!(
+        "civicconnect-checkin-v1\n{event_id}\n{user_id}\n{}\n{nonce}",
+        issued_at.timestamp()
+    )
+    .into_bytes()
+}
+
+/// An attendee's check-in token, as the organizer's device collected it
+#[derive(Debug, Clone)]
+pub struct CheckinToken<'a> {
+    pub user_id: Uuid,
+    /// When the attendee's device signed the token
+    pub issued_at: DateTime<Utc>,
+    pub nonce: &'a str,
+    /// Attendee's signature over [`checkin_message`], hex encoded
+    pub signature: &'a str,
+    /// When the organizer's device collected the token, by its own clock
+    pub collected_at: DateTime<Utc>,
+}
+
+/// Check-in tokens an organizer's device collected, under its signature
+#[derive(Debug, Clone)]
+pub struct Manifest<'a> {
+    pub event_id: Uuid,
+    pub organizer_id: Uuid,
+    /// H3 cell the organizer's device was in
+    pub location_cell: &'a str,
+    pub tokens: Vec<CheckinToken<'a>>,
+    /// Organizer's signature over [`manifest_message`], hex encoded
+    pub signature: &'a str,
+}
+
+/// Bytes an organizer's device signs for a manifest
+///
+/// Each token contributes its user, signature (as sent) and collection
+/// time to the second, so tokens can't be added, swapped or re-timed.
+#[must_use]
+pub fn manifest_message(
+    event_id: Uuid,
+    organizer_id: Uuid,
+    location_cell: &str,
+    tokens: &[CheckinToken<'_>],
+) -> Vec<u8> {
+    let mut message =
+        format!("civicconnect-manifest-v1\n{event_id}\n{organizer_id}\n{location_cell}\n");
+    for token in tokens {
+        // Writing to a String can't fail
+        let _ = writeln!(
+            message,
+            "{}\n{}\n{}",
+            token.user_id,
+            token.signature,
+            token.collected_at.timestamp()
+        );
+    }
+    message.into_bytes()
+}
+
+/// Check a scan's or check-in token's timestamps
+///
+/// The signed timestamp anchors everything: the code or token must have
+/// been shown during the event, the other device must have seen it
+/// (`seen_at`, by its own clock) while it was valid, and it must reach the
+/// server (at `received_at`) within the grace period after that.
+///
+/// # Errors
+///
+/// Returns [`ApiError::OutsideTimeWindow`] if any check fails.
+pub fn check_times(
+    signed_at: DateTime<Utc>,
+    seen_at: DateTime<Utc>,
+    received_at: DateTime<Utc>,
+    event: (DateTime<Utc>, DateTime<Utc>),
+    limits: &TimeLimits,
+) -> Result<()> {
+    let (start, end) = event;
+    let skew = limits.clock_skew;
+
+    let shown_during_event = signed_at >= start - skew && signed_at <= end + skew;
+    let seen_while_valid =
+        seen_at >= signed_at - skew && seen_at <= signed_at + limits.qr_valid + skew;
+    let not_from_future = signed_at <= received_at + skew && seen_at <= received_at + skew;
+    let arrived_in_time = received_at <= signed_at + limits.qr_valid + limits.grace + skew;
+
+    if shown_during_event && seen_while_valid && not_from_future && arrived_in_time {
+        Ok(())
+    } else {
+        Err(ApiError::OutsideTimeWindow)
+    }
+}
+
+/// Check a scan and, if it passes, screen and record it
+///
+/// `limits` decides how late the scan may arrive; see [`TimeLimits`].
+///
+/// # Errors
+///
+/// Returns the first check that failed ([`ApiError::InvalidSignature`],
+/// [`ApiError::OutsideTimeWindow`], [`ApiError::OutsideLocation`],
+/// [`ApiError::RateLimited`], [`ApiError::AlreadyVerified`], ...), or an
+/// error if a query fails.
+pub async fn verify_scan(
+    state: &AppState,
+    user_id: Uuid,
+    scan: &Scan<'_>,
+    received_at: DateTime<Utc>,
+    limits: &TimeLimits,
+) -> Result<Recorded> {
+    let scan_checks = async {
+        let authentic = authenticate_scan(state, scan, received_at, limits).await?;
+        check_history(state, authentic.event.id, user_id, scan.qr_timestamp).await?;
+        Ok(authentic)
+    };
+    let authentic = scan_checks.await.map_err(|e| count_rejection(state, e))?;
+
+    record(
+        state,
+        Attendance {
+            event_id: authentic.event.id,
+            user_id,
+            organizer_id: scan.organizer_id,
+            signature: &authentic.signature,
+            location_cell: scan.location_cell,
+            at: scan.qr_timestamp,
+            attested: false,
+        },
+    )
+    .await
+}
+
+/// Check a manifest and each token in it, recording those that pass
+///
+/// Returns a result per token, in order: rejected tokens get the error the
+/// QR flow would have given, without failing the rest.
+///
+/// # Errors
+///
+/// Returns [`ApiError::InvalidSignature`] if the manifest isn't signed by
+/// someone running the event, in which case nothing is recorded, or an
+/// error if a query fails.
+pub async fn verify_checkins(
+    state: &AppState,
+    manifest: &Manifest<'_>,
+    received_at: DateTime<Utc>,
+    limits: &TimeLimits,
+) -> Result<Vec<Result<Recorded>>> {
+    let manifest_checks = async {
+        if !location::is_valid_cell(manifest.location_cell) {
+            return Err(ApiError::InvalidInput("Invalid location cell".into()));
+        }
+        let event = visible_event(state, manifest.event_id).await?;
+        let key = organizer_key(state, event.id, manifest.organizer_id).await?;
+        let message = manifest_message(
+            event.id,
+            manifest.organizer_id,
+            manifest.location_cell,
+            &manifest.tokens,
+        );
+        if !crypto::verify_signature(&key, &message, &decode_signature(manifest.signature)?) {
+            return Err(ApiError::InvalidSignature);
+        }
+        Ok(event)
+    };
+    // A rejected manifest counts once, however many tokens it carried
+    let event = manifest_checks
+        .await
+        .map_err(|e| count_rejection(state, e))?;
+
+    let mut results = Vec::with_capacity(manifest.tokens.len());
+    // One at a time, so each check-in counts towards the next one's rate limit
+    for token in &manifest.tokens {
+        let result = match check_checkin(state, &event, manifest, token, received_at, limits).await
+        {
+            Ok(signature) => {
+                record(
+                    state,
+                    Attendance {
+                        event_id: event.id,
+                        user_id: token.user_id,
+                        organizer_id: manifest.organizer_id,
+                        signature: &signature,
+                        location_cell: manifest.location_cell,
+                        at: token.issued_at,
+                        attested: true,
+                    },
+                )
+                .await
+            }
+            Err(e) => Err(count_rejection(state, e)),
+        };
+        match result {
+            Err(e @ (ApiError::Database(_) | ApiError::Internal(_))) => return Err(e),
+            result => results.push(result),
+        }
+    }
+
+    Ok(results)
+}
+
+/// Count a check that failed, unless it failed for want of the database
+#[must_use]
+pub fn count_rejection(state: &AppState, err: ApiError) -> ApiError {
+    if !matches!(err, ApiError::Database(_) | ApiError::Internal(_)) {
+        state.metrics.verification_rejected(&err);
+    }
+    err
+}
+
+/// A scan whose signature, timing and place checked out
+#[derive(Debug, Clone)]
+pub struct AuthenticScan {
+    pub event: Event,
+    /// The organizer's signature, decoded
+    pub signature: Vec<u8>,
+}
+
+/// Check a scan's signature, timing and place, but not the attendee's
+/// history (see [`check_history`])
+///
+/// # Errors
+///
+/// Returns the first check that failed, or an error if a query fails.
+pub async fn authenticate_scan(
+    state: &AppState,
+    scan: &Scan<'_>,
+    received_at: DateTime<Utc>,
+    limits: &TimeLimits,
+) -> Result<AuthenticScan> {
+    if !location::is_valid_cell(scan.location_cell) {
+        return Err(ApiError::InvalidInput("Invalid location cell".into()));
+    }
+    let signature = decode_signature(scan.signature)?;
+    let event = visible_event(state, scan.event_id).await?;
+
+    // Only someone running the event can sign its codes
+    let key = organizer_key(state, event.id, scan.organizer_id).await?;
+    let message = qr_message(event.id, scan.organizer_id, scan.qr_timestamp, scan.nonce);
+    if !crypto::verify_signature(&key, &message, &signature) {
+        return Err(ApiError::InvalidSignature);
+    }
+
+    check_time_and_place(
+        &Presence {
+            event: &event,
+            signed_at: scan.qr_timestamp,
+            seen_at: scan.scanned_at,
+            location_cell: scan.location_cell,
+        },
+        received_at,
+        limits,
+    )?;
+
+    Ok(AuthenticScan {
+        event,
+        signature: signature.to_bytes().to_vec(),
+    })
+}
+
+/// Run the checks before recording a check-in, returning the decoded
+/// attendee signature
+async fn check_checkin(
+    state: &AppState,
+    event: &Event,
+    manifest: &Manifest<'_>,
+    token: &CheckinToken<'_>,
+    received_at: DateTime<Utc>,
+    limits: &TimeLimits,
+) -> Result<Vec<u8>> {
+    let signature = decode_signature(token.signature)?;
+    let key = db::users::signing_key(&state.db, token.user_id)
+        .await?
+        .and_then(verifying_key)
+        .ok_or(ApiError::InvalidSignature)?;
+    let message = checkin_message(event.id, token.user_id, token.issued_at, token.nonce);
+    if !crypto::verify_signature(&key, &message, &signature) {
+        return Err(ApiError::InvalidSignature);
+    }
+
+    check_time_and_place(
+        &Presence {
+            event,
+            signed_at: token.issued_at,
+            seen_at: token.collected_at,
+            location_cell: manifest.location_cell,
+        },
+        received_at,
+        limits,
+    )?;
+    check_history(state, event.id, token.user_id, token.issued_at).await?;
+
+    Ok(signature.to_bytes().to_vec())
+}
+
+/// A signed claim that someone was at an event
+struct Presence<'a> {
+    event: &'a Event,
+    /// When the code or token was signed
+    signed_at: DateTime<Utc>,
+    /// When the other device saw it, by its own clock
+    seen_at: DateTime<Utc>,
+    location_cell: &'a str,
+}
+
+/// The time and location checks shared by scans and check-ins
+fn check_time_and_place(
+    presence: &Presence<'_>,
+    received_at: DateTime<Utc>,
+    limits: &TimeLimits,
+) -> Result<()> {
+    let event = presence.event;
+    check_times(
+        presence.signed_at,
+        presence.seen_at,
+        received_at,
+        (event.start_time, event.end_time),
+        limits,
+    )?;
+
+    let in_geofence = location::get_neighbors(&event.location_hash, GEOFENCE_RINGS)
+        .iter()
+        .any(|cell| cell == presence.location_cell);
+    if !in_geofence {
+        return Err(ApiError::OutsideLocation);
+    }
+
+    Ok(())
+}
+
+/// Check an attendee hasn't already been verified for the event, and is
+/// within the daily limit
+///
+/// `at` is the signed time of the scan or check-in, so scans queued over
+/// several days are limited by when they were made rather than when they
+/// arrive: verifications made within a day either side of `at` count.
+/// Attendance credentials (see [`crate::credentials`]) count too.
+///
+/// # Errors
+///
+/// Returns [`ApiError::AlreadyVerified`] or [`ApiError::RateLimited`], or
+/// an error if a query fails.
+pub async fn check_history(
+    state: &AppState,
+    event_id: Uuid,
+    user_id: Uuid,
+    at: DateTime<Utc>,
+) -> Result<()> {
+    if db::verifications::exists(&state.db, event_id, user_id).await?
+        || credentials::is_claimed(&state.db, event_id, user_id).await?
+    {
+        return Err(ApiError::AlreadyVerified);
+    }
+
+    let day = Duration::days(1);
+    let recent = db::verifications::count_between(&state.db, user_id, at - day, at + day).await?
+        + db::credentials::issued_since(&state.db, user_id, at - day).await?;
+    if recent >= MAX_VERIFICATIONS_PER_DAY {
+        return Err(ApiError::RateLimited);
+    }
+
+    Ok(())
+}
+
+async fn visible_event(state: &AppState, id: Uuid) -> Result<Event> {
+    Ok(db::events::find_listing(&state.db, id)
+        .await?
+        .ok_or(ApiError::EventNotFound)?
+        .event)
+}
+
+/// Key of someone running the event; only they can sign its codes and
+/// manifests
+async fn organizer_key(
+    state: &AppState,
+    event_id: Uuid,
+    organizer_id: Uuid,
+) -> Result<VerifyingKey> {
+    db::events::organizer_signing_key(&state.db, event_id, organizer_id)
+        .await?
+        .and_then(verifying_key)
+        .ok_or(ApiError::InvalidSignature)
+}
+
+fn verifying_key(bytes: Vec<u8>) -> Option<VerifyingKey> {
+    let bytes = <[u8; 32]>::try_from(bytes).ok()?;
+    VerifyingKey::from_bytes(&bytes).ok()
+}
+
+fn decode_signature(hex_signature: &str) -> Result<Signature> {
+    hex::decode(hex_signature)
+        .ok()
+        .and_then(|bytes| Signature::from_slice(&bytes).ok())
+        .ok_or(ApiError::InvalidSignature)
+}
+
+/// A scan that passed the signature, time and location checks
+#[derive(Debug, Clone)]
+pub struct Attendance<'a> {
+    pub event_id: Uuid,
+    pub user_id: Uuid,
+    pub organizer_id: Uuid,
+    /// Signature over what was presented: the organizer's over a QR code,
+    /// or the attendee's over a check-in token
+    pub signature: &'a [u8],
+    /// H3 cell the attendee reported
+    pub location_cell: &'a str,
+    /// When the attendee was there: the signed time of the code or token
+    pub at: DateTime<Utc>,
+    /// Whether this is a check-in token from an organizer's manifest (see
+    /// [`Attempt::attested`])
+    pub attested: bool,
+}
+
+/// A recorded verification and how it was screened
+#[derive(Debug, Clone)]
+pub struct Recorded {
+    pub verification: Verification,
+    pub assessment: Assessment,
+}
+
+impl Recorded {
+    /// Whether the verification is awaiting review instead of awarding XP
+    #[must_use]
+    pub fn is_held(&self) -> bool {
+        self.verification.status == Verification::HELD_FOR_REVIEW
+    }
+}
+
+/// Screen an attendance and record it
+///
+/// # Errors
+///
+/// Returns [`ApiError::AlreadyVerified`] if the user already has a
+/// verification for this event, or an error if screening or a query fails.
+pub async fn record(state: &AppState, attendance: Attendance<'_>) -> Result<Recorded> {
+    let at = crate::audit::db_timestamp(attendance.at);
+    let assessment = state
+        .fraud
+        .assess(
+            &state.db,
+            &Attempt {
+                user_id: attendance.user_id,
+                event_id: attendance.event_id,
+                organizer_id: attendance.organizer_id,
+                location_cell: attendance.location_cell.to_string(),
+                at,
+                attested: attendance.attested,
+            },
+        )
+        .await?;
+
+    let (status, xp) = if assessment.held {
+        (Verification::HELD_FOR_REVIEW, 0)
+    } else {
+        (Verification::VERIFIED, XP_EVENT_ATTENDANCE)
+    };
+
+    let mut tx = state.db.begin().await?;
+    let verification = db::audit::append_verification(
+        &mut tx,
+        NewVerification {
+            event_id: attendance.event_id,
+            user_id: attendance.user_id,
+            organizer_id: attendance.organizer_id,
+            signature: attendance.signature,
+            verified_at: at,
+            experience_awarded: xp,
+            location_hash: attendance.location_cell,
+            status,
+            fraud_score: i16::try_from(assessment.score).unwrap_or(i16::MAX),
+            fraud_findings: json!(assessment.findings),
+        },
+    )
+    .await
+    .map_err(|e| match e {
+        ApiError::Database(ref err) if db::is_unique_violation(err) => {
+            state
+                .metrics
+                .verification_rejected(&ApiError::AlreadyVerified);
+            ApiError::AlreadyVerified
+        }
+        other => other,
+    })?;
+
+    if xp > 0 {
+        db::users::add_experience(&mut tx, attendance.user_id, xp).await?;
+    }
+    if !assessment.held {
+        webhooks::queue(
+            &mut tx,
+            &WebhookEvent::VerificationAccepted {
+                event_id: attendance.event_id,
+                verification_id: verification.id,
+                user_id: attendance.user_id,
+            },
+        )
+        .await?;
+    }
+    tx.commit().await?;
+
+    state.metrics.xp_awarded(xp);
+    if assessment.held {
+        // Counted once, under the rule that contributed most
+        let rule = assessment
+            .findings
+            .iter()
+            .max_by_key(|f| f.score)
+            .map_or("none", |f| f.rule.as_str());
+        state.metrics.verification("held", rule);
+        tracing::warn!(
+            verification_id = %verification.id,
+            score = assessment.score,
+            rules = ?assessment.findings.iter().map(|f| f.rule.as_str()).collect::<Vec<_>>(),
+            "Verification held for review"
+        );
+    } else {
+        state.metrics.verification("verified", "none");
+        publish_attendance(state, attendance.event_id).await;
+    }
+
+    Ok(Recorded {
+        verification,
+        assessment,
+    })
+}
+
+/// Tell the event's followers its verified attendance count
+pub async fn publish_attendance(state: &AppState, event_id: Uuid) {
+    match db::verifications::count_verified(&state.db, event_id).await {
+        Ok(verified) => {
+            state
+                .realtime
+                .publish(EventUpdate::Attendance { event_id, verified })
+                .await;
+        }
+        Err(e) => tracing::error!(error = ?e, %event_id, "Attendance count failed"),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000 + secs, 0).unwrap()
+    }
+
+    fn scan(qr: i64, scanned: i64) -> Scan<'static> {
+        Scan {
+            event_id: Uuid::nil(),
+            organizer_id: Uuid::nil(),
+            qr_timestamp: at(qr),
+            nonce: "",
+            signature: "",
+            location_cell: "",
+            scanned_at: at(scanned),
+        }
+    }
+
+    /// An event from 0 to 2 hours, with 30 second rotations
+    fn check(scan: &Scan<'_>, received: i64, limits: &TimeLimits) -> Result<()> {
+        check_times(
+            scan.qr_timestamp,
+            scan.scanned_at,
+            at(received),
+            (at(0), at(7_200)),
+            limits,
+        )
+    }
+
+    #[test]
+    fn queued_scans_are_timed_by_the_signed_code() {
+        let config = ScanConfig::default();
+        let live = TimeLimits::live(&config, 30);
+        let queued = TimeLimits::queued(&config, 30);
+
+        // Scanned at 1:00, uploaded three hours later
+        let late = scan(3_600, 3_610);
+        assert!(check(&late, 3_620, &live).is_ok());
+        assert!(matches!(
+            check(&late, 3_600 + 3 * 3_600, &live),
+            Err(ApiError::OutsideTimeWindow)
+        ));
+        assert!(check(&late, 3_600 + 3 * 3_600, &queued).is_ok());
+
+        // ...but not past the grace period
+        assert!(check(&late, 3_600 + 13 * 3_600, &queued).is_err());
+    }
+
+    #[test]
+    fn scan_times_must_agree_with_the_code() {
+        let limits = TimeLimits::queued(&ScanConfig::default(), 30);
+
+        // A code scanned long after it was replaced
+        assert!(check(&scan(3_600, 5_000), 6_000, &limits).is_err());
+        // Scanned before it was signed, beyond clock skew
+        assert!(check(&scan(3_600, 3_000), 6_000, &limits).is_err());
+        // Claimed to be from the future
+        assert!(check(&scan(9_000, 9_010), 3_600, &limits).is_err());
+        // Signed outside the event
+        assert!(check(&scan(9_000, 9_010), 9_020, &limits).is_err());
+        assert!(check(&scan(-3_600, -3_590), 0, &limits).is_err());
+        // Slow clocks within the allowed skew
+        assert!(check(&scan(3_600, 3_500), 3_650, &limits).is_ok());
+    }
+}
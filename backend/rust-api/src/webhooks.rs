@@ -0,0 +1,465 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Outbound webhooks
+//!
+//! Organizers and chapters subscribe URLs to changes to their events so
+//! their own tooling (spreadsheets, chat bots) can mirror them. A personal
+//! subscription hears about the events its owner organizes; a chapter
+//! subscription, about the chapter's events. A change is queued
+//! for every matching subscription in the transaction that makes it (see
+//! [`queue`]), and [`run_deliveries`] posts due deliveries in the
+//! background, retrying failures with exponential backoff. Deliveries that
+//! fail [`WebhookConfig::max_attempts`] times become dead letters, which
+//! the owner can list and replay.
+//!
+//! Subscription URLs must be public: hosts that are or resolve to
+//! loopback, private or link-local addresses are refused when subscribing,
+//! and again on every delivery (see [`crate::outbound`]).
+//!
+//! Each delivery is a JSON envelope
+//! `{"id", "type", "created_at", "data"}` sent with:
+//!
+//! - `X-CivicConnect-Event`: the event type
+//! - `X-CivicConnect-Delivery`: the envelope ID, unchanged across retries
+//!   and replays so receivers can deduplicate
+//! - `X-CivicConnect-Signature`: `t=<unix time>,v1=<hex HMAC-SHA256>`,
+//!   keyed with the subscription secret over `<t>.<body>`; receivers
+//!   should recompute it and reject stale timestamps
+//!
+//! Privacy: payloads carry event IDs and counts. User and organizer IDs
+//! are scrubbed unless the subscription opted into them, and usernames,
+//! emails and locations are never sent.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use sqlx::{PgConnection, PgPool};
+use url::Url;
+use uuid::Uuid;
+
+use crate::db::{self, webhooks::DueDelivery};
+use crate::error::{ApiError, Result};
+use crate::outbound;
+
+/// Event types a subscription can ask for
+pub const EVENT_TYPES: [&str; 5] = [
+    "event.created",
+    "event.cancelled",
+    "event.hidden",
+    "rsvp.changed",
+    "verification.accepted",
+];
+
+/// Payload fields that identify people, sent only to opted-in subscriptions
+const PII_FIELDS: [&str; 2] = ["user_id", "organizer_id"];
+
+/// Longest wait between attempts
+const MAX_BACKOFF: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// How long a claimed delivery is left alone before it's due again
+const CLAIM_LEASE: Duration = Duration::from_secs(5 * 60);
+
+/// How long a receiver has to answer
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A change subscriptions can hear about
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type")]
+pub enum WebhookEvent {
+    #[serde(rename = "event.created")]
+    EventCreated {
+        event_id: Uuid,
+        organizer_id: Uuid,
+        title: String,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    },
+    #[serde(rename = "event.cancelled")]
+    EventCancelled { event_id: Uuid },
+    /// Taken down by moderators
+    #[serde(rename = "event.hidden")]
+    EventHidden { event_id: Uuid },
+    #[serde(rename = "rsvp.changed")]
+    RsvpChanged {
+        event_id: Uuid,
+        user_id: Uuid,
+        attending: bool,
+        rsvps: i64,
+        capacity: Option<i32>,
+    },
+    /// Attendance was verified, or released from review
+    #[serde(rename = "verification.accepted")]
+    VerificationAccepted {
+        event_id: Uuid,
+        verification_id: Uuid,
+        user_id: Uuid,
+    },
+}
+
+impl WebhookEvent {
+    /// The event this change is about
+    #[must_use]
+    pub const fn event_id(&self) -> Uuid {
+        match self {
+            Self::EventCreated { event_id, .. }
+            | Self::EventCancelled { event_id }
+            | Self::EventHidden { event_id }
+            | Self::RsvpChanged { event_id, .. }
+            | Self::VerificationAccepted { event_id, .. } => *event_id,
+        }
+    }
+
+    /// The event type subscriptions select on
+    #[must_use]
+    pub const fn event_type(&self) -> &'static str {
+        match self {
+            Self::EventCreated { .. } => "event.created",
+            Self::EventCancelled { .. } => "event.cancelled",
+            Self::EventHidden { .. } => "event.hidden",
+            Self::RsvpChanged { .. } => "rsvp.changed",
+            Self::VerificationAccepted { .. } => "verification.accepted",
+        }
+    }
+
+    /// The envelope's `data`, with people's IDs removed unless `include_pii`
+    #[must_use]
+    pub fn data(&self, include_pii: bool) -> Value {
+        let mut data = serde_json::to_value(self).unwrap_or_default();
+        if let Some(fields) = data.as_object_mut() {
+            fields.remove("type");
+            if !include_pii {
+                for field in PII_FIELDS {
+                    fields.remove(field);
+                }
+            }
+        }
+        data
+    }
+}
+
+/// Webhook delivery configuration
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookConfig {
+    /// Allow plain-HTTP subscription URLs (development and tests only)
+    #[serde(default)]
+    pub allow_http: bool,
+
+    /// Allow loopback and private addresses (development and tests only)
+    #[serde(default)]
+    pub allow_private_networks: bool,
+
+    /// Attempts before a delivery becomes a dead letter
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: i32,
+
+    /// Wait before the first retry; doubles with each further attempt
+    #[serde(default = "default_retry_base_secs")]
+    pub retry_base_secs: u64,
+
+    /// Seconds between checks for due deliveries
+    #[serde(default = "default_poll_interval_secs")]
+    pub poll_interval_secs: u64,
+
+    /// Deliveries attempted per check
+    #[serde(default = "default_batch_size")]
+    pub batch_size: i64,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            allow_http: false,
+            allow_private_networks: false,
+            max_attempts: default_max_attempts(),
+            retry_base_secs: default_retry_base_secs(),
+            poll_interval_secs: default_poll_interval_secs(),
+            batch_size: default_batch_size(),
+        }
+    }
+}
+
+const fn default_max_attempts() -> i32 {
+    8
+}
+
+const fn default_retry_base_secs() -> u64 {
+    30
+}
+
+const fn default_poll_interval_secs() -> u64 {
+    5
+}
+
+const fn default_batch_size() -> i64 {
+    50
+}
+
+/// Check a subscription URL is one we'll post to
+///
+/// # Errors
+///
+/// Returns [`ApiError::InvalidInput`] unless the URL is absolute `https`
+/// (or `http`, if allowed) with a host that is not, and doesn't resolve
+/// to, a private address (unless allowed).
+pub async fn check_url(url: &str, config: &WebhookConfig) -> Result<()> {
+    let url = Url::parse(url).map_err(|_| ApiError::InvalidInput("Invalid URL".into()))?;
+    let scheme_ok = url.scheme() == "https" || (config.allow_http && url.scheme() == "http");
+    if !scheme_ok || url.host_str().is_none() {
+        return Err(ApiError::InvalidInput("Webhook URLs must use HTTPS".into()));
+    }
+    if !config.allow_private_networks {
+        outbound::check_resolved(&url).await?;
+    }
+    Ok(())
+}
+
+/// Queue a change for the event's organizer's and chapter's subscriptions
+///
+/// Call inside the transaction making the change.
+///
+/// # Errors
+///
+/// Returns an error if a query fails.
+pub async fn queue(conn: &mut PgConnection, event: &WebhookEvent) -> Result<()> {
+    let subscribers = db::webhooks::subscribers(conn, event.event_id(), event.event_type()).await?;
+    for subscriber in subscribers {
+        let data = event.data(subscriber.include_pii);
+        db::webhooks::enqueue(conn, subscriber.id, event.event_type(), &data).await?;
+    }
+    Ok(())
+}
+
+/// `X-CivicConnect-Signature` value for a body sent at `timestamp`
+///
+/// # Panics
+///
+/// Never; HMAC accepts keys of any length.
+#[must_use]
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    #[allow(clippy::expect_used)]
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!(
+        "t={timestamp},v1={}",
+        hex::encode(mac.finalize().into_bytes())
+    )
+}
+
+/// Wait before the attempt after `attempts` failed ones
+#[must_use]
+pub fn backoff(attempts: i32, base_secs: u64) -> Duration {
+    let doublings = u32::try_from(attempts.saturating_sub(1))
+        .unwrap_or(0)
+        .min(20);
+    Duration::from_secs(base_secs.saturating_mul(1 << doublings)).min(MAX_BACKOFF)
+}
+
+/// Posts due deliveries
+#[derive(Debug, Clone)]
+pub struct Dispatcher {
+    client: reqwest::Client,
+    config: WebhookConfig,
+}
+
+impl Dispatcher {
+    /// A dispatcher with its own HTTP client
+    ///
+    /// The client reaches only public addresses unless the configuration
+    /// allows private ones, whatever a URL resolved to when subscribed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP client can't be built.
+    pub fn new(config: WebhookConfig) -> Result<Self> {
+        let client = outbound::client_builder(config.allow_private_networks)
+            .timeout(DELIVERY_TIMEOUT)
+            .user_agent(concat!("CivicConnect/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .map_err(|e| ApiError::Internal(e.into()))?;
+
+        Ok(Self { client, config })
+    }
+
+    /// Attempt every due delivery once; returns how many were attempted
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a query fails.
+    pub async fn deliver_due(&self, db: &PgPool) -> Result<usize> {
+        let due =
+            db::webhooks::claim_due(db, self.config.batch_size, CLAIM_LEASE.as_secs_f64()).await?;
+        let outcomes =
+            futures_util::future::join_all(due.iter().map(|delivery| self.attempt(delivery))).await;
+
+        for (delivery, outcome) in due.iter().zip(outcomes) {
+            match outcome {
+                Ok(()) => db::webhooks::delivered(db, delivery.id).await?,
+                Err(error) if delivery.attempts >= self.config.max_attempts => {
+                    tracing::warn!(
+                        delivery_id = %delivery.id,
+                        subscription_id = %delivery.subscription_id,
+                        %error,
+                        "Webhook delivery dead-lettered"
+                    );
+                    db::webhooks::dead_letter(db, delivery.id, &error).await?;
+                }
+                Err(error) => {
+                    let wait = backoff(delivery.attempts, self.config.retry_base_secs);
+                    let at = Utc::now() + chrono::Duration::from_std(wait).unwrap_or_default();
+                    db::webhooks::retry_at(db, delivery.id, at, &error).await?;
+                }
+            }
+        }
+
+        Ok(due.len())
+    }
+
+    /// Post one delivery; the error describes why it failed
+    async fn attempt(&self, delivery: &DueDelivery) -> std::result::Result<(), String> {
+        let body = json!({
+            "id": delivery.id,
+            "type": delivery.event_type,
+            "created_at": delivery.created_at,
+            "data": delivery.payload,
+        })
+        .to_string();
+        let signature = sign(&delivery.secret, Utc::now().timestamp(), body.as_bytes());
+
+        let url = Url::parse(&delivery.url).map_err(|e| e.to_string())?;
+        if !self.config.allow_private_networks {
+            outbound::check_host(&url).map_err(|e| e.to_string())?;
+        }
+
+        let response = self
+            .client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header("X-CivicConnect-Event", &delivery.event_type)
+            .header("X-CivicConnect-Delivery", delivery.id.to_string())
+            .header("X-CivicConnect-Signature", signature)
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| e.without_url().to_string())?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!("HTTP {}", response.status()))
+        }
+    }
+}
+
+/// Deliver due webhooks forever
+pub async fn run_deliveries(db: PgPool, dispatcher: Dispatcher) {
+    let mut ticker = tokio::time::interval(Duration::from_secs(
+        dispatcher.config.poll_interval_secs.max(1),
+    ));
+
+    loop {
+        ticker.tick().await;
+        // Keep going while there's a backlog
+        loop {
+            match dispatcher.deliver_due(&db).await {
+                Ok(attempted)
+                    if usize::try_from(dispatcher.config.batch_size)
+                        .is_ok_and(|batch| attempted >= batch) => {}
+                Ok(_) => break,
+                Err(e) => {
+                    tracing::error!(error = ?e, "Webhook delivery failed");
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payloads_are_scrubbed_by_default() {
+        let event = WebhookEvent::RsvpChanged {
+            event_id: Uuid::nil(),
+            user_id: Uuid::max(),
+            attending: true,
+            rsvps: 3,
+            capacity: Some(10),
+        };
+
+        let scrubbed = event.data(false);
+        assert_eq!(scrubbed["rsvps"], 3);
+        assert!(scrubbed.get("user_id").is_none());
+        assert!(scrubbed.get("type").is_none());
+        assert_eq!(event.data(true)["user_id"], Uuid::max().to_string());
+        assert!(EVENT_TYPES.contains(&event.event_type()));
+    }
+
+    #[test]
+    fn signatures_cover_timestamp_and_body() {
+        let signature = sign("secret", 1_700_000_000, b"{}");
+        assert!(signature.starts_with("t=1700000000,v1="));
+        assert_eq!(signature.len(), "t=1700000000,v1=".len() + 64);
+
+        assert_ne!(signature, sign("secret", 1_700_000_001, b"{}"));
+        assert_ne!(signature, sign("secret", 1_700_000_000, b"[]"));
+        assert_ne!(signature, sign("other", 1_700_000_000, b"{}"));
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_a_cap() {
+        assert_eq!(backoff(1, 30), Duration::from_secs(30));
+        assert_eq!(backoff(2, 30), Duration::from_secs(60));
+        assert_eq!(backoff(4, 30), Duration::from_secs(240));
+        assert_eq!(backoff(40, 30), MAX_BACKOFF);
+        assert_eq!(backoff(3, 0), Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn only_https_urls_unless_allowed() {
+        let strict = WebhookConfig::default();
+        let http = WebhookConfig {
+            allow_http: true,
+            ..WebhookConfig::default()
+        };
+
+        assert!(check_url("https://hooks.example/civic", &strict)
+            .await
+            .is_ok());
+        assert!(check_url("http://hooks.example/civic", &strict)
+            .await
+            .is_err());
+        assert!(check_url("http://hooks.example/civic", &http).await.is_ok());
+        assert!(check_url("ftp://hooks.example/civic", &http).await.is_err());
+        assert!(check_url("not a url", &http).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn only_public_hosts_unless_allowed() {
+        let strict = WebhookConfig::default();
+        let private = WebhookConfig {
+            allow_private_networks: true,
+            ..WebhookConfig::default()
+        };
+
+        for url in [
+            "https://127.0.0.1/civic",
+            "https://10.0.0.5/civic",
+            "https://169.254.169.254/latest/meta-data",
+            "https://[::1]/civic",
+            "https://localhost/civic",
+        ] {
+            assert!(check_url(url, &strict).await.is_err(), "{url}");
+            assert!(check_url(url, &private).await.is_ok(), "{url}");
+        }
+    }
+}
@@ -0,0 +1,322 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Moderation queue and admin actions
+
+#![allow(clippy::unwrap_used, clippy::expect_used, clippy::future_not_send)]
+
+mod common;
+
+use axum::http::StatusCode;
+use axum_test::TestServer;
+use chrono::Utc;
+use civicconnect_api::{
+    audit,
+    state::AppState,
+    verification::{self, Attendance, XP_EVENT_ATTENDANCE},
+};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Register a user with a role and return their session token and ID
+async fn staff(server: &TestServer, pool: &PgPool, username: &str, role: &str) -> (String, Uuid) {
+    let body = common::register(server, username).await;
+    let id: Uuid = body["user_id"].as_str().unwrap().parse().unwrap();
+    sqlx::query("UPDATE users SET role = $2, current_level = 3 WHERE id = $1")
+        .bind(id)
+        .bind(role)
+        .execute(pool)
+        .await
+        .unwrap();
+
+    (body["token"].as_str().unwrap().to_string(), id)
+}
+
+async fn event(pool: &PgPool, organizer_id: Uuid) -> Uuid {
+    let id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO events (id, organizer_id, title, description, location_hash, start_time, end_time)
+         VALUES ($1, $2, 'Cleanup', '', '87195da49ffffff', now(), now() + interval '1 hour')",
+    )
+    .bind(id)
+    .bind(organizer_id)
+    .execute(pool)
+    .await
+    .unwrap();
+    id
+}
+
+async fn progress(pool: &PgPool, user_id: Uuid) -> (i16, i32) {
+    sqlx::query_as("SELECT current_level, experience_points FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn moderator_reviews_held_verification(pool: PgPool) {
+    let server = common::server(pool.clone(), common::test_config());
+    let state = AppState::new(common::test_config(), pool.clone()).unwrap();
+    let (moderator, _) = staff(&server, &pool, "moderator", "moderator").await;
+    let member = common::register(&server, "member").await;
+    let member_token = member["token"].as_str().unwrap();
+    let member_id: Uuid = member["user_id"].as_str().unwrap().parse().unwrap();
+
+    // Organizer scanning their own event is held
+    let event_id = event(&pool, member_id).await;
+    let held = verification::record(
+        &state,
+        Attendance {
+            event_id,
+            user_id: member_id,
+            organizer_id: member_id,
+            signature: &[1, 2, 3],
+            location_cell: "87195da49ffffff",
+            at: Utc::now(),
+            attested: false,
+        },
+    )
+    .await
+    .unwrap();
+    assert!(held.is_held());
+
+    // Members have no access
+    common::bearer(server.get("/api/v1/admin/verifications/held"), member_token)
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+
+    let queue: Value = common::bearer(server.get("/api/v1/admin/verifications/held"), &moderator)
+        .await
+        .json();
+    assert_eq!(queue[0]["id"], held.verification.id.to_string());
+    assert_eq!(
+        queue[0]["fraud_findings"][0]["rule"],
+        "implausible_attendance"
+    );
+
+    let review = format!(
+        "/api/v1/admin/verifications/{}/review",
+        held.verification.id
+    );
+    let action: Value = common::bearer(server.post(&review), &moderator)
+        .json(&json!({ "decision": "release", "reason": "Co-organizer, checked with the group" }))
+        .await
+        .json();
+    assert_eq!(action["action"], "release_verification");
+    assert_eq!(progress(&pool, member_id).await.1, XP_EVENT_ATTENDANCE);
+
+    // Reviewed once, then gone from the queue
+    common::bearer(server.post(&review), &moderator)
+        .json(&json!({ "decision": "reject", "reason": "Changed my mind" }))
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+    let queue: Value = common::bearer(server.get("/api/v1/admin/verifications/held"), &moderator)
+        .await
+        .json();
+    assert_eq!(queue, json!([]));
+
+    // Moderators can hide events but not suspend users
+    common::bearer(
+        server.post(&format!("/api/v1/admin/events/{event_id}/hide")),
+        &moderator,
+    )
+    .json(&json!({ "reason": "Spam" }))
+    .await
+    .assert_status_ok();
+    common::bearer(
+        server.post(&format!("/api/v1/admin/users/{member_id}/suspend")),
+        &moderator,
+    )
+    .json(&json!({ "reason": "Spam" }))
+    .await
+    .assert_status(StatusCode::FORBIDDEN);
+
+    // Verification, release and hide are all in the chain
+    let report = audit::verify_chain(&pool, None).await.unwrap();
+    assert!(report.is_intact(), "{report:?}");
+    assert_eq!(report.entries_checked, 3);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn staff_cannot_review_their_own_verification(pool: PgPool) {
+    let server = common::server(pool.clone(), common::test_config());
+    let state = AppState::new(common::test_config(), pool.clone()).unwrap();
+    let (moderator, moderator_id) = staff(&server, &pool, "moderator", "moderator").await;
+
+    let event_id = event(&pool, moderator_id).await;
+    let held = verification::record(
+        &state,
+        Attendance {
+            event_id,
+            user_id: moderator_id,
+            organizer_id: moderator_id,
+            signature: &[1, 2, 3],
+            location_cell: "87195da49ffffff",
+            at: Utc::now(),
+            attested: false,
+        },
+    )
+    .await
+    .unwrap();
+    assert!(held.is_held());
+
+    common::bearer(
+        server.post(&format!(
+            "/api/v1/admin/verifications/{}/review",
+            held.verification.id
+        )),
+        &moderator,
+    )
+    .json(&json!({ "decision": "release", "reason": "It was me, honestly" }))
+    .await
+    .assert_status(StatusCode::BAD_REQUEST);
+    assert_eq!(progress(&pool, moderator_id).await.1, 0);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn hidden_events_are_not_reported_as_cancelled(pool: PgPool) {
+    let server = common::server(pool.clone(), common::test_config());
+    let (moderator, _) = staff(&server, &pool, "moderator", "moderator").await;
+    let (organizer, organizer_id) = staff(&server, &pool, "organizer", "member").await;
+    let event_id = event(&pool, organizer_id).await;
+    common::bearer(server.post("/api/v1/webhooks"), &organizer)
+        .json(&json!({
+            "url": "https://hooks.example/civic",
+            "event_types": ["event.cancelled", "event.hidden"],
+        }))
+        .await
+        .assert_status(StatusCode::CREATED);
+
+    common::bearer(
+        server.post(&format!("/api/v1/admin/events/{event_id}/hide")),
+        &moderator,
+    )
+    .json(&json!({ "reason": "Spam" }))
+    .await
+    .assert_status_ok();
+
+    let queued: Vec<String> = sqlx::query_scalar("SELECT event_type FROM webhook_deliveries")
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert_eq!(queued, ["event.hidden"]);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn suspension_ends_sessions(pool: PgPool) {
+    let server = common::server(pool.clone(), common::test_config());
+    let (admin, admin_id) = staff(&server, &pool, "admin", "admin").await;
+    let member = common::register(&server, "member").await;
+    let member_token = member["token"].as_str().unwrap();
+    let member_id = member["user_id"].as_str().unwrap();
+
+    // Reasons are required
+    common::bearer(
+        server.post(&format!("/api/v1/admin/users/{member_id}/suspend")),
+        &admin,
+    )
+    .json(&json!({ "reason": "" }))
+    .await
+    .assert_status(StatusCode::BAD_REQUEST);
+
+    common::bearer(
+        server.post(&format!("/api/v1/admin/users/{member_id}/suspend")),
+        &admin,
+    )
+    .json(&json!({ "reason": "Harassment report" }))
+    .await
+    .assert_status_ok();
+
+    let rejected = common::bearer(server.post("/api/v1/auth/totp/enroll"), member_token).await;
+    rejected.assert_status(StatusCode::FORBIDDEN);
+    assert_eq!(rejected.json::<Value>()["code"], "ACCOUNT_SUSPENDED");
+
+    let login = server
+        .post("/api/v1/auth/login")
+        .json(&json!({ "email": "member@example.org", "password": "correct horse battery" }))
+        .await;
+    login.assert_status(StatusCode::FORBIDDEN);
+
+    common::bearer(
+        server.post(&format!("/api/v1/admin/users/{admin_id}/suspend")),
+        &admin,
+    )
+    .json(&json!({ "reason": "Oops" }))
+    .await
+    .assert_status(StatusCode::BAD_REQUEST);
+
+    common::bearer(
+        server.post(&format!("/api/v1/admin/users/{member_id}/unsuspend")),
+        &admin,
+    )
+    .json(&json!({ "reason": "Appeal upheld" }))
+    .await
+    .assert_status_ok();
+    common::login(&server, "member").await["token"]
+        .as_str()
+        .unwrap();
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn xp_reversal_records_level_progression(pool: PgPool) {
+    let server = common::server(pool.clone(), common::test_config());
+    let (admin, _) = staff(&server, &pool, "admin", "admin").await;
+    let member = common::register(&server, "member").await;
+    let member_id: Uuid = member["user_id"].as_str().unwrap().parse().unwrap();
+    sqlx::query("UPDATE users SET current_level = 2, experience_points = 600 WHERE id = $1")
+        .bind(member_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // Moderators need organizer level
+    common::bearer(
+        server.put(&format!("/api/v1/admin/users/{member_id}/role")),
+        &admin,
+    )
+    .json(&json!({ "role": "moderator", "reason": "Trusted organizer" }))
+    .await
+    .assert_status(StatusCode::BAD_REQUEST);
+
+    let action: Value = common::bearer(
+        server.post(&format!("/api/v1/admin/users/{member_id}/xp-reversals")),
+        &admin,
+    )
+    .json(&json!({ "amount": 200, "reason": "Farmed with a second account" }))
+    .await
+    .json();
+    assert_eq!(action["action"], "reverse_xp");
+
+    // 400 XP only supports level 1
+    assert_eq!(progress(&pool, member_id).await, (1, 400));
+
+    let (from, to, reason): (i16, i16, String) = sqlx::query_as(
+        "SELECT from_level, to_level, reason FROM level_progressions WHERE user_id = $1",
+    )
+    .bind(member_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!((from, to, reason.as_str()), (2, 1, "xp_reversal"));
+
+    // Never below zero
+    common::bearer(
+        server.post(&format!("/api/v1/admin/users/{member_id}/xp-reversals")),
+        &admin,
+    )
+    .json(&json!({ "amount": 1000, "reason": "Farmed with a second account" }))
+    .await
+    .assert_status_ok();
+    assert_eq!(progress(&pool, member_id).await, (0, 0));
+
+    let report = audit::verify_chain(&pool, None).await.unwrap();
+    assert!(report.is_intact(), "{report:?}");
+    assert_eq!(report.entries_checked, 4);
+}
@@ -0,0 +1,173 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Audit chain appends, checkpoints and tamper detection
+
+#![allow(clippy::unwrap_used, clippy::expect_used, clippy::future_not_send)]
+
+use chrono::Utc;
+use civicconnect_api::{
+    audit::{self, ChainBreak, CheckpointSigner},
+    db::audit::{self as audit_db, NewLevelProgression, NewVerification},
+};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// A user and an event for verifications to reference
+async fn fixtures(pool: &PgPool) -> (Uuid, Uuid) {
+    let user_id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO users (id, email_hash, username, password_hash)
+         VALUES ($1, $2, $3, 'x')",
+    )
+    .bind(user_id)
+    .bind(user_id.to_string())
+    .bind(format!("user-{user_id}"))
+    .execute(pool)
+    .await
+    .unwrap();
+
+    let event_id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO events (id, organizer_id, title, description, location_hash, start_time, end_time)
+         VALUES ($1, $2, 'Cleanup', '', '872a1072fffffff', now(), now() + interval '1 hour')",
+    )
+    .bind(event_id)
+    .bind(user_id)
+    .execute(pool)
+    .await
+    .unwrap();
+
+    (user_id, event_id)
+}
+
+/// Append `count` entries, alternating kinds
+async fn append(pool: &PgPool, user_id: Uuid, event_id: Uuid, count: i16) {
+    for i in 0..count {
+        let mut tx = pool.begin().await.unwrap();
+        if i == 0 {
+            audit_db::append_verification(
+                &mut tx,
+                NewVerification {
+                    event_id,
+                    user_id,
+                    organizer_id: user_id,
+                    signature: &[1, 2, 3],
+                    verified_at: Utc::now(),
+                    experience_awarded: 10,
+                    location_hash: "872a1072fffffff",
+                    status: "verified",
+                    fraud_score: 0,
+                    fraud_findings: json!([]),
+                },
+            )
+            .await
+            .unwrap();
+        } else {
+            audit_db::append_level_progression(
+                &mut tx,
+                NewLevelProgression {
+                    user_id,
+                    from_level: i - 1,
+                    to_level: i,
+                    reason: "xp_threshold",
+                    metadata: json!({ "xp": i * 100, "source": { "kind": "verification" } }),
+                },
+            )
+            .await
+            .unwrap();
+        }
+        tx.commit().await.unwrap();
+    }
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn chain_verifies_and_reports_first_break(pool: PgPool) {
+    let (user_id, event_id) = fixtures(&pool).await;
+    let signer = CheckpointSigner::new(&[5; 32]);
+    let key = signer.verifying_key();
+
+    append(&pool, user_id, event_id, 4).await;
+    assert_eq!(audit::checkpoint(&pool, &signer).await.unwrap(), Some(4));
+    assert_eq!(audit::checkpoint(&pool, &signer).await.unwrap(), None);
+
+    let report = audit::verify_chain(&pool, Some(&key)).await.unwrap();
+    assert!(report.is_intact(), "{report:?}");
+    assert_eq!(report.entries_checked, 4);
+    assert_eq!(report.checkpoints_checked, 1);
+
+    // The database refuses edits outright
+    assert!(sqlx::query("UPDATE level_progressions SET to_level = 5")
+        .execute(&pool)
+        .await
+        .is_err());
+    assert!(sqlx::query("DELETE FROM verifications")
+        .execute(&pool)
+        .await
+        .is_err());
+
+    // Someone who gets around the triggers is still caught
+    sqlx::query("ALTER TABLE level_progressions DISABLE TRIGGER USER")
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE level_progressions SET reason = 'forged' WHERE audit_seq = 3")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let report = audit::verify_chain(&pool, Some(&key)).await.unwrap();
+    assert_eq!(
+        report.first_break,
+        Some(ChainBreak::EntryModified { seq: 3 })
+    );
+    assert_eq!(report.last_valid_seq, 2);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn truncation_and_bypass_detected(pool: PgPool) {
+    let (user_id, event_id) = fixtures(&pool).await;
+    append(&pool, user_id, event_id, 3).await;
+
+    sqlx::query("ALTER TABLE level_progressions DISABLE TRIGGER USER")
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM level_progressions WHERE audit_seq = 3")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let report = audit::verify_chain(&pool, None).await.unwrap();
+    assert_eq!(
+        report.first_break,
+        Some(ChainBreak::Truncated {
+            expected_seq: 3,
+            last_seq: 2
+        })
+    );
+
+    // A row inserted without going through the chain
+    let id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO level_progressions (id, user_id, from_level, to_level, reason)
+         VALUES ($1, $2, 0, 5, 'manual')",
+    )
+    .bind(id)
+    .bind(user_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let report = audit::verify_chain(&pool, None).await.unwrap();
+    assert_eq!(
+        report.first_break,
+        Some(ChainBreak::Unchained {
+            kind: "level_progression",
+            id
+        })
+    );
+}
@@ -0,0 +1,113 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! iCalendar export and personal calendar feeds
+
+#![allow(clippy::unwrap_used, clippy::expect_used, clippy::future_not_send)]
+
+mod common;
+
+use axum::http::StatusCode;
+use serde_json::Value;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+async fn event(pool: &PgPool, organizer_id: Uuid, title: &str, venue: Option<&str>) -> Uuid {
+    let id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO events (id, organizer_id, title, description, location_hash, start_time, end_time, venue)
+         VALUES ($1, $2, $3, '', '87195da49ffffff', now() + interval '1 day', now() + interval '25 hours', $4)",
+    )
+    .bind(id)
+    .bind(organizer_id)
+    .bind(title)
+    .bind(venue)
+    .execute(pool)
+    .await
+    .unwrap();
+    id
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn event_exports_as_ics(pool: PgPool) {
+    let server = common::server(pool.clone(), common::test_config());
+    let organizer = common::register(&server, "organizer").await;
+    let organizer_id: Uuid = organizer["user_id"].as_str().unwrap().parse().unwrap();
+    let event_id = event(&pool, organizer_id, "Cleanup", Some("Community hall")).await;
+
+    let details: Value = server
+        .get(&format!("/api/v1/events/{event_id}"))
+        .await
+        .json();
+    assert_eq!(details["venue"], "Community hall");
+    assert_eq!(details["organizer_username"], "organizer");
+
+    let ics = server.get(&format!("/api/v1/events/{event_id}.ics")).await;
+    ics.assert_status_ok();
+    assert!(ics
+        .header("content-type")
+        .to_str()
+        .unwrap()
+        .starts_with("text/calendar"));
+    let body = ics.text();
+    assert!(body.contains(&format!("\r\nUID:{event_id}\r\n")));
+    assert!(body.contains("\r\nLOCATION:Community hall\r\n"));
+    assert!(body.contains("\r\nGEO:"));
+
+    server
+        .get(&format!("/api/v1/events/{}.ics", Uuid::new_v4()))
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn feed_lists_rsvps_until_revoked(pool: PgPool) {
+    let server = common::server(pool.clone(), common::test_config());
+    let organizer = common::register(&server, "organizer").await;
+    let organizer_id: Uuid = organizer["user_id"].as_str().unwrap().parse().unwrap();
+    let member = common::register(&server, "member").await;
+    let token = member["token"].as_str().unwrap();
+
+    let going = event(&pool, organizer_id, "Cleanup", None).await;
+    event(&pool, organizer_id, "Town hall", None).await;
+    common::bearer(server.put(&format!("/api/v1/events/{going}/rsvp")), token)
+        .await
+        .assert_status_ok();
+
+    let issued: Value = common::bearer(server.put("/api/v1/users/me/calendar-feed"), token)
+        .await
+        .json();
+    let url = issued["url"].as_str().unwrap();
+    let path = url.strip_prefix(common::PUBLIC_URL).unwrap();
+
+    let body = server.get(path).await.text();
+    assert!(body.contains("\r\nSUMMARY:Cleanup\r\n"));
+    assert!(!body.contains("Town hall"));
+    assert!(!body.contains("LOCATION:"));
+
+    // Rotating the URL revokes the old one
+    let rotated: Value = common::bearer(server.put("/api/v1/users/me/calendar-feed"), token)
+        .await
+        .json();
+    server
+        .get(path)
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+    let path = rotated["url"]
+        .as_str()
+        .unwrap()
+        .strip_prefix(common::PUBLIC_URL)
+        .unwrap()
+        .to_string();
+    server.get(&path).await.assert_status_ok();
+
+    common::bearer(server.delete("/api/v1/users/me/calendar-feed"), token)
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    server
+        .get(&path)
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,123 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Shared setup for API integration tests
+//!
+//! These tests need a Postgres server. `#[sqlx::test]` creates a fresh database
+//! per test from `DATABASE_URL`, so they are ignored by default; run with
+//! `cargo test -- --include-ignored` against a local server.
+
+#![allow(
+    dead_code,
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::future_not_send
+)]
+
+use axum::http::{header, HeaderValue};
+use axum_test::{TestRequest, TestServer};
+use civicconnect_api::{
+    config::{Config, PasswordConfig},
+    credentials::CredentialsConfig,
+    crypto::password::PasswordParams,
+    federation::FederationConfig,
+    fraud::FraudConfig,
+    idempotency::IdempotencyConfig,
+    realtime::RealtimeConfig,
+    retention::RetentionConfig,
+    routes,
+    server::ServerConfig,
+    state::AppState,
+    telemetry::TelemetryConfig,
+    verification::ScanConfig,
+    webhooks::WebhookConfig,
+};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+/// Origin the test server pretends to be served from
+pub const PUBLIC_URL: &str = "http://localhost:8080";
+
+/// Secret the test server signs tokens with
+pub const JWT_SECRET: &str = "integration-test-secret";
+
+/// Configuration with cheap password hashing
+pub fn test_config() -> Config {
+    Config {
+        bind_addr: "127.0.0.1:0".into(),
+        public_url: PUBLIC_URL.into(),
+        database_url: String::new(),
+        jwt_secret: JWT_SECRET.into(),
+        blind_index_keys: vec!["11".repeat(32)],
+        email_reindex_interval_secs: 3600,
+        token_ttl_hours: 1,
+        password: PasswordConfig {
+            params: PasswordParams {
+                memory_kib: 1024,
+                iterations: 2,
+                lanes: 1,
+            },
+            recovery_codes: PasswordParams {
+                memory_kib: 1024,
+                iterations: 2,
+                lanes: 1,
+            },
+            max_concurrent: 2,
+        },
+        recovery_key_file: None,
+        mail_sink_dir: None,
+        webauthn_required_level: None,
+        audit_signing_key_file: None,
+        audit_checkpoint_interval_secs: 3600,
+        fraud: FraudConfig::default(),
+        scan: ScanConfig::default(),
+        credentials: CredentialsConfig::default(),
+        redis_url: None,
+        realtime: RealtimeConfig::default(),
+        federation: FederationConfig::default(),
+        webhooks: WebhookConfig::default(),
+        server: ServerConfig::default(),
+        idempotency: IdempotencyConfig::default(),
+        retention: RetentionConfig::default(),
+        telemetry: TelemetryConfig::default(),
+    }
+}
+
+/// Test server over `pool`
+pub fn server(pool: PgPool, config: Config) -> TestServer {
+    let state = AppState::new(config, pool).unwrap();
+    TestServer::new(routes::create_router(state)).unwrap()
+}
+
+/// Add a bearer token to a request
+pub fn bearer(request: TestRequest, token: &str) -> TestRequest {
+    request.add_header(
+        header::AUTHORIZATION,
+        HeaderValue::from_str(&format!("Bearer {token}")).unwrap(),
+    )
+}
+
+/// Register a user and return the response body
+pub async fn register(server: &TestServer, username: &str) -> Value {
+    server
+        .post("/api/v1/auth/register")
+        .json(&json!({
+            "email": format!("{username}@example.org"),
+            "username": username,
+            "password": "correct horse battery",
+        }))
+        .await
+        .json()
+}
+
+/// Log in with a password and return the response body
+pub async fn login(server: &TestServer, username: &str) -> Value {
+    server
+        .post("/api/v1/auth/login")
+        .json(&json!({
+            "email": format!("{username}@example.org"),
+            "password": "correct horse battery",
+        }))
+        .await
+        .json()
+}
@@ -0,0 +1,208 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Unlinkable attendance credentials: blind issuance and redemption
+
+#![allow(clippy::unwrap_used, clippy::expect_used, clippy::future_not_send)]
+
+mod common;
+
+use axum::http::StatusCode;
+use axum_test::TestServer;
+use chrono::Utc;
+use civicconnect_api::{credentials, crypto, verification};
+use rsa::pkcs8::DecodePrivateKey;
+use rsa::traits::{PrivateKeyParts, PublicKeyParts};
+use rsa::{BigUint, RsaPrivateKey};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+const KEY_FILE: &str = "tests/fixtures/credentials.pem";
+
+/// Resolution 7 cell in central London
+const LONDON: &str = "87195da49ffffff";
+
+fn config() -> civicconnect_api::config::Config {
+    let mut config = common::test_config();
+    config.credentials.key_file = Some(KEY_FILE.into());
+    config
+}
+
+/// An organizer with a registered signing key, running an event now
+async fn event(server: &TestServer, pool: &PgPool) -> (Uuid, Uuid, ed25519_dalek::SigningKey) {
+    let session = common::register(server, "olive").await;
+    let (key, public) = crypto::generate_keypair();
+    common::bearer(
+        server.put("/api/v1/users/me/signing-key"),
+        session["token"].as_str().unwrap(),
+    )
+    .json(&json!({ "public_key": hex::encode(public.to_bytes()) }))
+    .await
+    .assert_status(StatusCode::NO_CONTENT);
+    let organizer_id: Uuid = session["user_id"].as_str().unwrap().parse().unwrap();
+
+    let id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO events (id, organizer_id, title, description, location_hash, start_time, end_time)
+         VALUES ($1, $2, 'Cleanup', '', $3, now() - interval '1 hour', now() + interval '1 hour')",
+    )
+    .bind(id)
+    .bind(organizer_id)
+    .bind(LONDON)
+    .execute(pool)
+    .await
+    .unwrap();
+
+    (id, organizer_id, key)
+}
+
+/// A scan of a code shown just now, asking for `blinded` to be signed
+fn request(
+    key: &ed25519_dalek::SigningKey,
+    event_id: Uuid,
+    organizer_id: Uuid,
+    blinded: &[u8],
+) -> Value {
+    let now = Utc::now();
+    let nonce = crypto::generate_nonce();
+    let message = verification::qr_message(event_id, organizer_id, now, &nonce);
+    json!({
+        "event_id": event_id,
+        "organizer_id": organizer_id,
+        "timestamp": now,
+        "nonce": nonce,
+        "signature": hex::encode(crypto::sign_message(key, &message).to_bytes()),
+        "location_cell": LONDON,
+        "blinded_token": hex::encode(blinded),
+    })
+}
+
+/// A client's side of blinding, with the blinding factor `r`
+struct Blinding {
+    n: BigUint,
+    len: usize,
+    r_inv: BigUint,
+}
+
+impl Blinding {
+    fn new() -> (Self, BigUint) {
+        // Only the public key is needed to blind; the test inverts `r`
+        // with the factors rather than implementing the Euclidean algorithm
+        let key =
+            RsaPrivateKey::from_pkcs8_pem(&std::fs::read_to_string(KEY_FILE).unwrap()).unwrap();
+        let one = BigUint::from(1u8);
+        let phi = key
+            .primes()
+            .iter()
+            .fold(one.clone(), |phi, p| phi * (p - &one));
+        let r = BigUint::from_bytes_be(&[0x5a; 48]);
+        let r_inv = r.modpow(&(phi - &one), key.n());
+        let re = r.modpow(key.e(), key.n());
+
+        let blinding = Self {
+            n: key.n().clone(),
+            len: key.size(),
+            r_inv,
+        };
+        (blinding, re)
+    }
+
+    fn pad(&self, value: &BigUint) -> Vec<u8> {
+        let bytes = value.to_bytes_be();
+        let mut padded = vec![0; self.len - bytes.len()];
+        padded.extend_from_slice(&bytes);
+        padded
+    }
+
+    fn blind(&self, re: &BigUint, token: &[u8]) -> Vec<u8> {
+        let m = credentials::message_representative(&self.n, token);
+        self.pad(&((m * re) % &self.n))
+    }
+
+    fn unblind(&self, blind_signature: &str) -> String {
+        let s = BigUint::from_bytes_be(&hex::decode(blind_signature).unwrap());
+        hex::encode(self.pad(&((s * &self.r_inv) % &self.n)))
+    }
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn blind_credentials_are_redeemed_once_for_xp(pool: PgPool) {
+    let server = common::server(pool.clone(), config());
+    let (event_id, organizer_id, key) = event(&server, &pool).await;
+    let session = common::register(&server, "alice").await;
+    let alice = session["token"].as_str().unwrap();
+
+    let published: Value = server.get("/api/v1/credentials/key").await.json();
+    assert!(published["public_key_pem"]
+        .as_str()
+        .unwrap()
+        .starts_with("-----BEGIN PUBLIC KEY-----"));
+
+    let (blinding, re) = Blinding::new();
+    let token = [7u8; credentials::TOKEN_LEN];
+    let response = common::bearer(server.post("/api/v1/credentials/attendance"), alice)
+        .json(&request(
+            &key,
+            event_id,
+            organizer_id,
+            &blinding.blind(&re, &token),
+        ))
+        .await;
+    response.assert_status_ok();
+    let issued: Value = response.json();
+    assert_eq!(issued["key_id"], published["key_id"]);
+
+    // One credential per event, and nothing the database ties to it
+    let response = common::bearer(server.post("/api/v1/credentials/attendance"), alice)
+        .json(&request(
+            &key,
+            event_id,
+            organizer_id,
+            &blinding.blind(&re, &[8; 32]),
+        ))
+        .await;
+    assert_eq!(response.json::<Value>()["code"], "ALREADY_VERIFIED");
+    let verifications: i64 = sqlx::query_scalar("SELECT count(*) FROM verifications")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(verifications, 0);
+
+    let credential = json!({
+        "token": hex::encode(token),
+        "signature": blinding.unblind(issued["blind_signature"].as_str().unwrap()),
+    });
+    let forged = json!({ "token": hex::encode([9u8; 32]), "signature": credential["signature"] });
+    let response = common::bearer(server.post("/api/v1/credentials/redeem"), alice)
+        .json(&json!({ "credentials": [credential, forged, credential] }))
+        .await;
+    response.assert_status_ok();
+    let redeemed: Value = response.json();
+    assert_eq!(redeemed["redeemed"], 1);
+    assert_eq!(redeemed["invalid"], 1);
+    assert_eq!(redeemed["already_spent"], 1);
+    assert_eq!(redeemed["xp_awarded"], 25);
+    assert_eq!(redeemed["new_total_xp"], 25);
+    assert_eq!(redeemed["attendances"], 1);
+
+    // Redeemable by anyone holding it, but only once
+    let bob = common::register(&server, "bob").await;
+    let response = common::bearer(
+        server.post("/api/v1/credentials/redeem"),
+        bob["token"].as_str().unwrap(),
+    )
+    .json(&json!({ "credentials": [credential] }))
+    .await;
+    assert_eq!(response.json::<Value>()["already_spent"], 1);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn credentials_are_off_without_a_key(pool: PgPool) {
+    let server = common::server(pool, common::test_config());
+    let response = server.get("/api/v1/credentials/key").await;
+    response.assert_status_bad_request();
+    assert_eq!(response.json::<Value>()["code"], "INVALID_INPUT");
+}
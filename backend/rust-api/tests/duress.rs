@@ -0,0 +1,650 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Duress mode: sanitized logins, session revocation and contact alerts
+
+#![allow(clippy::unwrap_used, clippy::expect_used, clippy::future_not_send)]
+
+mod common;
+
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use axum_test::TestServer;
+use chrono::Utc;
+use civicconnect_api::{crypto::totp, realtime::EventUpdate, routes, state::AppState};
+use data_encoding::BASE32_NOPAD;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+const DURESS_PASSWORD: &str = "purple monkey dishwasher";
+
+async fn login_with(server: &TestServer, username: &str, password: &str) -> String {
+    let response = server
+        .post("/api/v1/auth/login")
+        .json(&json!({
+            "email": format!("{username}@example.org"),
+            "password": password,
+        }))
+        .await;
+    response.assert_status_ok();
+    response.json::<Value>()["token"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+async fn get(server: &TestServer, path: &str, token: &str) -> axum_test::TestResponse {
+    common::bearer(server.get(path), token).await
+}
+
+fn id(body: &Value) -> Uuid {
+    body["id"].as_str().unwrap().parse().unwrap()
+}
+
+/// Alice with a duress password and TOTP; returns her session, TOTP
+/// secret and recovery codes
+async fn alice_with_totp(server: &TestServer) -> (String, Vec<u8>, Vec<String>) {
+    let token = common::register(server, "alice").await["token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    common::bearer(server.put("/api/v1/users/me/duress-password"), &token)
+        .json(&json!({ "password": DURESS_PASSWORD }))
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+
+    let enrollment: Value = common::bearer(server.post("/api/v1/auth/totp/enroll"), &token)
+        .await
+        .json();
+    let secret = BASE32_NOPAD
+        .decode(enrollment["secret"].as_str().unwrap().as_bytes())
+        .unwrap();
+    let confirmed: Value = common::bearer(server.post("/api/v1/auth/totp/confirm"), &token)
+        .json(&json!({ "code": totp_code(&secret, 0) }))
+        .await
+        .json();
+    let codes = confirmed["recovery_codes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|code| code.as_str().unwrap().to_string())
+        .collect();
+
+    (token, secret, codes)
+}
+
+fn totp_code(secret: &[u8], offset: i64) -> String {
+    totp::code_at(secret, totp::step_at(Utc::now().timestamp()) + offset)
+}
+
+/// The password step of Alice's login, which leaves a second factor to go
+async fn mfa_token(server: &TestServer, password: &str) -> String {
+    let response = server
+        .post("/api/v1/auth/login")
+        .json(&json!({ "email": "alice@example.org", "password": password }))
+        .await;
+    response.assert_status_ok();
+    response.json::<Value>()["mfa_token"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+async fn under_duress(pool: &PgPool) -> bool {
+    sqlx::query_scalar("SELECT duress_at IS NOT NULL FROM users WHERE username = 'alice'")
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+/// An organizer's network, and a session of theirs under duress
+struct Network {
+    server: TestServer,
+    /// Alice's session under duress
+    duress: String,
+    /// Bob, a member of Alice's organization and co-organizer of her event
+    bob: Uuid,
+    organization: Uuid,
+    invitation: Uuid,
+    event: Uuid,
+    webhook: Uuid,
+}
+
+async fn network(pool: &PgPool) -> Network {
+    let server = common::server(pool.clone(), common::test_config());
+    let alice = common::register(&server, "alice").await;
+    let alice_token = alice["token"].as_str().unwrap();
+    let alice_id: Uuid = alice["user_id"].as_str().unwrap().parse().unwrap();
+    let bob = common::register(&server, "bob").await;
+    let bob_id: Uuid = bob["user_id"].as_str().unwrap().parse().unwrap();
+    sqlx::query("UPDATE users SET current_level = 3")
+        .execute(pool)
+        .await
+        .unwrap();
+
+    let organization: Value = common::bearer(server.post("/api/v1/organizations"), alice_token)
+        .json(&json!({ "name": "East Side Tenants", "slug": "east-side" }))
+        .await
+        .json();
+    let organization = id(&organization);
+    let invite = || {
+        common::bearer(
+            server.post(&format!("/api/v1/organizations/{organization}/invitations")),
+            alice_token,
+        )
+        .json(&json!({}))
+    };
+    let url = invite().await.json::<Value>()["url"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    common::bearer(
+        server.post(url.strip_prefix(common::PUBLIC_URL).unwrap()),
+        bob["token"].as_str().unwrap(),
+    )
+    .await
+    .assert_status_ok();
+    let invitation = id(&invite().await.json());
+
+    let event = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO events (id, organizer_id, title, description, location_hash, start_time, end_time)
+         VALUES ($1, $2, 'Cleanup', '', '87195da49ffffff', now() + interval '1 day', now() + interval '25 hours')",
+    )
+    .bind(event)
+    .bind(alice_id)
+    .execute(pool)
+    .await
+    .unwrap();
+    common::bearer(
+        server.put(&format!("/api/v1/events/{event}/co-organizers/{bob_id}")),
+        alice_token,
+    )
+    .await
+    .assert_status(StatusCode::NO_CONTENT);
+
+    let webhook: Value = common::bearer(server.post("/api/v1/webhooks"), alice_token)
+        .json(&json!({
+            "url": "https://hooks.example/civic",
+            "event_types": ["event.cancelled"],
+        }))
+        .await
+        .json();
+
+    common::bearer(server.put("/api/v1/users/me/duress-password"), alice_token)
+        .json(&json!({ "password": DURESS_PASSWORD }))
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    let duress = login_with(&server, "alice", DURESS_PASSWORD).await;
+
+    Network {
+        server,
+        duress,
+        bob: bob_id,
+        organization,
+        invitation,
+        event,
+        webhook: id(&webhook),
+    }
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn duress_login_sanitizes_the_account_and_alerts_contacts(pool: PgPool) {
+    let server = common::server(pool.clone(), common::test_config());
+    let alice = common::register(&server, "alice").await;
+    let alice_token = alice["token"].as_str().unwrap();
+    let bob = common::register(&server, "bob").await;
+    let bob_id = bob["user_id"].as_str().unwrap();
+    let bob_token = bob["token"].as_str().unwrap();
+
+    // The real password can't double as the duress password
+    common::bearer(server.put("/api/v1/users/me/duress-password"), alice_token)
+        .json(&json!({ "password": "correct horse battery" }))
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+
+    common::bearer(server.put("/api/v1/users/me/duress-password"), alice_token)
+        .json(&json!({ "password": DURESS_PASSWORD, "notify_trusted_contacts": true }))
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    common::bearer(
+        server.put(&format!("/api/v1/users/me/trusted-contacts/{bob_id}")),
+        alice_token,
+    )
+    .await
+    .assert_status(StatusCode::NO_CONTENT);
+    let contacts: Value = get(&server, "/api/v1/users/me/trusted-contacts", alice_token)
+        .await
+        .json();
+    assert_eq!(contacts[0]["username"], "bob");
+
+    sqlx::query("UPDATE users SET current_level = 3 WHERE username = 'alice'")
+        .execute(&pool)
+        .await
+        .unwrap();
+    common::bearer(server.post("/api/v1/organizations"), alice_token)
+        .json(&json!({ "name": "East Side Tenants", "slug": "east-side" }))
+        .await
+        .assert_status(StatusCode::CREATED);
+
+    // Sessions carry whole seconds; let the earlier one fall behind
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    let duress_token = login_with(&server, "alice", DURESS_PASSWORD).await;
+
+    // Every earlier session is gone
+    get(&server, "/api/v1/users/me/organizations", alice_token)
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+
+    // The duress session works, but shows no network
+    let organizations = get(&server, "/api/v1/users/me/organizations", &duress_token).await;
+    organizations.assert_status_ok();
+    assert_eq!(organizations.json::<Value>(), json!([]));
+    let contacts: Value = get(&server, "/api/v1/users/me/trusted-contacts", &duress_token)
+        .await
+        .json();
+    assert_eq!(contacts, json!([]));
+
+    // Removing the duress password looks fine but does nothing
+    common::bearer(
+        server.delete("/api/v1/users/me/duress-password"),
+        &duress_token,
+    )
+    .await
+    .assert_status(StatusCode::NO_CONTENT);
+
+    // Bob is alerted in the background
+    let mut alerts = Value::Null;
+    for _ in 0..50 {
+        alerts = get(&server, "/api/v1/users/me/duress-alerts", bob_token)
+            .await
+            .json();
+        if alerts.as_array().is_some_and(|alerts| !alerts.is_empty()) {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    assert_eq!(alerts.as_array().unwrap().len(), 1);
+    assert_eq!(alerts[0]["username"], "alice");
+
+    // A second duress login doesn't alert again
+    login_with(&server, "alice", DURESS_PASSWORD).await;
+
+    // The real password ends duress mode and the duress sessions
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    let real_token = login_with(&server, "alice", "correct horse battery").await;
+    get(&server, "/api/v1/users/me/organizations", &duress_token)
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+    let organizations: Value = get(&server, "/api/v1/users/me/organizations", &real_token)
+        .await
+        .json();
+    assert_eq!(organizations.as_array().unwrap().len(), 1);
+
+    let (alerts, duress_set): (i64, bool) = sqlx::query_as(
+        "SELECT (SELECT count(*) FROM duress_alerts), duress_password_hash IS NOT NULL
+         FROM users WHERE username = 'alice'",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!((alerts, duress_set), (1, true));
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn wrong_passwords_are_still_refused(pool: PgPool) {
+    let server = common::server(pool, common::test_config());
+    let alice = common::register(&server, "alice").await;
+    common::bearer(
+        server.put("/api/v1/users/me/duress-password"),
+        alice["token"].as_str().unwrap(),
+    )
+    .json(&json!({ "password": DURESS_PASSWORD }))
+    .await
+    .assert_status(StatusCode::NO_CONTENT);
+
+    for email in ["alice@example.org", "nobody@example.org"] {
+        server
+            .post("/api/v1/auth/login")
+            .json(&json!({ "email": email, "password": "not the right one" }))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+    }
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn sanitized_sessions_cannot_list_members(pool: PgPool) {
+    let net = network(&pool).await;
+    let path = format!("/api/v1/organizations/{}/members", net.organization);
+
+    // As for anyone outside the organization
+    get(&net.server, &path, &net.duress)
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn sanitized_sessions_cannot_see_analytics(pool: PgPool) {
+    let net = network(&pool).await;
+    let path = format!("/api/v1/organizations/{}/analytics", net.organization);
+
+    get(&net.server, &path, &net.duress)
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn sanitized_sessions_cannot_invite(pool: PgPool) {
+    let net = network(&pool).await;
+
+    common::bearer(
+        net.server.post(&format!(
+            "/api/v1/organizations/{}/invitations",
+            net.organization
+        )),
+        &net.duress,
+    )
+    .json(&json!({}))
+    .await
+    .assert_status(StatusCode::FORBIDDEN);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn sanitized_sessions_cannot_revoke_invitations(pool: PgPool) {
+    let net = network(&pool).await;
+
+    common::bearer(
+        net.server.delete(&format!(
+            "/api/v1/organizations/{}/invitations/{}",
+            net.organization, net.invitation
+        )),
+        &net.duress,
+    )
+    .await
+    .assert_status(StatusCode::FORBIDDEN);
+    let revoked: bool = sqlx::query_scalar(
+        "SELECT revoked_at IS NOT NULL FROM organization_invitations WHERE id = $1",
+    )
+    .bind(net.invitation)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert!(!revoked);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn sanitized_sessions_cannot_change_roles(pool: PgPool) {
+    let net = network(&pool).await;
+
+    common::bearer(
+        net.server.put(&format!(
+            "/api/v1/organizations/{}/members/{}",
+            net.organization, net.bob
+        )),
+        &net.duress,
+    )
+    .json(&json!({ "role": "admin" }))
+    .await
+    .assert_status(StatusCode::FORBIDDEN);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn sanitized_sessions_cannot_remove_members(pool: PgPool) {
+    let net = network(&pool).await;
+
+    common::bearer(
+        net.server.delete(&format!(
+            "/api/v1/organizations/{}/members/{}",
+            net.organization, net.bob
+        )),
+        &net.duress,
+    )
+    .await
+    .assert_status(StatusCode::FORBIDDEN);
+    let members: i64 =
+        sqlx::query_scalar("SELECT count(*) FROM organization_members WHERE organization_id = $1")
+            .bind(net.organization)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(members, 2);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn sanitized_sessions_see_no_co_organizers(pool: PgPool) {
+    let net = network(&pool).await;
+    let path = format!("/api/v1/events/{}/co-organizers", net.event);
+
+    let listed: Value = net.server.get(&path).await.json();
+    assert_eq!(listed.as_array().unwrap().len(), 1);
+    let listed: Value = get(&net.server, &path, &net.duress).await.json();
+    assert_eq!(listed, json!([]));
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn sanitized_sessions_see_no_webhooks(pool: PgPool) {
+    let net = network(&pool).await;
+
+    let listed: Value = get(&net.server, "/api/v1/webhooks", &net.duress)
+        .await
+        .json();
+    assert_eq!(listed, json!([]));
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn sanitized_sessions_see_no_dead_letters(pool: PgPool) {
+    let net = network(&pool).await;
+    let path = format!("/api/v1/webhooks/{}/dead-letters", net.webhook);
+
+    // As for a webhook that isn't theirs
+    get(&net.server, &path, &net.duress)
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn sanitized_sessions_cannot_delete_webhooks(pool: PgPool) {
+    let net = network(&pool).await;
+
+    common::bearer(
+        net.server
+            .delete(&format!("/api/v1/webhooks/{}", net.webhook)),
+        &net.duress,
+    )
+    .await
+    .assert_status(StatusCode::NOT_FOUND);
+
+    let kept: i64 = sqlx::query_scalar("SELECT count(*) FROM webhook_subscriptions WHERE id = $1")
+        .bind(net.webhook)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(kept, 1);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn sanitized_sessions_cannot_issue_calendar_feeds(pool: PgPool) {
+    let net = network(&pool).await;
+
+    common::bearer(
+        net.server.put("/api/v1/users/me/calendar-feed"),
+        &net.duress,
+    )
+    .await
+    .assert_status(StatusCode::FORBIDDEN);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn sanitized_sessions_follow_no_events(pool: PgPool) {
+    let net = network(&pool).await;
+    let state = AppState::new(common::test_config(), pool.clone()).unwrap();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}/api/v1", listener.local_addr().unwrap());
+    let app = routes::create_router(state.clone());
+    tokio::spawn(async move { axum::serve(listener, app).await });
+
+    let mut stream = reqwest::Client::new()
+        .get(format!("{base}/events/stream"))
+        .bearer_auth(&net.duress)
+        .send()
+        .await
+        .unwrap()
+        .error_for_status()
+        .unwrap();
+
+    // Alice organizes the event, but the stream stays quiet
+    state
+        .realtime
+        .publish(EventUpdate::Cancelled {
+            event_id: net.event,
+        })
+        .await;
+    let chunk = tokio::time::timeout(Duration::from_millis(500), stream.chunk()).await;
+    assert!(chunk.is_err(), "{chunk:?}");
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn sanitized_sessions_cannot_enroll_passkeys(pool: PgPool) {
+    let net = network(&pool).await;
+
+    common::bearer(
+        net.server.post("/api/v1/auth/webauthn/register/start"),
+        &net.duress,
+    )
+    .json(&json!({ "name": "Security key" }))
+    .await
+    .assert_status(StatusCode::FORBIDDEN);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn a_password_alone_changes_nothing_with_a_second_factor(pool: PgPool) {
+    let server = common::server(pool.clone(), common::test_config());
+    let (real_token, secret, codes) = alice_with_totp(&server).await;
+
+    // Sessions carry whole seconds; let the earlier one fall behind
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    let duress_mfa = mfa_token(&server, DURESS_PASSWORD).await;
+    get(&server, "/api/v1/users/me/organizations", &real_token)
+        .await
+        .assert_status_ok();
+    assert!(!under_duress(&pool).await);
+
+    let response = server
+        .post("/api/v1/auth/totp/verify")
+        .json(&json!({ "mfa_token": duress_mfa, "code": totp_code(&secret, 1) }))
+        .await;
+    response.assert_status_ok();
+    let duress_token = response.json::<Value>()["token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    get(&server, "/api/v1/users/me/organizations", &real_token)
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+    assert!(under_duress(&pool).await);
+
+    // The real password alone doesn't end duress mode either
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    let real_mfa = mfa_token(&server, "correct horse battery").await;
+    get(&server, "/api/v1/users/me/organizations", &duress_token)
+        .await
+        .assert_status_ok();
+    assert!(under_duress(&pool).await);
+
+    server
+        .post("/api/v1/auth/mfa/recovery")
+        .json(&json!({ "mfa_token": real_mfa, "code": codes[0] }))
+        .await
+        .assert_status_ok();
+    get(&server, "/api/v1/users/me/organizations", &duress_token)
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+    assert!(!under_duress(&pool).await);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn sanitized_sessions_cannot_change_totp(pool: PgPool) {
+    let server = common::server(pool.clone(), common::test_config());
+    let (_, secret, _) = alice_with_totp(&server).await;
+    let duress_mfa = mfa_token(&server, DURESS_PASSWORD).await;
+    let duress_token = server
+        .post("/api/v1/auth/totp/verify")
+        .json(&json!({ "mfa_token": duress_mfa, "code": totp_code(&secret, 1) }))
+        .await
+        .json::<Value>()["token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let stored = || async {
+        sqlx::query_as::<_, (Option<String>, bool)>(
+            "SELECT (SELECT string_agg(code_hash, ',' ORDER BY code_hash) FROM mfa_recovery_codes),
+                    EXISTS (SELECT 1 FROM totp_credentials WHERE confirmed_at IS NOT NULL)",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap()
+    };
+    let before = stored().await;
+
+    for request in [
+        server.post("/api/v1/auth/totp/enroll"),
+        server
+            .post("/api/v1/auth/totp/confirm")
+            .json(&json!({ "code": totp_code(&secret, 1) })),
+        server.post("/api/v1/auth/totp/recovery-codes"),
+        server
+            .delete("/api/v1/auth/totp")
+            .json(&json!({ "code": totp_code(&secret, 1) })),
+    ] {
+        common::bearer(request, &duress_token)
+            .await
+            .assert_status(StatusCode::FORBIDDEN);
+    }
+
+    assert_eq!(stored().await, before);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn sanitized_sessions_cannot_disable_recovery(pool: PgPool) {
+    let net = network(&pool).await;
+    sqlx::query("UPDATE users SET email_encrypted = '\\x00' WHERE username = 'alice'")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    common::bearer(
+        net.server.delete("/api/v1/users/me/recovery-email"),
+        &net.duress,
+    )
+    .await
+    .assert_status(StatusCode::NO_CONTENT);
+
+    let kept: bool = sqlx::query_scalar(
+        "SELECT email_encrypted IS NOT NULL FROM users WHERE username = 'alice'",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert!(kept);
+}
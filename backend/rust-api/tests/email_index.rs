@@ -0,0 +1,78 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Email blind index rotation: registration and re-indexing
+
+#![allow(clippy::unwrap_used, clippy::expect_used, clippy::future_not_send)]
+
+mod common;
+
+use axum::http::StatusCode;
+use civicconnect_api::{
+    crypto::blind_index::BlindIndex,
+    db::{self, users::Reindexed},
+};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn rotation_neither_duplicates_accounts_nor_stalls(pool: PgPool) {
+    let old_key = "11".repeat(32);
+    let new_key = "22".repeat(32);
+    let server = common::server(pool.clone(), common::test_config());
+    common::register(&server, "alice").await;
+    common::register(&server, "bob").await;
+
+    // Rotate: alice and bob are still on the old key
+    let mut config = common::test_config();
+    config.blind_index_keys = vec![old_key, new_key];
+    let rotated = common::server(pool.clone(), config.clone());
+
+    rotated
+        .post("/api/v1/auth/register")
+        .json(&json!({
+            "email": "alice@example.org",
+            "username": "alice2",
+            "password": "correct horse battery",
+        }))
+        .await
+        .assert_status(StatusCode::CONFLICT);
+
+    // An account duplicated before registration checked every version
+    let index = BlindIndex::from_hex(&config.blind_index_keys).unwrap();
+    let id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO users (id, email_hash, email_key_version, username, password_hash)
+         VALUES ($1, $2, $3, 'bob2', 'x')",
+    )
+    .bind(id)
+    .bind(index.compute("bob@example.org"))
+    .bind(index.current_version())
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    // Bob's old row is skipped rather than failing the run
+    let reindexed = db::users::reindex_emails(&pool, &index).await.unwrap();
+    assert_eq!(
+        reindexed,
+        Reindexed {
+            moved: 1,
+            conflicts: 1,
+        }
+    );
+    let stale: i64 = sqlx::query_scalar("SELECT count(*) FROM users WHERE email_key_version = 1")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(stale, 1);
+
+    // Lookups resolve to the account on the newest key
+    let found = db::users::find_by_email(&pool, &index, "bob@example.org")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(found.id, id);
+}
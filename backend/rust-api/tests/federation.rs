@@ -0,0 +1,385 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! ActivityPub federation, against a mock remote server
+
+#![allow(clippy::unwrap_used, clippy::expect_used, clippy::future_not_send)]
+
+mod common;
+
+use std::time::Duration;
+
+use axum::http::{HeaderName, HeaderValue, StatusCode};
+use axum_test::TestServer;
+use civicconnect_api::{config::Config, federation::signature};
+use rsa::pkcs8::{DecodePrivateKey, EncodePublicKey, LineEnding};
+use rsa::RsaPrivateKey;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use uuid::Uuid;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const REMOTE_KEY: &str = include_str!("fixtures/federation-remote.pem");
+
+fn federated_config() -> Config {
+    let mut config = common::test_config();
+    config.federation.key_file = Some(
+        [
+            env!("CARGO_MANIFEST_DIR"),
+            "tests/fixtures/federation-local.pem",
+        ]
+        .iter()
+        .collect(),
+    );
+    config.federation.allow_http = true;
+    config.federation.allow_private_networks = true;
+    config
+}
+
+/// A remote server with one actor, whose inbox accepts anything
+struct Remote {
+    server: MockServer,
+    key: RsaPrivateKey,
+}
+
+impl Remote {
+    async fn start() -> Self {
+        let server = MockServer::start().await;
+        let key = RsaPrivateKey::from_pkcs8_pem(REMOTE_KEY).unwrap();
+        let actor = format!("{}/actor", server.uri());
+
+        Mock::given(method("GET"))
+            .and(path("/actor"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": actor,
+                "type": "Group",
+                "inbox": format!("{}/inbox", server.uri()),
+                "publicKey": {
+                    "id": format!("{actor}#main-key"),
+                    "owner": actor,
+                    "publicKeyPem": key.to_public_key().to_public_key_pem(LineEnding::LF).unwrap(),
+                },
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/inbox"))
+            .respond_with(ResponseTemplate::new(202))
+            .mount(&server)
+            .await;
+
+        Self { server, key }
+    }
+
+    fn actor(&self) -> String {
+        format!("{}/actor", self.server.uri())
+    }
+
+    /// POST `activity` to one of our inboxes, signed by the remote actor
+    async fn deliver(&self, api: &TestServer, inbox: &str, activity: &Value) -> StatusCode {
+        let body = serde_json::to_vec(activity).unwrap();
+        let key_id = format!("{}#main-key", self.actor());
+        let signed = signature::sign_post(&self.key, &key_id, "localhost:8080", inbox, &body);
+
+        api.post(inbox)
+            .add_header(header("host"), value("localhost:8080"))
+            .add_header(header("date"), value(&signed.date))
+            .add_header(header("digest"), value(&signed.digest))
+            .add_header(header("signature"), value(&signed.signature))
+            .bytes(body.into())
+            .await
+            .status_code()
+    }
+
+    /// Activities delivered to the remote inbox so far
+    async fn received(&self) -> Vec<Value> {
+        self.server
+            .received_requests()
+            .await
+            .unwrap_or_default()
+            .iter()
+            .filter(|request| request.url.path() == "/inbox")
+            .map(|request| serde_json::from_slice(&request.body).unwrap())
+            .collect()
+    }
+
+    /// Wait for a delivery of type `kind`
+    async fn wait_for(&self, kind: &str) -> Value {
+        for _ in 0..50 {
+            if let Some(activity) = self
+                .received()
+                .await
+                .into_iter()
+                .find(|a| a["type"] == kind)
+            {
+                return activity;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        panic!("no {kind} was delivered");
+    }
+}
+
+const fn header(name: &'static str) -> HeaderName {
+    HeaderName::from_static(name)
+}
+
+fn value(value: &str) -> HeaderValue {
+    HeaderValue::from_str(value).unwrap()
+}
+
+/// Register an organizer and return their ID and token
+async fn organizer(server: &TestServer, pool: &PgPool, username: &str) -> (Uuid, String) {
+    let body = common::register(server, username).await;
+    let id: Uuid = body["user_id"].as_str().unwrap().parse().unwrap();
+    sqlx::query("UPDATE users SET current_level = 3 WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await
+        .unwrap();
+    (id, body["token"].as_str().unwrap().to_string())
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn organizers_are_discoverable(pool: PgPool) {
+    let server = common::server(pool.clone(), federated_config());
+    let (id, _) = organizer(&server, &pool, "organizer").await;
+    common::register(&server, "member").await;
+
+    let finger = server
+        .get("/.well-known/webfinger")
+        .add_query_param("resource", "acct:organizer@localhost:8080")
+        .await;
+    finger.assert_status_ok();
+    let finger: Value = finger.json();
+    let actor_uri = format!("{}/ap/actors/{id}", common::PUBLIC_URL);
+    assert_eq!(finger["links"][0]["href"], actor_uri);
+
+    let actor: Value = server.get(&format!("/ap/actors/{id}")).await.json();
+    assert_eq!(actor["id"], actor_uri);
+    assert_eq!(actor["publicKey"]["owner"], actor_uri);
+    assert!(actor["publicKey"]["publicKeyPem"]
+        .as_str()
+        .unwrap()
+        .starts_with("-----BEGIN PUBLIC KEY-----"));
+
+    // Members aren't actors
+    server
+        .get("/.well-known/webfinger")
+        .add_query_param("resource", "acct:member@localhost:8080")
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn federation_is_off_without_a_key(pool: PgPool) {
+    let server = common::server(pool.clone(), common::test_config());
+    let (id, _) = organizer(&server, &pool, "organizer").await;
+
+    server
+        .get(&format!("/ap/actors/{id}"))
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn signed_follows_are_accepted(pool: PgPool) {
+    let server = common::server(pool.clone(), federated_config());
+    let remote = Remote::start().await;
+    let (id, _) = organizer(&server, &pool, "organizer").await;
+    let inbox = format!("/ap/actors/{id}/inbox");
+    let follow = json!({
+        "id": format!("{}/follows/1", remote.server.uri()),
+        "type": "Follow",
+        "actor": remote.actor(),
+        "object": format!("{}/ap/actors/{id}", common::PUBLIC_URL),
+    });
+
+    // Unsigned
+    server
+        .post(&inbox)
+        .json(&follow)
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+
+    assert_eq!(
+        remote.deliver(&server, &inbox, &follow).await,
+        StatusCode::ACCEPTED
+    );
+    let accept = remote.wait_for("Accept").await;
+    assert_eq!(accept["object"]["id"], follow["id"]);
+
+    let followers: Value = server
+        .get(&format!("/ap/actors/{id}/followers"))
+        .await
+        .json();
+    assert_eq!(followers["totalItems"], 1);
+
+    // Claiming to be someone else
+    let mut spoofed = follow.clone();
+    spoofed["actor"] = json!("https://elsewhere.example/actor");
+    assert_eq!(
+        remote.deliver(&server, &inbox, &spoofed).await,
+        StatusCode::UNAUTHORIZED
+    );
+
+    let undo = json!({
+        "id": format!("{}/undo/1", remote.server.uri()),
+        "type": "Undo",
+        "actor": remote.actor(),
+        "object": follow,
+    });
+    assert_eq!(
+        remote.deliver(&server, &inbox, &undo).await,
+        StatusCode::ACCEPTED
+    );
+    let followers: Value = server
+        .get(&format!("/ap/actors/{id}/followers"))
+        .await
+        .json();
+    assert_eq!(followers["totalItems"], 0);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn outbound_follows_await_acceptance(pool: PgPool) {
+    let server = common::server(pool.clone(), federated_config());
+    let remote = Remote::start().await;
+    let (id, token) = organizer(&server, &pool, "organizer").await;
+
+    // Members can't follow
+    let member = common::register(&server, "member").await;
+    common::bearer(
+        server.post("/api/v1/federation/following"),
+        member["token"].as_str().unwrap(),
+    )
+    .json(&json!({ "actor": remote.actor() }))
+    .await
+    .assert_status(StatusCode::FORBIDDEN);
+
+    let response = common::bearer(server.post("/api/v1/federation/following"), &token)
+        .json(&json!({ "actor": remote.actor() }))
+        .await;
+    response.assert_status(StatusCode::ACCEPTED);
+    let follow_id = response.json::<Value>()["follow_id"].clone();
+    let follow = remote.wait_for("Follow").await;
+    assert_eq!(follow["id"], follow_id);
+
+    let accepted = || async {
+        sqlx::query_scalar::<_, bool>(
+            "SELECT accepted_at IS NOT NULL FROM federation_following WHERE user_id = $1",
+        )
+        .bind(id)
+        .fetch_one(&pool)
+        .await
+        .unwrap()
+    };
+    assert!(!accepted().await);
+
+    let accept = json!({
+        "id": format!("{}/accepts/1", remote.server.uri()),
+        "type": "Accept",
+        "actor": remote.actor(),
+        "object": follow,
+    });
+    assert_eq!(
+        remote
+            .deliver(&server, &format!("/ap/actors/{id}/inbox"), &accept)
+            .await,
+        StatusCode::ACCEPTED
+    );
+    assert!(accepted().await);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn private_addresses_are_never_fetched(pool: PgPool) {
+    let mut config = federated_config();
+    config.federation.allow_private_networks = false;
+    let server = common::server(pool.clone(), config);
+    let remote = Remote::start().await;
+    let (id, token) = organizer(&server, &pool, "organizer").await;
+
+    // The key ID is on the loopback interface
+    let follow = json!({
+        "id": format!("{}/follows/1", remote.server.uri()),
+        "type": "Follow",
+        "actor": remote.actor(),
+        "object": format!("{}/ap/actors/{id}", common::PUBLIC_URL),
+    });
+    assert_eq!(
+        remote
+            .deliver(&server, &format!("/ap/actors/{id}/inbox"), &follow)
+            .await,
+        StatusCode::UNAUTHORIZED
+    );
+
+    let follow = |actor: String| {
+        common::bearer(server.post("/api/v1/federation/following"), &token)
+            .json(&json!({ "actor": actor }))
+    };
+    follow("http://169.254.169.254/actor".into())
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+    let by_name = remote.actor().replace("127.0.0.1", "localhost");
+    follow(by_name).await.assert_status(StatusCode::BAD_GATEWAY);
+
+    assert!(remote.server.received_requests().await.unwrap().is_empty());
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn actors_must_be_served_whole_from_their_own_server(pool: PgPool) {
+    let server = common::server(pool.clone(), federated_config());
+    let remote = Remote::start().await;
+    let (_, token) = organizer(&server, &pool, "organizer").await;
+    let key = remote
+        .key
+        .to_public_key()
+        .to_public_key_pem(LineEnding::LF)
+        .unwrap();
+    let document = |name: &str, inbox: &str, padding: usize| {
+        let actor = format!("{}/{name}", remote.server.uri());
+        json!({
+            "id": actor,
+            "type": "Person",
+            "inbox": inbox,
+            "summary": "x".repeat(padding),
+            "publicKey": {
+                "id": format!("{actor}#main-key"),
+                "owner": actor,
+                "publicKeyPem": key,
+            },
+        })
+    };
+
+    for (name, body) in [
+        (
+            "misdirected",
+            document("misdirected", "http://10.0.0.1/inbox", 0),
+        ),
+        (
+            "bloated",
+            document(
+                "bloated",
+                &format!("{}/inbox", remote.server.uri()),
+                512 * 1024,
+            ),
+        ),
+    ] {
+        Mock::given(method("GET"))
+            .and(path(format!("/{name}")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .mount(&remote.server)
+            .await;
+
+        common::bearer(server.post("/api/v1/federation/following"), &token)
+            .json(&json!({ "actor": format!("{}/{name}", remote.server.uri()) }))
+            .await
+            .assert_status(StatusCode::BAD_GATEWAY);
+    }
+}
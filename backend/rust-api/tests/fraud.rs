@@ -0,0 +1,167 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Fraud screening of verifications
+
+#![allow(clippy::unwrap_used, clippy::expect_used, clippy::future_not_send)]
+
+mod common;
+
+use chrono::Utc;
+use civicconnect_api::{
+    audit,
+    db::models::Verification,
+    state::AppState,
+    verification::{self, Attendance, XP_EVENT_ATTENDANCE},
+    ApiError,
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Resolution 7 cells in central London and Paris
+const LONDON: &str = "87195da49ffffff";
+const PARIS: &str = "871fb4662ffffff";
+
+async fn user(pool: &PgPool) -> Uuid {
+    let id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO users (id, email_hash, username, password_hash)
+         VALUES ($1, $2, $3, 'x')",
+    )
+    .bind(id)
+    .bind(id.to_string())
+    .bind(format!("user-{id}"))
+    .execute(pool)
+    .await
+    .unwrap();
+    id
+}
+
+async fn event(pool: &PgPool, organizer_id: Uuid, cell: &str) -> Uuid {
+    let id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO events (id, organizer_id, title, description, location_hash, start_time, end_time)
+         VALUES ($1, $2, 'Cleanup', '', $3, now(), now() + interval '1 hour')",
+    )
+    .bind(id)
+    .bind(organizer_id)
+    .bind(cell)
+    .execute(pool)
+    .await
+    .unwrap();
+    id
+}
+
+async fn attend(
+    state: &AppState,
+    event_id: Uuid,
+    user_id: Uuid,
+    organizer_id: Uuid,
+    cell: &str,
+) -> civicconnect_api::Result<verification::Recorded> {
+    verification::record(
+        state,
+        Attendance {
+            event_id,
+            user_id,
+            organizer_id,
+            signature: &[1, 2, 3],
+            location_cell: cell,
+            at: Utc::now(),
+            attested: false,
+        },
+    )
+    .await
+}
+
+async fn xp(pool: &PgPool, user_id: Uuid) -> i32 {
+    sqlx::query_scalar("SELECT experience_points FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn impossible_travel_is_held_without_xp(pool: PgPool) {
+    let state = AppState::new(common::test_config(), pool.clone()).unwrap();
+    let organizer = user(&pool).await;
+    let attendee = user(&pool).await;
+    let london = event(&pool, organizer, LONDON).await;
+    let paris = event(&pool, organizer, PARIS).await;
+
+    let first = attend(&state, london, attendee, organizer, LONDON)
+        .await
+        .unwrap();
+    assert!(!first.is_held());
+    assert_eq!(first.verification.experience_awarded, XP_EVENT_ATTENDANCE);
+    assert_eq!(xp(&pool, attendee).await, XP_EVENT_ATTENDANCE);
+
+    // Paris a moment after London
+    let second = attend(&state, paris, attendee, organizer, PARIS)
+        .await
+        .unwrap();
+    assert!(second.is_held());
+    assert_eq!(second.verification.status, Verification::HELD_FOR_REVIEW);
+    assert_eq!(second.verification.experience_awarded, 0);
+    assert_eq!(second.assessment.findings[0].rule, "impossible_travel");
+    assert_eq!(xp(&pool, attendee).await, XP_EVENT_ATTENDANCE);
+
+    // Repeat scans are refused, held or not
+    assert!(matches!(
+        attend(&state, paris, attendee, organizer, PARIS).await,
+        Err(ApiError::AlreadyVerified)
+    ));
+
+    // Held verifications are chained like any other
+    let report = audit::verify_chain(&pool, None).await.unwrap();
+    assert!(report.is_intact(), "{report:?}");
+    assert_eq!(report.entries_checked, 2);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn organizer_cannot_mint_own_attendance(pool: PgPool) {
+    let state = AppState::new(common::test_config(), pool.clone()).unwrap();
+    let organizer = user(&pool).await;
+    let event_id = event(&pool, organizer, LONDON).await;
+
+    let recorded = attend(&state, event_id, organizer, organizer, LONDON)
+        .await
+        .unwrap();
+    assert!(recorded.is_held());
+    assert_eq!(
+        recorded.assessment.findings[0].rule,
+        "implausible_attendance"
+    );
+    assert_eq!(xp(&pool, organizer).await, 0);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn accounts_verifying_in_lockstep_are_held(pool: PgPool) {
+    let mut config = common::test_config();
+    config.fraud.cluster_min_shared_events = 2;
+    config.fraud.cluster_min_accounts = 2;
+    let state = AppState::new(config, pool.clone()).unwrap();
+
+    let organizer = user(&pool).await;
+    let ring = [user(&pool).await, user(&pool).await, user(&pool).await];
+
+    // The same three accounts verify together at event after event
+    let mut held = Vec::new();
+    for _ in 0..3 {
+        let event_id = event(&pool, organizer, LONDON).await;
+        for &member in &ring {
+            let recorded = attend(&state, event_id, member, organizer, LONDON)
+                .await
+                .unwrap();
+            held.push(recorded.is_held());
+        }
+    }
+
+    // Nothing stands out until they have a history together
+    assert!(!held[..6].iter().any(|&h| h));
+    assert_eq!(held[6..], [false, false, true]);
+}
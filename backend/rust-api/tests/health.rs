@@ -0,0 +1,98 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Liveness and readiness probes
+
+#![allow(clippy::unwrap_used, clippy::expect_used, clippy::future_not_send)]
+
+mod common;
+
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use axum_test::TestServer;
+use civicconnect_api::{routes, state::AppState};
+use serde_json::Value;
+use sqlx::{postgres::PgPoolOptions, PgPool};
+
+/// A pool that can never connect, failing fast
+fn unreachable_pool() -> PgPool {
+    PgPoolOptions::new()
+        .acquire_timeout(Duration::from_millis(500))
+        .connect_lazy("postgres://localhost:1/unreachable")
+        .unwrap()
+}
+
+#[tokio::test]
+async fn liveness_ignores_dependencies() {
+    let server = common::server(unreachable_pool(), common::test_config());
+
+    let response = server.get("/health/live").await;
+    response.assert_status_ok();
+    let body: Value = response.json();
+    assert_eq!(body["status"], "alive");
+    assert!(!body["commit"].as_str().unwrap().is_empty());
+    assert!(body["built_at"].is_string());
+}
+
+#[tokio::test]
+async fn unreachable_database_is_not_ready() {
+    let server = common::server(unreachable_pool(), common::test_config());
+
+    let response = server.get("/health/ready").await;
+    response.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+    let body: Value = response.json();
+    assert_eq!(body["status"], "not_ready");
+    assert_eq!(body["checks"]["database"]["status"], "down");
+    assert_eq!(body["checks"]["migrations"]["status"], "down");
+    assert_eq!(body["checks"]["redis"]["status"], "disabled");
+    // The connection error stays in the logs
+    assert_eq!(body["checks"]["database"]["reason"], "unavailable");
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn ready_until_draining(pool: PgPool) {
+    let state = AppState::new(common::test_config(), pool).unwrap();
+    let draining = state.draining.clone();
+    let server = TestServer::new(routes::create_router(state)).unwrap();
+
+    let response = server.get("/health/ready").await;
+    response.assert_status_ok();
+    let body: Value = response.json();
+    assert_eq!(body["status"], "ready");
+    for check in ["database", "migrations"] {
+        assert_eq!(body["checks"][check]["status"], "up", "{check}");
+        assert!(body["checks"][check]["latency_ms"].is_u64(), "{check}");
+    }
+    assert_eq!(body["version"], env!("CARGO_PKG_VERSION"));
+
+    draining.store(true, Ordering::Relaxed);
+    let response = server.get("/health/ready").await;
+    response.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+    let body: Value = response.json();
+    assert_eq!(body["status"], "draining");
+    assert!(body.get("checks").is_none());
+
+    server.get("/health/live").await.assert_status_ok();
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn pending_migrations_are_not_ready(pool: PgPool) {
+    sqlx::query(
+        "DELETE FROM _sqlx_migrations WHERE version = (SELECT max(version) FROM _sqlx_migrations)",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    let server = common::server(pool, common::test_config());
+
+    let response = server.get("/health/ready").await;
+    response.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+    let body: Value = response.json();
+    assert_eq!(body["checks"]["database"]["status"], "up");
+    assert_eq!(body["checks"]["migrations"]["status"], "down");
+    assert_eq!(body["checks"]["migrations"]["reason"], "1 pending");
+}
@@ -0,0 +1,133 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Idempotency keys: retries replay the first response
+
+#![allow(clippy::unwrap_used, clippy::expect_used, clippy::future_not_send)]
+
+mod common;
+
+use axum::http::{HeaderValue, StatusCode};
+use civicconnect_api::idempotency::IDEMPOTENCY_KEY;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+const KEY: &str = "3b241101-e2bb-4255-8caf-4136c566a962";
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn retried_registration_replays_the_first_response(pool: PgPool) {
+    let server = common::server(pool.clone(), common::test_config());
+    let request = json!({
+        "email": "alice@example.org",
+        "username": "alice",
+        "password": "correct horse battery",
+    });
+
+    let first = server
+        .post("/api/v1/auth/register")
+        .add_header(IDEMPOTENCY_KEY, HeaderValue::from_static(KEY))
+        .json(&request)
+        .await;
+    first.assert_status_ok();
+    assert!(first.maybe_header("idempotent-replayed").is_none());
+
+    // Without the key this would be ALREADY_REGISTERED
+    let retry = server
+        .post("/api/v1/auth/register")
+        .add_header(IDEMPOTENCY_KEY, HeaderValue::from_static(KEY))
+        .json(&request)
+        .await;
+    retry.assert_status_ok();
+    assert_eq!(retry.header("idempotent-replayed"), "true");
+    assert_eq!(retry.header("content-type"), "application/json");
+    assert_eq!(retry.json::<Value>(), first.json::<Value>());
+
+    let users: i64 = sqlx::query_scalar("SELECT count(*) FROM users")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(users, 1);
+
+    // The stored response holds a session token, so it's encrypted
+    let token = first.json::<Value>()["token"].as_str().unwrap().to_string();
+    let stored: Vec<u8> = sqlx::query_scalar("SELECT response FROM idempotency_keys")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    let stored = String::from_utf8_lossy(&stored);
+    assert!(!stored.contains(&token) && !stored.contains("alice"));
+
+    // Same key, different body
+    server
+        .post("/api/v1/auth/register")
+        .add_header(IDEMPOTENCY_KEY, HeaderValue::from_static(KEY))
+        .json(&json!({
+            "email": "bob@example.org",
+            "username": "bob",
+            "password": "correct horse battery",
+        }))
+        .await
+        .assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+
+    // Requests without a key behave as before
+    server
+        .post("/api/v1/auth/register")
+        .json(&request)
+        .await
+        .assert_status(StatusCode::CONFLICT);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn keys_are_scoped_to_the_caller(pool: PgPool) {
+    let server = common::server(pool, common::test_config());
+    let alice = common::register(&server, "alice").await;
+    let bob = common::register(&server, "bob").await;
+
+    let issue = |session: &Value| {
+        common::bearer(
+            server.put("/api/v1/users/me/calendar-feed"),
+            session["token"].as_str().unwrap(),
+        )
+        .add_header(IDEMPOTENCY_KEY, HeaderValue::from_static(KEY))
+    };
+
+    let first: Value = issue(&alice).await.json();
+    let retry = issue(&alice).await;
+    assert_eq!(retry.header("idempotent-replayed"), "true");
+    assert_eq!(retry.json::<Value>()["url"], first["url"]);
+
+    // Bob's request with the same key runs, and gets his own feed
+    let other = issue(&bob).await;
+    other.assert_status_ok();
+    assert!(other.maybe_header("idempotent-replayed").is_none());
+    assert_ne!(other.json::<Value>()["url"], first["url"]);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn client_errors_are_replayed(pool: PgPool) {
+    let server = common::server(pool, common::test_config());
+    let session = common::register(&server, "alice").await;
+    let token = session["token"].as_str().unwrap();
+
+    // A rejected request is still the request's outcome
+    let report = || {
+        common::bearer(server.post("/api/v1/reports"), token)
+            .add_header(IDEMPOTENCY_KEY, HeaderValue::from_static(KEY))
+            .json(&json!({ "target_type": "nothing" }))
+    };
+    report()
+        .await
+        .assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+    let retry = report().await;
+    retry.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(retry.header("idempotent-replayed"), "true");
+
+    common::bearer(server.post("/api/v1/reports"), token)
+        .add_header(IDEMPOTENCY_KEY, HeaderValue::from_static(""))
+        .json(&json!({}))
+        .await
+        .assert_status_bad_request();
+}
@@ -0,0 +1,166 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Request limits, compression and graceful shutdown
+//!
+//! Slow requests are simulated with a database that never answers: the
+//! calendar feed waits on the pool until its acquire timeout.
+
+#![allow(clippy::unwrap_used, clippy::expect_used, clippy::future_not_send)]
+
+mod common;
+
+use std::time::{Duration, Instant};
+
+use axum::http::{header, HeaderValue, StatusCode};
+use civicconnect_api::{config::Config, routes, server, state::AppState};
+use serde_json::{json, Value};
+use sqlx::{postgres::PgPoolOptions, PgPool};
+use tokio::sync::oneshot;
+
+const SLOW: &str = "/api/v1/calendar/some-token.ics";
+
+/// A pool whose connections time out after `wait`
+fn unreachable_pool(wait: Duration) -> PgPool {
+    PgPoolOptions::new()
+        .acquire_timeout(wait)
+        .connect_lazy("postgres://localhost:1/unreachable")
+        .unwrap()
+}
+
+/// Serve the app on a local port, returning its base URL
+///
+/// Used where requests must overlap, which the test server can't do.
+async fn spawn(config: Config, pool: PgPool) -> String {
+    let app = routes::create_router(AppState::new(config, pool).unwrap());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    base
+}
+
+#[tokio::test]
+async fn requests_past_the_cap_are_shed() {
+    let mut config = common::test_config();
+    config.server.max_concurrent_requests = 1;
+    let base = spawn(config, unreachable_pool(Duration::from_secs(1))).await;
+    let client = reqwest::Client::new();
+    let slow = || client.get(format!("{base}{SLOW}")).send();
+
+    let (first, second) = tokio::join!(slow(), async {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let second = slow().await.unwrap();
+        // Health probes aren't shed
+        let live = client.get(format!("{base}/health/live")).send().await;
+        (second, live.unwrap())
+    });
+    let (second, live) = second;
+
+    assert_eq!(
+        first.unwrap().status(),
+        reqwest::StatusCode::INTERNAL_SERVER_ERROR
+    );
+    assert_eq!(second.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(second.headers()["retry-after"], "2");
+    assert_eq!(second.json::<Value>().await.unwrap()["code"], "OVERLOADED");
+    assert_eq!(live.status(), reqwest::StatusCode::OK);
+}
+
+#[tokio::test]
+async fn slow_requests_time_out() {
+    let mut config = common::test_config();
+    config.server.request_timeout_secs = 1;
+    let server = common::server(unreachable_pool(Duration::from_secs(10)), config);
+
+    let started = Instant::now();
+    let response = server.get(SLOW).await;
+    response.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.json::<Value>()["code"], "TIMED_OUT");
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn oversized_bodies_are_rejected() {
+    let mut config = common::test_config();
+    config.server.max_body_bytes = 1024;
+    let server = common::server(unreachable_pool(Duration::from_secs(1)), config);
+
+    let response = server
+        .post("/api/v1/auth/register")
+        .json(&json!({
+            "username": "alice",
+            "email": "alice@example.org",
+            "password": "x".repeat(2048),
+        }))
+        .await;
+    response.assert_status(StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn responses_are_compressed_on_request() {
+    let server = common::server(
+        unreachable_pool(Duration::from_secs(1)),
+        common::test_config(),
+    );
+
+    let response = server
+        .get("/api/v1/openapi.json")
+        .add_header(header::ACCEPT_ENCODING, HeaderValue::from_static("gzip"))
+        .await;
+    response.assert_status_ok();
+    assert_eq!(response.header(header::CONTENT_ENCODING), "gzip");
+
+    let plain = server.get("/api/v1/openapi.json").await;
+    assert!(plain.maybe_header(header::CONTENT_ENCODING).is_none());
+}
+
+#[tokio::test]
+async fn shutdown_drains_before_closing() {
+    let mut config = common::test_config();
+    config.server.drain_delay_secs = 1;
+    config.server.shutdown_grace_secs = 1;
+    let limits = config.server.clone();
+    let state = AppState::new(config, unreachable_pool(Duration::from_millis(200))).unwrap();
+    let draining = state.draining.clone();
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let ready = format!("http://{}/health/ready", listener.local_addr().unwrap());
+    let (stop, stopped) = oneshot::channel::<()>();
+    let serving = tokio::spawn(async move {
+        server::serve(
+            listener,
+            routes::create_router(state),
+            &limits,
+            draining,
+            async {
+                stopped.await.ok();
+            },
+        )
+        .await
+    });
+
+    let client = reqwest::Client::new();
+    let body: Value = client
+        .get(&ready)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["status"], "not_ready");
+
+    stop.send(()).unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let response = client.get(&ready).send().await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["status"], "draining");
+
+    tokio::time::timeout(Duration::from_secs(5), serving)
+        .await
+        .expect("server didn't stop")
+        .unwrap()
+        .unwrap();
+    assert!(client.get(&ready).send().await.is_err());
+}
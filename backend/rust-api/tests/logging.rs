@@ -0,0 +1,88 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Logs carry no passwords, emails or tokens
+
+#![allow(clippy::unwrap_used, clippy::expect_used, clippy::future_not_send)]
+
+mod common;
+
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use civicconnect_api::{api::auth::RegisterRequest, telemetry::logs::JsonLogLayer};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use tracing_subscriber::layer::SubscriberExt;
+
+const PASSWORD: &str = "correct horse battery";
+
+/// Log lines written so far
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn register_and_login_leave_no_secrets_in_logs(pool: PgPool) {
+    let captured = Captured::default();
+    let writer = captured.clone();
+    let subscriber = tracing_subscriber::registry().with(JsonLogLayer::new(move || writer.clone()));
+    let _default = tracing::subscriber::set_default(subscriber);
+
+    let server = common::server(pool, common::test_config());
+    common::register(&server, "alice").await;
+    let session = common::login(&server, "alice").await;
+    server
+        .post("/api/v1/auth/login")
+        .json(&json!({ "email": "alice@example.org", "password": "wrong horse battery" }))
+        .await
+        .assert_status_unauthorized();
+    server
+        .post("/api/v1/auth/password-reset/request")
+        .json(&json!({ "email": "alice@example.org" }))
+        .await;
+
+    // A request logged whole, and secrets in a message
+    let request: RegisterRequest = serde_json::from_value(json!({
+        "email": "bob@example.org",
+        "username": "bob",
+        "password": PASSWORD,
+    }))
+    .unwrap();
+    tracing::warn!(?request, "Registration rejected");
+    tracing::info!(
+        "Issued {} to carol@example.org",
+        session["token"].as_str().unwrap()
+    );
+
+    let logs = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+    let lines: Vec<Value> = logs
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert!(lines
+        .iter()
+        .any(|line| line["spans"][0]["route"] == "/api/v1/auth/login"));
+    assert!(lines.iter().any(|line| line["fields"]["request"]
+        .as_str()
+        .is_some_and(|request| request.contains("password: [redacted]"))));
+    assert!(lines
+        .iter()
+        .any(|line| line["fields"]["message"] == "Issued [redacted] to [redacted]"));
+
+    let token = session["token"].as_str().unwrap();
+    for secret in [PASSWORD, "wrong horse", "example.org", token] {
+        assert!(!logs.contains(secret), "{secret} logged in:\n{logs}");
+    }
+}
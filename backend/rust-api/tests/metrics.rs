@@ -0,0 +1,228 @@
+// SPDX-License-Identifier: MPL-2
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Prometheus metrics
+
+#![allow(clippy::unwrap_used, clippy::expect_used, clippy::future_not_send)]
+
+mod common;
+
+use std::time::Duration;
+
+use axum_test::TestServer;
+use chrono::{Duration as Interval, Utc};
+use civicconnect_api::{
+    crypto, routes,
+    state::AppState,
+    verification::{self, Attendance},
+};
+use serde_json::{json, Value};
+use sqlx::{postgres::PgPoolOptions, PgPool};
+use uuid::Uuid;
+
+const LONDON: &str = "87195da49ffffff";
+const PARIS: &str = "871fb4662ffffff";
+
+/// Value of the sample whose line starts with `series`
+fn sample(text: &str, series: &str) -> u64 {
+    text.lines()
+        .find_map(|line| line.strip_prefix(series)?.trim().parse().ok())
+        .unwrap_or_else(|| panic!("no sample for {series} in:\n{text}"))
+}
+
+#[tokio::test]
+async fn requests_are_counted_by_route_template() {
+    let pool = PgPoolOptions::new()
+        .acquire_timeout(Duration::from_millis(200))
+        .connect_lazy("postgres://localhost:1/unreachable")
+        .unwrap();
+    let server = common::server(pool, common::test_config());
+    let token = "9".repeat(64);
+
+    server.get(&format!("/api/v1/calendar/{token}.ics")).await;
+    server.get(&format!("/api/v1/calendar/{token}.ics")).await;
+    server.get("/no/such/route").await;
+
+    let text = server.get("/metrics").await.text();
+    assert_eq!(
+        sample(
+            &text,
+            r#"civicconnect_http_requests_total{method="GET",route="/api/v1/calendar/:token",status="500"}"#
+        ),
+        2
+    );
+    assert_eq!(
+        sample(
+            &text,
+            r#"civicconnect_http_request_duration_seconds_count{method="GET",route="/api/v1/calendar/:token"}"#
+        ),
+        2
+    );
+    assert!(text.contains(r#"route="unmatched""#));
+    assert!(!text.contains(&token));
+    assert_eq!(sample(&text, "civicconnect_db_pool_max_connections"), 10);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn hashing_verifications_and_xp_are_measured(pool: PgPool) {
+    let state = AppState::new(common::test_config(), pool.clone()).unwrap();
+    let server = TestServer::new(routes::create_router(state.clone())).unwrap();
+
+    let organizer = common::register(&server, "organizer").await;
+    let attendee = common::register(&server, "attendee").await;
+    common::login(&server, "attendee").await;
+    common::login(&server, "nobody").await;
+
+    let organizer_id: Uuid = organizer["user_id"].as_str().unwrap().parse().unwrap();
+    let attendee_id: Uuid = attendee["user_id"].as_str().unwrap().parse().unwrap();
+    for cell in [LONDON, PARIS] {
+        let event_id = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO events (id, organizer_id, title, description, location_hash, start_time, end_time)
+             VALUES ($1, $2, 'Cleanup', '', $3, now(), now() + interval '1 hour')",
+        )
+        .bind(event_id)
+        .bind(organizer_id)
+        .bind(cell)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        // London, then Paris a moment later, twice
+        let attendance = Attendance {
+            event_id,
+            user_id: attendee_id,
+            organizer_id,
+            signature: &[1, 2, 3],
+            location_cell: cell,
+            at: Utc::now(),
+            attested: false,
+        };
+        let _ = verification::record(&state, attendance.clone()).await;
+        if cell == PARIS {
+            let _ = verification::record(&state, attendance).await;
+        }
+    }
+
+    let text = server.get("/metrics").await.text();
+    let hashing = "civicconnect_password_hash_duration_seconds_count";
+    assert_eq!(
+        sample(&text, &format!(r#"{hashing}{{operation="hash"}}"#)),
+        2
+    );
+    // Each login checks the password and the duress password; unknown
+    // accounts are timed as verifications too
+    assert_eq!(
+        sample(&text, &format!(r#"{hashing}{{operation="verify"}}"#)),
+        4
+    );
+
+    let outcomes = "civicconnect_verifications_total";
+    assert_eq!(
+        sample(
+            &text,
+            &format!(r#"{outcomes}{{outcome="verified",reason="none"}}"#)
+        ),
+        1
+    );
+    assert_eq!(
+        sample(
+            &text,
+            &format!(r#"{outcomes}{{outcome="held",reason="impossible_travel"}}"#)
+        ),
+        1
+    );
+    assert_eq!(
+        sample(
+            &text,
+            &format!(r#"{outcomes}{{outcome="rejected",reason="already_verified"}}"#)
+        ),
+        1
+    );
+    assert_eq!(
+        sample(&text, "civicconnect_xp_awarded_total"),
+        u64::try_from(verification::XP_EVENT_ATTENDANCE).unwrap()
+    );
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn every_rejected_scan_is_counted(pool: PgPool) {
+    let server = common::server(pool.clone(), common::test_config());
+    let olive = common::register(&server, "olive").await;
+    let olive_id: Uuid = olive["user_id"].as_str().unwrap().parse().unwrap();
+    let (key, public) = crypto::generate_keypair();
+    common::bearer(
+        server.put("/api/v1/users/me/signing-key"),
+        olive["token"].as_str().unwrap(),
+    )
+    .json(&json!({ "public_key": hex::encode(public.to_bytes()) }))
+    .await;
+    let event_id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO events (id, organizer_id, title, description, location_hash, start_time, end_time)
+         VALUES ($1, $2, 'Cleanup', '', $3, now() - interval '3 hours', now() + interval '1 hour')",
+    )
+    .bind(event_id)
+    .bind(olive_id)
+    .bind(LONDON)
+    .execute(&pool)
+    .await
+    .unwrap();
+    let alice = common::register(&server, "alice").await;
+    let token = alice["token"].as_str().unwrap();
+
+    let scan = |at: chrono::DateTime<Utc>, cell: &str| {
+        let nonce = crypto::generate_nonce();
+        let message = verification::qr_message(event_id, olive_id, at, &nonce);
+        json!({
+            "event_id": event_id,
+            "organizer_id": olive_id,
+            "timestamp": at,
+            "nonce": nonce,
+            "signature": hex::encode(crypto::sign_message(&key, &message).to_bytes()),
+            "location_cell": cell,
+        })
+    };
+    let mut forged = scan(Utc::now(), LONDON);
+    forged["nonce"] = json!("replayed");
+    let valid = scan(Utc::now(), LONDON);
+
+    for rejected in [
+        forged,
+        scan(Utc::now(), PARIS),
+        scan(Utc::now() - Interval::hours(2), LONDON),
+        scan(Utc::now(), "not a cell"),
+        valid.clone(),
+        valid,
+    ] {
+        common::bearer(server.post("/api/v1/verify/scan"), token)
+            .json(&rejected)
+            .await;
+    }
+
+    // A manifest not signed by the organizer counts once
+    let manifest = common::bearer(server.post("/api/v1/verify/checkins"), token)
+        .json(&json!({
+            "event_id": event_id,
+            "location_cell": LONDON,
+            "tokens": [],
+            "signature": "00".repeat(64),
+        }))
+        .await;
+    assert_eq!(manifest.json::<Value>()["code"], "INVALID_SIGNATURE");
+
+    let text = server.get("/metrics").await.text();
+    let rejected = |reason: &str| {
+        sample(
+            &text,
+            &format!(r#"civicconnect_verifications_total{{outcome="rejected",reason="{reason}"}}"#),
+        )
+    };
+    assert_eq!(rejected("invalid_signature"), 2);
+    assert_eq!(rejected("outside_location"), 1);
+    assert_eq!(rejected("outside_time_window"), 1);
+    assert_eq!(rejected("other"), 1);
+    assert_eq!(rejected("already_verified"), 1);
+}
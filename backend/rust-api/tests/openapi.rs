@@ -0,0 +1,205 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! The API v1 document against the router
+//!
+//! Axum can't list a router's routes, so the route table is read from
+//! `src/routes.rs` and compared with the document; every documented
+//! operation is then requested to check the router really serves it.
+
+#![allow(clippy::unwrap_used, clippy::expect_used, clippy::future_not_send)]
+
+mod common;
+
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+use axum::http::{Method, StatusCode};
+use civicconnect_api::api::openapi::ApiDoc;
+use serde_json::Value;
+use sqlx::postgres::PgPoolOptions;
+use utoipa::OpenApi;
+
+const ROUTES: &str = include_str!("../src/routes.rs");
+
+/// `(method, path)` for each API v1 route in `src/routes.rs`, with
+/// `:param` segments written as `{param}`
+fn routed() -> BTreeSet<(String, String)> {
+    // API v1 routes come before the ActivityPub ones
+    let start = ROUTES.find("fn api_v1_routes").unwrap();
+    let end = ROUTES.find("fn federation_routes").unwrap();
+    let mut source = &ROUTES[start..end];
+
+    let mut routes = BTreeSet::new();
+    while let Some(at) = source.find(".route(") {
+        source = &source[at + ".route(".len()..];
+        let call = &source[..closing_paren(source)];
+
+        let path = call.split('"').nth(1).unwrap();
+        let path = path
+            .split('/')
+            .map(|segment| {
+                segment
+                    .strip_prefix(':')
+                    .map_or_else(|| segment.to_string(), |param| format!("{{{param}}}"))
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+
+        for method in ["get", "post", "put", "delete"] {
+            if call.contains(&format!("{method}(api::")) {
+                routes.insert((method.to_string(), path.clone()));
+            }
+        }
+    }
+    routes
+}
+
+/// Offset of the `)` closing a call whose `(` was just before `source`
+fn closing_paren(source: &str) -> usize {
+    let mut depth = 1;
+    for (i, c) in source.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return i;
+                }
+            }
+            _ => {}
+        }
+    }
+    panic!("unbalanced route call");
+}
+
+/// `(method, path)` for each operation in the document
+fn documented() -> BTreeSet<(String, String)> {
+    let mut operations = BTreeSet::new();
+    for (path, item) in ApiDoc::openapi().paths.paths {
+        for (method, operation) in [
+            ("get", &item.get),
+            ("post", &item.post),
+            ("put", &item.put),
+            ("delete", &item.delete),
+        ] {
+            if operation.is_some() {
+                operations.insert((method.to_string(), path.clone()));
+            }
+        }
+        assert!(
+            item.patch.is_none() && item.head.is_none(),
+            "{path} documents a method the router doesn't use"
+        );
+    }
+    operations
+}
+
+#[test]
+fn document_matches_router() {
+    let routed = routed();
+    let documented = documented();
+    assert!(routed.len() > 50, "couldn't read the route table");
+
+    let undocumented: Vec<_> = routed.difference(&documented).collect();
+    let unrouted: Vec<_> = documented.difference(&routed).collect();
+    assert!(
+        undocumented.is_empty(),
+        "routes missing from ApiDoc: {undocumented:?}"
+    );
+    assert!(
+        unrouted.is_empty(),
+        "ApiDoc documents unrouted paths: {unrouted:?}"
+    );
+}
+
+#[test]
+fn document_is_openapi_3_1_with_schemas() {
+    let document: Value = serde_json::to_value(ApiDoc::openapi()).unwrap();
+    assert!(document["openapi"].as_str().unwrap().starts_with("3.1"));
+    assert!(document["components"]["securitySchemes"]["bearer"].is_object());
+
+    let schemas = document["components"]["schemas"].as_object().unwrap();
+    for name in [
+        "RegisterRequest",
+        "EventSummary",
+        "VerifyResponse",
+        "TotpVerifyRequest",
+        "ErrorResponse",
+    ] {
+        assert!(schemas.contains_key(name), "{name} has no schema");
+    }
+    assert_eq!(
+        schemas["OrganizationRole"]["enum"],
+        serde_json::json!(["member", "admin", "owner"])
+    );
+
+    let header = |path: &str, method: &str| {
+        document["paths"][path][method]["parameters"]
+            .as_array()
+            .is_some_and(|parameters| {
+                parameters
+                    .iter()
+                    .any(|parameter| parameter["name"] == "Idempotency-Key")
+            })
+    };
+    assert!(header("/verify/scan", "post"));
+    assert!(header("/users/me/calendar-feed", "put"));
+    assert!(!header("/events", "get"));
+
+    // Stubs promise no success they can't deliver
+    for (path, method) in [
+        ("/users/me", "get"),
+        ("/users/{id}", "get"),
+        ("/events", "post"),
+    ] {
+        let responses = document["paths"][path][method]["responses"]
+            .as_object()
+            .unwrap();
+        assert!(
+            responses.keys().all(|status| !status.starts_with('2')),
+            "{method} {path}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn documented_operations_are_served() {
+    // Public handlers fail fast on the unreachable database, which is fine
+    let pool = PgPoolOptions::new()
+        .acquire_timeout(Duration::from_secs(1))
+        .connect_lazy("postgres://localhost:1/unreachable")
+        .unwrap();
+    let server = common::server(pool, common::test_config());
+
+    let document = server.get("/api/v1/openapi.json").await;
+    document.assert_status_ok();
+    assert_eq!(
+        document.json::<Value>(),
+        serde_json::to_value(ApiDoc::openapi()).unwrap()
+    );
+
+    for (method, path) in documented() {
+        let url = format!(
+            "/api/v1{}",
+            path.split('/')
+                .map(|segment| if segment.starts_with('{') {
+                    "00000000-0000-0000-0000-000000000000"
+                } else {
+                    segment
+                })
+                .collect::<Vec<_>>()
+                .join("/")
+        );
+        let method = Method::from_bytes(method.to_uppercase().as_bytes()).unwrap();
+        let response = server.method(method.clone(), &url).await;
+
+        // Unmatched routes get the router's empty 404, or 405
+        let status = response.status_code();
+        assert_ne!(status, StatusCode::METHOD_NOT_ALLOWED, "{method} {url}");
+        assert!(
+            status != StatusCode::NOT_FOUND || !response.text().is_empty(),
+            "{method} {url} isn't routed"
+        );
+    }
+}
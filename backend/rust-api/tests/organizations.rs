@@ -0,0 +1,431 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Organizations: roles, invitations, co-organizers and chapter analytics
+
+#![allow(clippy::unwrap_used, clippy::expect_used, clippy::future_not_send)]
+
+mod common;
+
+use axum::http::StatusCode;
+use axum_test::TestServer;
+use civicconnect_api::webhooks::Dispatcher;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use uuid::Uuid;
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+struct Account {
+    token: String,
+    id: Uuid,
+}
+
+async fn account(server: &TestServer, pool: &PgPool, username: &str, level: i16) -> Account {
+    let body = common::register(server, username).await;
+    let id: Uuid = body["user_id"].as_str().unwrap().parse().unwrap();
+    sqlx::query("UPDATE users SET current_level = $2 WHERE id = $1")
+        .bind(id)
+        .bind(level)
+        .execute(pool)
+        .await
+        .unwrap();
+    Account {
+        token: body["token"].as_str().unwrap().to_string(),
+        id,
+    }
+}
+
+async fn event(pool: &PgPool, organizer_id: Uuid) -> Uuid {
+    let event_id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO events (id, organizer_id, title, description, location_hash, start_time, end_time)
+         VALUES ($1, $2, 'Cleanup', '', '87195da49ffffff', now() + interval '1 day', now() + interval '25 hours')",
+    )
+    .bind(event_id)
+    .bind(organizer_id)
+    .execute(pool)
+    .await
+    .unwrap();
+    event_id
+}
+
+/// Found an organization; returns its ID
+async fn found(server: &TestServer, owner: &Account, slug: &str) -> Uuid {
+    let response = common::bearer(server.post("/api/v1/organizations"), &owner.token)
+        .json(&json!({ "name": "East Side Tenants", "slug": slug }))
+        .await;
+    response.assert_status(StatusCode::CREATED);
+    response.json::<Value>()["id"]
+        .as_str()
+        .unwrap()
+        .parse()
+        .unwrap()
+}
+
+/// Issue an invitation; returns its token
+async fn invite(server: &TestServer, inviter: &Account, org: Uuid, body: Value) -> String {
+    let response = common::bearer(
+        server.post(&format!("/api/v1/organizations/{org}/invitations")),
+        &inviter.token,
+    )
+    .json(&body)
+    .await;
+    response.assert_status(StatusCode::CREATED);
+    let url = response.json::<Value>()["url"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    url.strip_prefix(&format!("{}/api/v1/invitations/", common::PUBLIC_URL))
+        .and_then(|rest| rest.strip_suffix("/accept"))
+        .unwrap()
+        .to_string()
+}
+
+async fn accept(server: &TestServer, account: &Account, token: &str) -> axum_test::TestResponse {
+    common::bearer(
+        server.post(&format!("/api/v1/invitations/{token}/accept")),
+        &account.token,
+    )
+    .await
+}
+
+fn set_role(
+    server: &TestServer,
+    actor: &Account,
+    org: Uuid,
+    user: &Account,
+    role: &str,
+) -> axum_test::TestRequest {
+    common::bearer(
+        server.put(&format!("/api/v1/organizations/{org}/members/{}", user.id)),
+        &actor.token,
+    )
+    .json(&json!({ "role": role }))
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn roles_combine_with_platform_level(pool: PgPool) {
+    let server = common::server(pool.clone(), common::test_config());
+    let owner = account(&server, &pool, "owner", 3).await;
+    let newcomer = account(&server, &pool, "newcomer", 0).await;
+    let organizer = account(&server, &pool, "organizer", 3).await;
+
+    // Only organizers found organizations, and slugs are unique
+    common::bearer(server.post("/api/v1/organizations"), &newcomer.token)
+        .json(&json!({ "name": "Ward Seven", "slug": "ward-7" }))
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+    common::bearer(server.post("/api/v1/organizations"), &owner.token)
+        .json(&json!({ "name": "Ward Seven", "slug": "Ward 7" }))
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+    let org = found(&server, &owner, "east-side").await;
+    common::bearer(server.post("/api/v1/organizations"), &organizer.token)
+        .json(&json!({ "name": "Other", "slug": "east-side" }))
+        .await
+        .assert_status(StatusCode::CONFLICT);
+
+    // Anyone can join as a member through a link
+    let token = invite(&server, &owner, org, json!({ "max_uses": 2 })).await;
+    for member in [&newcomer, &organizer] {
+        let joined = accept(&server, member, &token).await;
+        joined.assert_status_ok();
+        assert_eq!(joined.json::<Value>()["role"], "member");
+    }
+    let third = account(&server, &pool, "third", 0).await;
+    accept(&server, &third, &token)
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+
+    let details: Value = server
+        .get(&format!("/api/v1/organizations/{org}"))
+        .await
+        .json();
+    assert_eq!(details["member_count"], 3);
+    common::bearer(
+        server.get(&format!("/api/v1/organizations/{org}/members")),
+        &third.token,
+    )
+    .await
+    .assert_status(StatusCode::FORBIDDEN);
+    let members: Value = common::bearer(
+        server.get(&format!("/api/v1/organizations/{org}/members")),
+        &newcomer.token,
+    )
+    .await
+    .json();
+    assert_eq!(members[0]["role"], "owner");
+
+    // Admins must be organizers on the platform
+    set_role(&server, &owner, org, &newcomer, "admin")
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+    set_role(&server, &owner, org, &organizer, "admin")
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+
+    // Admins manage members but not other admins
+    set_role(&server, &organizer, org, &newcomer, "admin")
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+    common::bearer(
+        server.post(&format!("/api/v1/organizations/{org}/invitations")),
+        &organizer.token,
+    )
+    .json(&json!({ "role": "admin" }))
+    .await
+    .assert_status(StatusCode::FORBIDDEN);
+
+    // Members can't run the chapter however high their level
+    common::bearer(
+        server.post(&format!("/api/v1/organizations/{org}/invitations")),
+        &newcomer.token,
+    )
+    .json(&json!({}))
+    .await
+    .assert_status(StatusCode::FORBIDDEN);
+
+    let mine: Value = common::bearer(
+        server.get("/api/v1/users/me/organizations"),
+        &organizer.token,
+    )
+    .await
+    .json();
+    assert_eq!(mine[0]["slug"], "east-side");
+    assert_eq!(mine[0]["role"], "admin");
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn organizations_keep_an_owner(pool: PgPool) {
+    let server = common::server(pool.clone(), common::test_config());
+    let owner = account(&server, &pool, "owner", 3).await;
+    let member = account(&server, &pool, "member", 3).await;
+    let org = found(&server, &owner, "east-side").await;
+    let token = invite(&server, &owner, org, json!({})).await;
+    accept(&server, &member, &token).await.assert_status_ok();
+
+    // A single-use link is spent
+    let late = account(&server, &pool, "late", 0).await;
+    accept(&server, &late, &token)
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+
+    let leave = |account: &Account| {
+        common::bearer(
+            server.delete(&format!(
+                "/api/v1/organizations/{org}/members/{}",
+                account.id
+            )),
+            &account.token,
+        )
+    };
+    leave(&owner).await.assert_status(StatusCode::BAD_REQUEST);
+    set_role(&server, &owner, org, &owner, "member")
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+
+    // With a second owner, the first can step down
+    set_role(&server, &owner, org, &member, "owner")
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    leave(&owner).await.assert_status(StatusCode::NO_CONTENT);
+    leave(&member).await.assert_status(StatusCode::BAD_REQUEST);
+
+    // Revoked links can't be used
+    let spare = account(&server, &pool, "spare", 0).await;
+    let response = common::bearer(
+        server.post(&format!("/api/v1/organizations/{org}/invitations")),
+        &member.token,
+    )
+    .json(&json!({}))
+    .await;
+    let invitation: Value = response.json();
+    let revoke = format!(
+        "/api/v1/organizations/{org}/invitations/{}",
+        invitation["id"].as_str().unwrap()
+    );
+    common::bearer(server.delete(&revoke), &member.token)
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    common::bearer(server.delete(&revoke), &member.token)
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+    let url = invitation["url"].as_str().unwrap();
+    common::bearer(
+        server.post(url.strip_prefix(common::PUBLIC_URL).unwrap()),
+        &spare.token,
+    )
+    .await
+    .assert_status(StatusCode::BAD_REQUEST);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn chapter_events_and_co_organizers(pool: PgPool) {
+    let server = common::server(pool.clone(), common::test_config());
+    let owner = account(&server, &pool, "owner", 3).await;
+    let organizer = account(&server, &pool, "organizer", 3).await;
+    let member = account(&server, &pool, "member", 0).await;
+    let outsider = account(&server, &pool, "outsider", 3).await;
+    let org = found(&server, &owner, "east-side").await;
+    let token = invite(&server, &owner, org, json!({ "max_uses": 5 })).await;
+    accept(&server, &organizer, &token).await.assert_status_ok();
+    accept(&server, &member, &token).await.assert_status_ok();
+
+    let event_id = event(&pool, organizer.id).await;
+    let assign = |account: &Account, org: Option<Uuid>| {
+        common::bearer(
+            server.put(&format!("/api/v1/events/{event_id}/organization")),
+            &account.token,
+        )
+        .json(&json!({ "organization_id": org }))
+    };
+
+    // Outsiders can't hold events under the chapter's name
+    assign(&outsider, Some(org))
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+    assign(&organizer, Some(org))
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    let details: Value = server
+        .get(&format!("/api/v1/events/{event_id}"))
+        .await
+        .json();
+    assert_eq!(details["organization_id"], org.to_string());
+
+    let co_organizer = |actor: &Account, user: &Account| {
+        common::bearer(
+            server.put(&format!(
+                "/api/v1/events/{event_id}/co-organizers/{}",
+                user.id
+            )),
+            &actor.token,
+        )
+    };
+
+    // Chapter events take co-organizers from the chapter, added by the
+    // organizer or a chapter admin
+    co_organizer(&outsider, &member)
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+    co_organizer(&organizer, &outsider)
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+    co_organizer(&owner, &member)
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+
+    let listed: Value = server
+        .get(&format!("/api/v1/events/{event_id}/co-organizers"))
+        .await
+        .json();
+    assert_eq!(listed.as_array().unwrap().len(), 1);
+    assert_eq!(listed[0]["username"], "member");
+
+    // Co-organizers can step down
+    common::bearer(
+        server.delete(&format!(
+            "/api/v1/events/{event_id}/co-organizers/{}",
+            member.id
+        )),
+        &member.token,
+    )
+    .await
+    .assert_status(StatusCode::NO_CONTENT);
+
+    // Chapter analytics count the event and its RSVPs, for admins only
+    common::bearer(
+        server.put(&format!("/api/v1/events/{event_id}/rsvp")),
+        &member.token,
+    )
+    .await
+    .assert_status_ok();
+    let analytics = format!("/api/v1/organizations/{org}/analytics");
+    common::bearer(server.get(&analytics), &member.token)
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+    let stats: Value = common::bearer(server.get(&analytics), &owner.token)
+        .await
+        .json();
+    assert_eq!(stats["members"], 3);
+    assert_eq!(stats["owners"], 1);
+    assert_eq!(stats["events"], 1);
+    assert_eq!(stats["upcoming_events"], 1);
+    assert_eq!(stats["rsvps"], 1);
+
+    // Personal events take organizers as co-organizers
+    assign(&organizer, None)
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    co_organizer(&organizer, &member)
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+    co_organizer(&organizer, &outsider)
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn chapter_webhooks_hear_chapter_events(pool: PgPool) {
+    let mut config = common::test_config();
+    config.webhooks.allow_http = true;
+    config.webhooks.allow_private_networks = true;
+    let dispatcher = Dispatcher::new(config.webhooks.clone()).unwrap();
+    let server = common::server(pool.clone(), config);
+    let receiver = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(204))
+        .mount(&receiver)
+        .await;
+
+    let owner = account(&server, &pool, "owner", 3).await;
+    let member = account(&server, &pool, "member", 0).await;
+    let org = found(&server, &owner, "east-side").await;
+    let token = invite(&server, &owner, org, json!({})).await;
+    accept(&server, &member, &token).await.assert_status_ok();
+
+    let request = json!({
+        "url": receiver.uri(),
+        "event_types": ["rsvp.changed"],
+        "organization_id": org,
+    });
+    common::bearer(server.post("/api/v1/webhooks"), &member.token)
+        .json(&request)
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+    let created = common::bearer(server.post("/api/v1/webhooks"), &owner.token)
+        .json(&request)
+        .await;
+    created.assert_status(StatusCode::CREATED);
+    assert_eq!(created.json::<Value>()["organization_id"], org.to_string());
+
+    // Only the chapter's events are delivered
+    let personal = event(&pool, owner.id).await;
+    let chapter = event(&pool, owner.id).await;
+    common::bearer(
+        server.put(&format!("/api/v1/events/{chapter}/organization")),
+        &owner.token,
+    )
+    .json(&json!({ "organization_id": org }))
+    .await
+    .assert_status(StatusCode::NO_CONTENT);
+
+    for event_id in [personal, chapter] {
+        common::bearer(
+            server.put(&format!("/api/v1/events/{event_id}/rsvp")),
+            &member.token,
+        )
+        .await
+        .assert_status_ok();
+    }
+    assert_eq!(dispatcher.deliver_due(&pool).await.unwrap(), 1);
+
+    let requests = receiver.received_requests().await.unwrap();
+    let body: Value = serde_json::from_slice(&requests[0].body).unwrap();
+    assert_eq!(body["data"]["event_id"], chapter.to_string());
+}
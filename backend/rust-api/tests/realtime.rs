@@ -0,0 +1,173 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! RSVPs and the real-time event update stream
+//!
+//! Redis fan-out is not covered here; these run against a single replica.
+
+#![allow(clippy::unwrap_used, clippy::expect_used, clippy::future_not_send)]
+
+mod common;
+
+use axum::http::StatusCode;
+use chrono::Utc;
+use civicconnect_api::{
+    realtime::EventUpdate,
+    routes,
+    state::AppState,
+    verification::{self, Attendance},
+};
+use serde_json::Value;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+const LONDON: &str = "87195da49ffffff";
+
+async fn event(pool: &PgPool, organizer_id: Uuid, capacity: i32) -> Uuid {
+    let id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO events (id, organizer_id, title, description, location_hash, start_time, end_time, capacity)
+         VALUES ($1, $2, 'Cleanup', '', $3, now(), now() + interval '1 hour', $4)",
+    )
+    .bind(id)
+    .bind(organizer_id)
+    .bind(LONDON)
+    .bind(capacity)
+    .execute(pool)
+    .await
+    .unwrap();
+    id
+}
+
+fn user_id(body: &Value) -> Uuid {
+    body["user_id"].as_str().unwrap().parse().unwrap()
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn rsvps_respect_capacity(pool: PgPool) {
+    let server = common::server(pool.clone(), common::test_config());
+    let organizer = common::register(&server, "organizer").await;
+    let alice = common::register(&server, "alice").await;
+    let bob = common::register(&server, "bob").await;
+    let event_id = event(&pool, user_id(&organizer), 1).await;
+    let rsvp = format!("/api/v1/events/{event_id}/rsvp");
+
+    let body: Value = common::bearer(server.put(&rsvp), alice["token"].as_str().unwrap())
+        .await
+        .json();
+    assert_eq!(
+        (body["rsvps"].as_i64(), body["capacity"].as_i64()),
+        (Some(1), Some(1))
+    );
+
+    // Repeating an RSVP is harmless; a second attendee doesn't fit
+    common::bearer(server.put(&rsvp), alice["token"].as_str().unwrap())
+        .await
+        .assert_status_ok();
+    let full = common::bearer(server.put(&rsvp), bob["token"].as_str().unwrap()).await;
+    full.assert_status(StatusCode::CONFLICT);
+    assert_eq!(full.json::<Value>()["code"], "EVENT_FULL");
+
+    // A place opens up when Alice withdraws
+    common::bearer(server.delete(&rsvp), alice["token"].as_str().unwrap())
+        .await
+        .assert_status_ok();
+    common::bearer(server.put(&rsvp), bob["token"].as_str().unwrap())
+        .await
+        .assert_status_ok();
+
+    common::bearer(
+        server.put(&format!("/api/v1/events/{}/rsvp", Uuid::new_v4())),
+        bob["token"].as_str().unwrap(),
+    )
+    .await
+    .assert_status(StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn stream_delivers_updates_for_followed_events(pool: PgPool) {
+    let state = AppState::new(common::test_config(), pool.clone()).unwrap();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}/api/v1", listener.local_addr().unwrap());
+    let app = routes::create_router(state.clone());
+    tokio::spawn(async move { axum::serve(listener, app).await });
+
+    let client = reqwest::Client::new();
+    let register = |username: &str| {
+        client
+            .post(format!("{base}/auth/register"))
+            .json(&serde_json::json!({
+                "email": format!("{username}@example.org"),
+                "username": username,
+                "password": "correct horse battery",
+            }))
+            .send()
+    };
+    let organizer: Value = register("organizer").await.unwrap().json().await.unwrap();
+    let attendee: Value = register("attendee").await.unwrap().json().await.unwrap();
+    let token = attendee["token"].as_str().unwrap();
+
+    let followed = event(&pool, user_id(&organizer), 10).await;
+    let other = event(&pool, user_id(&organizer), 10).await;
+    client
+        .put(format!("{base}/events/{followed}/rsvp"))
+        .bearer_auth(token)
+        .send()
+        .await
+        .unwrap()
+        .error_for_status()
+        .unwrap();
+
+    let mut stream = client
+        .get(format!("{base}/events/stream"))
+        .bearer_auth(token)
+        .send()
+        .await
+        .unwrap()
+        .error_for_status()
+        .unwrap();
+
+    // Updates for other events are filtered out
+    state
+        .realtime
+        .publish(EventUpdate::Cancelled { event_id: other })
+        .await;
+    verification::record(
+        &state,
+        Attendance {
+            event_id: followed,
+            user_id: user_id(&attendee),
+            organizer_id: user_id(&organizer),
+            signature: &[1, 2, 3],
+            location_cell: LONDON,
+            at: Utc::now(),
+            attested: false,
+        },
+    )
+    .await
+    .unwrap();
+
+    let mut received = String::new();
+    while !received.contains("\n\n") {
+        let chunk = stream.chunk().await.unwrap().expect("stream ended");
+        received.push_str(std::str::from_utf8(&chunk).unwrap());
+    }
+    let (event, _) = received.split_once("\n\n").unwrap();
+    assert!(event.starts_with("event: attendance\n"), "{event}");
+    let data: Value = serde_json::from_str(event.split_once("data: ").unwrap().1).unwrap();
+    assert_eq!(data["event_id"], followed.to_string());
+    assert_eq!(data["verified"], 1);
+
+    // No session, no stream
+    let anonymous = client
+        .get(format!("{base}/events/stream"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(
+        anonymous.status().as_u16(),
+        StatusCode::UNAUTHORIZED.as_u16()
+    );
+}
@@ -0,0 +1,59 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Password reset
+
+#![allow(clippy::unwrap_used, clippy::expect_used, clippy::future_not_send)]
+
+mod common;
+
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use chrono::Utc;
+use civicconnect_api::{crypto, db};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn a_reset_revokes_existing_sessions(pool: PgPool) {
+    let server = common::server(pool.clone(), common::test_config());
+    let alice = common::register(&server, "alice").await;
+    let alice_id: Uuid = alice["user_id"].as_str().unwrap().parse().unwrap();
+    let stolen = alice["token"].as_str().unwrap();
+
+    // The link from the reset email
+    let token = crypto::generate_nonce();
+    db::password_resets::insert(
+        &pool,
+        alice_id,
+        &crypto::hash_token(&token),
+        Utc::now() + chrono::Duration::minutes(30),
+    )
+    .await
+    .unwrap();
+
+    // Sessions carry whole seconds; let the stolen one fall behind
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    server
+        .post("/api/v1/auth/password-reset/confirm")
+        .json(&json!({ "token": token, "new_password": "a brand new passphrase" }))
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+
+    common::bearer(server.get("/api/v1/users/me/organizations"), stolen)
+        .await
+        .assert_status_unauthorized();
+
+    let login = server
+        .post("/api/v1/auth/login")
+        .json(&json!({ "email": "alice@example.org", "password": "a brand new passphrase" }))
+        .await;
+    login.assert_status_ok();
+    let token = login.json::<Value>()["token"].as_str().unwrap().to_string();
+    common::bearer(server.get("/api/v1/users/me/organizations"), &token)
+        .await
+        .assert_status_ok();
+}
@@ -0,0 +1,182 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Abuse reports and triage
+
+#![allow(clippy::unwrap_used, clippy::expect_used, clippy::future_not_send)]
+
+mod common;
+
+use axum::http::StatusCode;
+use axum_test::TestServer;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Register a user and return their session token and ID
+async fn user(server: &TestServer, username: &str) -> (String, Uuid) {
+    let body = common::register(server, username).await;
+    (
+        body["token"].as_str().unwrap().to_string(),
+        body["user_id"].as_str().unwrap().parse().unwrap(),
+    )
+}
+
+async fn event(pool: &PgPool, organizer_id: Uuid) -> Uuid {
+    let id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO events (id, organizer_id, title, description, location_hash, start_time, end_time)
+         VALUES ($1, $2, 'Cleanup', '', '87195da49ffffff', now(), now() + interval '1 hour')",
+    )
+    .bind(id)
+    .bind(organizer_id)
+    .execute(pool)
+    .await
+    .unwrap();
+    id
+}
+
+async fn message(pool: &PgPool, sender_id: Uuid, recipient_id: Uuid) -> Uuid {
+    let id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO messages (id, sender_id, recipient_id, encrypted_content)
+         VALUES ($1, $2, $3, '\\x00')",
+    )
+    .bind(id)
+    .bind(sender_id)
+    .bind(recipient_id)
+    .execute(pool)
+    .await
+    .unwrap();
+    id
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn reports_are_deduplicated_and_triaged(pool: PgPool) {
+    let server = common::server(pool.clone(), common::test_config());
+    let (alice, _) = user(&server, "alice").await;
+    let (bob, bob_id) = user(&server, "bob").await;
+    let (mallory, mallory_id) = user(&server, "mallory").await;
+    let (moderator, moderator_id) = user(&server, "moderator").await;
+    sqlx::query("UPDATE users SET role = 'moderator' WHERE id = $1")
+        .bind(moderator_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let report = json!({
+        "target_type": "user",
+        "target_id": mallory_id,
+        "category": "harassment",
+        "description": "Keeps messaging me after I asked them to stop",
+    });
+
+    let first = common::bearer(server.post("/api/v1/reports"), &alice)
+        .json(&report)
+        .await;
+    first.assert_status(StatusCode::CREATED);
+    let report_id = first.json::<Value>()["report_id"].clone();
+
+    let repeat: Value = common::bearer(server.post("/api/v1/reports"), &alice)
+        .json(&report)
+        .await
+        .json();
+    assert_eq!(repeat["duplicate"], true);
+    assert_eq!(repeat["report_id"], report_id);
+
+    common::bearer(server.post("/api/v1/reports"), &bob)
+        .json(&report)
+        .await
+        .assert_status(StatusCode::CREATED);
+
+    // Nobody can report themselves
+    common::bearer(server.post("/api/v1/reports"), &mallory)
+        .json(&report)
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+
+    // Message reports need evidence, and the message must be to the reporter
+    let to_bob = message(&pool, mallory_id, bob_id).await;
+    let message_report = |evidence: Option<&str>| {
+        json!({
+            "target_type": "message",
+            "target_id": to_bob,
+            "category": "threat",
+            "description": "Threatening message",
+            "evidence": evidence,
+        })
+    };
+    common::bearer(server.post("/api/v1/reports"), &bob)
+        .json(&message_report(None))
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+    common::bearer(server.post("/api/v1/reports"), &alice)
+        .json(&message_report(Some("I know where you live")))
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+    common::bearer(server.post("/api/v1/reports"), &bob)
+        .json(&message_report(Some("I know where you live")))
+        .await
+        .assert_status(StatusCode::CREATED);
+
+    // Only moderators see the queue
+    common::bearer(server.get("/api/v1/admin/reports"), &mallory)
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+
+    let queue: Value = common::bearer(server.get("/api/v1/admin/reports"), &moderator)
+        .await
+        .json();
+    assert_eq!(queue.as_array().unwrap().len(), 3);
+    assert_eq!(queue[0]["target_id"], mallory_id.to_string());
+    assert_eq!(queue[0]["reports_on_target"], 2);
+
+    let status = format!(
+        "/api/v1/admin/reports/{}/status",
+        report_id.as_str().unwrap()
+    );
+    for (next, expected) in [
+        ("triaged", StatusCode::OK),
+        ("actioned", StatusCode::OK),
+        ("open", StatusCode::BAD_REQUEST),
+    ] {
+        common::bearer(server.post(&status), &moderator)
+            .json(&json!({ "status": next, "reason": "Reviewed messages" }))
+            .await
+            .assert_status(expected);
+    }
+
+    // Resolved, so Alice can report again
+    common::bearer(server.post("/api/v1/reports"), &alice)
+        .json(&report)
+        .await
+        .assert_status(StatusCode::CREATED);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn report_bombing_is_rate_limited(pool: PgPool) {
+    let server = common::server(pool.clone(), common::test_config());
+    let (reporter, _) = user(&server, "reporter").await;
+    let (_, organizer_id) = user(&server, "organizer").await;
+
+    for i in 0..11 {
+        let event_id = event(&pool, organizer_id).await;
+        let response = common::bearer(server.post("/api/v1/reports"), &reporter)
+            .json(&json!({
+                "target_type": "event",
+                "target_id": event_id,
+                "category": "spam",
+                "description": "Spam",
+            }))
+            .await;
+
+        let expected = if i < 10 {
+            StatusCode::CREATED
+        } else {
+            StatusCode::TOO_MANY_REQUESTS
+        };
+        response.assert_status(expected);
+    }
+}
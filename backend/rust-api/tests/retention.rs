@@ -0,0 +1,162 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Retention: purging messages, verifications and locations
+
+#![allow(clippy::unwrap_used, clippy::expect_used, clippy::future_not_send)]
+
+use chrono::{Duration, Utc};
+use civicconnect_api::{
+    audit::{self, CheckpointSigner},
+    db::{
+        self,
+        audit::{self as audit_db, NewLevelProgression, NewVerification},
+    },
+    retention::{self, Purged, RetentionConfig},
+};
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+async fn user(pool: &PgPool, name: &str) -> Uuid {
+    let id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO users (id, email_hash, username, password_hash, location_hash)
+         VALUES ($1, $2, $3, 'x', '872a1072fffffff')",
+    )
+    .bind(id)
+    .bind(id.to_string())
+    .bind(name)
+    .execute(pool)
+    .await
+    .unwrap();
+    id
+}
+
+async fn verification(pool: &PgPool, event_id: Uuid, user_id: Uuid, status: &str) {
+    let mut tx = pool.begin().await.unwrap();
+    audit_db::append_verification(
+        &mut tx,
+        NewVerification {
+            event_id,
+            user_id,
+            organizer_id: user_id,
+            signature: &[1, 2, 3],
+            verified_at: Utc::now(),
+            experience_awarded: 25,
+            location_hash: "872a1072fffffff",
+            status,
+            fraud_score: if status == "verified" { 0 } else { 60 },
+            fraud_findings: json!([]),
+        },
+    )
+    .await
+    .unwrap();
+    tx.commit().await.unwrap();
+}
+
+async fn message(pool: &PgPool, from: Uuid, to: Uuid, read: bool) {
+    sqlx::query(
+        "INSERT INTO messages (id, sender_id, recipient_id, encrypted_content, read_at)
+         VALUES ($1, $2, $3, '\\x00', CASE WHEN $4 THEN now() END)",
+    )
+    .bind(Uuid::new_v4())
+    .bind(from)
+    .bind(to)
+    .bind(read)
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn old_data_is_purged_and_the_chain_still_verifies(pool: PgPool) {
+    let alice = user(&pool, "alice").await;
+    let bob = user(&pool, "bob").await;
+    let event_id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO events (id, organizer_id, title, description, location_hash, start_time, end_time)
+         VALUES ($1, $2, 'Cleanup', '', '872a1072fffffff', now(), now() + interval '1 hour')",
+    )
+    .bind(event_id)
+    .bind(bob)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    verification(&pool, event_id, alice, "verified").await;
+    verification(&pool, event_id, bob, "held_for_review").await;
+    let mut tx = pool.begin().await.unwrap();
+    audit_db::append_level_progression(
+        &mut tx,
+        NewLevelProgression {
+            user_id: alice,
+            from_level: 0,
+            to_level: 1,
+            reason: "xp_threshold",
+            metadata: json!({}),
+        },
+    )
+    .await
+    .unwrap();
+    tx.commit().await.unwrap();
+    let signer = CheckpointSigner::new(&[5; 32]);
+    audit::checkpoint(&pool, &signer).await.unwrap();
+
+    message(&pool, bob, alice, true).await;
+    message(&pool, bob, alice, false).await;
+
+    // Nothing is old enough yet
+    let config = RetentionConfig::default();
+    let purged = retention::purge(&pool, &config, Utc::now()).await.unwrap();
+    assert!(purged.is_empty(), "{purged:?}");
+
+    let later = Utc::now() + Duration::days(91);
+    let purged = retention::purge(&pool, &config, later).await.unwrap();
+    assert_eq!(
+        purged,
+        Purged {
+            messages: 1,
+            verifications: 1,
+            locations: 2,
+        }
+    );
+
+    // Bob's awaits review, so only Alice's is collapsed
+    let (remaining, held_user): (i64, Uuid) =
+        sqlx::query_as("SELECT count(*) OVER (), user_id FROM verifications")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!((remaining, held_user), (1, bob));
+    let archived: i32 = sqlx::query_scalar("SELECT archived_attendances FROM users WHERE id = $1")
+        .bind(alice)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(archived, 1);
+    assert_eq!(
+        db::verifications::count_verified(&pool, event_id)
+            .await
+            .unwrap(),
+        1
+    );
+    let unread: i64 = sqlx::query_scalar("SELECT count(*) FROM messages")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(unread, 1);
+
+    let report = audit::verify_chain(&pool, Some(&signer.verifying_key()))
+        .await
+        .unwrap();
+    assert!(report.is_intact(), "{report:?}");
+    assert_eq!(report.entries_checked, 3);
+
+    // Deleting without a tombstone is still refused
+    assert!(sqlx::query("DELETE FROM verifications")
+        .execute(&pool)
+        .await
+        .is_err());
+}
@@ -0,0 +1,85 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Exported spans carry no personal data
+
+#![allow(clippy::unwrap_used, clippy::expect_used, clippy::future_not_send)]
+
+mod common;
+
+use std::time::Duration;
+
+use civicconnect_api::telemetry::{scrub::REDACTED, ScrubbingExporter};
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider, SpanData};
+use sqlx::postgres::PgPoolOptions;
+use tracing_subscriber::layer::SubscriberExt;
+
+/// Every string recorded on a span: attributes, events and their
+/// attributes
+fn recorded(span: &SpanData) -> Vec<String> {
+    let mut strings: Vec<String> = span
+        .attributes
+        .iter()
+        .map(|kv| format!("{}={}", kv.key, kv.value))
+        .collect();
+    for event in span.events.iter() {
+        strings.push(event.name.to_string());
+        strings.extend(
+            event
+                .attributes
+                .iter()
+                .map(|kv| format!("{}={}", kv.key, kv.value)),
+        );
+    }
+    strings
+}
+
+#[tokio::test]
+async fn spans_are_scrubbed_before_export() {
+    let exporter = InMemorySpanExporter::default();
+    let provider = SdkTracerProvider::builder()
+        .with_simple_exporter(ScrubbingExporter(exporter.clone()))
+        .build();
+    let subscriber = tracing_subscriber::registry()
+        .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+    let _default = tracing::subscriber::set_default(subscriber);
+
+    let pool = PgPoolOptions::new()
+        .acquire_timeout(Duration::from_millis(200))
+        .connect_lazy("postgres://localhost:1/unreachable")
+        .unwrap();
+    let server = common::server(pool, common::test_config());
+    let token = "3f9a0c1d2e4b5a6978c0d1e2f3a4b5c6".repeat(2);
+    server
+        .get(&format!(
+            "/api/v1/calendar/{token}.ics?email=alice@example.org"
+        ))
+        .await;
+
+    // Fields named for personal data, and personal-looking text elsewhere
+    tracing::info_span!(
+        "signup",
+        email = "bob@example.org",
+        detail = "cell 87195da49ffffff"
+    )
+    .in_scope(|| tracing::info!("Mailed carol@example.org a reset token {token}"));
+
+    provider.force_flush().unwrap();
+    let spans = exporter.get_finished_spans().unwrap();
+
+    let request = spans.iter().find(|span| span.name == "request").unwrap();
+    assert!(recorded(request).contains(&"route=/api/v1/calendar/:token".to_string()));
+
+    let signup = spans.iter().find(|span| span.name == "signup").unwrap();
+    let strings = recorded(signup);
+    assert!(strings.contains(&format!("email={REDACTED}")));
+    assert!(strings.contains(&format!("detail=cell {REDACTED}")));
+    assert!(strings.contains(&format!("Mailed {REDACTED} a reset token {REDACTED}")));
+
+    for string in spans.iter().flat_map(recorded) {
+        for secret in [token.as_str(), "example.org", "87195da49ffffff"] {
+            assert!(!string.contains(secret), "{secret} exported in {string}");
+        }
+    }
+}
@@ -0,0 +1,151 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! TOTP enrollment, two-step login and recovery codes
+
+#![allow(clippy::unwrap_used, clippy::expect_used, clippy::future_not_send)]
+
+mod common;
+
+use axum::http::StatusCode;
+use axum_test::TestServer;
+use chrono::Utc;
+use civicconnect_api::crypto::{
+    jwt::{self, AuthMethod, TokenScope},
+    totp,
+};
+use data_encoding::BASE32_NOPAD;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+/// Enroll TOTP for a new user; returns the secret and recovery codes
+async fn enroll(server: &TestServer, username: &str) -> (Vec<u8>, Vec<String>) {
+    let token = common::register(server, username).await["token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let enrollment: Value = common::bearer(server.post("/api/v1/auth/totp/enroll"), &token)
+        .await
+        .json();
+    let secret = BASE32_NOPAD
+        .decode(enrollment["secret"].as_str().unwrap().as_bytes())
+        .unwrap();
+    assert!(enrollment["provisioning_uri"]
+        .as_str()
+        .unwrap()
+        .starts_with(&format!("otpauth://totp/CivicConnect:{username}?")));
+
+    // A wrong code doesn't activate it
+    common::bearer(server.post("/api/v1/auth/totp/confirm"), &token)
+        .json(&json!({ "code": "000000" }))
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+
+    let confirmed: Value = common::bearer(server.post("/api/v1/auth/totp/confirm"), &token)
+        .json(&json!({ "code": current_code(&secret, 0) }))
+        .await
+        .json();
+    let codes = confirmed["recovery_codes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|code| code.as_str().unwrap().to_string())
+        .collect();
+
+    (secret, codes)
+}
+
+fn current_code(secret: &[u8], offset: i64) -> String {
+    totp::code_at(secret, totp::step_at(Utc::now().timestamp()) + offset)
+}
+
+async fn mfa_token(server: &TestServer, username: &str) -> String {
+    let login = common::login(server, username).await;
+    assert_eq!(login["mfa_required"], true);
+    assert_eq!(login["methods"], json!(["totp"]));
+    login["mfa_token"].as_str().unwrap().to_string()
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn totp_two_step_login(pool: PgPool) {
+    let server = common::server(pool, common::test_config());
+    let (secret, codes) = enroll(&server, "alice").await;
+    assert_eq!(codes.len(), 10);
+
+    let mfa_token = mfa_token(&server, "alice").await;
+
+    // The code used to confirm enrollment can't be replayed
+    server
+        .post("/api/v1/auth/totp/verify")
+        .json(&json!({ "mfa_token": mfa_token, "code": current_code(&secret, 0) }))
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+
+    // The next step is within the window
+    let session: Value = server
+        .post("/api/v1/auth/totp/verify")
+        .json(&json!({ "mfa_token": mfa_token, "code": current_code(&secret, 1) }))
+        .await
+        .json();
+    let claims = jwt::decode_token(
+        session["token"].as_str().unwrap(),
+        common::JWT_SECRET.as_bytes(),
+        TokenScope::Session,
+    )
+    .unwrap();
+    assert_eq!(
+        claims.amr,
+        vec![AuthMethod::Password, AuthMethod::OneTimePassword]
+    );
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn recovery_codes_are_single_use(pool: PgPool) {
+    let server = common::server(pool, common::test_config());
+    let (_, codes) = enroll(&server, "bob").await;
+
+    let redeem = |code: String| {
+        let server = &server;
+        async move {
+            let mfa_token = mfa_token(server, "bob").await;
+            server
+                .post("/api/v1/auth/mfa/recovery")
+                .json(&json!({ "mfa_token": mfa_token, "code": code }))
+                .await
+        }
+    };
+
+    redeem(codes[0].clone()).await.assert_status_ok();
+    redeem(codes[0].clone())
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+    redeem("0000-000000000000".into())
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+    redeem(codes[1].to_uppercase()).await.assert_status_ok();
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn repeated_failures_lock_totp(pool: PgPool) {
+    let server = common::server(pool, common::test_config());
+    let (secret, _) = enroll(&server, "carol").await;
+    let mfa_token = mfa_token(&server, "carol").await;
+
+    for _ in 0..5 {
+        server
+            .post("/api/v1/auth/totp/verify")
+            .json(&json!({ "mfa_token": mfa_token, "code": "000000" }))
+            .await
+            .assert_status(StatusCode::UNAUTHORIZED);
+    }
+
+    server
+        .post("/api/v1/auth/totp/verify")
+        .json(&json!({ "mfa_token": mfa_token, "code": current_code(&secret, 1) }))
+        .await
+        .assert_status(StatusCode::TOO_MANY_REQUESTS);
+}
@@ -0,0 +1,421 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! QR scans, live and queued offline
+
+#![allow(clippy::unwrap_used, clippy::expect_used, clippy::future_not_send)]
+
+mod common;
+
+use axum_test::TestServer;
+use chrono::{DateTime, Duration, Utc};
+use civicconnect_api::{
+    crypto,
+    verification::{self, CheckinToken},
+};
+use ed25519_dalek::SigningKey;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Resolution 7 cells in central London and Paris
+const LONDON: &str = "87195da49ffffff";
+const PARIS: &str = "871fb4662ffffff";
+
+/// A user whose device has registered a signing key
+async fn with_key(server: &TestServer, username: &str) -> (Uuid, SigningKey) {
+    let session = common::register(server, username).await;
+    let (key, public) = crypto::generate_keypair();
+    common::bearer(
+        server.put("/api/v1/users/me/signing-key"),
+        session["token"].as_str().unwrap(),
+    )
+    .json(&json!({ "public_key": hex::encode(public.to_bytes()) }))
+    .await
+    .assert_status(axum::http::StatusCode::NO_CONTENT);
+
+    let id = session["user_id"].as_str().unwrap().parse().unwrap();
+    (id, key)
+}
+
+/// An event that started three hours ago and runs for another hour
+async fn event(pool: &PgPool, organizer_id: Uuid) -> Uuid {
+    event_in(pool, organizer_id, LONDON, Duration::hours(3)).await
+}
+
+/// An event in `cell` that started `ago` and runs for another hour
+async fn event_in(pool: &PgPool, organizer_id: Uuid, cell: &str, ago: Duration) -> Uuid {
+    let id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO events (id, organizer_id, title, description, location_hash, start_time, end_time)
+         VALUES ($1, $2, 'Cleanup', '', $3, $4, now() + interval '1 hour')",
+    )
+    .bind(id)
+    .bind(organizer_id)
+    .bind(cell)
+    .bind(Utc::now() - ago)
+    .execute(pool)
+    .await
+    .unwrap();
+    id
+}
+
+/// A scan of a code the organizer's device signed at `at`
+fn scan(key: &SigningKey, event_id: Uuid, organizer_id: Uuid, at: DateTime<Utc>) -> Value {
+    scan_in(key, event_id, organizer_id, at, LONDON)
+}
+
+/// A scan made in `cell`
+fn scan_in(
+    key: &SigningKey,
+    event_id: Uuid,
+    organizer_id: Uuid,
+    at: DateTime<Utc>,
+    cell: &str,
+) -> Value {
+    let nonce = crypto::generate_nonce();
+    let message = verification::qr_message(event_id, organizer_id, at, &nonce);
+    json!({
+        "event_id": event_id,
+        "organizer_id": organizer_id,
+        "timestamp": at,
+        "nonce": nonce,
+        "signature": hex::encode(crypto::sign_message(key, &message).to_bytes()),
+        "location_cell": cell,
+    })
+}
+
+/// The same scan, queued on the device `after` it was signed
+fn queued(mut scan: Value, after: Duration) -> Value {
+    let at: DateTime<Utc> = serde_json::from_value(scan["timestamp"].clone()).unwrap();
+    scan["scanned_at"] = json!(at + after);
+    scan
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn queued_scans_are_timed_by_the_signed_code(pool: PgPool) {
+    let server = common::server(pool.clone(), common::test_config());
+    let (olive, key) = with_key(&server, "olive").await;
+    let session = common::register(&server, "alice").await;
+    let token = session["token"].as_str().unwrap();
+    let two_hours_ago = Utc::now() - Duration::hours(2);
+
+    // Shown and scanned two hours ago, with no signal
+    let first = event(&pool, olive).await;
+    let offline = scan(&key, first, olive, two_hours_ago);
+    let response = common::bearer(server.post("/api/v1/verify/scan"), token)
+        .json(&offline)
+        .await;
+    response.assert_status_bad_request();
+    assert_eq!(response.json::<Value>()["code"], "OUTSIDE_TIME_WINDOW");
+
+    let second = event(&pool, olive).await;
+    let third = event(&pool, olive).await;
+    let mut forged = scan(&key, third, olive, two_hours_ago);
+    forged["timestamp"] = json!(Utc::now());
+
+    let response = common::bearer(server.post("/api/v1/verify/scan/batch"), token)
+        .json(&json!({ "scans": [
+            queued(offline.clone(), Duration::seconds(10)),
+            // Scanned long after the code was replaced
+            queued(scan(&key, second, olive, two_hours_ago), Duration::minutes(30)),
+            // Timestamp changed after signing
+            queued(forged, Duration::seconds(10)),
+            queued(offline, Duration::seconds(10)),
+        ] }))
+        .await;
+    response.assert_status_ok();
+    let results = response.json::<Value>()["results"].clone();
+
+    assert_eq!(results[0]["index"], 0);
+    assert_eq!(results[0]["status"], "verified");
+    assert_eq!(results[0]["verification"]["xp_awarded"], 25);
+    assert_eq!(results[0]["verification"]["new_total_xp"], 25);
+    assert_eq!(results[1]["status"], "rejected");
+    assert_eq!(results[1]["error"]["code"], "OUTSIDE_TIME_WINDOW");
+    assert_eq!(results[2]["error"]["code"], "INVALID_SIGNATURE");
+    assert_eq!(results[3]["error"]["code"], "ALREADY_VERIFIED");
+
+    let recorded: i64 = sqlx::query_scalar("SELECT count(*) FROM verifications")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(recorded, 1);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn queued_scans_are_screened_and_limited_by_when_they_were_made(pool: PgPool) {
+    let mut config = common::test_config();
+    config.scan.offline_grace_secs = 3 * 24 * 3600;
+    let server = common::server(pool.clone(), config);
+    let (olive, key) = with_key(&server, "olive").await;
+    let session = common::register(&server, "alice").await;
+    let token = session["token"].as_str().unwrap();
+
+    // Three events in London yesterday, then one in Paris today, all
+    // uploaded together
+    let yesterday = Utc::now() - Duration::hours(30);
+    let today = Utc::now() - Duration::minutes(10);
+    let mut scans = Vec::new();
+    for _ in 0..3 {
+        let id = event_in(&pool, olive, LONDON, Duration::hours(31)).await;
+        scans.push(queued(
+            scan(&key, id, olive, yesterday),
+            Duration::seconds(5),
+        ));
+    }
+    let paris = event_in(&pool, olive, PARIS, Duration::hours(1)).await;
+    scans.push(queued(
+        scan_in(&key, paris, olive, today, PARIS),
+        Duration::seconds(5),
+    ));
+
+    let response = common::bearer(server.post("/api/v1/verify/scan/batch"), token)
+        .json(&json!({ "scans": scans }))
+        .await;
+    response.assert_status_ok();
+    let statuses: Vec<Value> = response.json::<Value>()["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|result| result["status"].clone())
+        .collect();
+    assert_eq!(statuses, vec![json!("verified"); 4]);
+
+    // Recorded when they were made, not when they arrived
+    let recorded: Vec<DateTime<Utc>> =
+        sqlx::query_scalar("SELECT verified_at FROM verifications ORDER BY verified_at")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(recorded[0].timestamp(), yesterday.timestamp());
+    assert_eq!(recorded[3].timestamp(), today.timestamp());
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn scans_need_the_organizers_key_and_are_rate_limited(pool: PgPool) {
+    let server = common::server(pool.clone(), common::test_config());
+    let (olive, key) = with_key(&server, "olive").await;
+    let (mallory, mallory_key) = with_key(&server, "mallory").await;
+    let session = common::register(&server, "alice").await;
+    let token = session["token"].as_str().unwrap();
+    let now = Utc::now();
+
+    // Someone not running the event can't sign its codes
+    let first = event(&pool, olive).await;
+    let response = common::bearer(server.post("/api/v1/verify/scan"), token)
+        .json(&scan(&mallory_key, first, mallory, now))
+        .await;
+    assert_eq!(response.json::<Value>()["code"], "INVALID_SIGNATURE");
+
+    let response = common::bearer(server.post("/api/v1/verify/scan"), token)
+        .json(&scan(&key, first, olive, now))
+        .await;
+    response.assert_status_ok();
+    assert_eq!(response.json::<Value>()["xp_awarded"], 25);
+
+    let mut scans = Vec::new();
+    for _ in 0..3 {
+        let id = event(&pool, olive).await;
+        scans.push(queued(scan(&key, id, olive, now), Duration::seconds(5)));
+    }
+    let response = common::bearer(server.post("/api/v1/verify/scan/batch"), token)
+        .json(&json!({ "scans": scans }))
+        .await;
+    let statuses: Vec<Value> = response.json::<Value>()["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|result| result["error"]["code"].clone())
+        .collect();
+    assert_eq!(statuses, [Value::Null, Value::Null, json!("RATE_LIMITED")]);
+
+    let too_many = vec![scans[0].clone(); 21];
+    common::bearer(server.post("/api/v1/verify/scan/batch"), token)
+        .json(&json!({ "scans": too_many }))
+        .await
+        .assert_status_bad_request();
+}
+
+/// A check-in token the attendee's device signed at `at`, collected
+/// by the organizer's device shortly after
+fn token(key: &SigningKey, event_id: Uuid, user_id: Uuid, at: DateTime<Utc>) -> Value {
+    let nonce = crypto::generate_nonce();
+    let message = verification::checkin_message(event_id, user_id, at, &nonce);
+    json!({
+        "user_id": user_id,
+        "issued_at": at,
+        "nonce": nonce,
+        "signature": hex::encode(crypto::sign_message(key, &message).to_bytes()),
+        "collected_at": at + Duration::seconds(5),
+    })
+}
+
+/// A manifest of `tokens`, signed by `organizer`
+fn manifest(key: &SigningKey, event_id: Uuid, organizer_id: Uuid, tokens: &[Value]) -> Value {
+    let collected: Vec<CheckinToken<'_>> = tokens
+        .iter()
+        .map(|token| CheckinToken {
+            user_id: token["user_id"].as_str().unwrap().parse().unwrap(),
+            issued_at: serde_json::from_value(token["issued_at"].clone()).unwrap(),
+            nonce: token["nonce"].as_str().unwrap(),
+            signature: token["signature"].as_str().unwrap(),
+            collected_at: serde_json::from_value(token["collected_at"].clone()).unwrap(),
+        })
+        .collect();
+    let message = verification::manifest_message(event_id, organizer_id, LONDON, &collected);
+    json!({
+        "event_id": event_id,
+        "location_cell": LONDON,
+        "tokens": tokens,
+        "signature": hex::encode(crypto::sign_message(key, &message).to_bytes()),
+    })
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn organizers_upload_attendee_tokens_collected_offline(pool: PgPool) {
+    let server = common::server(pool.clone(), common::test_config());
+    let (olive, olive_key) = with_key(&server, "olive").await;
+    let (mallory, mallory_key) = with_key(&server, "mallory").await;
+    let (alice, alice_key) = with_key(&server, "alice").await;
+    let (bob, _) = with_key(&server, "bob").await;
+    let carol: Uuid = common::register(&server, "carol").await["user_id"]
+        .as_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    let olive_token = common::login(&server, "olive").await["token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let mallory_token = common::login(&server, "mallory").await["token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let id = event(&pool, olive).await;
+    let an_hour_ago = Utc::now() - Duration::hours(1);
+    let tokens = [
+        token(&alice_key, id, alice, an_hour_ago),
+        // Signed by someone else's device
+        token(&alice_key, id, bob, an_hour_ago),
+        // No key registered
+        token(&alice_key, id, carol, an_hour_ago),
+        token(&alice_key, id, alice, an_hour_ago),
+    ];
+
+    // Tokens changed after the organizer signed
+    let mut tampered = manifest(&olive_key, id, olive, &tokens);
+    tampered["tokens"][1]["collected_at"] = json!(Utc::now());
+    let response = common::bearer(server.post("/api/v1/verify/checkins"), &olive_token)
+        .json(&tampered)
+        .await;
+    response.assert_status_bad_request();
+    assert_eq!(response.json::<Value>()["code"], "INVALID_SIGNATURE");
+
+    // Signed by someone not running the event
+    let response = common::bearer(server.post("/api/v1/verify/checkins"), &mallory_token)
+        .json(&manifest(&mallory_key, id, mallory, &tokens))
+        .await;
+    assert_eq!(response.json::<Value>()["code"], "INVALID_SIGNATURE");
+
+    let response = common::bearer(server.post("/api/v1/verify/checkins"), &olive_token)
+        .json(&manifest(&olive_key, id, olive, &tokens))
+        .await;
+    response.assert_status_ok();
+    let results = response.json::<Value>()["results"].clone();
+    assert_eq!(results[0]["status"], "verified");
+    assert_eq!(results[0]["verification"]["xp_awarded"], 25);
+    assert_eq!(results[1]["error"]["code"], "INVALID_SIGNATURE");
+    assert_eq!(results[2]["error"]["code"], "INVALID_SIGNATURE");
+    assert_eq!(results[3]["error"]["code"], "ALREADY_VERIFIED");
+
+    let (user_id, organizer_id): (Uuid, Uuid) =
+        sqlx::query_as("SELECT user_id, organizer_id FROM verifications")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!((user_id, organizer_id), (alice, olive));
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn scans_are_screened_for_fraud(pool: PgPool) {
+    let server = common::server(pool.clone(), common::test_config());
+    let (olive, key) = with_key(&server, "olive").await;
+    let olive_token = common::login(&server, "olive").await["token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    // An organizer scanning their own code is recorded, but held
+    let id = event(&pool, olive).await;
+    let response = common::bearer(server.post("/api/v1/verify/scan"), &olive_token)
+        .json(&scan(&key, id, olive, Utc::now()))
+        .await;
+    response.assert_status_ok();
+    assert_eq!(response.json::<Value>()["xp_awarded"], 0);
+
+    let (status, rule): (String, String) =
+        sqlx::query_as("SELECT status, fraud_findings->0->>'rule' FROM verifications")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(
+        (status.as_str(), rule.as_str()),
+        ("held_for_review", "implausible_attendance")
+    );
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn a_full_manifest_is_not_held(pool: PgPool) {
+    let config = common::test_config();
+    let max = config.scan.max_manifest;
+    let server = common::server(pool.clone(), config);
+    let (olive, olive_key) = with_key(&server, "olive").await;
+    let olive_token = common::login(&server, "olive").await["token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let id = event(&pool, olive).await;
+
+    // A queue of attendees checking in a second apart, well past the rate
+    // scans are held at
+    let start = Utc::now() - Duration::minutes(30);
+    let mut tokens = Vec::with_capacity(max);
+    for i in 0..max {
+        let (key, public) = crypto::generate_keypair();
+        let user_id = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO users (id, email_hash, username, password_hash, signing_key)
+             VALUES ($1, $2, $3, 'x', $4)",
+        )
+        .bind(user_id)
+        .bind(user_id.to_string())
+        .bind(format!("attendee-{i}"))
+        .bind(public.to_bytes().as_slice())
+        .execute(&pool)
+        .await
+        .unwrap();
+        let at = start + Duration::seconds(i64::try_from(i).unwrap());
+        tokens.push(token(&key, id, user_id, at));
+    }
+
+    let response = common::bearer(server.post("/api/v1/verify/checkins"), &olive_token)
+        .json(&manifest(&olive_key, id, olive, &tokens))
+        .await;
+    response.assert_status_ok();
+    let results = response.json::<Value>()["results"].clone();
+    let verified = results
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|result| result["status"] == "verified")
+        .count();
+    assert_eq!(verified, max);
+}
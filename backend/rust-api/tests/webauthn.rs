@@ -0,0 +1,267 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Passkey registration and login, driven by a software authenticator
+
+#![allow(clippy::unwrap_used, clippy::expect_used, clippy::future_not_send)]
+
+mod common;
+
+use axum::http::StatusCode;
+use axum_test::TestServer;
+use chrono::Utc;
+use civicconnect_api::crypto::{
+    jwt::{self, AuthMethod, TokenScope},
+    totp,
+};
+use data_encoding::BASE32_NOPAD;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use webauthn_authenticator_rs::{softpasskey::SoftPasskey, WebauthnAuthenticator};
+use webauthn_rs::prelude::Url;
+
+type Authenticator = WebauthnAuthenticator<SoftPasskey>;
+
+fn authenticator() -> Authenticator {
+    WebauthnAuthenticator::new(SoftPasskey::new(true))
+}
+
+fn origin() -> Url {
+    Url::parse(common::PUBLIC_URL).unwrap()
+}
+
+fn amr(token: &str) -> Vec<AuthMethod> {
+    jwt::decode_token(token, common::JWT_SECRET.as_bytes(), TokenScope::Session)
+        .unwrap()
+        .amr
+}
+
+async fn enroll(server: &TestServer, authenticator: &mut Authenticator, token: &str) {
+    let start: Value = common::bearer(server.post("/api/v1/auth/webauthn/register/start"), token)
+        .json(&json!({ "name": "Security key" }))
+        .await
+        .json();
+
+    let credential = authenticator
+        .do_registration(
+            origin(),
+            serde_json::from_value(start["options"].clone()).unwrap(),
+        )
+        .unwrap();
+
+    common::bearer(server.post("/api/v1/auth/webauthn/register/finish"), token)
+        .json(&json!({ "challenge_id": start["challenge_id"], "credential": credential }))
+        .await
+        .assert_status(StatusCode::CREATED);
+}
+
+async fn assert_with(
+    server: &TestServer,
+    authenticator: &mut Authenticator,
+    start_body: Value,
+) -> (Value, Value) {
+    let start: Value = server
+        .post("/api/v1/auth/webauthn/login/start")
+        .json(&start_body)
+        .await
+        .json();
+
+    let credential = authenticator
+        .do_authentication(
+            origin(),
+            serde_json::from_value(start["options"].clone()).unwrap(),
+        )
+        .unwrap();
+    let finish = json!({ "challenge_id": start["challenge_id"], "credential": credential });
+
+    let response = server
+        .post("/api/v1/auth/webauthn/login/finish")
+        .json(&finish)
+        .await;
+    response.assert_status_ok();
+
+    (response.json(), finish)
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn passkey_as_second_factor(pool: PgPool) {
+    let server = common::server(pool, common::test_config());
+    let mut authenticator = authenticator();
+
+    let registered = common::register(&server, "alice").await;
+    enroll(
+        &server,
+        &mut authenticator,
+        registered["token"].as_str().unwrap(),
+    )
+    .await;
+
+    // Password alone no longer yields a session
+    let login = common::login(&server, "alice").await;
+    assert_eq!(login["mfa_required"], true);
+    assert!(login.get("token").is_none());
+    let mfa_token = login["mfa_token"].as_str().unwrap();
+
+    // The MFA token is not a session
+    common::bearer(
+        server.post("/api/v1/auth/webauthn/register/start"),
+        mfa_token,
+    )
+    .json(&json!({ "name": "Another key" }))
+    .await
+    .assert_status(StatusCode::UNAUTHORIZED);
+
+    let (session, finish) = assert_with(
+        &server,
+        &mut authenticator,
+        json!({ "mfa_token": mfa_token }),
+    )
+    .await;
+    assert_eq!(
+        amr(session["token"].as_str().unwrap()),
+        vec![AuthMethod::Password, AuthMethod::HardwareKey]
+    );
+
+    // Challenges are single use
+    server
+        .post("/api/v1/auth/webauthn/login/finish")
+        .json(&finish)
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn passwordless_login(pool: PgPool) {
+    let server = common::server(pool, common::test_config());
+    let mut authenticator = authenticator();
+
+    let registered = common::register(&server, "bob").await;
+    enroll(
+        &server,
+        &mut authenticator,
+        registered["token"].as_str().unwrap(),
+    )
+    .await;
+
+    let (session, _) = assert_with(&server, &mut authenticator, json!({ "username": "bob" })).await;
+    assert_eq!(session["username"], "bob");
+    assert_eq!(
+        amr(session["token"].as_str().unwrap()),
+        vec![AuthMethod::HardwareKey]
+    );
+
+    // Users without a passkey can't log in passwordless
+    common::register(&server, "dave").await;
+    server
+        .post("/api/v1/auth/webauthn/login/start")
+        .json(&json!({ "username": "dave" }))
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn required_level_forces_enrollment(pool: PgPool) {
+    let config = civicconnect_api::config::Config {
+        webauthn_required_level: Some(0),
+        ..common::test_config()
+    };
+    let server = common::server(pool, config);
+    let mut authenticator = authenticator();
+
+    common::register(&server, "carol").await;
+
+    let login = common::login(&server, "carol").await;
+    assert_eq!(login["enrollment_required"], true);
+    assert!(login.get("token").is_none());
+    let enrollment_token = login["enrollment_token"].as_str().unwrap();
+
+    // Enrollment tokens can't start a login
+    server
+        .post("/api/v1/auth/webauthn/login/start")
+        .json(&json!({ "mfa_token": enrollment_token }))
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+
+    enroll(&server, &mut authenticator, enrollment_token).await;
+
+    let login = common::login(&server, "carol").await;
+    assert_eq!(login["mfa_required"], true);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn required_level_refuses_other_factors(pool: PgPool) {
+    let config = civicconnect_api::config::Config {
+        webauthn_required_level: Some(0),
+        ..common::test_config()
+    };
+    let server = common::server(pool, config);
+    let mut authenticator = authenticator();
+
+    let registered = common::register(&server, "dave").await;
+    let token = registered["token"].as_str().unwrap();
+    let enrollment: Value = common::bearer(server.post("/api/v1/auth/totp/enroll"), token)
+        .await
+        .json();
+    let secret = BASE32_NOPAD
+        .decode(enrollment["secret"].as_str().unwrap().as_bytes())
+        .unwrap();
+    let code = |offset| totp::code_at(&secret, totp::step_at(Utc::now().timestamp()) + offset);
+    let confirmed: Value = common::bearer(server.post("/api/v1/auth/totp/confirm"), token)
+        .json(&json!({ "code": code(0) }))
+        .await
+        .json();
+    enroll(&server, &mut authenticator, token).await;
+
+    // TOTP isn't offered, and can't be used anyway
+    let login = common::login(&server, "dave").await;
+    assert_eq!(login["methods"], json!(["webauthn"]));
+    let mfa_token = login["mfa_token"].as_str().unwrap();
+    server
+        .post("/api/v1/auth/totp/verify")
+        .json(&json!({ "mfa_token": mfa_token, "code": code(1) }))
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+    server
+        .post("/api/v1/auth/mfa/recovery")
+        .json(&json!({ "mfa_token": mfa_token, "code": confirmed["recovery_codes"][0] }))
+        .await
+        .assert_status(StatusCode::FORBIDDEN);
+
+    assert_with(
+        &server,
+        &mut authenticator,
+        json!({ "mfa_token": mfa_token }),
+    )
+    .await;
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn suspended_and_revoked_sessions_cannot_enroll(pool: PgPool) {
+    let server = common::server(pool.clone(), common::test_config());
+    let alice = common::register(&server, "alice").await;
+    let token = alice["token"].as_str().unwrap();
+    let start = || {
+        common::bearer(server.post("/api/v1/auth/webauthn/register/start"), token)
+            .json(&json!({ "name": "Security key" }))
+    };
+
+    sqlx::query("UPDATE users SET suspended_at = now() WHERE username = 'alice'")
+        .execute(&pool)
+        .await
+        .unwrap();
+    start().await.assert_status(StatusCode::FORBIDDEN);
+
+    sqlx::query(
+        "UPDATE users SET suspended_at = NULL, sessions_valid_after = now() + interval '1 minute'
+         WHERE username = 'alice'",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    start().await.assert_status_unauthorized();
+}
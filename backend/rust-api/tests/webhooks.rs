@@ -0,0 +1,312 @@
+// SPDX-License-Identifier: MPL-2.0
+// SPDX-FileCopyrightText: 2025 Jonathan D.A. Jewell
+
+//! Outbound webhooks, against a mock receiver
+
+#![allow(clippy::unwrap_used, clippy::expect_used, clippy::future_not_send)]
+
+mod common;
+
+use axum::http::StatusCode;
+use axum_test::TestServer;
+use civicconnect_api::{
+    config::Config,
+    webhooks::{self, Dispatcher},
+};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use uuid::Uuid;
+use wiremock::http::HeaderValue;
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+fn webhook_config() -> Config {
+    let mut config = common::test_config();
+    config.webhooks.allow_http = true;
+    config.webhooks.allow_private_networks = true;
+    config.webhooks.max_attempts = 2;
+    config.webhooks.retry_base_secs = 0;
+    config
+}
+
+async fn receiver(status: u16) -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(status))
+        .mount(&server)
+        .await;
+    server
+}
+
+/// Register an organizer with an event; returns their token and the event
+async fn organizer_with_event(server: &TestServer, pool: &PgPool) -> (String, Uuid) {
+    let body = common::register(server, "organizer").await;
+    let organizer_id: Uuid = body["user_id"].as_str().unwrap().parse().unwrap();
+    sqlx::query("UPDATE users SET current_level = 3 WHERE id = $1")
+        .bind(organizer_id)
+        .execute(pool)
+        .await
+        .unwrap();
+
+    let event_id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO events (id, organizer_id, title, description, location_hash, start_time, end_time)
+         VALUES ($1, $2, 'Cleanup', '', '87195da49ffffff', now() + interval '1 day', now() + interval '25 hours')",
+    )
+    .bind(event_id)
+    .bind(organizer_id)
+    .execute(pool)
+    .await
+    .unwrap();
+
+    (body["token"].as_str().unwrap().to_string(), event_id)
+}
+
+async fn subscribe(server: &TestServer, token: &str, body: Value) -> Value {
+    let response = common::bearer(server.post("/api/v1/webhooks"), token)
+        .json(&body)
+        .await;
+    response.assert_status(StatusCode::CREATED);
+    response.json()
+}
+
+/// A received header (the mock server splits values on commas)
+fn header(request: &Request, name: &str) -> String {
+    let values = request.headers.get(&name.into()).unwrap();
+    values
+        .iter()
+        .map(HeaderValue::as_str)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+async fn rsvp(server: &TestServer, username: &str, event_id: Uuid) {
+    let member = common::register(server, username).await;
+    common::bearer(
+        server.put(&format!("/api/v1/events/{event_id}/rsvp")),
+        member["token"].as_str().unwrap(),
+    )
+    .await
+    .assert_status_ok();
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn organizers_manage_webhooks(pool: PgPool) {
+    let server = common::server(pool.clone(), common::test_config());
+    let (token, _) = organizer_with_event(&server, &pool).await;
+    let request = json!({
+        "url": "https://hooks.example/civic",
+        "event_types": ["rsvp.changed", "event.cancelled"],
+    });
+
+    // Members have no events to report on
+    let member = common::register(&server, "member").await;
+    common::bearer(
+        server.post("/api/v1/webhooks"),
+        member["token"].as_str().unwrap(),
+    )
+    .json(&request)
+    .await
+    .assert_status(StatusCode::FORBIDDEN);
+
+    for invalid in [
+        json!({ "url": "http://hooks.example/civic", "event_types": ["rsvp.changed"] }),
+        json!({ "url": "https://hooks.example/civic", "event_types": ["user.created"] }),
+        json!({ "url": "https://hooks.example/civic", "event_types": [] }),
+    ] {
+        common::bearer(server.post("/api/v1/webhooks"), &token)
+            .json(&invalid)
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+    }
+
+    let created = subscribe(&server, &token, request).await;
+    assert_eq!(created["secret"].as_str().unwrap().len(), 64);
+    assert_eq!(created["include_pii"], false);
+
+    let listed: Value = common::bearer(server.get("/api/v1/webhooks"), &token)
+        .await
+        .json();
+    assert_eq!(listed.as_array().unwrap().len(), 1);
+    assert!(listed[0].get("secret").is_none());
+
+    let webhook = format!("/api/v1/webhooks/{}", created["id"].as_str().unwrap());
+    common::bearer(server.delete(&webhook), member["token"].as_str().unwrap())
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+    common::bearer(server.delete(&webhook), &token)
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    common::bearer(server.delete(&webhook), &token)
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn deliveries_are_signed_and_scrubbed(pool: PgPool) {
+    let config = webhook_config();
+    let dispatcher = Dispatcher::new(config.webhooks.clone()).unwrap();
+    let server = common::server(pool.clone(), config);
+    let receiver = receiver(204).await;
+    let (token, event_id) = organizer_with_event(&server, &pool).await;
+
+    let scrubbed = subscribe(
+        &server,
+        &token,
+        json!({ "url": format!("{}/scrubbed", receiver.uri()), "event_types": ["rsvp.changed"] }),
+    )
+    .await;
+    subscribe(
+        &server,
+        &token,
+        json!({
+            "url": format!("{}/full", receiver.uri()),
+            "event_types": ["rsvp.changed"],
+            "include_pii": true,
+        }),
+    )
+    .await;
+
+    rsvp(&server, "member", event_id).await;
+    assert_eq!(dispatcher.deliver_due(&pool).await.unwrap(), 2);
+    assert_eq!(dispatcher.deliver_due(&pool).await.unwrap(), 0);
+
+    let requests = receiver.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 2);
+    for request in &requests {
+        let body: Value = serde_json::from_slice(&request.body).unwrap();
+        assert_eq!(body["type"], "rsvp.changed");
+        assert_eq!(body["data"]["event_id"], event_id.to_string());
+        assert_eq!(body["data"]["rsvps"], 1);
+        assert_eq!(header(request, "x-civicconnect-delivery"), body["id"]);
+
+        if request.url.path() == "/scrubbed" {
+            assert!(body["data"].get("user_id").is_none());
+
+            // The signature is over the timestamp and body, with the secret
+            let signature = header(request, "x-civicconnect-signature");
+            let timestamp: i64 = signature
+                .strip_prefix("t=")
+                .and_then(|s| s.split(',').next())
+                .unwrap()
+                .parse()
+                .unwrap();
+            assert_eq!(
+                signature,
+                webhooks::sign(
+                    scrubbed["secret"].as_str().unwrap(),
+                    timestamp,
+                    &request.body
+                )
+            );
+        } else {
+            assert!(body["data"]["user_id"].is_string());
+        }
+    }
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn failed_deliveries_are_dead_lettered_and_replayed(pool: PgPool) {
+    let config = webhook_config();
+    let dispatcher = Dispatcher::new(config.webhooks.clone()).unwrap();
+    let server = common::server(pool.clone(), config);
+    let receiver = receiver(500).await;
+    let (token, event_id) = organizer_with_event(&server, &pool).await;
+
+    let created = subscribe(
+        &server,
+        &token,
+        json!({ "url": receiver.uri(), "event_types": ["rsvp.changed"] }),
+    )
+    .await;
+    let webhook = format!("/api/v1/webhooks/{}", created["id"].as_str().unwrap());
+
+    rsvp(&server, "member", event_id).await;
+    // Two attempts, then a dead letter
+    assert_eq!(dispatcher.deliver_due(&pool).await.unwrap(), 1);
+    assert_eq!(dispatcher.deliver_due(&pool).await.unwrap(), 1);
+    assert_eq!(dispatcher.deliver_due(&pool).await.unwrap(), 0);
+
+    let letters: Value = common::bearer(server.get(&format!("{webhook}/dead-letters")), &token)
+        .await
+        .json();
+    assert_eq!(letters.as_array().unwrap().len(), 1);
+    assert_eq!(letters[0]["attempts"], 2);
+    assert!(letters[0]["last_error"].as_str().unwrap().contains("500"));
+    let letter_id = letters[0]["id"].as_str().unwrap();
+
+    // The receiver recovers
+    receiver.reset().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&receiver)
+        .await;
+
+    common::bearer(
+        server.post(&format!("{webhook}/dead-letters/{}/replay", Uuid::new_v4())),
+        &token,
+    )
+    .await
+    .assert_status(StatusCode::NOT_FOUND);
+    let replayed = common::bearer(
+        server.post(&format!("{webhook}/dead-letters/{letter_id}/replay")),
+        &token,
+    )
+    .await;
+    replayed.assert_status(StatusCode::ACCEPTED);
+    assert_eq!(replayed.json::<Value>()["replayed"], 1);
+
+    assert_eq!(dispatcher.deliver_due(&pool).await.unwrap(), 1);
+    let requests = receiver.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 1);
+    let body: Value = serde_json::from_slice(&requests[0].body).unwrap();
+    assert_eq!(body["id"], letter_id);
+
+    let letters: Value = common::bearer(server.get(&format!("{webhook}/dead-letters")), &token)
+        .await
+        .json();
+    assert!(letters.as_array().unwrap().is_empty());
+}
+
+#[sqlx::test(migrations = "./migrations")]
+#[ignore = "requires PostgreSQL (DATABASE_URL)"]
+async fn private_addresses_are_refused(pool: PgPool) {
+    let mut config = webhook_config();
+    config.webhooks.allow_private_networks = false;
+    let dispatcher = Dispatcher::new(config.webhooks.clone()).unwrap();
+    let server = common::server(pool.clone(), config);
+    let receiver = receiver(204).await;
+    let (token, event_id) = organizer_with_event(&server, &pool).await;
+
+    for url in [
+        "http://127.0.0.1:9/civic",
+        "http://10.0.0.5/civic",
+        "http://169.254.169.254/latest/meta-data",
+        "http://localhost/civic",
+    ] {
+        common::bearer(server.post("/api/v1/webhooks"), &token)
+            .json(&json!({ "url": url, "event_types": ["rsvp.changed"] }))
+            .await
+            .assert_status(StatusCode::BAD_REQUEST);
+    }
+
+    // A host that was public when subscribed and now resolves inward
+    let subscription = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO webhook_subscriptions (id, owner_id, url, secret, event_types)
+         SELECT $1, organizer_id, $2, 'secret', ARRAY['rsvp.changed'] FROM events WHERE id = $3",
+    )
+    .bind(subscription)
+    .bind(receiver.uri())
+    .bind(event_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    rsvp(&server, "member", event_id).await;
+    assert_eq!(dispatcher.deliver_due(&pool).await.unwrap(), 1);
+    assert!(receiver.received_requests().await.unwrap().is_empty());
+}